@@ -0,0 +1,140 @@
+use crate::dashboard::app::{state_dir, TRACKER_STATE_FILE};
+use crate::file_tracker::seen_ids_path;
+use crate::statusline;
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Dashboard snapshots are written here, inside `state_dir`
+pub const SNAPSHOTS_DIR: &str = "snapshots";
+
+/// Which kinds of saved state `clean` removes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CleanTargets {
+    /// Incremental parser file-tracker state
+    pub state: bool,
+    /// Parsed-entry cache
+    pub cache: bool,
+    /// Saved dashboard snapshots
+    pub snapshots: bool,
+}
+
+impl CleanTargets {
+    pub fn all() -> Self {
+        Self {
+            state: true,
+            cache: true,
+            snapshots: true,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        !(self.state || self.cache || self.snapshots)
+    }
+}
+
+/// Existing files that `targets` covers for `claude_dir`
+pub fn find_files(claude_dir: &str, targets: CleanTargets) -> Vec<PathBuf> {
+    let state_dir = state_dir(claude_dir);
+    let mut files = Vec::new();
+
+    if targets.state {
+        // Older versions kept the tracker state directly in the Claude directory
+        let claude_dir = PathBuf::from(shellexpand::tilde(claude_dir).as_ref());
+        files.extend([state_dir.join(TRACKER_STATE_FILE), claude_dir.join(TRACKER_STATE_FILE)]);
+        files.push(seen_ids_path(&state_dir.join(TRACKER_STATE_FILE)));
+        files.extend(statusline::state_files(&state_dir));
+    }
+    // No entry cache exists yet, so `targets.cache` has nothing to remove
+    if targets.snapshots {
+        if let Ok(entries) = fs::read_dir(state_dir.join(SNAPSHOTS_DIR)) {
+            files.extend(entries.flatten().map(|entry| entry.path()));
+        }
+    }
+
+    files.retain(|path| path.is_file());
+    files.sort();
+    files
+}
+
+/// Run `clean`. Naming the kinds of state to delete removes them straight away; with
+/// none named, everything is listed and only deleted when `confirm` is set.
+pub fn run_clean<W: Write>(
+    claude_dir: &str,
+    targets: CleanTargets,
+    confirm: bool,
+    writer: &mut W,
+) -> Result<()> {
+    let dry_run = targets.is_empty() && !confirm;
+    let targets = if targets.is_empty() { CleanTargets::all() } else { targets };
+
+    let files = find_files(claude_dir, targets);
+    if files.is_empty() {
+        writeln!(writer, "Nothing to clean.")?;
+        return Ok(());
+    }
+
+    if dry_run {
+        writeln!(writer, "Would delete:")?;
+        for path in &files {
+            writeln!(writer, "  {}", path.display())?;
+        }
+        writeln!(writer, "Re-run with --confirm to delete these files.")?;
+        return Ok(());
+    }
+
+    for path in &files {
+        fs::remove_file(path).with_context(|| format!("Failed to delete {}", path.display()))?;
+        writeln!(writer, "Deleted {}", path.display())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup() -> (TempDir, String) {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().to_string_lossy().to_string();
+        let snapshots = state_dir(&dir).join(SNAPSHOTS_DIR);
+        fs::create_dir_all(&snapshots).unwrap();
+        fs::write(state_dir(&dir).join(TRACKER_STATE_FILE), "{}").unwrap();
+        fs::write(snapshots.join("2025-06-01.json"), "{}").unwrap();
+        (temp_dir, dir)
+    }
+
+    #[test]
+    fn test_no_flags_is_a_dry_run() {
+        let (_temp_dir, dir) = setup();
+        let mut output = Vec::new();
+        let targets = CleanTargets {
+            state: false,
+            cache: false,
+            snapshots: false,
+        };
+        run_clean(&dir, targets, false, &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Would delete:"));
+        assert!(output.contains(TRACKER_STATE_FILE));
+        assert_eq!(find_files(&dir, CleanTargets::all()).len(), 2);
+    }
+
+    #[test]
+    fn test_state_flag_deletes_only_state() {
+        let (_temp_dir, dir) = setup();
+        let targets = CleanTargets {
+            state: true,
+            cache: false,
+            snapshots: false,
+        };
+        run_clean(&dir, targets, false, &mut Vec::new()).unwrap();
+
+        let remaining = find_files(&dir, CleanTargets::all());
+        assert_eq!(remaining.len(), 1);
+        assert!(remaining[0].ends_with("2025-06-01.json"));
+    }
+}
@@ -1,5 +1,10 @@
 use chrono::NaiveDate;
 use clap::{Parser, Subcommand, ValueEnum};
+use crate::currency::{parse_currency_code, DEFAULT_EXCHANGE_RATE_URL};
+use crate::dashboard::app::{DEFAULT_FEED_SIZE, DEFAULT_MIN_REFRESH_INTERVAL_MS};
+use crate::pricing::{DEFAULT_BATCH_DISCOUNT, DEFAULT_PRICING_TIMEOUT_SECS};
+use crate::statusline::DEFAULT_STATUSLINE_FORMAT;
+use std::path::PathBuf;
 
 fn parse_refresh_rate(s: &str) -> Result<f64, String> {
     s.parse::<f64>()
@@ -13,6 +18,42 @@ fn parse_refresh_rate(s: &str) -> Result<f64, String> {
         })
 }
 
+fn parse_watch_interval(s: &str) -> Result<f64, String> {
+    s.parse::<f64>()
+        .map_err(|_| "Invalid watch interval".to_string())
+        .and_then(|v| {
+            if v > 0.0 && v <= 3600.0 {
+                Ok(v)
+            } else {
+                Err("Watch interval must be between 0 and 3600 seconds".to_string())
+            }
+        })
+}
+
+fn parse_batch_discount(s: &str) -> Result<f64, String> {
+    s.parse::<f64>()
+        .map_err(|_| "Invalid batch discount".to_string())
+        .and_then(|v| {
+            if (0.0..=1.0).contains(&v) {
+                Ok(v)
+            } else {
+                Err("Batch discount must be between 0 and 1".to_string())
+            }
+        })
+}
+
+fn parse_exchange_rate(s: &str) -> Result<f64, String> {
+    s.parse::<f64>()
+        .map_err(|_| "Invalid exchange rate".to_string())
+        .and_then(|v| {
+            if v > 0.0 && v.is_finite() {
+                Ok(v)
+            } else {
+                Err("Exchange rate must be positive".to_string())
+            }
+        })
+}
+
 #[derive(Parser, Debug)]
 #[command(name = "claude-usage")]
 #[command(about = "Analyze Claude Code usage and costs from local logs")]
@@ -23,29 +64,201 @@ pub struct Cli {
 }
 
 #[derive(Subcommand, Debug)]
+#[allow(clippy::large_enum_variant)] // Parsed once at startup
 pub enum Commands {
     /// Show usage statistics (default)
     #[command(visible_alias = "stats")]
     Show(Args),
     
+    /// List recognized models and their pricing
+    Models {
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: OutputFormat,
+    },
+
+    /// Check a Claude directory's logs and explain why usage might be missing
+    Validate {
+        /// Path to the Claude logs directory
+        #[arg(long = "claude-dir", default_value = "~/.claude")]
+        claude_dir: String,
+
+        /// Show the breakdown for every file
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Run a checklist of common setup problems and suggest fixes
+    Doctor {
+        /// Path to the Claude logs directory
+        #[arg(long = "claude-dir", default_value = "~/.claude")]
+        claude_dir: String,
+    },
+
+    /// Delete saved state; lists what would go unless a kind is named or --confirm is given
+    Clean {
+        /// Path to the Claude logs directory
+        #[arg(long = "claude-dir", default_value = "~/.claude")]
+        claude_dir: String,
+
+        /// Delete the incremental parser's file-tracker state
+        #[arg(long)]
+        state: bool,
+
+        /// Delete the parsed-entry cache
+        #[arg(long)]
+        cache: bool,
+
+        /// Delete saved dashboard snapshots
+        #[arg(long)]
+        snapshots: bool,
+
+        /// Delete everything listed when no kind is named
+        #[arg(long)]
+        confirm: bool,
+    },
+
+    /// Print each new request as it's logged, like `tail -f`
+    Follow {
+        /// Only show requests for this model (e.g. "opus")
+        #[arg(short, long)]
+        model: Option<String>,
+
+        /// Path to Claude logs directory (repeat to aggregate several accounts)
+        #[arg(long = "claude-dir", default_value = "~/.claude")]
+        claude_dirs: Vec<String>,
+
+        /// How many directory levels below projects/ to search for log files
+        #[arg(long, default_value = "3")]
+        max_depth: usize,
+    },
+
+    /// Show when you spend most: cost by hour of day and by day of week
+    Patterns {
+        /// Start date for analysis (YYYY-MM-DD)
+        #[arg(short, long)]
+        start_date: Option<NaiveDate>,
+
+        /// End date for analysis (YYYY-MM-DD)
+        #[arg(short, long)]
+        end_date: Option<NaiveDate>,
+
+        /// Bucket by UTC instead of the local clock
+        #[arg(long)]
+        utc: bool,
+
+        /// Path to Claude logs directory (repeat to aggregate several accounts)
+        #[arg(long = "claude-dir", default_value = "~/.claude")]
+        claude_dirs: Vec<String>,
+    },
+
+    /// Show the span of available data: earliest and latest entries, files, projects and
+    /// sessions
+    #[command(visible_alias = "info")]
+    Range {
+        /// Path to Claude logs directory (repeat to aggregate several accounts)
+        #[arg(long = "claude-dir", default_value = "~/.claude")]
+        claude_dirs: Vec<String>,
+
+        /// How many directory levels below projects/ to search for log files
+        #[arg(long, default_value = "3")]
+        max_depth: usize,
+    },
+
+    /// Print today's and this week's cost on one line, for tmux or shell status bars
+    Statusline {
+        /// Output line; {today}, {week} and {month} are replaced with dollar amounts
+        #[arg(long, default_value = DEFAULT_STATUSLINE_FORMAT)]
+        format_string: String,
+
+        /// Path to Claude logs directory (repeat to aggregate several accounts)
+        #[arg(long = "claude-dir", default_value = "~/.claude")]
+        claude_dirs: Vec<String>,
+
+        /// How many directory levels below projects/ to search for log files
+        #[arg(long, default_value = "3")]
+        max_depth: usize,
+    },
+
     /// Launch interactive dashboard
     #[command(visible_aliases = &["dash", "d"])]
     Dashboard {
-        /// Refresh interval in seconds (supports decimals, e.g. 0.5)
+        /// Display refresh interval in seconds (supports decimals, e.g. 0.5). This is how
+        /// often the screen redraws; log files are re-read at most every
+        /// --min-refresh-interval-ms
         #[arg(short, long, default_value = "0.5", value_parser = parse_refresh_rate)]
         refresh: f64,
         
-        /// Initial time range in hours
-        #[arg(long, default_value = "1")]
-        hours: usize,
+        /// Refresh when log files change, and at least this often (seconds) otherwise
+        #[arg(long, default_value = "30", value_parser = parse_watch_interval)]
+        watch_interval: f64,
+
+        /// Data refresh floor: re-read log files at most this often (milliseconds), however
+        /// fast --refresh redraws
+        #[arg(long, default_value_t = DEFAULT_MIN_REFRESH_INTERVAL_MS)]
+        min_refresh_interval_ms: u64,
+
+        /// Number of recent requests kept in the live feed
+        #[arg(long, default_value_t = DEFAULT_FEED_SIZE)]
+        feed_size: usize,
+
+        /// Initial time range in hours (defaults to the last used range, or 1)
+        #[arg(long)]
+        hours: Option<usize>,
         
-        /// Initial model filter
+        /// Initial model filter (defaults to the last used filter)
         #[arg(short, long)]
         model: Option<String>,
         
-        /// Path to Claude logs directory
-        #[arg(long, default_value = "~/.claude")]
+        /// Path to Claude logs directory (repeat to aggregate several accounts)
+        #[arg(long = "claude-dir", default_value = "~/.claude")]
+        claude_dirs: Vec<String>,
+
+        /// Verify file contents with CRC32 checksums (for filesystems with unreliable mtime)
+        #[arg(long)]
+        checksums: bool,
+
+        /// How many directory levels below projects/ to search for log files
+        #[arg(long, default_value = "3")]
+        max_depth: usize,
+
+        /// Warn if log files exist deeper than --max-depth, where they are skipped
+        #[arg(long)]
+        discover_max_depth: bool,
+
+        /// Print the current hour, 5h, 24h, 2d and 7d stats as JSON and exit instead of
+        /// starting the dashboard
+        #[arg(long, alias = "json")]
+        snapshot: bool,
+
+        /// Load the data the dashboard would show, print its stats to stderr and exit
+        /// without starting the dashboard
+        #[arg(long, conflicts_with = "snapshot")]
+        dry_run: bool,
+    },
+
+    /// Write one row per request, with every token type, to a file for safekeeping or a
+    /// spreadsheet
+    Export {
+        /// File format (json, ndjson, csv or markdown)
+        #[arg(short, long, value_enum, default_value = "csv")]
+        format: OutputFormat,
+
+        /// File to write; replaced if it exists
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Path to the Claude logs directory
+        #[arg(long = "claude-dir", default_value = "~/.claude")]
         claude_dir: String,
+
+        /// Start date (YYYY-MM-DD)
+        #[arg(short, long)]
+        start_date: Option<NaiveDate>,
+
+        /// End date (YYYY-MM-DD)
+        #[arg(short, long)]
+        end_date: Option<NaiveDate>,
     },
 }
 
@@ -59,49 +272,327 @@ pub struct Args {
     #[arg(short, long)]
     pub end_date: Option<NaiveDate>,
 
-    /// Group results by
-    #[arg(short, long, value_enum, default_value = "day")]
-    pub group_by: GroupBy,
+    /// Group results by one or more comma-separated dimensions (e.g. model,week)
+    #[arg(short, long, value_enum, value_delimiter = ',', default_value = "day")]
+    pub group_by: Vec<GroupBy>,
 
     /// Filter by model name
     #[arg(short, long)]
     pub model: Option<String>,
 
+    /// Exclude models matching this name (repeatable; wins over --model)
+    #[arg(long = "exclude-model")]
+    pub exclude_model: Vec<String>,
+
+    /// Count synthetic entries as a zero-cost "Synthetic" model instead of skipping them
+    #[arg(long)]
+    pub include_synthetic: bool,
+
+    /// Also count sidechain entries (tool-use sub-conversations), which are left out by default
+    #[arg(long)]
+    pub include_sidechains: bool,
+
+    /// Count only sidechain entries
+    #[arg(long, conflicts_with = "include_sidechains")]
+    pub only_sidechains: bool,
+
+    /// Count tool results that were logged as assistant messages, which are skipped by
+    /// default since their tokens are already billed as input to the next request
+    #[arg(long)]
+    pub include_tool_results: bool,
+
+    /// Field that identifies copies of one request when deduplicating
+    #[arg(long, value_enum, default_value = "request-id")]
+    pub dedup_key: DedupKey,
+
     /// Output format
     #[arg(short, long, value_enum, default_value = "table")]
     pub format: OutputFormat,
 
+    /// Also price every request as if it had used this model (e.g. claude-sonnet-4-20250514)
+    #[arg(long, value_name = "MODEL")]
+    pub reprice_as: Option<String>,
+
+    /// With --format json, print compact JSON without whitespace
+    #[arg(long)]
+    pub json_compact: bool,
+
+    /// With --format csv, separate fields with this character instead of a comma
+    #[arg(long, value_name = "CHAR")]
+    pub delimiter: Option<char>,
+
+    /// With --format csv, separate fields with tabs
+    #[arg(long, conflicts_with = "delimiter")]
+    pub tsv: bool,
+
     /// Show detailed token breakdown
     #[arg(short, long)]
     pub detailed: bool,
 
-    /// Path to Claude logs directory
-    #[arg(long, default_value = "~/.claude")]
-    pub claude_dir: String,
+    /// Show only these comma-separated columns, in this order (table, CSV and JSON)
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        conflicts_with_all = ["detailed", "running_total", "percentages", "sparkline"]
+    )]
+    pub output_fields: Option<Vec<OutputField>>,
+
+    /// With --group-by model, add peak day cost, average daily cost and active days (shown with --detailed)
+    #[arg(long)]
+    pub with_daily_stats: bool,
+
+    /// With --group-by day, list each model's tokens and cost under its day, then the
+    /// day's total (table and JSON)
+    #[arg(long)]
+    pub breakdown: bool,
+
+    /// Split cache write costs into 5-minute and 1-hour cache writes (shown with --detailed)
+    #[arg(long, conflicts_with_all = ["aggregate_cache", "output_fields"])]
+    pub cache_detail: bool,
+
+    /// Path to Claude logs directory (repeat to aggregate several accounts)
+    #[arg(long = "claude-dir", default_value = "~/.claude")]
+    pub claude_dirs: Vec<String>,
+
+    /// How many directory levels below projects/ to search for log files
+    #[arg(long, default_value = "3")]
+    pub max_depth: usize,
+
+    /// Warn if log files exist deeper than --max-depth, where they are skipped
+    #[arg(long)]
+    pub discover_max_depth: bool,
+
+    /// Parse only N randomly sampled files and scale totals up (fast rough estimate)
+    #[arg(long, value_name = "N")]
+    pub sample: Option<usize>,
+
+    /// Seed for --sample, for reproducible estimates
+    #[arg(long, requires = "sample")]
+    pub seed: Option<u64>,
+
+    /// Only count the N most recent requests, whatever their date
+    /// (e.g. `claude-usage show --recent 20 --group-by session --format table`)
+    #[arg(long, value_name = "N", conflicts_with_all = ["start_date", "end_date", "sample"])]
+    pub recent: Option<usize>,
+
+    /// Aggregate while parsing instead of loading every entry first (lower memory on large logs)
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "reprice_as",
+            "velocity",
+            "gaps",
+            "calendar",
+            "anomalies",
+            "warn_context",
+            "with_daily_stats",
+            "cache_detail",
+            "profile",
+            "recent",
+        ]
+    )]
+    pub streaming: bool,
+
+    /// Print only the total cost as a bare number, for scripts (honors date and model filters)
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "format",
+            "detailed",
+            "summary",
+            "velocity",
+            "gaps",
+            "calendar",
+            "anomalies",
+            "warn_context",
+            "profile",
+            "recent",
+        ]
+    )]
+    pub total_only: bool,
+
+    /// Price multiplier for batch and background requests (0.5 = half price, 1 = no discount)
+    #[arg(
+        long,
+        value_name = "FLOAT",
+        default_value_t = DEFAULT_BATCH_DISCOUNT,
+        value_parser = parse_batch_discount
+    )]
+    pub batch_discount: f64,
 
     /// Refresh pricing information from Anthropic API
     #[arg(long)]
     pub refresh_pricing: bool,
 
-    /// Show summary statistics
+    /// Give up refreshing pricing (--refresh-pricing) or fetching the exchange rate
+    /// (--currency) after this many seconds, retries included, and fall back to the
+    /// built-in prices or USD
+    #[arg(long, default_value_t = DEFAULT_PRICING_TIMEOUT_SECS)]
+    pub pricing_timeout: f64,
+
+    /// Show every unrecognized model as one "unknown" row (each is still priced on its own)
+    #[arg(long)]
+    pub aggregate_unknown_models: bool,
+
+    /// Check GitHub for a newer claude-usage release (1 second timeout, silent if offline)
+    #[arg(long)]
+    pub version_check: bool,
+
+    /// Show costs in this currency (a three-letter code such as EUR), converted from USD
+    #[arg(long, value_name = "CODE", default_value = "USD", value_parser = parse_currency_code)]
+    pub currency: String,
+
+    /// Units of --currency per US dollar; fetched from --exchange-rate-url when not given
+    #[arg(long, value_name = "RATE", value_parser = parse_exchange_rate)]
+    pub exchange_rate: Option<f64>,
+
+    /// Where to fetch the exchange rate, a JSON response with a "rates" object keyed by code
+    #[arg(long, value_name = "URL", default_value = DEFAULT_EXCHANGE_RATE_URL)]
+    pub exchange_rate_url: String,
+
+    /// Show summary statistics (with --format json the output becomes {"stats", "summary"})
     #[arg(long)]
     pub summary: bool,
+
+    /// Add each row's share of total cost and tokens
+    #[arg(long)]
+    pub percentages: bool,
+
+    /// Add a cumulative cost column (running_total_usd in CSV/JSON)
+    #[arg(long)]
+    pub running_total: bool,
+
+    /// Show cache write/read tokens as part of input (cost still uses per-tier rates)
+    #[arg(long)]
+    pub aggregate_cache: bool,
+
+    /// Add a Cost Trend sparkline column to table output
+    #[arg(long)]
+    pub sparkline: bool,
+
+    /// Add a column to table output showing how each row's cost is calculated: tokens times
+    /// the model's rates
+    #[arg(long)]
+    pub explain: bool,
+
+    /// Show tokens per minute in 15-minute windows instead of the usual report
+    #[arg(long)]
+    pub velocity: bool,
+
+    /// Show idle time between consecutive requests per session instead of the usual report
+    #[arg(long)]
+    pub gaps: bool,
+
+    /// Show daily spend as a month calendar instead of the usual report
+    #[arg(long)]
+    pub calendar: bool,
+
+    /// Number of months to show in the calendar, ending with the latest month
+    #[arg(long, default_value = "1", requires = "calendar", value_parser = clap::value_parser!(u32).range(1..))]
+    pub months: u32,
+
+    /// Project daily cost this many days ahead from the recent trend (needs --group-by day)
+    #[arg(long, value_name = "DAYS")]
+    pub forecast: Option<usize>,
+
+    /// List unusually expensive days after the main output (needs --group-by day)
+    #[arg(long)]
+    pub anomalies: bool,
+
+    /// Standard deviations above the rolling 7-day mean that count as an anomaly
+    #[arg(long, default_value = "2.5", requires = "anomalies")]
+    pub sigma: f64,
+
+    /// Standard deviations above the whole period's mean cost that count as an anomaly
+    #[arg(long, default_value = "2.0", requires = "anomalies")]
+    pub anomaly_sigma: f64,
+
+    /// Warn about sessions whose input tokens exceed this many (context window pressure)
+    #[arg(long, value_name = "N")]
+    pub warn_context: Option<u64>,
+
+    /// Print how long each parse phase took to stderr (as JSON with --format json/ndjson)
+    #[arg(long)]
+    pub profile: bool,
+
+    /// Find and parse the logs, then print which files, dates and how many entries would be
+    /// analyzed instead of the report
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Suppress progress output, printing only the results
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// Disable colored output (also honors the NO_COLOR environment variable)
+    #[arg(long)]
+    pub no_color: bool,
 }
 
-#[derive(Debug, Clone, ValueEnum)]
+#[derive(Debug, Clone, ValueEnum, PartialEq)]
 pub enum GroupBy {
     Day,
     Week,
     Month,
+    Quarter,
+    Year,
     Model,
+    /// Same as model,day
     ModelDay,
+    /// One row per conversation session
+    Session,
+    /// One row per request, unaggregated (large; best with --format csv or ndjson)
+    Request,
     None,
 }
 
+/// A column selectable with `--output-fields`
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq)]
+pub enum OutputField {
+    Date,
+    Model,
+    Requests,
+    #[value(alias = "input")]
+    InputTokens,
+    #[value(alias = "output")]
+    OutputTokens,
+    #[value(alias = "cache-write")]
+    CacheWriteTokens,
+    #[value(alias = "cache-read")]
+    CacheReadTokens,
+    #[value(alias = "tokens")]
+    TotalTokens,
+    #[value(alias = "cost")]
+    CostUsd,
+    /// Only for rows of a single priced model
+    #[value(alias = "cache-savings")]
+    CacheSavingsUsd,
+    #[value(alias = "cache-hit-rate")]
+    CacheHitRate,
+    RunningTotal,
+}
+
+/// Which field makes two log lines the same request. The request id is authoritative:
+/// a streamed response is logged over several lines with different uuids but one request
+/// id, so deduplicating by uuid counts it once per line.
+#[derive(Debug, Clone, Copy, Default, ValueEnum, PartialEq, Eq)]
+pub enum DedupKey {
+    /// The API request id; the latest line of each request is kept
+    #[default]
+    RequestId,
+    /// Each log line's own uuid, merging only exact copies of a line
+    Uuid,
+}
+
 #[derive(Debug, Clone, ValueEnum, PartialEq)]
 pub enum OutputFormat {
     Table,
     Json,
     Csv,
     Markdown,
+    /// One compact JSON object per line (JSON Lines)
+    Ndjson,
+    /// Plain-text bar chart of cost per row
+    AsciiChart,
 }
\ No newline at end of file
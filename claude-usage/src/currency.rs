@@ -0,0 +1,146 @@
+use crate::pricing::{fetch_with_retry, RetryPolicy};
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Where `--currency` looks up the USD exchange rate when `--exchange-rate` isn't given
+pub const DEFAULT_EXCHANGE_RATE_URL: &str = "https://api.frankfurter.app/latest?from=USD";
+
+/// Currency costs are displayed in. Costs are always computed in USD; this only converts
+/// them when they're formatted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Currency {
+    /// ISO 4217 code, e.g. `EUR`
+    pub code: String,
+    /// Units of this currency per US dollar
+    pub rate: f64,
+}
+
+impl Default for Currency {
+    fn default() -> Self {
+        Self::usd()
+    }
+}
+
+impl Currency {
+    pub fn usd() -> Self {
+        Self::new("USD", 1.0)
+    }
+
+    pub fn new(code: &str, rate: f64) -> Self {
+        Self {
+            code: code.to_ascii_uppercase(),
+            rate,
+        }
+    }
+
+    /// Prefix for amounts: the usual sign where there's one, otherwise the code
+    pub fn symbol(&self) -> String {
+        match self.code.as_str() {
+            "USD" => "$".to_string(),
+            "EUR" => "€".to_string(),
+            "GBP" => "£".to_string(),
+            "JPY" | "CNY" => "¥".to_string(),
+            "INR" => "₹".to_string(),
+            "KRW" => "₩".to_string(),
+            code => format!("{} ", code),
+        }
+    }
+
+    /// `usd` converted to this currency
+    pub fn convert(&self, usd: f64) -> f64 {
+        usd * self.rate
+    }
+
+    /// `usd` converted and written with the currency symbol and two decimals
+    pub fn format(&self, usd: f64) -> String {
+        format!("{}{:.2}", self.symbol(), self.convert(usd))
+    }
+
+    /// `format` for an optional cost, empty when there's none
+    pub fn format_optional(&self, usd: Option<f64>) -> String {
+        usd.map(|cost| self.format(cost)).unwrap_or_default()
+    }
+
+    /// Lowercase code for machine-readable column names such as `running_total_eur`
+    pub fn key(&self) -> String {
+        self.code.to_ascii_lowercase()
+    }
+}
+
+/// Validate a `--currency` code: three ASCII letters
+pub fn parse_currency_code(s: &str) -> Result<String, String> {
+    if s.len() == 3 && s.chars().all(|c| c.is_ascii_alphabetic()) {
+        Ok(s.to_ascii_uppercase())
+    } else {
+        Err("Currency must be a three-letter code such as EUR".to_string())
+    }
+}
+
+#[derive(Deserialize)]
+struct ExchangeRates {
+    rates: HashMap<String, f64>,
+}
+
+/// Pull `code`'s rate out of a `{"rates": {"EUR": 0.92, ...}}` response
+fn parse_exchange_rate(body: &str, code: &str) -> Result<f64> {
+    let response: ExchangeRates = serde_json::from_str(body)?;
+    response
+        .rates
+        .get(code)
+        .copied()
+        .filter(|rate| *rate > 0.0)
+        .ok_or_else(|| anyhow!("No exchange rate for {}", code))
+}
+
+/// Display currency for `code`: at `rate` when given, otherwise at the rate fetched from
+/// `url`. Fetching can fail (offline, unknown code); costs are then shown in USD.
+pub async fn resolve_currency(
+    code: &str,
+    rate: Option<f64>,
+    url: &str,
+    timeout: Duration,
+) -> Currency {
+    if let Some(rate) = rate {
+        return Currency::new(code, rate);
+    }
+    if code.eq_ignore_ascii_case("USD") {
+        return Currency::usd();
+    }
+    let policy = RetryPolicy::with_timeout(timeout);
+    match fetch_with_retry(url, &policy)
+        .await
+        .and_then(|body| parse_exchange_rate(&body, &code.to_ascii_uppercase()))
+    {
+        Ok(rate) => Currency::new(code, rate),
+        Err(e) => {
+            eprintln!("Warning: Failed to fetch the {} exchange rate, showing USD: {}", code, e);
+            Currency::usd()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_converts() {
+        let eur = Currency::new("eur", 0.9);
+        assert_eq!(eur.code, "EUR");
+        assert_eq!(eur.format(10.0), "€9.00");
+        assert_eq!(eur.format_optional(None), "");
+        assert_eq!(Currency::new("CHF", 0.8).format(1.0), "CHF 0.80");
+        assert_eq!(Currency::usd().format(1.234), "$1.23");
+    }
+
+    #[test]
+    fn test_parse_exchange_rate() {
+        let body = r#"{"amount":1.0,"base":"USD","rates":{"EUR":0.92,"GBP":0.79}}"#;
+        assert_eq!(parse_exchange_rate(body, "GBP").unwrap(), 0.79);
+        assert!(parse_exchange_rate(body, "XYZ").is_err());
+        assert_eq!(parse_currency_code("eur"), Ok("EUR".to_string()));
+        assert!(parse_currency_code("euro").is_err());
+    }
+}
@@ -1,35 +1,80 @@
 use crate::file_tracker::FileTracker;
+use crate::file_watcher::CrossPlatformWatcher;
 use crate::incremental_parser::IncrementalParsing;
 use crate::model_name::ModelName;
-use crate::parser::LogParser;
+use crate::parser::{dedup_key, LogParser};
 use crate::pricing::get_default_pricing;
-use anyhow::Result;
-use chrono::{DateTime, Duration, Utc};
-use std::collections::{HashSet, VecDeque};
-use std::path::PathBuf;
-use uuid::Uuid;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Local, NaiveDateTime, TimeZone, Utc};
+use ratatui::layout::Rect;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
-use super::data::{RequestInfo, RollingWindow};
+use super::data::{RequestInfo, RollingWindow, TimeRangeStats};
 
-#[derive(Debug, Clone, PartialEq)]
+/// Incremental parsing state kept by the dashboard inside `state_dir`
+pub const TRACKER_STATE_FILE: &str = "dashboard-file-tracker.json";
+
+/// Where the dashboard keeps its state for a Claude directory
+pub fn state_dir(claude_dir: &str) -> PathBuf {
+    PathBuf::from(shellexpand::tilde(claude_dir).as_ref()).join(".claude-usage")
+}
+
+/// A gap between ticks longer than this means the process was suspended (e.g. laptop
+/// sleep), so the next refresh is treated as a catch-up. Ticks are at most 60s apart.
+const CATCH_UP_GAP_SECONDS: i64 = 180;
+
+/// Default floor between log re-reads, so very fast display refresh rates don't turn into
+/// constant disk reads
+pub const DEFAULT_MIN_REFRESH_INTERVAL_MS: u64 = 250;
+
+/// Default number of requests kept in the live feed
+pub const DEFAULT_FEED_SIZE: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ModelFilter {
     All,
     Specific(ModelName),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum TimeRange {
     OneHour,
     TwoHours,
     SixHours,
     TwelveHours,
     TwentyFourHours,
+    /// Arbitrary number of hours entered with the `g` key
+    Custom(usize),
+    /// Fixed span entered with the `/` key, for looking back at a past period
+    Between { start: DateTime<Utc>, end: DateTime<Utc> },
+}
+
+/// Longest custom range, limited by the 7 days of data the rolling window retains
+pub const MAX_CUSTOM_HOURS: usize = 168;
+
+/// Format of each timestamp typed into the `/` date range prompt, in local time
+pub const DATE_RANGE_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
+/// Parse "start end", two local `DATE_RANGE_FORMAT` timestamps separated by whitespace
+pub fn parse_date_range(input: &str) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let parse = |text: &str| {
+        let naive = NaiveDateTime::parse_from_str(text, DATE_RANGE_FORMAT).ok()?;
+        Some(Local.from_local_datetime(&naive).earliest()?.with_timezone(&Utc))
+    };
+    let mut parts = input.split_whitespace();
+    let (start, end) = (parse(parts.next()?)?, parse(parts.next()?)?);
+    parts.next().is_none().then_some((start, end))
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum ChartType {
     Bar,
     Line,
+    /// Input, output and cache tokens per bucket instead of cost
+    Tokens,
 }
 
 impl TimeRange {
@@ -40,6 +85,26 @@ impl TimeRange {
             TimeRange::SixHours => 360,
             TimeRange::TwelveHours => 720,
             TimeRange::TwentyFourHours => 1440,
+            TimeRange::Custom(hours) => hours * 60,
+            TimeRange::Between { start, end } => (*end - *start).num_minutes().max(1) as usize,
+        }
+    }
+
+    /// When the chart's rightmost bucket ends: `now`, or the end of a fixed span
+    pub fn chart_end(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            TimeRange::Between { end, .. } => *end,
+            _ => now,
+        }
+    }
+
+    /// Rolling window bucket width for this range, coarser for long ranges so a week of
+    /// history doesn't need a bucket per minute
+    pub fn bucket_minutes(&self) -> usize {
+        match self.minutes() {
+            0..=60 => 1,
+            61..=360 => 5,
+            _ => 15,
         }
     }
 
@@ -49,101 +114,381 @@ impl TimeRange {
             TimeRange::TwoHours => TimeRange::SixHours,
             TimeRange::SixHours => TimeRange::TwelveHours,
             TimeRange::TwelveHours => TimeRange::TwentyFourHours,
-            TimeRange::TwentyFourHours => TimeRange::OneHour,
+            TimeRange::TwentyFourHours | TimeRange::Custom(_) | TimeRange::Between { .. } => {
+                TimeRange::OneHour
+            }
+        }
+    }
+
+    /// Map an hour count onto the preset ranges, falling back to a custom range
+    pub fn from_hours(hours: usize) -> Self {
+        match hours {
+            1 => TimeRange::OneHour,
+            2 => TimeRange::TwoHours,
+            6 => TimeRange::SixHours,
+            12 => TimeRange::TwelveHours,
+            24 => TimeRange::TwentyFourHours,
+            h if h > 0 && h <= MAX_CUSTOM_HOURS => TimeRange::Custom(h),
+            _ => TimeRange::OneHour,
+        }
+    }
+}
+
+/// UI choices remembered between dashboard runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DashboardPrefs {
+    pub chart_type: ChartType,
+    pub model_filter: ModelFilter,
+    pub time_range: TimeRange,
+}
+
+impl Default for DashboardPrefs {
+    fn default() -> Self {
+        Self {
+            chart_type: ChartType::Bar,
+            model_filter: ModelFilter::All,
+            time_range: TimeRange::OneHour,
+        }
+    }
+}
+
+impl DashboardPrefs {
+    /// Load saved preferences, or the defaults if none were saved yet
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let json = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Spending limits that turn the cost projections red when exceeded, read from
+/// `~/.claude-usage/budgets.toml`:
+///
+/// ```toml
+/// daily = 20.0
+/// monthly = 400.0
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct BudgetConfig {
+    pub daily: Option<f64>,
+    pub monthly: Option<f64>,
+}
+
+impl BudgetConfig {
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::home_dir().map(|home| home.join(".claude-usage").join("budgets.toml"))
+    }
+
+    /// Load budgets from `path`, or `None` if the file is missing
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        let Ok(contents) = std::fs::read_to_string(path) else {
+            return Ok(None);
+        };
+        let budget = toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        Ok(Some(budget))
+    }
+}
+
+/// Point-in-time view of the dashboard's data, independent of rendering
+#[derive(Debug, Clone, Serialize)]
+pub struct DashboardSnapshot {
+    pub last_update: DateTime<Utc>,
+    pub time_range_minutes: usize,
+    pub total_cost_1h: f64,
+    pub total_cost_5h: f64,
+    pub total_cost_24h: f64,
+    pub total_cost_7d: f64,
+    pub request_count_1h: u32,
+    pub request_count_24h: u32,
+    pub top_models_24h: Vec<(ModelName, f64)>,
+    pub feed_len: usize,
+}
+
+/// The stats panel's time ranges, printed by `dashboard --snapshot`
+#[derive(Debug, Clone, Serialize)]
+pub struct RangeStatsSnapshot {
+    pub last_update: DateTime<Utc>,
+    /// Model every range is filtered to, `None` for all models
+    pub model_filter: Option<String>,
+    pub current_hour: TimeRangeStats,
+    pub last_5h: TimeRangeStats,
+    pub last_24h: TimeRangeStats,
+    pub last_2d: TimeRangeStats,
+    pub last_7d: TimeRangeStats,
+}
+
+/// Widget areas computed during the last `terminal.draw()`, kept so mouse events can be
+/// hit-tested against what is on screen
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DashboardLayout {
+    /// The request feed pane, borders included
+    pub feed: Option<Rect>,
+    /// The "Model: ... ▼" label in the header
+    pub model_label: Option<Rect>,
+}
+
+impl DashboardLayout {
+    /// Check whether a terminal cell lies inside the request feed pane
+    pub fn is_over_feed(&self, column: u16, row: u16) -> bool {
+        self.feed.is_some_and(|area| contains(area, column, row))
+    }
+
+    pub fn is_over_model_label(&self, column: u16, row: u16) -> bool {
+        self.model_label.is_some_and(|area| contains(area, column, row))
+    }
+
+    /// Feed index shown at a clicked cell, given the current scroll offset; `None` on the
+    /// borders or outside the feed
+    pub fn feed_item_at(&self, column: u16, row: u16, feed_scroll: usize) -> Option<usize> {
+        let area = self.feed?;
+        let inner_top = area.y + 1;
+        let inner_bottom = (area.y + area.height).saturating_sub(1);
+        if !contains(area, column, row) || row < inner_top || row >= inner_bottom {
+            return None;
         }
+        Some(feed_scroll + (row - inner_top) as usize)
     }
 }
 
+fn contains(area: Rect, column: u16, row: u16) -> bool {
+    column >= area.x && column < area.x + area.width && row >= area.y && row < area.y + area.height
+}
+
 pub struct App {
-    // CLAUDETODO: Consider using Arc<String> or PathBuf for claude_dir to avoid cloning on every refresh
-    pub claude_dir: String,
+    // CLAUDETODO: Consider using Arc<String> or PathBuf for claude_dirs to avoid cloning on every refresh
+    pub claude_dirs: Vec<String>,
+    pub max_depth: usize,
     pub model_filter: ModelFilter,
+    /// Models with requests in the rolling window, in the order `m` cycles through them
+    pub available_models: Vec<ModelName>,
     pub time_range: TimeRange,
     pub chart_type: ChartType,
     pub rolling_window: RollingWindow,
     // CLAUDETODO: VecDeque might not be optimal for a feed that's mostly push_front/pop_back.
     // Consider using a ring buffer or a simple Vec with reverse iteration
     pub request_feed: VecDeque<RequestInfo>,
+    /// Most requests kept in `request_feed`; older ones drop off the bottom
+    pub feed_size: usize,
     pub feed_scroll: usize,
     pub feed_paused: bool,
+    /// Index into the visible feed of the row last clicked
+    pub selected_feed_item: Option<usize>,
+    /// Session id prefix the feed and session stats are limited to
+    pub selected_session: Option<String>,
+    /// Prefix typed so far in session filter mode, `None` when not entering one
+    pub session_input: Option<String>,
+    /// Widget areas from the last draw, used for mouse hit-testing
+    pub layout: DashboardLayout,
+    /// Digits typed so far in "go to range" mode, `None` when not entering a range
+    pub range_input: Option<String>,
+    /// Start and end being typed after pressing `/`, if in that mode
+    pub date_range_input: Option<String>,
+    /// Whether the keybinding help overlay is open
+    pub show_help: bool,
     pub last_update: DateTime<Utc>,
     pub refresh_rate: f64,
+    /// When file watching is active, refresh on file changes and at least this often
+    watcher: Option<(CrossPlatformWatcher, Duration)>,
+    /// Log files are re-read at most this often, however fast the display refreshes
+    min_refresh_interval: Duration,
+    /// A refresh came due before `min_refresh_interval` had passed and is still owed
+    refresh_pending: bool,
+    /// Wall-clock time of the previous tick, used to notice suspends
+    last_tick: DateTime<Utc>,
+    /// Set after a long gap between ticks; the backfill runs on the following tick so the
+    /// "catching up" notice is drawn first
+    pub catching_up: bool,
+    /// Outcome of the last `e` export, shown in the header
+    pub export_status: Option<String>,
+    /// Problems met while starting up or refreshing, shown in the header since stderr is
+    /// hidden behind the dashboard; printed to stderr once it exits
+    pub warnings: Vec<String>,
+    /// Where chart type, model filter and time range are saved on exit
+    prefs_file: PathBuf,
+    pub budget: Option<BudgetConfig>,
     // CLAUDETODO: pricing_map is loaded once but never updated. If pricing rarely changes,
     // consider making it a global static or lazy_static to avoid storing in every App instance
     pub pricing_map: crate::models::PricingMap,
-    // Using HashSet<Uuid> instead of HashSet<String> for ~56% memory savings
-    seen_request_ids: HashSet<Uuid>,
+    /// Requests already added, by `dedup_key`, with the logged timestamp of the copy that
+    /// was counted. A request read again on a later refresh isn't counted twice, and a later
+    /// copy replaces the earlier one, as in `show`'s deduplication.
+    seen_requests: HashMap<String, (DateTime<Utc>, RequestInfo)>,
     _file_tracker: Option<FileTracker>,
     _use_incremental: bool,
 }
 
 impl App {
-    pub fn new(claude_dir: String, initial_hours: usize, refresh_rate: f64) -> Self {
-        let time_range = match initial_hours {
-            1 => TimeRange::OneHour,
-            2 => TimeRange::TwoHours,
-            6 => TimeRange::SixHours,
-            12 => TimeRange::TwelveHours,
-            24 => TimeRange::TwentyFourHours,
-            _ => TimeRange::OneHour,
-        };
-
-        // Initialize file tracker for incremental parsing
-        let state_dir = PathBuf::from(&claude_dir).join(".claude-usage");
+    /// `initial_hours` overrides the saved time range when given
+    pub fn new(claude_dirs: Vec<String>, initial_hours: Option<usize>, refresh_rate: f64, checksums: bool, max_depth: usize) -> Self {
+        // Initialize file tracker for incremental parsing (state lives in the first directory)
+        let primary_dir = claude_dirs.first().cloned().unwrap_or_else(|| "~/.claude".to_string());
+        let state_dir = state_dir(&primary_dir);
+        let mut warnings = Vec::new();
         // Create state directory if it doesn't exist
         if let Err(e) = std::fs::create_dir_all(&state_dir) {
-            eprintln!("Warning: Failed to create state directory: {}", e);
+            warnings.push(format!("Failed to create state directory: {}", e));
         }
-        let state_file = state_dir.join("dashboard-file-tracker.json");
-        let file_tracker = FileTracker::with_persistence(state_file);
+        let state_file = state_dir.join(TRACKER_STATE_FILE);
+        let file_tracker = FileTracker::with_persistence(state_file).with_checksums(checksums);
+
+        let prefs_file = state_dir.join("dashboard-prefs.json");
+        let prefs = DashboardPrefs::load(&prefs_file).unwrap_or_else(|e| {
+            warnings.push(format!("Failed to load dashboard preferences: {}", e));
+            DashboardPrefs::default()
+        });
+        let time_range = initial_hours.map_or(prefs.time_range, TimeRange::from_hours);
+        let budget = BudgetConfig::default_path().and_then(|path| {
+            BudgetConfig::load(&path).unwrap_or_else(|e| {
+                warnings.push(format!("{:#}", e));
+                None
+            })
+        });
         
         Self {
-            claude_dir,
-            model_filter: ModelFilter::All,
+            claude_dirs,
+            max_depth,
+            model_filter: prefs.model_filter,
+            available_models: Vec::new(),
             time_range,
-            chart_type: ChartType::Bar,
-            rolling_window: RollingWindow::new(time_range.minutes()),
-            request_feed: VecDeque::with_capacity(100),
+            chart_type: prefs.chart_type,
+            rolling_window: RollingWindow::new(time_range.minutes(), time_range.bucket_minutes()),
+            request_feed: VecDeque::with_capacity(DEFAULT_FEED_SIZE),
+            feed_size: DEFAULT_FEED_SIZE,
             feed_scroll: 0,
             feed_paused: false,
+            selected_feed_item: None,
+            selected_session: None,
+            session_input: None,
+            layout: DashboardLayout::default(),
+            range_input: None,
+            date_range_input: None,
+            show_help: false,
             last_update: Utc::now(),
             refresh_rate,
+            watcher: None,
+            min_refresh_interval: Duration::milliseconds(DEFAULT_MIN_REFRESH_INTERVAL_MS as i64),
+            refresh_pending: false,
+            last_tick: Utc::now(),
+            catching_up: false,
+            export_status: None,
+            warnings,
+            prefs_file,
+            budget,
             pricing_map: get_default_pricing(),
-            // CLAUDETODO: Consider pre-allocating HashSet capacity based on expected request count
-            // to reduce rehashing. E.g., HashSet::with_capacity(1000) for typical usage
-            seen_request_ids: HashSet::new(),
+            // CLAUDETODO: Consider pre-allocating HashMap capacity based on expected request count
+            // to reduce rehashing. E.g., HashMap::with_capacity(1000) for typical usage
+            seen_requests: HashMap::new(),
             _file_tracker: Some(file_tracker),
             _use_incremental: true, // Enable by default
         }
     }
 
+    /// Refresh when log files change instead of on every tick, falling back to a refresh
+    /// every `watch_interval`. If the watcher can't be started, every tick refreshes as before.
+    pub fn with_file_watcher(mut self, watch_interval: std::time::Duration) -> Self {
+        let projects_dirs: Vec<PathBuf> = self
+            .claude_dirs
+            .iter()
+            .map(|dir| PathBuf::from(shellexpand::tilde(dir).as_ref()).join("projects"))
+            .filter(|dir| dir.exists())
+            .collect();
+
+        match CrossPlatformWatcher::new(projects_dirs) {
+            Ok(watcher) => {
+                let interval = Duration::from_std(watch_interval).unwrap_or(Duration::seconds(30));
+                self.watcher = Some((watcher, interval));
+            }
+            Err(e) => self.warn(format!("File watching unavailable, refreshing on a timer: {}", e)),
+        }
+        self
+    }
+
+    /// Warn in the header when log files lie deeper than `max_depth` and are being skipped
+    pub fn check_max_depth(&mut self) {
+        let parser = LogParser::new_multi(self.claude_dirs.clone()).with_max_depth(self.max_depth);
+        match parser.depth_mismatch() {
+            Ok(Some(warning)) => self.warn(warning),
+            Ok(None) => {}
+            Err(e) => self.warn(format!("Failed to check how deep log files are: {}", e)),
+        }
+    }
+
+    /// Record a warning for the header, unless the same one is already shown
+    pub fn warn(&mut self, message: String) {
+        if !self.warnings.contains(&message) {
+            self.warnings.push(message);
+        }
+    }
+
+    /// Keep up to `feed_size` requests in the live feed (at least 1)
+    pub fn with_feed_size(mut self, feed_size: usize) -> Self {
+        self.feed_size = feed_size.max(1);
+        self.request_feed = VecDeque::with_capacity(self.feed_size);
+        self
+    }
+
+    /// Re-read log files at most once per `interval`; ticks in between only redraw
+    pub fn with_min_refresh_interval(mut self, interval: std::time::Duration) -> Self {
+        self.min_refresh_interval = Duration::from_std(interval).unwrap_or(Duration::zero());
+        self
+    }
+
+    pub fn prefs(&self) -> DashboardPrefs {
+        DashboardPrefs {
+            chart_type: self.chart_type,
+            model_filter: self.model_filter.clone(),
+            // A fixed span would be stale by the next run
+            time_range: match self.time_range {
+                TimeRange::Between { .. } => TimeRange::OneHour,
+                time_range => time_range,
+            },
+        }
+    }
+
+    /// Remember the current chart type, model filter and time range for the next run
+    pub fn save_prefs(&self) -> Result<()> {
+        self.prefs().save(&self.prefs_file)
+    }
+
     pub fn refresh_data(&mut self) -> Result<()> {
         // Parse logs from the last 7 days for all stats panels
         let start_date = Utc::now() - Duration::hours(168); // Always fetch 7 days for all time ranges
-        let parser = LogParser::new(self.claude_dir.clone())
+        let parser = LogParser::new_multi(self.claude_dirs.clone())
             .with_date_range(Some(start_date), None)
+            .with_max_depth(self.max_depth)
             .quiet();
         
         // On first load, clear everything and ensure proper sorting
-        let is_first_load = self.seen_request_ids.is_empty();
+        let is_first_load = self.seen_requests.is_empty();
         
         // Use incremental parsing if available, but do full load on first run
+        let mut warm_up_error = None;
         let entries = if let Some(ref mut tracker) = self._file_tracker {
             if self._use_incremental && !is_first_load {
                 parser.parse_logs_incremental(tracker)?
             } else {
                 // First load or incremental disabled - do full parse
-                let entries = parser.parse_logs()?;
-                // Update tracker with all files so next refresh is incremental
+                let (entries, reads) = parser.parse_logs_with_reads()?;
+                // Record every file as read so the next refresh is incremental
                 if is_first_load && self._use_incremental {
-                    // Force tracker to scan all files
-                    let _ = parser.parse_logs_incremental(tracker);
+                    warm_up_error = parser.warm_up_tracker(tracker, &entries, &reads).err();
                 }
                 entries
             }
         } else {
             parser.parse_logs()?
         };
+        if let Some(e) = warm_up_error {
+            self.warn(format!("Failed to update file tracker state: {}", e));
+        }
         
         if is_first_load {
             self.rolling_window.clear();
@@ -155,8 +500,14 @@ impl App {
         let mut new_requests = Vec::new();
         
         for entry in entries {
-            // Skip if we've already seen this request
-            if self.seen_request_ids.contains(&entry.uuid) {
+            // Keep the latest copy of each request, as `show` does: a streamed request's
+            // first line only has part of its output tokens
+            let key = dedup_key(parser.dedup_key, &entry);
+            if self
+                .seen_requests
+                .get(&key)
+                .is_some_and(|(timestamp, _)| entry.timestamp <= *timestamp)
+            {
                 continue;
             }
             
@@ -164,7 +515,9 @@ impl App {
                 if let Some(usage) = &message.usage {
                     if !message.model.is_synthetic() {
                         let request = RequestInfo {
-                            timestamp: entry.timestamp,
+                            // Clamped here rather than by add_request, so the copy kept in
+                            // seen_requests matches the window's when it's replaced
+                            timestamp: entry.timestamp.min(Utc::now()),
                             // CLAUDETODO: Cloning ModelName on every request. Consider using Arc<ModelName>
                             // or storing model as an enum index if the set of models is limited
                             model: message.model.clone(),
@@ -172,75 +525,387 @@ impl App {
                             output_tokens: usage.output_tokens as u32,
                             cache_tokens: (usage.cache_creation_input_tokens + usage.cache_read_input_tokens) as u32,
                             cost: self.calculate_cost(&message.model, usage),
+                            session_id: entry.session_id.clone(),
                         };
                         
+                        let counted = (entry.timestamp, request.clone());
+                        if let Some((_, previous)) = self.seen_requests.insert(key, counted) {
+                            self.rolling_window.remove_request(&previous);
+                            let feed_pos = self.request_feed.iter().position(|r| *r == previous);
+                            if let Some(pos) = feed_pos {
+                                self.request_feed.remove(pos);
+                            }
+                            new_requests.retain(|r| *r != previous);
+                        }
                         // CLAUDETODO: Cloning RequestInfo here is unnecessary. add_request could take ownership
                         // and new_requests could store references or indices
                         self.rolling_window.add_request(request.clone());
                         new_requests.push(request);
-                        // UUID type implements Copy, so no cloning needed
-                        self.seen_request_ids.insert(entry.uuid);
                     }
                 }
             }
         }
         
+        self.push_to_feed(new_requests);
+        self.update_available_models();
+        
+        // On first load, ensure feed is sorted properly (newest first)
+        if is_first_load {
+            self.sort_request_feed();
+        }
+        
+        self.last_update = Utc::now();
+        Ok(())
+    }
+
+    /// Add requests to the top of the feed (unless paused), newest first
+    fn push_to_feed(&mut self, mut new_requests: Vec<RequestInfo>) {
         // Sort new requests by timestamp (oldest first)
-        new_requests.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+        new_requests.sort_by_key(|r| r.timestamp);
         
         // Add new requests to the feed (most recent first)
         if !self.feed_paused {
-            // Add in reverse order so newest appears at top
-            for request in new_requests.into_iter().rev() {
+            let added = new_requests.iter().filter(|r| self.is_visible_in_feed(r)).count();
+            // Push oldest first so the newest ends up at the top
+            for request in new_requests {
                 self.request_feed.push_front(request);
                 
                 // Limit feed size
-                if self.request_feed.len() > 100 {
+                if self.request_feed.len() > self.feed_size {
                     self.request_feed.pop_back();
                 }
             }
+
+            // Keep the selection on the same request as newer ones are pushed above it
+            let feed_len = self.visible_feed().count();
+            self.selected_feed_item = self
+                .selected_feed_item
+                .map(|index| index + added)
+                .filter(|&index| index < feed_len);
         }
-        
-        // On first load, ensure feed is sorted properly (newest first)
-        if is_first_load {
-            self.sort_request_feed();
+    }
+
+    /// Feed requests straight into the rolling window and request feed, bypassing log
+    /// parsing, so dashboard logic can be tested deterministically
+    pub fn inject_entries_for_test(&mut self, entries: Vec<RequestInfo>) {
+        for request in &entries {
+            self.rolling_window.add_request(request.clone());
         }
-        
+        self.push_to_feed(entries);
+        self.update_available_models();
         self.last_update = Utc::now();
-        Ok(())
+    }
+
+    /// Distinct models in the rolling window, known models first in their usual order
+    pub fn get_model_list(&self) -> Vec<ModelName> {
+        let mut models: Vec<ModelName> = Vec::new();
+        for request in self.rolling_window.requests() {
+            if !models.contains(&request.model) {
+                models.push(request.model.clone());
+            }
+        }
+        let known = ModelName::all_known();
+        models.sort_by_key(|model| {
+            let position = known.iter().position(|known_model| known_model == model);
+            (position.unwrap_or(known.len()), model.to_string())
+        });
+        models
+    }
+
+    /// Refresh `available_models`, dropping back to all models if the filtered one has just
+    /// aged out of the window. A filter for a model that was never seen (e.g. from
+    /// `--model`) is left alone.
+    fn update_available_models(&mut self) {
+        let models = self.get_model_list();
+        if let ModelFilter::Specific(model) = &self.model_filter {
+            if self.available_models.contains(model) && !models.contains(model) {
+                self.model_filter = ModelFilter::All;
+            }
+        }
+        self.available_models = models;
+    }
+
+    /// The model the stats are filtered to, if any
+    fn filtered_model(&self) -> Option<&ModelName> {
+        match &self.model_filter {
+            ModelFilter::All => None,
+            ModelFilter::Specific(m) => Some(m),
+        }
+    }
+
+    pub fn get_stats_snapshot(&self) -> DashboardSnapshot {
+        let model_filter = self.filtered_model();
+        let stats_1h = self.rolling_window.get_current_hour_stats(model_filter);
+        let stats_24h = self.rolling_window.get_24h_stats(model_filter);
+
+        DashboardSnapshot {
+            last_update: self.last_update,
+            time_range_minutes: self.time_range.minutes(),
+            total_cost_1h: stats_1h.cost,
+            total_cost_5h: self.rolling_window.get_5h_stats(model_filter).cost,
+            total_cost_24h: stats_24h.cost,
+            total_cost_7d: self.rolling_window.get_7d_stats(model_filter).cost,
+            request_count_1h: stats_1h.requests,
+            request_count_24h: stats_24h.requests,
+            top_models_24h: self.rolling_window.get_model_costs(24, model_filter),
+            feed_len: self.request_feed.len(),
+        }
+    }
+
+    pub fn get_range_stats_snapshot(&self) -> RangeStatsSnapshot {
+        let model_filter = self.filtered_model();
+        RangeStatsSnapshot {
+            last_update: self.last_update,
+            model_filter: model_filter.map(ModelName::to_string),
+            current_hour: self.rolling_window.get_current_hour_stats(model_filter),
+            last_5h: self.rolling_window.get_5h_stats(model_filter),
+            last_24h: self.rolling_window.get_24h_stats(model_filter),
+            last_2d: self.rolling_window.get_2d_stats(model_filter),
+            last_7d: self.rolling_window.get_7d_stats(model_filter),
+        }
     }
 
     fn calculate_cost(&self, model: &ModelName, usage: &crate::models::TokenUsage) -> f64 {
         // CLAUDETODO: get_model_pricing does HashMap lookups and string comparisons for Unknown models.
         // Consider caching pricing lookups for frequently used models or pre-computing a model->pricing index
         if let Some(pricing) = crate::pricing::get_model_pricing(&self.pricing_map, model) {
-            pricing.calculate_cost(usage)
+            pricing.calculate_cost_with_tier(usage)
         } else {
             0.0
         }
     }
 
     pub fn on_tick(&mut self) {
-        // Refresh data from JSONL files
+        self.tick_at(Utc::now());
+    }
+
+    fn tick_at(&mut self, now: DateTime<Utc>) {
+        let suspended = now - self.last_tick > Duration::seconds(CATCH_UP_GAP_SECONDS);
+        self.last_tick = now;
+
+        if suspended && !self.catching_up {
+            // Show the catching-up state for a frame before the large backfill
+            self.catching_up = true;
+            return;
+        }
+
+        // Always drain watcher events so they don't pile up between refreshes
+        let files_changed = self
+            .watcher
+            .as_mut()
+            .map(|(watcher, _)| !watcher.poll_changes().is_empty());
+        let due = self.refresh_pending
+            || match (&self.watcher, files_changed) {
+                (Some((_, interval)), Some(changed)) => {
+                    changed || now - self.last_update >= *interval
+                }
+                _ => true,
+            };
+        // Changes seen too soon after the last read are picked up once the floor passes
+        let throttled = now - self.last_update < self.min_refresh_interval;
+        self.refresh_pending = due && throttled && !self.catching_up;
+
+        if (due && !throttled) || self.catching_up {
+            // Refresh data from JSONL files
+            if let Err(e) = self.refresh_data() {
+                self.warn(format!("Error refreshing data: {}", e));
+            }
+        }
+        self.catching_up = false;
+    }
+
+    /// Re-read the logs now (`r`), without waiting for file changes, the fallback interval or
+    /// the minimum refresh interval
+    pub fn refresh_now(&mut self) {
+        self.refresh_pending = false;
         if let Err(e) = self.refresh_data() {
-            eprintln!("Error refreshing data: {}", e);
+            self.warn(format!("Error refreshing data: {}", e));
         }
     }
 
+    /// All models, then each model in `available_models` (those seen in the rolling window),
+    /// then back to all
     pub fn cycle_model_filter(&mut self) {
-        self.model_filter = match &self.model_filter {
-            ModelFilter::All => ModelFilter::Specific(ModelName::Claude4Opus),
-            ModelFilter::Specific(ModelName::Claude4Opus) => ModelFilter::Specific(ModelName::Claude4Sonnet),
-            ModelFilter::Specific(ModelName::Claude4Sonnet) => ModelFilter::Specific(ModelName::Claude3Haiku),
-            ModelFilter::Specific(_) => ModelFilter::All,
+        let models = &self.available_models;
+        let next = match &self.model_filter {
+            ModelFilter::All => models.first(),
+            ModelFilter::Specific(model) => models
+                .iter()
+                .position(|available| available == model)
+                .and_then(|index| models.get(index + 1)),
         };
+        self.model_filter = next.map_or(ModelFilter::All, |model| ModelFilter::Specific(model.clone()));
     }
 
     pub fn cycle_time_range(&mut self) {
         self.time_range = self.time_range.next();
+        self.rolling_window.set_bucket_minutes(self.time_range.bucket_minutes());
         self.rolling_window.set_window_minutes(self.time_range.minutes());
     }
 
+    /// Set the chart window to an arbitrary number of hours
+    pub fn set_custom_hours(&mut self, hours: usize) {
+        let hours = hours.clamp(1, MAX_CUSTOM_HOURS);
+        self.time_range = TimeRange::from_hours(hours);
+        self.rolling_window.set_bucket_minutes(self.time_range.bucket_minutes());
+        self.rolling_window.set_window_minutes(hours * 60);
+    }
+
+    /// Show a fixed span of the retained history, from `start` to `end`. The start is
+    /// clamped to the 7 days of history kept; an empty or reversed span is ignored.
+    pub fn set_time_range_custom(&mut self, start: DateTime<Utc>, end: DateTime<Utc>) {
+        let now = Utc::now();
+        let start = start.max(now - Duration::hours(MAX_CUSTOM_HOURS as i64));
+        if end <= start {
+            return;
+        }
+        self.time_range = TimeRange::Between { start, end };
+        self.rolling_window.set_bucket_minutes(self.time_range.bucket_minutes());
+        // Keep everything back to the start of the span, not just the span's length
+        let retained = (now - start).num_minutes().max(0) as usize;
+        self.rolling_window.set_window_minutes(retained.max(self.time_range.minutes()));
+    }
+
+    /// Enter date range mode, where "start end" in `YYYY-MM-DDTHH:MM` local time followed
+    /// by Enter sets a fixed span
+    pub fn start_date_range_input(&mut self) {
+        self.date_range_input = Some(String::new());
+    }
+
+    pub fn push_date_range_char(&mut self, c: char) {
+        if let Some(input) = &mut self.date_range_input {
+            // Two timestamps and a separator
+            if (c.is_ascii_digit() || matches!(c, '-' | ':' | 'T' | ' ')) && input.len() < 33 {
+                input.push(c);
+            }
+        }
+    }
+
+    pub fn pop_date_range_char(&mut self) {
+        if let Some(input) = &mut self.date_range_input {
+            input.pop();
+        }
+    }
+
+    pub fn cancel_date_range_input(&mut self) {
+        self.date_range_input = None;
+    }
+
+    /// Apply the typed span (ignored if it doesn't parse) and leave input mode
+    pub fn submit_date_range_input(&mut self) {
+        if let Some(input) = self.date_range_input.take() {
+            if let Some((start, end)) = parse_date_range(&input) {
+                self.set_time_range_custom(start, end);
+            }
+        }
+    }
+
+    /// Enter "go to range" mode, where digits followed by Enter set a custom range
+    pub fn start_range_input(&mut self) {
+        self.range_input = Some(String::new());
+    }
+
+    pub fn push_range_digit(&mut self, digit: char) {
+        if let Some(input) = &mut self.range_input {
+            // Three digits is already more than MAX_CUSTOM_HOURS
+            if digit.is_ascii_digit() && input.len() < 3 {
+                input.push(digit);
+            }
+        }
+    }
+
+    pub fn pop_range_digit(&mut self) {
+        if let Some(input) = &mut self.range_input {
+            input.pop();
+        }
+    }
+
+    pub fn cancel_range_input(&mut self) {
+        self.range_input = None;
+    }
+
+    /// Apply the typed range (ignored if empty or zero) and leave input mode
+    pub fn submit_range_input(&mut self) {
+        if let Some(input) = self.range_input.take() {
+            if let Ok(hours) = input.parse::<usize>() {
+                if hours > 0 {
+                    self.set_custom_hours(hours);
+                }
+            }
+        }
+    }
+
+    /// Enter session filter mode, where a typed session id prefix followed by Enter
+    /// limits the feed to that session
+    pub fn start_session_input(&mut self) {
+        self.session_input = Some(String::new());
+    }
+
+    pub fn push_session_char(&mut self, c: char) {
+        if let Some(input) = &mut self.session_input {
+            // Session ids are UUIDs
+            if c.is_ascii_hexdigit() || c == '-' {
+                input.push(c);
+            }
+        }
+    }
+
+    pub fn pop_session_char(&mut self) {
+        if let Some(input) = &mut self.session_input {
+            input.pop();
+        }
+    }
+
+    pub fn cancel_session_input(&mut self) {
+        self.session_input = None;
+    }
+
+    /// Apply the typed prefix and leave input mode; an empty prefix clears the filter
+    pub fn submit_session_input(&mut self) {
+        if let Some(input) = self.session_input.take() {
+            self.set_selected_session((!input.is_empty()).then_some(input));
+        }
+    }
+
+    pub fn set_selected_session(&mut self, session: Option<String>) {
+        self.selected_session = session;
+        self.feed_scroll = 0;
+        self.selected_feed_item = None;
+    }
+
+    fn is_visible_in_feed(&self, request: &RequestInfo) -> bool {
+        self.selected_session
+            .as_deref()
+            .is_none_or(|prefix| request.in_session(prefix))
+    }
+
+    /// Feed requests shown with the current session filter, newest first
+    pub fn visible_feed(&self) -> impl Iterator<Item = &RequestInfo> {
+        self.request_feed.iter().filter(|r| self.is_visible_in_feed(r))
+    }
+
+    /// Write the rolling window's requests to a timestamped CSV file in `dir`
+    pub fn export_csv_to(&self, dir: &Path) -> Result<PathBuf> {
+        let name = format!("claude-usage-{}.csv", Local::now().format("%Y%m%d-%H%M%S"));
+        let path = dir.join(name);
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(&path)?);
+        self.rolling_window.export_to_csv(&mut writer)?;
+        writer.flush()?;
+        Ok(path)
+    }
+
+    /// Export to the current directory and report where the file went (or why it didn't)
+    pub fn export_csv(&mut self) {
+        self.export_status = Some(match self.export_csv_to(Path::new(".")) {
+            Ok(path) => format!("Exported to {}", path.display()),
+            Err(e) => format!("Export failed: {}", e),
+        });
+    }
+
+    pub fn toggle_help(&mut self) {
+        self.show_help = !self.show_help;
+    }
+
     pub fn toggle_feed_pause(&mut self) {
         self.feed_paused = !self.feed_paused;
         
@@ -253,7 +918,7 @@ impl App {
     /// Ensure request feed is sorted with most recent first
     fn sort_request_feed(&mut self) {
         let mut temp: Vec<_> = self.request_feed.drain(..).collect();
-        temp.sort_by(|a, b| b.timestamp.cmp(&a.timestamp)); // Reverse order - newest first
+        temp.sort_by_key(|r| std::cmp::Reverse(r.timestamp)); // Newest first
         self.request_feed.extend(temp);
     }
 
@@ -264,15 +929,507 @@ impl App {
     }
 
     pub fn scroll_feed_down(&mut self) {
-        if self.feed_scroll < self.request_feed.len().saturating_sub(10) {
+        if self.feed_scroll < self.max_feed_scroll() {
             self.feed_scroll += 1;
         }
     }
 
+    pub fn page_feed_up(&mut self) {
+        self.feed_scroll = self.feed_scroll.saturating_sub(self.feed_page_size());
+    }
+
+    pub fn page_feed_down(&mut self) {
+        self.feed_scroll = (self.feed_scroll + self.feed_page_size()).min(self.max_feed_scroll());
+    }
+
+    /// Jump to the top of the feed (newest request)
+    pub fn scroll_feed_to_newest(&mut self) {
+        self.feed_scroll = 0;
+    }
+
+    /// Jump to the bottom of the feed (oldest request)
+    pub fn scroll_feed_to_oldest(&mut self) {
+        self.feed_scroll = self.max_feed_scroll();
+    }
+
+    /// Select a feed row; indices past the end of the feed are ignored
+    pub fn select_feed_item(&mut self, index: usize) {
+        if index < self.visible_feed().count() {
+            self.selected_feed_item = Some(index);
+        }
+    }
+
+    /// Number of feed rows visible on the last draw (falls back to 10 before the first draw)
+    fn feed_page_size(&self) -> usize {
+        match self.layout.feed {
+            Some(area) => (area.height.saturating_sub(2) as usize).max(1), // Account for borders
+            None => 10,
+        }
+    }
+
+    fn max_feed_scroll(&self) -> usize {
+        self.visible_feed().count().saturating_sub(self.feed_page_size())
+    }
+
     pub fn toggle_chart_type(&mut self) {
         self.chart_type = match self.chart_type {
             ChartType::Bar => ChartType::Line,
-            ChartType::Line => ChartType::Bar,
+            ChartType::Line | ChartType::Tokens => ChartType::Bar,
+        };
+    }
+
+    /// Switch between the tokens chart and the cost bar chart
+    pub fn toggle_token_chart(&mut self) {
+        self.chart_type = match self.chart_type {
+            ChartType::Tokens => ChartType::Bar,
+            ChartType::Bar | ChartType::Line => ChartType::Tokens,
         };
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_budget_config_load() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("budgets.toml");
+        assert_eq!(BudgetConfig::load(&path).unwrap(), None);
+
+        std::fs::write(&path, "daily = 20.0\nmonthly = 400\n").unwrap();
+        assert_eq!(
+            BudgetConfig::load(&path).unwrap(),
+            Some(BudgetConfig {
+                daily: Some(20.0),
+                monthly: Some(400.0),
+            })
+        );
+
+        std::fs::write(&path, "daily = \"lots\"\n").unwrap();
+        assert!(BudgetConfig::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_prefs_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("dashboard-prefs.json");
+        assert_eq!(DashboardPrefs::load(&path).unwrap(), DashboardPrefs::default());
+
+        let prefs = DashboardPrefs {
+            chart_type: ChartType::Line,
+            model_filter: ModelFilter::Specific(ModelName::Claude4Sonnet),
+            time_range: TimeRange::Custom(36),
+        };
+        prefs.save(&path).unwrap();
+        assert_eq!(DashboardPrefs::load(&path).unwrap(), prefs);
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(DashboardPrefs::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_app_restores_prefs_unless_overridden() {
+        let temp_dir = TempDir::new().unwrap();
+        let claude_dir = temp_dir.path().to_string_lossy().to_string();
+        let state_dir = temp_dir.path().join(".claude-usage");
+        std::fs::create_dir_all(&state_dir).unwrap();
+        let prefs = DashboardPrefs {
+            chart_type: ChartType::Line,
+            model_filter: ModelFilter::Specific(ModelName::Claude4Opus),
+            time_range: TimeRange::SixHours,
+        };
+        prefs.save(&state_dir.join("dashboard-prefs.json")).unwrap();
+
+        let app = App::new(vec![claude_dir.clone()], None, 1.0, false, 3);
+        assert_eq!(app.prefs(), prefs);
+
+        let app = App::new(vec![claude_dir.clone()], Some(24), 1.0, false, 3);
+        assert_eq!(app.time_range, TimeRange::TwentyFourHours);
+        assert_eq!(app.chart_type, ChartType::Line);
+        assert!(app.warnings.is_empty());
+
+        // Unreadable preferences fall back to the defaults with a warning for the header
+        std::fs::write(state_dir.join("dashboard-prefs.json"), "not json").unwrap();
+        let app = App::new(vec![claude_dir], None, 1.0, false, 3);
+        assert_eq!(app.prefs(), DashboardPrefs::default());
+        assert_eq!(app.warnings.len(), 1);
+        assert!(app.warnings[0].starts_with("Failed to load dashboard preferences"));
+    }
+
+    fn app_with_projects(temp_dir: &TempDir) -> App {
+        std::fs::create_dir_all(temp_dir.path().join("projects")).unwrap();
+        App::new(vec![temp_dir.path().to_string_lossy().to_string()], Some(1), 1.0, false, 3)
+    }
+
+    #[test]
+    fn test_check_max_depth_warns_in_header() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = app_with_projects(&temp_dir);
+        app.check_max_depth();
+        assert!(app.warnings.is_empty());
+
+        let deep = temp_dir.path().join("projects/a/b/c");
+        std::fs::create_dir_all(&deep).unwrap();
+        std::fs::write(deep.join("session.jsonl"), "").unwrap();
+        app.check_max_depth();
+        assert_eq!(app.warnings.len(), 1);
+        assert!(app.warnings[0].contains("depth 4, beyond --max-depth 3"));
+    }
+
+    #[test]
+    fn test_catch_up_after_suspend() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = app_with_projects(&temp_dir);
+        let woke_at = app.last_tick + Duration::minutes(45);
+
+        app.tick_at(woke_at);
+        assert!(app.catching_up);
+
+        app.tick_at(woke_at + Duration::seconds(1));
+        assert!(!app.catching_up);
+    }
+
+    #[test]
+    fn test_watcher_refreshes_on_fallback_interval() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app =
+            app_with_projects(&temp_dir).with_file_watcher(std::time::Duration::from_secs(30));
+        assert!(app.watcher.is_some());
+        let started = app.last_update;
+
+        // No file changes and the interval hasn't elapsed: nothing to do
+        app.tick_at(started + Duration::seconds(1));
+        assert_eq!(app.last_update, started);
+
+        app.tick_at(started + Duration::seconds(31));
+        assert_ne!(app.last_update, started);
+    }
+
+    #[test]
+    fn test_refresh_now_ignores_interval_and_throttle() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = app_with_projects(&temp_dir)
+            .with_file_watcher(std::time::Duration::from_secs(30))
+            .with_min_refresh_interval(std::time::Duration::from_secs(60));
+        let started = app.last_update;
+
+        app.tick_at(started + Duration::seconds(1));
+        assert_eq!(app.last_update, started);
+
+        app.refresh_now();
+        assert_ne!(app.last_update, started);
+    }
+
+    #[test]
+    fn test_feed_size_caps_feed() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = app_with_projects(&temp_dir).with_feed_size(3);
+        app.inject_entries_for_test(
+            (0..5)
+                .map(|minutes_ago| request(minutes_ago, ModelName::Claude4Opus, 1.0))
+                .collect(),
+        );
+
+        assert_eq!(app.request_feed.len(), 3);
+        // The newest requests are the ones kept
+        assert!(app.request_feed.iter().all(|r| r.timestamp > Utc::now() - Duration::minutes(3)));
+    }
+
+    #[test]
+    fn test_export_csv_writes_timestamped_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = app_with_projects(&temp_dir);
+        app.inject_entries_for_test(vec![
+            request(1, ModelName::Claude4Opus, 1.0),
+            request(2, ModelName::Claude4Sonnet, 0.5),
+        ]);
+
+        let path = app.export_csv_to(temp_dir.path()).unwrap();
+        assert!(path.file_name().unwrap().to_string_lossy().starts_with("claude-usage-"));
+        assert_eq!(std::fs::read_to_string(path).unwrap().lines().count(), 3);
+    }
+
+    #[test]
+    fn test_min_refresh_interval_throttles_reads() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = app_with_projects(&temp_dir)
+            .with_min_refresh_interval(std::time::Duration::from_millis(250));
+        let started = app.last_update;
+
+        // Without a watcher every tick is due, but reads are held back by the floor
+        app.tick_at(started + Duration::milliseconds(100));
+        assert_eq!(app.last_update, started);
+        assert!(app.refresh_pending);
+
+        app.tick_at(started + Duration::milliseconds(300));
+        assert_ne!(app.last_update, started);
+        assert!(!app.refresh_pending);
+    }
+
+    fn request(minutes_ago: i64, model: ModelName, cost: f64) -> RequestInfo {
+        RequestInfo {
+            timestamp: Utc::now() - Duration::minutes(minutes_ago),
+            model,
+            input_tokens: 100,
+            output_tokens: 50,
+            cache_tokens: 0,
+            cost,
+            session_id: "test-session".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_snapshot_reflects_injected_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = App::new(
+            vec![temp_dir.path().to_string_lossy().to_string()],
+            Some(1),
+            1.0,
+            false,
+            3,
+        );
+        app.inject_entries_for_test(vec![
+            request(5, ModelName::Claude4Opus, 1.0),
+            request(30, ModelName::Claude4Sonnet, 0.25),
+        ]);
+        app.inject_entries_for_test(vec![request(3 * 60, ModelName::Claude4Opus, 2.0)]);
+        app.inject_entries_for_test(vec![request(2 * 24 * 60, ModelName::Claude4Sonnet, 4.0)]);
+
+        let snapshot = app.get_stats_snapshot();
+        assert_eq!(snapshot.time_range_minutes, 60);
+        assert_eq!(snapshot.request_count_1h, 2);
+        assert_eq!(snapshot.request_count_24h, 3);
+        assert!((snapshot.total_cost_1h - 1.25).abs() < 1e-9);
+        assert!((snapshot.total_cost_5h - 3.25).abs() < 1e-9);
+        assert!((snapshot.total_cost_24h - 3.25).abs() < 1e-9);
+        assert!((snapshot.total_cost_7d - 7.25).abs() < 1e-9);
+        assert_eq!(
+            snapshot.top_models_24h,
+            vec![(ModelName::Claude4Opus, 3.0), (ModelName::Claude4Sonnet, 0.25)]
+        );
+        assert_eq!(snapshot.feed_len, 4);
+        assert!(serde_json::to_string(&snapshot).is_ok());
+    }
+
+    #[test]
+    fn test_range_stats_snapshot_respects_model_filter() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = App::new(
+            vec![temp_dir.path().to_string_lossy().to_string()],
+            Some(1),
+            1.0,
+            false,
+            3,
+        );
+        app.inject_entries_for_test(vec![
+            request(5, ModelName::Claude4Opus, 1.0),
+            request(30, ModelName::Claude4Sonnet, 0.25),
+            request(30 * 60, ModelName::Claude4Opus, 2.0),
+        ]);
+
+        app.model_filter = ModelFilter::All;
+        let snapshot = app.get_range_stats_snapshot();
+        assert_eq!(snapshot.model_filter, None);
+        assert_eq!(snapshot.current_hour.requests, 2);
+        assert!((snapshot.last_24h.cost - 1.25).abs() < 1e-9);
+        assert!((snapshot.last_2d.cost - 3.25).abs() < 1e-9);
+        let json = serde_json::to_value(&snapshot).unwrap();
+        assert_eq!(json["last_7d"]["model_costs"]["claude-opus-4-20250514"], 3.0);
+        assert_eq!(json["last_7d"]["model_costs"]["claude-sonnet-4-20250514"], 0.25);
+
+        app.model_filter = ModelFilter::Specific(ModelName::Claude4Opus);
+        let snapshot = app.get_range_stats_snapshot();
+        assert!(snapshot.model_filter.is_some());
+        assert_eq!(snapshot.current_hour.requests, 1);
+        assert!((snapshot.last_7d.cost - 3.0).abs() < 1e-9);
+        assert_eq!(snapshot.last_7d.model_costs.len(), 1);
+    }
+
+    #[test]
+    fn test_dashboard_and_show_count_requests_alike() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path().join("projects").join("p");
+        std::fs::create_dir_all(&project).unwrap();
+        let line = |uuid: &str, request_id: &str, minutes_ago: i64, output_tokens: u64| {
+            format!(
+                r#"{{"type":"assistant","uuid":"{}","timestamp":"{}","sessionId":"s","requestId":"{}","message":{{"id":"m-{}","role":"assistant","model":"claude-sonnet-4-20250514","usage":{{"input_tokens":100,"output_tokens":{}}}}}}}"#,
+                uuid,
+                (Utc::now() - Duration::minutes(minutes_ago)).to_rfc3339(),
+                request_id,
+                uuid,
+                output_tokens
+            )
+        };
+        // r1 is streamed over two lines with different uuids; the later one has every
+        // output token
+        let log = project.join("session.jsonl");
+        let lines = [line("a", "r1", 30, 1), line("b", "r1", 29, 500), line("c", "r2", 20, 10)];
+        std::fs::write(&log, lines.join("\n") + "\n").unwrap();
+
+        let claude_dir = temp_dir.path().to_string_lossy().to_string();
+        let pricing = get_default_pricing();
+        // Request count and cost as `show` reports them
+        let show = || {
+            let entries = LogParser::new(claude_dir.clone()).quiet().parse_logs().unwrap();
+            let cost: f64 = entries
+                .iter()
+                .filter_map(|entry| entry.message.as_ref())
+                .map(|message| {
+                    let pricing = crate::pricing::get_model_pricing(&pricing, &message.model);
+                    pricing.unwrap().calculate_cost_with_tier(message.usage.as_ref().unwrap())
+                })
+                .sum();
+            (entries.len(), cost)
+        };
+        let dashboard = |app: &App| {
+            let requests: Vec<&RequestInfo> = app.rolling_window.requests().collect();
+            (requests.len(), requests.iter().map(|r| r.cost).sum::<f64>())
+        };
+        let assert_alike = |(show_count, show_cost): (usize, f64), (count, cost): (usize, f64)| {
+            assert_eq!(count, show_count);
+            assert!((cost - show_cost).abs() < 1e-12, "{} vs {}", cost, show_cost);
+        };
+
+        let mut app = App::new(vec![claude_dir.clone()], Some(1), 1.0, false, 3);
+        app.refresh_data().unwrap();
+        assert_eq!(show().0, 2);
+        assert_alike(show(), dashboard(&app));
+
+        // A late copy of r1 picked up by the next refresh replaces the one counted before
+        let mut file = std::fs::OpenOptions::new().append(true).open(&log).unwrap();
+        writeln!(file, "{}", line("d", "r1", 10, 800)).unwrap();
+        writeln!(file, "{}", line("e", "r3", 5, 10)).unwrap();
+        app.refresh_data().unwrap();
+        assert_eq!(show().0, 3);
+        assert_alike(show(), dashboard(&app));
+        assert_eq!(app.request_feed.iter().filter(|r| r.output_tokens == 800).count(), 1);
+        assert_eq!(app.request_feed.len(), 3);
+    }
+
+    #[test]
+    fn test_model_cycle_follows_data() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = app_with_projects(&temp_dir);
+        app.model_filter = ModelFilter::All;
+        app.inject_entries_for_test(vec![
+            request(5, ModelName::Claude37Sonnet, 1.0),
+            request(10, ModelName::Claude4Opus, 1.0),
+            request(15, ModelName::Claude37Sonnet, 1.0),
+        ]);
+        assert_eq!(app.available_models, vec![ModelName::Claude4Opus, ModelName::Claude37Sonnet]);
+
+        let mut visited = Vec::new();
+        for _ in 0..3 {
+            app.cycle_model_filter();
+            visited.push(app.model_filter.clone());
+        }
+        assert_eq!(
+            visited,
+            vec![
+                ModelFilter::Specific(ModelName::Claude4Opus),
+                ModelFilter::Specific(ModelName::Claude37Sonnet),
+                ModelFilter::All,
+            ]
+        );
+
+        // The filtered model leaving the window resets the filter
+        app.model_filter = ModelFilter::Specific(ModelName::Claude4Opus);
+        app.rolling_window.clear();
+        app.inject_entries_for_test(vec![request(5, ModelName::Claude37Sonnet, 1.0)]);
+        assert_eq!(app.model_filter, ModelFilter::All);
+    }
+
+    #[test]
+    fn test_time_range_custom() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = App::new(
+            vec![temp_dir.path().to_string_lossy().to_string()],
+            Some(1),
+            1.0,
+            false,
+            3,
+        );
+        app.inject_entries_for_test(vec![
+            request(30, ModelName::Claude4Opus, 1.0),
+            request(90, ModelName::Claude4Sonnet, 0.5),
+            request(3 * 24 * 60, ModelName::Claude4Opus, 2.0),
+        ]);
+
+        let now = Utc::now();
+        let (start, end) = (now - Duration::minutes(120), now - Duration::minutes(60));
+        app.set_time_range_custom(start, end);
+        assert_eq!(app.time_range, TimeRange::Between { start, end });
+        assert_eq!(app.time_range.minutes(), 60);
+        assert_eq!(app.time_range.chart_end(now), end);
+        let stats = app.rolling_window.get_stats_between(start, end, None);
+        assert_eq!(stats.requests, 1);
+        assert!((stats.cost - 0.5).abs() < 1e-9);
+
+        // Narrowing to a span days back keeps the older history
+        app.set_time_range_custom(now - Duration::days(4), now - Duration::days(2));
+        let stats = app.rolling_window.get_stats_between(now - Duration::days(4), now, None);
+        assert_eq!(stats.requests, 3);
+
+        // Reversed spans are ignored, and fixed spans aren't remembered
+        let before = app.time_range;
+        app.set_time_range_custom(end, start);
+        assert_eq!(app.time_range, before);
+        assert_eq!(app.prefs().time_range, TimeRange::OneHour);
+    }
+
+    #[test]
+    fn test_parse_date_range() {
+        let (start, end) = parse_date_range("2025-06-01T10:00  2025-06-15T23:59").unwrap();
+        assert_eq!(end - start, Duration::days(14) + Duration::minutes(13 * 60 + 59));
+        assert!(parse_date_range("2025-06-01T10:00").is_none());
+        assert!(parse_date_range("2025-06-01 2025-06-02").is_none());
+        assert!(parse_date_range("2025-06-01T10:00 2025-06-02T10:00 extra").is_none());
+    }
+
+    #[test]
+    fn test_session_filter_limits_feed() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = App::new(
+            vec![temp_dir.path().to_string_lossy().to_string()],
+            Some(1),
+            1.0,
+            false,
+            3,
+        );
+        let in_session = |minutes_ago, session_id: &str| RequestInfo {
+            session_id: session_id.to_string(),
+            ..request(minutes_ago, ModelName::Claude4Opus, 1.0)
+        };
+        app.inject_entries_for_test(vec![
+            in_session(1, "abc-123"),
+            in_session(2, "def-456"),
+            in_session(3, "abc-123"),
+        ]);
+
+        app.start_session_input();
+        for c in "abX".chars() {
+            app.push_session_char(c);
+        }
+        app.submit_session_input();
+        assert_eq!(app.selected_session.as_deref(), Some("ab"));
+
+        let feed: Vec<_> = app.visible_feed().collect();
+        assert_eq!(feed.len(), 2);
+        assert!(feed.iter().all(|r| r.session_id == "abc-123"));
+        assert_eq!(app.rolling_window.get_stats_for_session("ab").requests, 2);
+
+        // New requests from other sessions don't move the selection
+        app.select_feed_item(1);
+        app.inject_entries_for_test(vec![in_session(0, "def-456")]);
+        assert_eq!(app.selected_feed_item, Some(1));
+        app.inject_entries_for_test(vec![in_session(0, "abc-123")]);
+        assert_eq!(app.selected_feed_item, Some(2));
+
+        app.start_session_input();
+        app.submit_session_input();
+        assert_eq!(app.selected_session, None);
+        assert_eq!(app.visible_feed().count(), 5);
+    }
+}
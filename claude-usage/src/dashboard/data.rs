@@ -1,37 +1,67 @@
 //! Data structures and rolling window implementation for the dashboard.
 //!
 //! This module provides the core data structures for tracking Claude usage statistics
-//! in time-bucketed windows. The `RollingWindow` maintains minute-by-minute data and
-//! provides aggregated stats for different time ranges (1h, 5h, 24h, 2d, 7d).
+//! in time-bucketed windows. The `RollingWindow` keeps requests in buckets of a configurable
+//! number of minutes and provides aggregated stats for different time ranges (1h, 5h, 24h,
+//! 2d, 7d).
 
 use crate::model_name::ModelName;
-use chrono::{DateTime, Duration, Timelike, Utc};
-use std::collections::{HashMap, VecDeque};
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io::Write;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TimeRangeStats {
     pub requests: u32,
     pub tokens: u64,
     pub cost: f64,
-    pub model_costs: HashMap<String, f64>,
+    #[serde(serialize_with = "serialize_model_costs")]
+    pub model_costs: HashMap<ModelName, f64>,
 }
 
-#[derive(Debug, Clone)]
+/// Model costs as an object keyed by model id, in a stable order
+fn serialize_model_costs<S: serde::Serializer>(
+    costs: &HashMap<ModelName, f64>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let sorted: BTreeMap<String, f64> = costs
+        .iter()
+        .map(|(model, cost)| (model.canonical_string(), *cost))
+        .collect();
+    serializer.collect_map(sorted)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct RequestInfo {
     pub timestamp: DateTime<Utc>,
     pub model: ModelName,
     pub input_tokens: u32,
     pub output_tokens: u32,
     pub cache_tokens: u32,
+    #[serde(rename = "cost_usd")]
     pub cost: f64,
+    pub session_id: String,
+}
+
+impl RequestInfo {
+    /// Whether this request belongs to a session whose id starts with `prefix`
+    pub fn in_session(&self, prefix: &str) -> bool {
+        self.session_id.starts_with(prefix)
+    }
 }
 
+/// Requests in one bucket of `RollingWindow::bucket_minutes` minutes, starting at `timestamp`
 #[derive(Debug, Clone)]
 pub struct MinuteBucket {
     pub timestamp: DateTime<Utc>,
     pub requests: Vec<RequestInfo>,
     pub total_cost: f64,
-    pub model_costs: HashMap<String, f64>,
+    pub model_costs: HashMap<ModelName, f64>,
+    pub total_input_tokens: u64,
+    pub total_output_tokens: u64,
+    pub total_cache_tokens: u64,
 }
 
 impl MinuteBucket {
@@ -41,32 +71,104 @@ impl MinuteBucket {
             requests: Vec::new(),
             total_cost: 0.0,
             model_costs: HashMap::new(),
+            total_input_tokens: 0,
+            total_output_tokens: 0,
+            total_cache_tokens: 0,
         }
     }
 
     pub fn add_request(&mut self, request: RequestInfo) {
-        let model_key = request.model.family().to_string();
-        *self.model_costs.entry(model_key).or_insert(0.0) += request.cost;
+        *self.model_costs.entry(request.model.clone()).or_insert(0.0) += request.cost;
         self.total_cost += request.cost;
+        self.total_input_tokens += request.input_tokens as u64;
+        self.total_output_tokens += request.output_tokens as u64;
+        self.total_cache_tokens += request.cache_tokens as u64;
         self.requests.push(request);
     }
+
+    /// Take a request added earlier back out, returning whether it was here
+    pub fn remove_request(&mut self, request: &RequestInfo) -> bool {
+        let Some(pos) = self.requests.iter().position(|r| r == request) else {
+            return false;
+        };
+        let request = self.requests.remove(pos);
+        if let Some(cost) = self.model_costs.get_mut(&request.model) {
+            *cost -= request.cost;
+        }
+        self.total_cost -= request.cost;
+        self.total_input_tokens -= request.input_tokens as u64;
+        self.total_output_tokens -= request.output_tokens as u64;
+        self.total_cache_tokens -= request.cache_tokens as u64;
+        true
+    }
+
+    pub fn total_tokens(&self) -> u64 {
+        self.total_input_tokens + self.total_output_tokens + self.total_cache_tokens
+    }
+
+    /// Input, output and cache tokens in this bucket, only counting `model_filter` if given
+    pub fn token_counts(&self, model_filter: Option<&ModelName>) -> (u64, u64, u64) {
+        match model_filter {
+            Some(model) => self
+                .requests
+                .iter()
+                .filter(|r| r.model == *model)
+                .fold((0, 0, 0), |(input, output, cache), r| {
+                    (
+                        input + r.input_tokens as u64,
+                        output + r.output_tokens as u64,
+                        cache + r.cache_tokens as u64,
+                    )
+                }),
+            None => (
+                self.total_input_tokens,
+                self.total_output_tokens,
+                self.total_cache_tokens,
+            ),
+        }
+    }
 }
 
 pub struct RollingWindow {
     pub buckets: VecDeque<MinuteBucket>,
     pub window_minutes: usize,
+    /// Width of each bucket; long windows use coarser buckets to keep the bucket count down
+    pub bucket_minutes: usize,
 }
 
 impl RollingWindow {
-    pub fn new(window_minutes: usize) -> Self {
+    /// `bucket_minutes` is the base granularity (at least 1)
+    pub fn new(window_minutes: usize, bucket_minutes: usize) -> Self {
+        let bucket_minutes = bucket_minutes.max(1);
         // Always allocate capacity for at least 7 days of data
         let min_capacity = 168 * 60; // 7 days in minutes
-        let capacity = window_minutes.max(min_capacity);
+        let capacity = window_minutes.max(min_capacity).div_ceil(bucket_minutes);
         
         Self {
             buckets: VecDeque::with_capacity(capacity),
             window_minutes,
+            bucket_minutes,
+        }
+    }
+
+    /// Switch to buckets of `minutes` minutes, re-bucketing the retained requests
+    pub fn set_bucket_minutes(&mut self, minutes: usize) {
+        let minutes = minutes.max(1);
+        if minutes == self.bucket_minutes {
+            return;
         }
+        self.bucket_minutes = minutes;
+        let buckets = std::mem::take(&mut self.buckets);
+        for request in buckets.into_iter().flat_map(|bucket| bucket.requests) {
+            self.add_request(request);
+        }
+    }
+
+    /// Start of the bucket `timestamp` falls in
+    fn bucket_start(&self, timestamp: DateTime<Utc>) -> DateTime<Utc> {
+        let seconds = timestamp.timestamp();
+        let width = (self.bucket_minutes * 60) as i64;
+        DateTime::from_timestamp(seconds - seconds.rem_euclid(width), 0).unwrap_or(timestamp)
     }
 
     pub fn clear(&mut self) {
@@ -76,16 +178,19 @@ impl RollingWindow {
     pub fn set_window_minutes(&mut self, minutes: usize) {
         self.window_minutes = minutes;
         // Trim buckets if needed
-        while self.buckets.len() > minutes {
+        while self.buckets.len() > minutes.div_ceil(self.bucket_minutes) {
             self.buckets.pop_front();
         }
     }
 
-    pub fn add_request(&mut self, request: RequestInfo) {
-        // Round timestamp to minute
-        let minute = request.timestamp
-            .with_second(0).unwrap()
-            .with_nanosecond(0).unwrap();
+    /// Add a request to its bucket. Requests timestamped in the future (a skewed clock on
+    /// the machine that wrote the log) are counted as happening now rather than falling
+    /// off the right edge of every chart and time range.
+    pub fn add_request(&mut self, mut request: RequestInfo) {
+        request.timestamp = request.timestamp.min(Utc::now());
+
+        // Round timestamp down to the bucket granularity
+        let minute = self.bucket_start(request.timestamp);
 
         // Find or create bucket for this minute
         let bucket_pos = self.buckets.iter().position(|b| b.timestamp == minute);
@@ -110,6 +215,16 @@ impl RollingWindow {
         }
     }
 
+    /// Take back out a request passed to `add_request`, e.g. when a later copy of it
+    /// replaces it. Returns whether it was still in the window; a future-dated request is
+    /// only found if it was clamped before it was added.
+    pub fn remove_request(&mut self, request: &RequestInfo) -> bool {
+        self.buckets
+            .iter_mut()
+            .rev()
+            .any(|bucket| bucket.remove_request(request))
+    }
+
     fn trim_old_buckets(&mut self) {
         // Always keep at least 7 days of data for the stats panels
         // This ensures all time ranges (1h, 5h, 24h, 2d, 7d) work correctly regardless of chart view
@@ -119,8 +234,9 @@ impl RollingWindow {
         // Use the larger of the window size or minimum retention
         let retention_minutes = self.window_minutes.max(min_retention_minutes);
         
-        // Add a small buffer to ensure stats calculations at boundaries don't miss data
-        let buffer_minutes = 5;
+        // Add a small buffer to ensure stats calculations at boundaries don't miss data, plus
+        // one bucket since a bucket's timestamp is its start
+        let buffer_minutes = 5 + self.bucket_minutes;
         let cutoff = Utc::now() - Duration::minutes((retention_minutes + buffer_minutes) as i64);
         
         while let Some(bucket) = self.buckets.front() {
@@ -135,34 +251,132 @@ impl RollingWindow {
     pub fn get_minute_costs(&self, model_filter: Option<&ModelName>) -> Vec<(DateTime<Utc>, f64)> {
         self.buckets.iter().map(|bucket| {
             let cost = match model_filter {
-                Some(model) => bucket.model_costs.get(model.family()).copied().unwrap_or(0.0),
+                Some(model) => bucket.model_costs.get(model).copied().unwrap_or(0.0),
                 None => bucket.total_cost,
             };
             (bucket.timestamp, cost)
         }).collect()
     }
 
+    /// Total tokens per bucket, optionally for one model only
+    pub fn get_minute_tokens(&self, model_filter: Option<&ModelName>) -> Vec<(DateTime<Utc>, u64)> {
+        self.buckets
+            .iter()
+            .map(|bucket| {
+                let (input, output, cache) = bucket.token_counts(model_filter);
+                (bucket.timestamp, input + output + cache)
+            })
+            .collect()
+    }
+
+    /// Every retained request, oldest first
+    pub fn requests(&self) -> impl Iterator<Item = &RequestInfo> {
+        self.buckets.iter().flat_map(|bucket| &bucket.requests)
+    }
+
+    /// Write one CSV row per retained request, oldest first
+    pub fn export_to_csv<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writeln!(
+            writer,
+            "timestamp,session_id,model,input_tokens,output_tokens,cache_tokens,cost_usd"
+        )?;
+        for request in self.requests() {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{:.6}",
+                request.timestamp.to_rfc3339(),
+                request.session_id,
+                request.model.canonical_string(),
+                request.input_tokens,
+                request.output_tokens,
+                request.cache_tokens,
+                request.cost
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Write every retained request, oldest first, as a JSON array
+    pub fn export_to_json<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let requests: Vec<&RequestInfo> = self.requests().collect();
+        serde_json::to_writer_pretty(&mut *writer, &requests)?;
+        writeln!(writer)?;
+        Ok(())
+    }
+
+    /// Average requests per minute and tokens per minute over the last `minutes` minutes
+    pub fn get_rate(&self, minutes: i64) -> (f64, f64) {
+        if minutes <= 0 {
+            return (0.0, 0.0);
+        }
+
+        let cutoff = Utc::now() - Duration::minutes(minutes);
+        let mut total_requests = 0u64;
+        let mut total_tokens = 0u64;
+
+        // Filter on request timestamps; a coarse bucket can straddle the cutoff
+        for request in self.requests().filter(|r| r.timestamp >= cutoff) {
+            total_requests += 1;
+            total_tokens += (request.input_tokens + request.output_tokens + request.cache_tokens) as u64;
+        }
+
+        (
+            total_requests as f64 / minutes as f64,
+            total_tokens as f64 / minutes as f64,
+        )
+    }
+
+    /// Per-minute request counts for the last `minutes` minutes, oldest first, with empty
+    /// minutes filled in as zero (suitable for a sparkline)
+    pub fn get_minute_request_counts(&self, minutes: usize) -> Vec<u64> {
+        let now = Utc::now();
+        let mut counts = vec![0u64; minutes];
+
+        for request in self.requests() {
+            let minutes_ago = (now - request.timestamp).num_minutes();
+            if minutes_ago >= 0 && (minutes_ago as usize) < minutes {
+                counts[minutes - 1 - minutes_ago as usize] += 1;
+            }
+        }
+
+        counts
+    }
+
     /// Get stats for a specific time range
     fn get_time_range_stats(&self, hours: i64, model_filter: Option<&ModelName>) -> TimeRangeStats {
         let cutoff = Utc::now() - Duration::hours(hours);
+        self.get_stats_where(|r| r.timestamp >= cutoff, model_filter)
+    }
+
+    /// Stats for requests from `start` up to, but not including, `end`
+    pub fn get_stats_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        model_filter: Option<&ModelName>,
+    ) -> TimeRangeStats {
+        self.get_stats_where(|r| r.timestamp >= start && r.timestamp < end, model_filter)
+    }
+
+    fn get_stats_where(
+        &self,
+        keep: impl Fn(&RequestInfo) -> bool,
+        model_filter: Option<&ModelName>,
+    ) -> TimeRangeStats {
         let mut total_requests = 0u32;
         let mut total_tokens = 0u64;
         let mut total_cost = 0.0;
         let mut model_costs = HashMap::new();
 
-        for bucket in &self.buckets {
-            if bucket.timestamp >= cutoff {
-                for request in &bucket.requests {
-                    if model_filter.is_none() || request.model.family() == model_filter.unwrap().family() {
-                        total_requests += 1;
-                        total_tokens += (request.input_tokens + request.output_tokens + request.cache_tokens) as u64;
-                        total_cost += request.cost;
-                        
-                        // Also add to model breakdown (respecting filter)
-                        let model_key = request.model.family().to_string();
-                        *model_costs.entry(model_key).or_insert(0.0) += request.cost;
-                    }
-                }
+        // Filter on request timestamps; a coarse bucket can straddle the cutoff
+        for request in self.requests().filter(|r| keep(r)) {
+            if model_filter.is_none_or(|m| request.model == *m) {
+                total_requests += 1;
+                total_tokens += (request.input_tokens + request.output_tokens + request.cache_tokens) as u64;
+                total_cost += request.cost;
+                
+                // Also add to model breakdown (respecting filter)
+                *model_costs.entry(request.model.clone()).or_insert(0.0) += request.cost;
             }
         }
 
@@ -174,6 +388,44 @@ impl RollingWindow {
         }
     }
     
+    /// Totals for every retained request in sessions whose id starts with `session_id`,
+    /// regardless of the time range
+    pub fn get_stats_for_session(&self, session_id: &str) -> TimeRangeStats {
+        let mut stats = TimeRangeStats {
+            requests: 0,
+            tokens: 0,
+            cost: 0.0,
+            model_costs: HashMap::new(),
+        };
+
+        for request in self.requests() {
+            if request.in_session(session_id) {
+                stats.requests += 1;
+                stats.tokens += (request.input_tokens + request.output_tokens + request.cache_tokens) as u64;
+                stats.cost += request.cost;
+                *stats.model_costs.entry(request.model.clone()).or_insert(0.0) += request.cost;
+            }
+        }
+
+        stats
+    }
+
+    /// Cost per model over the last `hours`, most expensive first
+    pub fn get_model_costs(&self, hours: i64, model_filter: Option<&ModelName>) -> Vec<(ModelName, f64)> {
+        let cutoff = Utc::now() - Duration::hours(hours);
+        let mut model_costs: HashMap<ModelName, f64> = HashMap::new();
+
+        for request in self.requests().filter(|r| r.timestamp >= cutoff) {
+            if model_filter.is_none_or(|m| request.model == *m) {
+                *model_costs.entry(request.model.clone()).or_insert(0.0) += request.cost;
+            }
+        }
+
+        let mut model_costs: Vec<_> = model_costs.into_iter().collect();
+        model_costs.sort_by(|a, b| b.1.total_cmp(&a.1));
+        model_costs
+    }
+
     pub fn get_current_hour_stats(&self, model_filter: Option<&ModelName>) -> TimeRangeStats {
         self.get_time_range_stats(1, model_filter)
     }
@@ -193,4 +445,182 @@ impl RollingWindow {
     pub fn get_7d_stats(&self, model_filter: Option<&ModelName>) -> TimeRangeStats {
         self.get_time_range_stats(168, model_filter)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request_at(timestamp: DateTime<Utc>, tokens: u32) -> RequestInfo {
+        RequestInfo {
+            timestamp,
+            model: ModelName::Claude4Sonnet,
+            input_tokens: tokens,
+            output_tokens: 0,
+            cache_tokens: 0,
+            cost: 0.01,
+            session_id: "session-a".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_get_rate() {
+        let mut window = RollingWindow::new(60, 1);
+        let now = Utc::now();
+        window.add_request(request_at(now - Duration::minutes(1), 100));
+        window.add_request(request_at(now - Duration::minutes(2), 200));
+        window.add_request(request_at(now - Duration::minutes(3), 300));
+        // Outside the 5 minute rate window
+        window.add_request(request_at(now - Duration::minutes(30), 1000));
+
+        let (req_per_min, tokens_per_min) = window.get_rate(5);
+        assert!((req_per_min - 0.6).abs() < 1e-9);
+        assert!((tokens_per_min - 120.0).abs() < 1e-9);
+        assert_eq!(window.get_rate(0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_minute_request_counts() {
+        let mut window = RollingWindow::new(60, 1);
+        let now = Utc::now();
+        window.add_request(request_at(now - Duration::minutes(2), 10));
+        window.add_request(request_at(now - Duration::minutes(2), 10));
+
+        let counts = window.get_minute_request_counts(5);
+        assert_eq!(counts.len(), 5);
+        assert_eq!(counts.iter().sum::<u64>(), 2);
+    }
+
+    #[test]
+    fn test_stats_for_session() {
+        let mut window = RollingWindow::new(60, 1);
+        let now = Utc::now();
+        window.add_request(request_at(now - Duration::minutes(1), 100));
+        window.add_request(request_at(now - Duration::hours(30), 200));
+        window.add_request(RequestInfo {
+            session_id: "session-b".to_string(),
+            ..request_at(now - Duration::minutes(2), 1000)
+        });
+
+        let stats = window.get_stats_for_session("session-a");
+        assert_eq!(stats.requests, 2);
+        assert_eq!(stats.tokens, 300);
+        assert_eq!(window.get_stats_for_session("session-").requests, 3);
+        assert_eq!(window.get_stats_for_session("other").requests, 0);
+    }
+
+    #[test]
+    fn test_model_filter_matches_exact_model() {
+        let mut window = RollingWindow::new(60, 1);
+        let now = Utc::now();
+        window.add_request(request_at(now - Duration::minutes(1), 100));
+        window.add_request(RequestInfo {
+            model: ModelName::Claude37Sonnet,
+            ..request_at(now - Duration::minutes(1), 1000)
+        });
+
+        let sonnet_4 = Some(&ModelName::Claude4Sonnet);
+        let stats = window.get_time_range_stats(1, sonnet_4);
+        assert_eq!(stats.requests, 1);
+        assert_eq!(stats.tokens, 100);
+        assert_eq!(window.get_model_costs(1, sonnet_4).len(), 1);
+        let minute_cost: f64 = window.get_minute_costs(sonnet_4).iter().map(|(_, c)| c).sum();
+        assert!((minute_cost - 0.01).abs() < 1e-9);
+        let minute_tokens: u64 = window.get_minute_tokens(sonnet_4).iter().map(|(_, t)| t).sum();
+        assert_eq!(minute_tokens, 100);
+    }
+
+    #[test]
+    fn test_export() {
+        let mut window = RollingWindow::new(60, 1);
+        let now = Utc::now();
+        for minutes_ago in [3, 1, 2] {
+            window.add_request(request_at(now - Duration::minutes(minutes_ago), 100));
+        }
+
+        let mut csv = Vec::new();
+        window.export_to_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("timestamp,session_id,model,"));
+        assert!(lines[1].ends_with(",session-a,claude-sonnet-4-20250514,100,0,0,0.010000"));
+
+        let mut json = Vec::new();
+        window.export_to_json(&mut json).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        let rows = json.as_array().unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0]["model"], "claude-sonnet-4-20250514");
+        assert_eq!(rows[0]["cost_usd"], 0.01);
+    }
+
+    #[test]
+    fn test_bucket_granularity() {
+        let now = Utc::now();
+        let minutes_ago = [1, 7, 14, 29, 59, 61, 90, 300, 1500];
+
+        for bucket_minutes in [1, 5, 15] {
+            let mut window = RollingWindow::new(60, bucket_minutes);
+            for &ago in &minutes_ago {
+                window.add_request(request_at(now - Duration::minutes(ago), 100));
+            }
+
+            let width = bucket_minutes as i64 * 60;
+            assert!(window.buckets.iter().all(|b| b.timestamp.timestamp() % width == 0));
+            assert!(window.buckets.len() <= minutes_ago.len());
+            // Totals don't depend on how requests are bucketed
+            assert_eq!(window.get_current_hour_stats(None).requests, 5, "{} min buckets", bucket_minutes);
+            assert_eq!(window.get_5h_stats(None).requests, 7);
+            assert_eq!(window.get_24h_stats(None).tokens, 800);
+            assert!((window.get_rate(30).0 - 4.0 / 30.0).abs() < 1e-9);
+            assert_eq!(window.get_minute_request_counts(15).iter().sum::<u64>(), 3);
+        }
+
+        let mut window = RollingWindow::new(60, 1);
+        for &ago in &minutes_ago {
+            window.add_request(request_at(now - Duration::minutes(ago), 100));
+        }
+        window.set_bucket_minutes(15);
+        assert_eq!(window.bucket_minutes, 15);
+        assert_eq!(window.requests().count(), minutes_ago.len());
+        assert_eq!(window.get_current_hour_stats(None).requests, 5);
+    }
+
+    #[test]
+    fn test_minute_tokens() {
+        let mut window = RollingWindow::new(60, 1);
+        let minute = Utc::now() - Duration::minutes(2);
+        let mut opus = request_at(minute, 100);
+        opus.model = ModelName::Claude4Opus;
+        opus.output_tokens = 50;
+        let mut cached = request_at(minute, 200);
+        cached.cache_tokens = 1000;
+        window.add_request(opus);
+        window.add_request(cached);
+        window.add_request(request_at(minute, 300));
+
+        let bucket = &window.buckets[0];
+        assert_eq!(
+            (bucket.total_input_tokens, bucket.total_output_tokens, bucket.total_cache_tokens),
+            (600, 50, 1000)
+        );
+        assert_eq!(window.get_minute_tokens(None)[0].1, 1650);
+        assert_eq!(window.get_minute_tokens(Some(&ModelName::Claude4Sonnet))[0].1, 1500);
+
+        // Re-bucketing keeps the totals
+        window.set_bucket_minutes(5);
+        assert_eq!(window.get_minute_tokens(None).iter().map(|(_, t)| t).sum::<u64>(), 1650);
+    }
+
+    #[test]
+    fn test_future_request_counts_as_now() {
+        let mut window = RollingWindow::new(60, 1);
+        window.add_request(request_at(Utc::now() + Duration::minutes(10), 100));
+
+        assert!(window.buckets[0].timestamp <= Utc::now());
+        assert_eq!(window.get_minute_costs(None).len(), 1);
+        assert_eq!(window.get_minute_request_counts(5).iter().sum::<u64>(), 1);
+        assert_eq!(window.get_current_hour_stats(None).requests, 1);
+    }
+}
@@ -1,16 +1,250 @@
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
 
-use super::app::App;
+use super::app::{App, DashboardLayout};
 
 pub fn handle_key_event(key: KeyEvent, app: &mut App) {
+    if app.show_help {
+        // The overlay swallows every key; h, q and Esc close it
+        if matches!(key.code, KeyCode::Char('h') | KeyCode::Char('q') | KeyCode::Esc) {
+            app.toggle_help();
+        }
+        return;
+    }
+    if app.range_input.is_some() {
+        handle_range_input(key, app);
+        return;
+    }
+    if app.session_input.is_some() {
+        handle_session_input(key, app);
+        return;
+    }
+    if app.date_range_input.is_some() {
+        handle_date_range_input(key, app);
+        return;
+    }
+
     match key.code {
         KeyCode::Char('m') => app.cycle_model_filter(),
         KeyCode::Char('t') => app.cycle_time_range(),
+        KeyCode::Char('g') => app.start_range_input(),
+        KeyCode::Char('/') => app.start_date_range_input(),
         KeyCode::Char('c') => app.toggle_chart_type(),
+        KeyCode::Char('b') => app.toggle_token_chart(),
+        KeyCode::Char('f') => app.start_session_input(),
+        KeyCode::Char('e') => app.export_csv(),
         KeyCode::Up => app.scroll_feed_up(),
         KeyCode::Down => app.scroll_feed_down(),
+        KeyCode::PageUp => app.page_feed_up(),
+        KeyCode::PageDown => app.page_feed_down(),
+        KeyCode::Home => app.scroll_feed_to_newest(),
+        KeyCode::End => app.scroll_feed_to_oldest(),
         KeyCode::Char('p') => app.toggle_feed_pause(),
-        KeyCode::Char('r') => {} // Force refresh - will be handled in the next tick
+        KeyCode::Char('h') => app.toggle_help(),
+        KeyCode::Char('r') => app.refresh_now(),
+        _ => {}
+    }
+}
+
+/// Keys while typing a custom range in hours: digits, Backspace, Enter to apply, Esc to cancel
+fn handle_range_input(key: KeyEvent, app: &mut App) {
+    match key.code {
+        KeyCode::Char(c) if c.is_ascii_digit() => app.push_range_digit(c),
+        KeyCode::Backspace => app.pop_range_digit(),
+        KeyCode::Enter => app.submit_range_input(),
+        KeyCode::Esc => app.cancel_range_input(),
+        _ => {}
+    }
+}
+
+/// Keys while typing a start and end time: characters, Backspace, Enter to apply, Esc to cancel
+fn handle_date_range_input(key: KeyEvent, app: &mut App) {
+    match key.code {
+        KeyCode::Char(c) => app.push_date_range_char(c),
+        KeyCode::Backspace => app.pop_date_range_char(),
+        KeyCode::Enter => app.submit_date_range_input(),
+        KeyCode::Esc => app.cancel_date_range_input(),
         _ => {}
     }
-}
\ No newline at end of file
+}
+
+/// Keys while typing a session id prefix: characters, Backspace, Enter to apply (empty
+/// clears the filter), Esc to cancel
+fn handle_session_input(key: KeyEvent, app: &mut App) {
+    match key.code {
+        KeyCode::Char(c) => app.push_session_char(c),
+        KeyCode::Backspace => app.pop_session_char(),
+        KeyCode::Enter => app.submit_session_input(),
+        KeyCode::Esc => app.cancel_session_input(),
+        _ => {}
+    }
+}
+
+/// Mouse support: the wheel scrolls the feed while hovering over it, clicking a feed row
+/// selects it, and clicking the model label in the header cycles the model filter
+pub fn handle_mouse_event(event: MouseEvent, app: &mut App, layout: &DashboardLayout) {
+    let (column, row) = (event.column, event.row);
+    match event.kind {
+        MouseEventKind::ScrollUp if layout.is_over_feed(column, row) => app.scroll_feed_up(),
+        MouseEventKind::ScrollDown if layout.is_over_feed(column, row) => app.scroll_feed_down(),
+        MouseEventKind::Down(MouseButton::Left) => {
+            if layout.is_over_model_label(column, row) {
+                app.cycle_model_filter();
+            } else if let Some(index) = layout.feed_item_at(column, row, app.feed_scroll) {
+                app.select_feed_item(index);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dashboard::app::{ModelFilter, TimeRange, DATE_RANGE_FORMAT};
+    use crate::dashboard::data::RequestInfo;
+    use crate::model_name::ModelName;
+    use chrono::{Duration, Utc};
+    use crossterm::event::KeyModifiers;
+    use ratatui::layout::Rect;
+    use tempfile::TempDir;
+
+    fn mouse(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+        MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    fn app_with_feed(temp_dir: &TempDir, len: i64) -> App {
+        let claude_dir = temp_dir.path().to_string_lossy().to_string();
+        let mut app = App::new(vec![claude_dir], Some(1), 1.0, false, 3);
+        app.model_filter = ModelFilter::All;
+        app.inject_entries_for_test(
+            (0..len)
+                .map(|minutes_ago| RequestInfo {
+                    timestamp: Utc::now() - Duration::minutes(minutes_ago),
+                    model: ModelName::Claude4Sonnet,
+                    input_tokens: 100,
+                    output_tokens: 50,
+                    cache_tokens: 0,
+                    cost: 0.01,
+                    session_id: "test-session".to_string(),
+                })
+                .collect(),
+        );
+        app
+    }
+
+    fn layout() -> DashboardLayout {
+        DashboardLayout {
+            // Five visible rows between the borders, on screen rows 11-15
+            feed: Some(Rect::new(0, 10, 80, 7)),
+            model_label: Some(Rect::new(1, 1, 20, 1)),
+        }
+    }
+
+    #[test]
+    fn test_click_selects_feed_row() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = app_with_feed(&temp_dir, 20);
+        let layout = layout();
+        let click = MouseEventKind::Down(MouseButton::Left);
+
+        // The border row selects nothing
+        handle_mouse_event(mouse(click, 5, 10), &mut app, &layout);
+        assert_eq!(app.selected_feed_item, None);
+
+        handle_mouse_event(mouse(click, 5, 12), &mut app, &layout);
+        assert_eq!(app.selected_feed_item, Some(1));
+
+        // Rows map through the scroll offset
+        app.feed_scroll = 3;
+        handle_mouse_event(mouse(click, 5, 11), &mut app, &layout);
+        assert_eq!(app.selected_feed_item, Some(3));
+    }
+
+    #[test]
+    fn test_wheel_scrolls_only_over_feed() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = app_with_feed(&temp_dir, 20);
+        let layout = layout();
+
+        handle_mouse_event(mouse(MouseEventKind::ScrollDown, 5, 2), &mut app, &layout);
+        assert_eq!(app.feed_scroll, 0);
+
+        handle_mouse_event(mouse(MouseEventKind::ScrollDown, 5, 12), &mut app, &layout);
+        handle_mouse_event(mouse(MouseEventKind::ScrollDown, 5, 12), &mut app, &layout);
+        handle_mouse_event(mouse(MouseEventKind::ScrollUp, 5, 12), &mut app, &layout);
+        assert_eq!(app.feed_scroll, 1);
+    }
+
+    #[test]
+    fn test_click_model_label_cycles_filter() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = app_with_feed(&temp_dir, 1);
+        let layout = layout();
+
+        let click = MouseEventKind::Down(MouseButton::Left);
+        handle_mouse_event(mouse(click, 30, 1), &mut app, &layout);
+        assert_eq!(app.model_filter, ModelFilter::All);
+
+        handle_mouse_event(mouse(click, 3, 1), &mut app, &layout);
+        assert_eq!(app.model_filter, ModelFilter::Specific(ModelName::Claude4Sonnet));
+    }
+
+    #[test]
+    fn test_help_overlay_toggles_and_swallows_keys() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = app_with_feed(&temp_dir, 1);
+        let key = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+
+        handle_key_event(key('h'), &mut app);
+        assert!(app.show_help);
+
+        // Other keys are ignored while the overlay is open
+        handle_key_event(key('p'), &mut app);
+        assert!(!app.feed_paused);
+
+        handle_key_event(key('h'), &mut app);
+        assert!(!app.show_help);
+
+        handle_key_event(key('h'), &mut app);
+        handle_key_event(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE), &mut app);
+        assert!(!app.show_help);
+
+        handle_key_event(key('h'), &mut app);
+        handle_key_event(key('q'), &mut app);
+        assert!(!app.show_help);
+    }
+    #[test]
+    fn test_date_range_prompt_sets_fixed_span() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = app_with_feed(&temp_dir, 1);
+        let press = |app: &mut App, code| {
+            handle_key_event(KeyEvent::new(code, KeyModifiers::NONE), app)
+        };
+        let local = |hours_ago| {
+            (chrono::Local::now() - Duration::hours(hours_ago))
+                .format(DATE_RANGE_FORMAT)
+                .to_string()
+        };
+
+        press(&mut app, KeyCode::Char('/'));
+        for c in format!("{} {}", local(3), local(1)).chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert!(app.date_range_input.is_none());
+        assert!(matches!(app.time_range, TimeRange::Between { .. }));
+        assert_eq!(app.time_range.minutes(), 120);
+
+        // Esc leaves the range alone
+        press(&mut app, KeyCode::Char('/'));
+        press(&mut app, KeyCode::Char('2'));
+        press(&mut app, KeyCode::Esc);
+        assert!(app.date_range_input.is_none());
+        assert_eq!(app.time_range.minutes(), 120);
+    }
+}
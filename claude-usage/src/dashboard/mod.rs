@@ -6,4 +6,4 @@ pub mod ui;
 pub mod widgets;
 
 // Re-export the main function
-pub use runner::run_dashboard;
\ No newline at end of file
+pub use runner::{print_dry_run, print_snapshot, run_dashboard, RefreshRates};
\ No newline at end of file
@@ -1,6 +1,7 @@
 use anyhow::Result;
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    cursor::Show,
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -9,78 +10,199 @@ use ratatui::{
     Terminal,
 };
 use std::io;
+use std::panic;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use super::app::{App, ModelFilter};
-use super::events::handle_key_event;
+use super::app::{App, ModelFilter, RangeStatsSnapshot};
+use super::events::{handle_key_event, handle_mouse_event};
 use super::ui;
 use crate::model_name::ModelName;
 
+/// How often the dashboard redraws and how often it re-reads the logs
+#[derive(Debug, Clone, Copy)]
+pub struct RefreshRates {
+    /// Redraw interval in seconds
+    pub display_seconds: f64,
+    /// Re-read at least this often (seconds) when file watching is active
+    pub watch_interval_seconds: f64,
+    /// Never re-read more often than this (milliseconds)
+    pub min_data_interval_ms: u64,
+}
+
+/// Leave raw mode and the alternate screen, ignoring errors
+fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture, Show);
+}
+
+type PanicHook = Box<dyn Fn(&panic::PanicHookInfo) + Send + Sync>;
+
+/// Raw mode, the alternate screen and a panic hook that undoes them, for as long as it
+/// lives. Dropping it restores the terminal and the previous hook however the dashboard
+/// exits, including early `?` returns.
+struct TerminalGuard {
+    previous_hook: Arc<PanicHook>,
+}
+
+impl TerminalGuard {
+    fn new() -> Result<Self> {
+        // Put the terminal back before the panic message is printed, or it's garbled and
+        // the shell is left in raw mode
+        let previous_hook = Arc::new(panic::take_hook());
+        let hook = previous_hook.clone();
+        panic::set_hook(Box::new(move |info| {
+            restore_terminal();
+            hook(info);
+        }));
+        // Created before the terminal is touched so a failure below is undone too
+        let guard = TerminalGuard { previous_hook };
+
+        enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+        // The hook can't be changed while unwinding, and ours has already run by then
+        if !std::thread::panicking() {
+            let previous_hook = self.previous_hook.clone();
+            panic::set_hook(Box::new(move |info| previous_hook(info)));
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)] // One per dashboard flag
 pub async fn run_dashboard(
-    refresh_seconds: f64,
-    initial_hours: usize,
+    rates: RefreshRates,
+    initial_hours: Option<usize>,
     initial_model: Option<String>,
-    claude_dir: String,
+    claude_dirs: Vec<String>,
+    checksums: bool,
+    max_depth: usize,
+    discover_max_depth: bool,
+    feed_size: usize,
 ) -> Result<()> {
-    // Setup terminal
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
+    let guard = TerminalGuard::new()?;
+    let backend = CrosstermBackend::new(io::stdout());
     let mut terminal = Terminal::new(backend)?;
 
     // Create app state
-    let mut app = App::new(claude_dir, initial_hours, refresh_seconds);
+    let mut app = App::new(claude_dirs, initial_hours, rates.display_seconds, checksums, max_depth)
+        .with_file_watcher(Duration::from_secs_f64(rates.watch_interval_seconds))
+        .with_min_refresh_interval(Duration::from_millis(rates.min_data_interval_ms))
+        .with_feed_size(feed_size);
     if let Some(model_str) = initial_model {
         // Try to parse the model string into a ModelName
         let model_name = ModelName::from_model_string(&model_str);
         app.model_filter = ModelFilter::Specific(model_name);
     }
+    if discover_max_depth {
+        app.check_max_depth();
+    }
 
     // Initial data load
     app.refresh_data()?;
 
-    let res = run_app(&mut terminal, app, Duration::from_secs_f64(refresh_seconds)).await;
-
-    // Restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+    let res = run_app(&mut terminal, &mut app, Duration::from_secs_f64(rates.display_seconds)).await;
+    // Restore the terminal before printing anything
+    drop(guard);
 
     if let Err(err) = res {
         eprintln!("Error: {:?}", err);
     }
+    print_warnings(&app);
 
+    if let Err(err) = app.save_prefs() {
+        eprintln!("Warning: Failed to save dashboard preferences: {}", err);
+    }
+
+    Ok(())
+}
+
+/// Load the logs once and print the stats panel's time ranges as JSON, without the TUI.
+/// Stats cover all models unless `model` is given; saved dashboard preferences are left alone.
+pub fn print_snapshot(
+    model: Option<String>,
+    claude_dirs: Vec<String>,
+    checksums: bool,
+    max_depth: usize,
+) -> Result<()> {
+    let snapshot = load_snapshot(model, claude_dirs, checksums, max_depth)?;
+    println!("{}", serde_json::to_string_pretty(&snapshot)?);
+    Ok(())
+}
+
+/// `dashboard --dry-run`: load the data as the dashboard would on startup and print the
+/// stats to stderr, then exit without starting the TUI
+pub fn print_dry_run(
+    model: Option<String>,
+    claude_dirs: Vec<String>,
+    checksums: bool,
+    max_depth: usize,
+) -> Result<()> {
+    let snapshot = load_snapshot(model, claude_dirs, checksums, max_depth)?;
+    eprintln!("Dry run: dashboard data loaded, not starting the dashboard");
+    eprintln!("{}", serde_json::to_string_pretty(&snapshot)?);
     Ok(())
 }
 
+fn load_snapshot(
+    model: Option<String>,
+    claude_dirs: Vec<String>,
+    checksums: bool,
+    max_depth: usize,
+) -> Result<RangeStatsSnapshot> {
+    let mut app = App::new(claude_dirs, None, 0.0, checksums, max_depth);
+    app.model_filter = match model {
+        Some(model_str) => ModelFilter::Specific(ModelName::from_model_string(&model_str)),
+        None => ModelFilter::All,
+    };
+    app.refresh_data()?;
+    print_warnings(&app);
+    Ok(app.get_range_stats_snapshot())
+}
+
+/// Print the warnings the header showed, once the terminal is usable again
+fn print_warnings(app: &App) {
+    for warning in &app.warnings {
+        eprintln!("Warning: {}", warning);
+    }
+}
+
 async fn run_app<B: Backend>(
     terminal: &mut Terminal<B>,
-    mut app: App,
+    app: &mut App,
     tick_rate: Duration,
 ) -> io::Result<()> {
     let mut last_tick = Instant::now();
 
     loop {
-        terminal.draw(|f| ui::draw(f, &mut app))?;
+        terminal.draw(|f| ui::draw(f, app))?;
 
         let timeout = tick_rate
             .checked_sub(last_tick.elapsed())
             .unwrap_or_else(|| Duration::from_secs(0));
 
         if crossterm::event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Char('q') => return Ok(()),
+            match event::read()? {
+                Event::Key(key) => match key.code {
+                    // With the help overlay open, q closes it instead of quitting
+                    KeyCode::Char('q') if !app.show_help => return Ok(()),
                     KeyCode::Char('c') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
                         return Ok(());
                     }
-                    _ => handle_key_event(key, &mut app),
+                    _ => handle_key_event(key, app),
+                },
+                Event::Mouse(mouse) => {
+                    // Hit-test against the areas from the frame the user is looking at
+                    let layout = app.layout;
+                    handle_mouse_event(mouse, app, &layout);
                 }
+                _ => {}
             }
         }
 
@@ -2,13 +2,13 @@ use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, Clear, Paragraph},
     Frame,
 };
 use chrono::Local;
 
-use super::app::{App, ModelFilter};
-use super::widgets::{minute_chart::draw_minute_chart, request_feed::draw_request_feed, stats_panel::draw_stats_panel, summary_bar::draw_summary_bar};
+use super::app::{App, ModelFilter, TimeRange, MAX_CUSTOM_HOURS};
+use super::widgets::{minute_chart::draw_minute_chart, request_feed::draw_request_feed, stats_panel::draw_stats_panel, summary_bar::{draw_summary_bar, draw_summary_sparkline, SPARKLINE_WIDTH}, throughput::draw_throughput};
 
 pub fn draw(f: &mut Frame, app: &mut App) {
     let chunks = Layout::default()
@@ -27,25 +27,69 @@ pub fn draw(f: &mut Frame, app: &mut App) {
     draw_header(f, chunks[0], app);
     draw_main_content(f, chunks[1], app);
     draw_summary_bar(f, chunks[2], app);
-    draw_help(f, chunks[3]);
+    draw_help(f, chunks[3], app);
+
+    if app.show_help {
+        draw_help_overlay(f, f.size());
+    }
 }
 
-fn draw_header(f: &mut Frame, area: Rect, app: &App) {
+fn draw_header(f: &mut Frame, area: Rect, app: &mut App) {
     let model_text = match &app.model_filter {
         ModelFilter::All => "All Models".to_string(),
         ModelFilter::Specific(m) => m.to_string(),
     };
 
+    // "Model: <name> ▼" on the first line inside the border, clickable to cycle the filter
+    let label_width = ("Model: ".len() + model_text.chars().count() + " ▼".chars().count()) as u16;
+    app.layout.model_label = Some(Rect {
+        x: area.x + 1,
+        y: area.y + 1,
+        width: label_width.min(area.width.saturating_sub(2)),
+        height: 1,
+    });
+
     let local_update_time = app.last_update.with_timezone(&Local);
-    let header_text = vec![
+    let mut header_text = vec![
         Span::raw("Model: "),
         Span::styled(model_text, Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
         Span::raw(" ▼ | Last Update: "),
         Span::raw(local_update_time.format("%H:%M:%S").to_string()),
         Span::raw(format!(" | Auto-refresh: {}s", app.refresh_rate)),
     ];
+    if let TimeRange::Between { start, end } = app.time_range {
+        let local = |time: chrono::DateTime<chrono::Utc>| {
+            time.with_timezone(&Local).format("%m-%d %H:%M").to_string()
+        };
+        header_text.push(Span::raw(" | "));
+        header_text.push(Span::styled(
+            format!("Custom: {} – {}", local(start), local(end)),
+            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+        ));
+    }
+    if app.catching_up {
+        header_text.push(Span::styled(
+            " | Catching up…",
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+        ));
+    }
+    if let Some(status) = &app.export_status {
+        header_text.push(Span::styled(format!(" | {}", status), Style::default().fg(Color::Green)));
+    }
+    if let Some(warning) = app.warnings.last() {
+        let more = match app.warnings.len() {
+            1 => String::new(),
+            count => format!(" (+{} more)", count - 1),
+        };
+        header_text.push(Span::styled(
+            format!(" | ⚠ {}{}", warning, more),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
 
-    let header = Paragraph::new(Line::from(header_text))
+    let header_line = Line::from(header_text);
+    let text_width = header_line.width() as u16;
+    let header = Paragraph::new(header_line)
         .style(Style::default().fg(Color::White))
         .block(
             Block::default()
@@ -56,6 +100,18 @@ fn draw_header(f: &mut Frame, area: Rect, app: &App) {
         );
 
     f.render_widget(header, area);
+
+    // Weekly cost trend at the right end of the header line, when there's room for it
+    let inner_width = area.width.saturating_sub(2);
+    if inner_width > text_width + SPARKLINE_WIDTH + 1 {
+        let sparkline_area = Rect {
+            x: area.x + 1 + inner_width - SPARKLINE_WIDTH,
+            y: area.y + 1,
+            width: SPARKLINE_WIDTH,
+            height: 1,
+        };
+        draw_summary_sparkline(f, sparkline_area, app);
+    }
 }
 
 fn draw_main_content(f: &mut Frame, area: Rect, app: &mut App) {
@@ -76,6 +132,7 @@ fn draw_main_content(f: &mut Frame, area: Rect, app: &mut App) {
         .constraints(
             [
                 Constraint::Length(16),  // Chart (increased from 12)
+                Constraint::Length(4),   // Throughput
                 Constraint::Min(5),      // Feed
             ]
             .as_ref(),
@@ -83,13 +140,133 @@ fn draw_main_content(f: &mut Frame, area: Rect, app: &mut App) {
         .split(chunks[0]);
 
     draw_minute_chart(f, left_chunks[0], app);
-    draw_request_feed(f, left_chunks[1], app);
+    draw_throughput(f, left_chunks[1], app);
+    app.layout.feed = Some(left_chunks[2]);
+    draw_request_feed(f, left_chunks[2], app);
 
     // Right side - stats
     draw_stats_panel(f, chunks[1], app);
 }
 
-fn draw_help(f: &mut Frame, area: Rect) {
+/// Every keybinding with what it does, in the order shown by the help overlay
+const KEYBINDINGS: &[(&str, &str)] = &[
+    ("q / Ctrl-C", "Quit"),
+    ("h", "Show or hide this help"),
+    ("m", "Cycle model filter (or click the model label)"),
+    ("t", "Cycle time range"),
+    ("g", "Go to a custom range in hours"),
+    ("/", "Show a fixed span: start end as YYYY-MM-DDTHH:MM"),
+    ("c", "Toggle chart type"),
+    ("b", "Toggle the tokens chart"),
+    ("f", "Filter the feed by session id prefix"),
+    ("e", "Export the feed to CSV"),
+    ("↑ / ↓", "Scroll the feed (or use the mouse wheel)"),
+    ("PgUp / PgDn", "Scroll the feed a page at a time"),
+    ("Home / End", "Jump to the newest / oldest request"),
+    ("p", "Pause or resume the feed"),
+    ("r", "Refresh now"),
+    ("Click", "Select a feed row"),
+];
+
+/// Centered popup listing all keybindings, drawn over the rest of the dashboard
+fn draw_help_overlay(f: &mut Frame, area: Rect) {
+    let key_width = KEYBINDINGS.iter().map(|(key, _)| key.chars().count()).max().unwrap_or(0);
+    let mut lines: Vec<Line> = KEYBINDINGS
+        .iter()
+        .map(|(key, description)| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{:<width$}", key, width = key_width),
+                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw("  "),
+                Span::raw(*description),
+            ])
+        })
+        .collect();
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "Press h, q or Esc to close",
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    let popup = centered_rect(60, lines.len() as u16 + 2, area);
+    let help = Paragraph::new(lines).block(
+        Block::default()
+            .title(" Keybindings ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+    f.render_widget(Clear, popup);
+    f.render_widget(help, popup);
+}
+
+/// A `width` x `height` rectangle centered in `area`, shrunk to fit if `area` is smaller
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+fn draw_help(f: &mut Frame, area: Rect, app: &App) {
+    if let Some(input) = &app.range_input {
+        let prompt = vec![
+            Span::raw("Range in hours (1-"),
+            Span::raw(MAX_CUSTOM_HOURS.to_string()),
+            Span::raw("): "),
+            Span::styled(
+                format!("{}_", input),
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("  [Enter] apply [Esc] cancel"),
+        ];
+        let help = Paragraph::new(Line::from(prompt))
+            .style(Style::default().fg(Color::White))
+            .block(Block::default().borders(Borders::TOP))
+            .alignment(Alignment::Center);
+        f.render_widget(help, area);
+        return;
+    }
+
+    if let Some(input) = &app.date_range_input {
+        let prompt = vec![
+            Span::raw("Start and end (YYYY-MM-DDTHH:MM YYYY-MM-DDTHH:MM): "),
+            Span::styled(
+                format!("{}_", input),
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("  [Enter] apply [Esc] cancel"),
+        ];
+        let help = Paragraph::new(Line::from(prompt))
+            .style(Style::default().fg(Color::White))
+            .block(Block::default().borders(Borders::TOP))
+            .alignment(Alignment::Center);
+        f.render_widget(help, area);
+        return;
+    }
+
+    if let Some(input) = &app.session_input {
+        let prompt = vec![
+            Span::raw("Session id prefix: "),
+            Span::styled(
+                format!("{}_", input),
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("  [Enter] apply (empty clears) [Esc] cancel"),
+        ];
+        let help = Paragraph::new(Line::from(prompt))
+            .style(Style::default().fg(Color::White))
+            .block(Block::default().borders(Borders::TOP))
+            .alignment(Alignment::Center);
+        f.render_widget(help, area);
+        return;
+    }
+
     let help_text = vec![
         Span::raw("["),
         Span::styled("q", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
@@ -98,12 +275,24 @@ fn draw_help(f: &mut Frame, area: Rect) {
         Span::raw("]odel ["),
         Span::styled("t", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
         Span::raw("]ime ["),
+        Span::styled("g", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+        Span::raw("]o to range ["),
         Span::styled("c", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
         Span::raw("]hart ["),
+        Span::styled("b", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+        Span::raw("] tokens ["),
+        Span::styled("f", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+        Span::raw("]ilter session ["),
+        Span::styled("e", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+        Span::raw("]xport ["),
         Span::styled("↑↓", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
         Span::raw("] scroll ["),
+        Span::styled("PgUp/PgDn", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+        Span::raw("] page ["),
         Span::styled("p", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
-        Span::raw("]ause"),
+        Span::raw("]ause ["),
+        Span::styled("h", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+        Span::raw("]elp"),
     ];
 
     let help = Paragraph::new(Line::from(help_text))
@@ -0,0 +1,131 @@
+use chrono::{DateTime, Datelike, Months, NaiveDate, Utc};
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use crate::dashboard::app::{App, ModelFilter};
+
+/// Days of history the daily average is taken over
+const PROJECTION_DAYS: f64 = 7.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CostProjection {
+    pub today: f64,
+    pub month: f64,
+}
+
+/// Project today's and this month's cost from the total spent over the last 7 days,
+/// assuming every day costs the 7-day daily average
+pub fn project_costs(seven_day_cost: f64, now: DateTime<Utc>) -> CostProjection {
+    let daily_average = seven_day_cost / PROJECTION_DAYS;
+    CostProjection {
+        today: daily_average,
+        month: daily_average * days_in_month(now.date_naive()) as f64,
+    }
+}
+
+fn days_in_month(date: NaiveDate) -> i64 {
+    let first = date.with_day(1).unwrap();
+    (first + Months::new(1) - first).num_days()
+}
+
+pub fn draw_cost_projection_widget(f: &mut Frame, area: Rect, app: &App) {
+    let model_filter = match &app.model_filter {
+        ModelFilter::All => None,
+        ModelFilter::Specific(m) => Some(m),
+    };
+    let seven_day_cost = app.rolling_window.get_7d_stats(model_filter).cost;
+    let projection = project_costs(seven_day_cost, Utc::now());
+
+    let budget = app.budget.as_ref();
+    let style_for = |cost: f64, limit: Option<f64>| {
+        let color = if limit.is_some_and(|limit| cost > limit) {
+            Color::Red
+        } else {
+            Color::Green
+        };
+        Style::default().fg(color).add_modifier(Modifier::BOLD)
+    };
+
+    let lines = vec![
+        Line::from(vec![
+            Span::raw("Today projected: "),
+            Span::styled(
+                format!("${:.2}", projection.today),
+                style_for(projection.today, budget.and_then(|b| b.daily)),
+            ),
+        ]),
+        Line::from(vec![
+            Span::raw("Month projected: "),
+            Span::styled(
+                format!("${:.2}", projection.month),
+                style_for(projection.month, budget.and_then(|b| b.monthly)),
+            ),
+        ]),
+    ];
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .title(" Projection (7-day avg) ")
+            .borders(Borders::ALL),
+    );
+    f.render_widget(paragraph, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dashboard::data::RequestInfo;
+    use crate::model_name::ModelName;
+    use chrono::{Duration, TimeZone};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_projection_follows_daily_average() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = App::new(
+            vec![temp_dir.path().to_string_lossy().to_string()],
+            Some(1),
+            1.0,
+            false,
+            3,
+        );
+        let now = Utc::now();
+        let requests = (0..7)
+            .map(|day| RequestInfo {
+                timestamp: now - Duration::hours(day * 24 + 1),
+                model: ModelName::Claude4Sonnet,
+                input_tokens: 1000,
+                output_tokens: 100,
+                cache_tokens: 0,
+                cost: 2.0,
+                session_id: "test-session".to_string(),
+            })
+            .collect();
+        app.inject_entries_for_test(requests);
+
+        let seven_day_cost = app.rolling_window.get_7d_stats(None).cost;
+        assert!((seven_day_cost - 14.0).abs() < 1e-9);
+
+        // June has 30 days
+        let june = Utc.with_ymd_and_hms(2025, 6, 10, 12, 0, 0).unwrap();
+        let projection = project_costs(seven_day_cost, june);
+        assert!((projection.today - 2.0).abs() < 1e-9);
+        assert!((projection.month - 60.0).abs() < 1e-9);
+
+        // Doubling spend doubles both projections
+        let doubled = project_costs(seven_day_cost * 2.0, june);
+        assert!((doubled.today - 2.0 * projection.today).abs() < 1e-9);
+        assert!((doubled.month - 2.0 * projection.month).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_days_in_month() {
+        assert_eq!(days_in_month(NaiveDate::from_ymd_opt(2024, 2, 10).unwrap()), 29);
+        assert_eq!(days_in_month(NaiveDate::from_ymd_opt(2025, 12, 31).unwrap()), 31);
+    }
+}
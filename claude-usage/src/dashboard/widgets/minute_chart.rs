@@ -7,12 +7,21 @@ use ratatui::{
     Frame,
 };
 
-use crate::dashboard::app::{App, ChartType, ModelFilter};
+use crate::dashboard::app::{App, ChartType, ModelFilter, TimeRange};
+
+/// Label of the rightmost bucket: the present, or the end of a fixed span
+fn end_label(app: &App) -> &'static str {
+    match app.time_range {
+        TimeRange::Between { .. } => "end",
+        _ => "now",
+    }
+}
 
 pub fn draw_minute_chart(f: &mut Frame, area: Rect, app: &App) {
     match app.chart_type {
         ChartType::Bar => draw_bar_chart(f, area, app),
         ChartType::Line => draw_line_chart(f, area, app),
+        ChartType::Tokens => draw_tokens_chart(f, area, app),
     }
 }
 
@@ -25,11 +34,11 @@ fn draw_bar_chart(f: &mut Frame, area: Rect, app: &App) {
     let minute_costs = app.rolling_window.get_minute_costs(model_filter);
     
     // Create bars for the last N minutes
-    let now = chrono::Utc::now();
+    let now = app.time_range.chart_end(chrono::Utc::now());
     let window_minutes = app.time_range.minutes();
     
-    // Group data into buckets (e.g., 5-minute buckets for better visibility)
-    let bucket_size = if window_minutes <= 60 { 1 } else if window_minutes <= 360 { 5 } else { 10 };
+    // One bar per rolling window bucket, which is already coarser for long ranges
+    let bucket_size = app.time_range.bucket_minutes();
     let num_buckets = window_minutes / bucket_size;
     
     let mut buckets: Vec<(String, f64)> = Vec::new();
@@ -38,10 +47,10 @@ fn draw_bar_chart(f: &mut Frame, area: Rect, app: &App) {
     for i in 0..num_buckets {
         let minutes_ago = i * bucket_size;
         let label = if minutes_ago == 0 {
-            "now".to_string()
-        } else if minutes_ago % 60 == 0 {
+            end_label(app).to_string()
+        } else if minutes_ago.is_multiple_of(60) {
             format!("-{}h", minutes_ago / 60)
-        } else if minutes_ago % 10 == 0 {
+        } else if minutes_ago.is_multiple_of(10) {
             format!("-{}", minutes_ago)
         } else {
             String::new()
@@ -99,11 +108,11 @@ fn draw_line_chart(f: &mut Frame, area: Rect, app: &App) {
     let minute_costs = app.rolling_window.get_minute_costs(model_filter);
     
     // Get time window parameters
-    let now = chrono::Utc::now();
+    let now = app.time_range.chart_end(chrono::Utc::now());
     let window_minutes = app.time_range.minutes();
     
-    // Group data into buckets for smoothing
-    let bucket_size = if window_minutes <= 60 { 1 } else if window_minutes <= 360 { 5 } else { 10 };
+    // One point per rolling window bucket, which is already coarser for long ranges
+    let bucket_size = app.time_range.bucket_minutes();
     let num_buckets = window_minutes / bucket_size;
     
     let mut data_points: Vec<(f64, f64)> = Vec::new();
@@ -136,8 +145,8 @@ fn draw_line_chart(f: &mut Frame, area: Rect, app: &App) {
         .map(|i| {
             let minutes_ago = (num_buckets - 1 - i) * bucket_size;
             if minutes_ago == 0 {
-                Span::raw("now")
-            } else if minutes_ago >= 60 && minutes_ago % 60 == 0 {
+                Span::raw(end_label(app))
+            } else if minutes_ago >= 60 && minutes_ago.is_multiple_of(60) {
                 Span::raw(format!("-{}h", minutes_ago / 60))
             } else {
                 Span::raw(format!("-{}m", minutes_ago))
@@ -183,4 +192,107 @@ fn draw_line_chart(f: &mut Frame, area: Rect, app: &App) {
         );
     
     f.render_widget(chart, area);
-}
\ No newline at end of file
+}
+
+/// Compact token count for axis labels
+fn short_tokens(tokens: f64) -> String {
+    if tokens >= 1_000_000.0 {
+        format!("{:.1}M", tokens / 1_000_000.0)
+    } else if tokens >= 1_000.0 {
+        format!("{:.0}k", tokens / 1_000.0)
+    } else {
+        format!("{:.0}", tokens)
+    }
+}
+
+fn draw_tokens_chart(f: &mut Frame, area: Rect, app: &App) {
+    let model_filter = match &app.model_filter {
+        ModelFilter::All => None,
+        ModelFilter::Specific(m) => Some(m),
+    };
+
+    let now = app.time_range.chart_end(chrono::Utc::now());
+    let window_minutes = app.time_range.minutes();
+    let bucket_size = app.time_range.bucket_minutes();
+    let num_buckets = window_minutes / bucket_size;
+
+    // Input, output and cache tokens per bucket, newest first
+    let mut buckets: Vec<[f64; 3]> = vec![[0.0; 3]; num_buckets];
+    for bucket in &app.rolling_window.buckets {
+        let minutes_ago = (now - bucket.timestamp).num_minutes() as usize;
+        let bucket_idx = minutes_ago / bucket_size;
+        if bucket_idx < buckets.len() {
+            let (input, output, cache) = bucket.token_counts(model_filter);
+            buckets[bucket_idx][0] += input as f64;
+            buckets[bucket_idx][1] += output as f64;
+            buckets[bucket_idx][2] += cache as f64;
+        }
+    }
+
+    // One series per token kind (x: time index, y: tokens), newest on the right
+    let series: Vec<Vec<(f64, f64)>> = (0..3)
+        .map(|kind| {
+            buckets
+                .iter()
+                .enumerate()
+                .map(|(i, counts)| ((num_buckets - 1 - i) as f64, counts[kind]))
+                .collect()
+        })
+        .collect();
+
+    let max_tokens = buckets.iter().flatten().fold(0.0, |max: f64, &tokens| max.max(tokens));
+    let y_max = if max_tokens > 0.0 { max_tokens * 1.1 } else { 100.0 }; // Add 10% padding
+
+    let x_labels: Vec<Span> = (0..num_buckets)
+        .step_by((num_buckets / 10).max(1))
+        .map(|i| {
+            let minutes_ago = (num_buckets - 1 - i) * bucket_size;
+            if minutes_ago == 0 {
+                Span::raw(end_label(app))
+            } else if minutes_ago >= 60 && minutes_ago.is_multiple_of(60) {
+                Span::raw(format!("-{}h", minutes_ago / 60))
+            } else {
+                Span::raw(format!("-{}m", minutes_ago))
+            }
+        })
+        .collect();
+
+    let y_labels: Vec<Span> = (0..=5)
+        .map(|i| Span::raw(short_tokens(y_max * (i as f64) / 5.0)))
+        .collect();
+
+    let datasets = [("Input", Color::Cyan), ("Output", Color::Yellow), ("Cache", Color::Blue)]
+        .into_iter()
+        .zip(&series)
+        .map(|((name, color), data)| {
+            Dataset::default()
+                .name(name)
+                .marker(symbols::Marker::Braille)
+                .style(Style::default().fg(color))
+                .data(data)
+        })
+        .collect();
+
+    let chart = Chart::new(datasets)
+        .block(
+            Block::default()
+                .title(format!(" Rolling {}-Minute Tokens ", window_minutes))
+                .borders(Borders::ALL),
+        )
+        .x_axis(
+            Axis::default()
+                .title("Time")
+                .style(Style::default().fg(Color::Gray))
+                .labels(x_labels)
+                .bounds([0.0, (num_buckets - 1) as f64]),
+        )
+        .y_axis(
+            Axis::default()
+                .title("Tokens")
+                .style(Style::default().fg(Color::Gray))
+                .labels(y_labels)
+                .bounds([0.0, y_max]),
+        );
+
+    f.render_widget(chart, area);
+}
@@ -1,4 +1,6 @@
+pub mod cost_projection;
 pub mod minute_chart;
 pub mod request_feed;
 pub mod stats_panel;
-pub mod summary_bar;
\ No newline at end of file
+pub mod summary_bar;
+pub mod throughput;
\ No newline at end of file
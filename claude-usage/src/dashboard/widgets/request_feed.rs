@@ -11,11 +11,11 @@ use crate::dashboard::app::App;
 
 pub fn draw_request_feed(f: &mut Frame, area: Rect, app: &App) {
     let items: Vec<ListItem> = app
-        .request_feed
-        .iter()
+        .visible_feed()
+        .enumerate()
         .skip(app.feed_scroll)
         .take(area.height as usize - 2) // Account for borders
-        .map(|request| {
+        .map(|(index, request)| {
             let model_color = match request.model.family() {
                 "opus" => Color::Magenta,
                 "sonnet" => Color::Yellow,
@@ -29,7 +29,7 @@ pub fn draw_request_feed(f: &mut Frame, area: Rect, app: &App) {
                 Span::raw(local_time.format("%Y-%m-%d %H:%M:%S").to_string()),
                 Span::raw("] "),
                 Span::styled(
-                    format!("{:<8}", request.model.family()),
+                    format!("{:<8}", request.model.display_short()),
                     Style::default().fg(model_color).add_modifier(Modifier::BOLD),
                 ),
                 Span::raw(" │ "),
@@ -45,15 +45,22 @@ pub fn draw_request_feed(f: &mut Frame, area: Rect, app: &App) {
                 ),
             ];
 
-            ListItem::new(Line::from(line))
+            let item = ListItem::new(Line::from(line));
+            if app.selected_feed_item == Some(index) {
+                item.style(Style::default().add_modifier(Modifier::REVERSED))
+            } else {
+                item
+            }
         })
         .collect();
 
-    let title = if app.feed_paused {
-        " Live Request Feed [PAUSED] "
-    } else {
-        " Live Request Feed "
-    };
+    let mut title = " Live Request Feed ".to_string();
+    if let Some(session) = &app.selected_session {
+        title.push_str(&format!("[session {}…] ", session));
+    }
+    if app.feed_paused {
+        title.push_str("[PAUSED] ");
+    }
 
     let feed = List::new(items)
         .block(
@@ -6,8 +6,9 @@ use ratatui::{
     Frame,
 };
 
-use crate::dashboard::app::{App, ModelFilter};
+use crate::dashboard::app::{App, ModelFilter, TimeRange};
 use crate::dashboard::data::TimeRangeStats;
+use super::cost_projection::draw_cost_projection_widget;
 
 pub fn draw_stats_panel(f: &mut Frame, area: Rect, app: &App) {
     let chunks = Layout::default()
@@ -18,6 +19,7 @@ pub fn draw_stats_panel(f: &mut Frame, area: Rect, app: &App) {
             Constraint::Length(10),  // Last 24 hours
             Constraint::Length(10),  // Last 2 days
             Constraint::Length(10),  // Last 7 days
+            Constraint::Length(4),   // Cost projection
             Constraint::Min(1),      // Remaining space
         ].as_ref())
         .split(area);
@@ -33,11 +35,23 @@ pub fn draw_stats_panel(f: &mut Frame, area: Rect, app: &App) {
     let stats_2d = app.rolling_window.get_2d_stats(model_filter);
     let stats_7d = app.rolling_window.get_7d_stats(model_filter);
 
-    draw_stats_widget(f, chunks[0], &current_stats, " Current Hour Stats ");
+    // A session filter or a fixed span swaps the current hour for their totals
+    match (&app.selected_session, app.time_range) {
+        (Some(session), _) => {
+            let session_stats = app.rolling_window.get_stats_for_session(session);
+            draw_stats_widget(f, chunks[0], &session_stats, &format!(" Session {}… ", session));
+        }
+        (None, TimeRange::Between { start, end }) => {
+            let span_stats = app.rolling_window.get_stats_between(start, end, model_filter);
+            draw_stats_widget(f, chunks[0], &span_stats, " Custom Range ");
+        }
+        (None, _) => draw_stats_widget(f, chunks[0], &current_stats, " Current Hour Stats "),
+    }
     draw_stats_widget(f, chunks[1], &stats_5h, " Last 5 Hours ");
     draw_stats_widget(f, chunks[2], &stats_24h, " Last 24 Hours ");
     draw_stats_widget(f, chunks[3], &stats_2d, " Last 2 Days ");
     draw_stats_widget(f, chunks[4], &stats_7d, " Last 7 Days ");
+    draw_cost_projection_widget(f, chunks[5], app);
 }
 
 fn draw_stats_widget(f: &mut Frame, area: Rect, stats: &TimeRangeStats, title: &str) {
@@ -72,7 +86,7 @@ fn draw_stats_widget(f: &mut Frame, area: Rect, stats: &TimeRangeStats, title: &
     model_entries.sort_by(|a, b| b.1.partial_cmp(a.1).unwrap());
 
     for (model, cost) in model_entries {
-        let color = match model.as_str() {
+        let color = match model.family() {
             "opus" => Color::Magenta,
             "sonnet" => Color::Yellow,
             "haiku" => Color::Green,
@@ -82,7 +96,7 @@ fn draw_stats_widget(f: &mut Frame, area: Rect, stats: &TimeRangeStats, title: &
         lines.push(Line::from(vec![
             Span::raw(" ▪ "),
             Span::styled(
-                format!("{:<7}", capitalize(model)),
+                format!("{:<9}", model.display_short()),
                 Style::default().fg(color),
             ),
             Span::styled(
@@ -111,12 +125,4 @@ fn format_tokens(tokens: u64) -> String {
     } else {
         format!("{}", tokens)
     }
-}
-
-fn capitalize(s: &str) -> String {
-    let mut chars = s.chars();
-    match chars.next() {
-        None => String::new(),
-        Some(f) => f.to_uppercase().collect::<String>() + chars.as_str(),
-    }
 }
\ No newline at end of file
@@ -1,3 +1,5 @@
+use chrono::{NaiveDate, Utc};
+use std::collections::HashMap;
 use ratatui::{
     layout::{Alignment, Rect},
     style::{Color, Modifier, Style},
@@ -7,11 +9,46 @@ use ratatui::{
 };
 
 use crate::dashboard::app::App;
+use crate::dashboard::data::RollingWindow;
+use crate::formatters::make_sparkline;
+
+/// Days covered by the header sparkline, one character each
+pub const SPARKLINE_DAYS: usize = 7;
+
+/// Cost per UTC day for the `SPARKLINE_DAYS` days ending with `today`, oldest first
+pub fn daily_costs(window: &RollingWindow, today: NaiveDate) -> Vec<f64> {
+    let mut costs = vec![0.0; SPARKLINE_DAYS];
+    for bucket in &window.buckets {
+        let days_ago = (today - bucket.timestamp.date_naive()).num_days();
+        if (0..SPARKLINE_DAYS as i64).contains(&days_ago) {
+            costs[SPARKLINE_DAYS - 1 - days_ago as usize] += bucket.total_cost;
+        }
+    }
+    costs
+}
+
+/// Columns taken by the header sparkline: a `7d ` label and one character per day
+pub const SPARKLINE_WIDTH: u16 = 3 + SPARKLINE_DAYS as u16;
+
+/// Mini sparkline of daily cost over the past week, e.g. `7d ▁▂▃▄▅▆█`
+pub fn draw_summary_sparkline(f: &mut Frame, area: Rect, app: &App) {
+    let costs = daily_costs(&app.rolling_window, Utc::now().date_naive());
+    let sparkline = Paragraph::new(Line::from(vec![
+        Span::styled(format!("{}d ", SPARKLINE_DAYS), Style::default().fg(Color::DarkGray)),
+        Span::styled(make_sparkline(&costs), Style::default().fg(Color::Green)),
+    ]))
+    .alignment(Alignment::Right);
+    f.render_widget(sparkline, area);
+}
 
 pub fn draw_summary_bar(f: &mut Frame, area: Rect, app: &App) {
     let stats = app.rolling_window.get_24h_stats(None);
     let total_cost = stats.cost;
-    let model_costs = &stats.model_costs;
+    // One entry per family, so the line stays short
+    let mut model_costs: HashMap<&str, f64> = HashMap::new();
+    for (model, cost) in &stats.model_costs {
+        *model_costs.entry(model.family()).or_insert(0.0) += cost;
+    }
 
     let mut spans = vec![
         Span::raw("Total: "),
@@ -26,7 +63,7 @@ pub fn draw_summary_bar(f: &mut Frame, area: Rect, app: &App) {
     model_entries.sort_by(|a, b| b.1.partial_cmp(a.1).unwrap());
 
     for (model, cost) in model_entries {
-        let color = match model.as_str() {
+        let color = match *model {
             "opus" => Color::Magenta,
             "sonnet" => Color::Yellow,
             "haiku" => Color::Green,
@@ -61,4 +98,34 @@ fn capitalize(s: &str) -> String {
         None => String::new(),
         Some(f) => f.to_uppercase().collect::<String>() + chars.as_str(),
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dashboard::data::RequestInfo;
+    use crate::model_name::ModelName;
+    use chrono::Duration;
+
+    #[test]
+    fn test_daily_costs_sparkline() {
+        let now = Utc::now();
+        let mut window = RollingWindow::new(60, 15);
+        // Linearly increasing towards today; the eighth day back is out of range
+        for days_ago in 0..=SPARKLINE_DAYS as i64 {
+            window.add_request(RequestInfo {
+                timestamp: now - Duration::days(days_ago),
+                model: ModelName::Claude4Sonnet,
+                input_tokens: 100,
+                output_tokens: 10,
+                cache_tokens: 0,
+                cost: (SPARKLINE_DAYS as i64 - days_ago) as f64,
+                session_id: "test-session".to_string(),
+            });
+        }
+
+        let costs = daily_costs(&window, now.date_naive());
+        assert_eq!(costs, [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]);
+        assert_eq!(make_sparkline(&costs), "▁▂▃▄▅▆█");
+    }
+}
@@ -0,0 +1,63 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Sparkline},
+    Frame,
+};
+
+use crate::dashboard::app::App;
+
+/// Minutes averaged for the headline requests/min and tokens/min figures
+const RATE_WINDOW_MINUTES: i64 = 5;
+
+pub fn draw_throughput(f: &mut Frame, area: Rect, app: &App) {
+    let block = Block::default()
+        .title(format!(" Throughput (last {} min avg) ", RATE_WINDOW_MINUTES))
+        .borders(Borders::ALL);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(28), Constraint::Min(10)].as_ref())
+        .split(inner);
+
+    let (req_per_min, tokens_per_min) = app.rolling_window.get_rate(RATE_WINDOW_MINUTES);
+    let lines = vec![
+        Line::from(vec![
+            Span::raw("Req/min: "),
+            Span::styled(
+                format!("{:.1}", req_per_min),
+                Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+            ),
+        ]),
+        Line::from(vec![
+            Span::raw("Tokens/min: "),
+            Span::styled(
+                format_tokens(tokens_per_min),
+                Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+            ),
+        ]),
+    ];
+    f.render_widget(Paragraph::new(lines), chunks[0]);
+
+    // One bar per minute, as many as fit in the remaining width
+    let counts = app
+        .rolling_window
+        .get_minute_request_counts(chunks[1].width as usize);
+    let sparkline = Sparkline::default()
+        .data(&counts)
+        .style(Style::default().fg(Color::Yellow));
+    f.render_widget(sparkline, chunks[1]);
+}
+
+fn format_tokens(tokens: f64) -> String {
+    if tokens >= 1_000_000.0 {
+        format!("{:.1}M", tokens / 1_000_000.0)
+    } else if tokens >= 1_000.0 {
+        format!("{:.1}K", tokens / 1_000.0)
+    } else {
+        format!("{:.0}", tokens)
+    }
+}
@@ -0,0 +1,319 @@
+use crate::dashboard::app::{state_dir, TRACKER_STATE_FILE};
+use crate::file_tracker::FileTracker;
+use crate::model_name::ModelName;
+use crate::parser::{format_change_date, LogParser};
+use crate::pricing::{get_default_pricing, get_model_pricing};
+use anyhow::Result;
+use colored::Colorize;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    Fail,
+    /// Not run because an earlier check it depends on failed
+    Skipped,
+}
+
+/// Outcome of one `doctor` check
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// Suggested fix, shown for failures
+    pub fix: Option<String>,
+}
+
+impl CheckResult {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Pass,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Fail,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn check(
+        name: &'static str,
+        passed: bool,
+        detail: impl Into<String>,
+        fix: impl Into<String>,
+    ) -> Self {
+        if passed {
+            Self::pass(name, detail)
+        } else {
+            Self::fail(name, detail, fix)
+        }
+    }
+}
+
+const CHECK_NAMES: [&str; 8] = [
+    "Claude directory exists",
+    "Projects directory exists",
+    "JSONL files found",
+    "Log files are readable",
+    "Entries parse",
+    "Entries after the format-change cutoff",
+    "Pricing known for every model",
+    "Incremental parser state is readable",
+];
+
+/// Run every check against `claude_dir`. Checks that need log files are skipped once
+/// one of the checks before them fails.
+pub fn run_checks(claude_dir: &str) -> Vec<CheckResult> {
+    let mut results = log_checks(claude_dir);
+    results.push(tracker_state_check(claude_dir));
+
+    // Fill in the log checks that never ran
+    let ran = results.len() - 1;
+    for name in &CHECK_NAMES[ran..CHECK_NAMES.len() - 1] {
+        results.insert(
+            results.len() - 1,
+            CheckResult {
+                name,
+                status: CheckStatus::Skipped,
+                detail: "skipped after an earlier failure".to_string(),
+                fix: None,
+            },
+        );
+    }
+
+    results
+}
+
+/// Checks 1-7, stopping at the first failure since each needs the one before it
+fn log_checks(claude_dir: &str) -> Vec<CheckResult> {
+    let mut results = Vec::new();
+
+    let root = PathBuf::from(shellexpand::tilde(claude_dir).as_ref());
+    results.push(CheckResult::check(
+        CHECK_NAMES[0],
+        root.is_dir(),
+        root.display().to_string(),
+        "Is Claude Code installed? Pass --claude-dir if it keeps its data elsewhere",
+    ));
+    if !root.is_dir() {
+        return results;
+    }
+
+    let projects = root.join("projects");
+    results.push(CheckResult::check(
+        CHECK_NAMES[1],
+        projects.is_dir(),
+        projects.display().to_string(),
+        "Run Claude Code at least once so it creates projects/",
+    ));
+    if !projects.is_dir() {
+        return results;
+    }
+
+    let parser = LogParser::new(claude_dir.to_string()).quiet();
+    let files = parser.find_jsonl_files().unwrap_or_default();
+    results.push(CheckResult::check(
+        CHECK_NAMES[2],
+        !files.is_empty(),
+        format!("{} files", files.len()),
+        format!(
+            "Logs deeper than {} levels are ignored; try --max-depth",
+            parser.max_depth
+        ),
+    ));
+    if files.is_empty() {
+        return results;
+    }
+
+    let unreadable: Vec<_> = files.iter().filter(|path| File::open(path).is_err()).collect();
+    results.push(CheckResult::check(
+        CHECK_NAMES[3],
+        unreadable.is_empty(),
+        match unreadable.first() {
+            Some(path) => format!("{} unreadable, e.g. {}", unreadable.len(), path.display()),
+            None => "all readable".to_string(),
+        },
+        "Check the files' permissions",
+    ));
+    if !unreadable.is_empty() {
+        return results;
+    }
+
+    let cutoff = format_change_date();
+    let mut entries = 0usize;
+    let mut recent_entries = 0usize;
+    let mut models = HashSet::new();
+    for path in &files {
+        for entry in parser.parse_jsonl_file(path).unwrap_or_default() {
+            entries += 1;
+            if entry.timestamp > cutoff {
+                recent_entries += 1;
+            }
+            if let Some(message) = entry.message {
+                models.insert(message.model);
+            }
+        }
+    }
+
+    results.push(CheckResult::check(
+        CHECK_NAMES[4],
+        entries > 0,
+        format!("{} entries with usage data", entries),
+        "Run `claude-usage validate --verbose` to see what the files contain",
+    ));
+    if entries == 0 {
+        return results;
+    }
+
+    results.push(CheckResult::check(
+        CHECK_NAMES[5],
+        recent_entries > 0,
+        format!("{} entries after {}", recent_entries, cutoff.date_naive()),
+        "Only logs written after the June 2024 format change can be analyzed",
+    ));
+    if recent_entries == 0 {
+        return results;
+    }
+
+    let pricing_map = get_default_pricing();
+    let mut unpriced: Vec<String> = models
+        .iter()
+        .filter(|model| !model.is_synthetic() && get_model_pricing(&pricing_map, model).is_none())
+        .map(ModelName::to_string)
+        .collect();
+    unpriced.sort();
+    results.push(CheckResult::check(
+        CHECK_NAMES[6],
+        unpriced.is_empty(),
+        if unpriced.is_empty() {
+            format!("{} models", models.len())
+        } else {
+            format!("no pricing for {}", unpriced.join(", "))
+        },
+        "Upgrade claude-usage or try --refresh-pricing",
+    ));
+
+    results
+}
+
+/// Check 8: a missing state file is fine, an unparsable one is not
+fn tracker_state_check(claude_dir: &str) -> CheckResult {
+    let state_file = state_dir(claude_dir).join(TRACKER_STATE_FILE);
+    if !state_file.exists() {
+        return CheckResult::pass(CHECK_NAMES[7], "no state file yet");
+    }
+
+    match FileTracker::load(state_file.clone()) {
+        Ok(tracker) => CheckResult::pass(
+            CHECK_NAMES[7],
+            format!("{} files tracked", tracker.tracked_files_count()),
+        ),
+        Err(e) => CheckResult::fail(
+            CHECK_NAMES[7],
+            format!("{}: {}", state_file.display(), e),
+            "Run `claude-usage clean --state`; it's rebuilt on the next dashboard run",
+        ),
+    }
+}
+
+/// One line per check (✓ pass, ✗ fail, - skipped), with the suggested fix under failures
+pub fn format_checks(results: &[CheckResult], colored: bool) -> String {
+    let mut out = String::new();
+    for result in results {
+        let mark = match (result.status, colored) {
+            (CheckStatus::Pass, true) => "✓".green().bold().to_string(),
+            (CheckStatus::Fail, true) => "✗".red().bold().to_string(),
+            (CheckStatus::Skipped, true) => "-".bright_black().to_string(),
+            (CheckStatus::Pass, false) => "✓".to_string(),
+            (CheckStatus::Fail, false) => "✗".to_string(),
+            (CheckStatus::Skipped, false) => "-".to_string(),
+        };
+        out.push_str(&format!("{} {}: {}\n", mark, result.name, result.detail));
+        if let Some(fix) = &result.fix {
+            out.push_str(&format!("    Fix: {}\n", fix));
+        }
+    }
+    out
+}
+
+/// Run `doctor`, writing the checklist and returning the exit code: 0 if every check
+/// passed, 1 otherwise
+pub fn run_doctor<W: Write>(claude_dir: &str, colored: bool, writer: &mut W) -> Result<u8> {
+    let results = run_checks(claude_dir);
+    write!(writer, "{}", format_checks(&results, colored))?;
+
+    let failed = results
+        .iter()
+        .any(|result| result.status != CheckStatus::Pass);
+    Ok(if failed { 1 } else { 0 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn statuses(results: &[CheckResult]) -> Vec<CheckStatus> {
+        results.iter().map(|result| result.status).collect()
+    }
+
+    #[test]
+    fn test_healthy_directory_passes() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_dir = temp_dir.path().join("projects").join("test-project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(
+            project_dir.join("session.jsonl"),
+            r#"{"type":"assistant","uuid":"a1","timestamp":"2024-12-01T00:00:00Z","sessionId":"s","requestId":"req-a1","message":{"id":"msg-a1","role":"assistant","model":"claude-opus-4-20250514","usage":{"input_tokens":100,"output_tokens":50}}}"#,
+        )
+        .unwrap();
+
+        let dir = temp_dir.path().to_string_lossy().to_string();
+        let results = run_checks(&dir);
+        assert_eq!(results.len(), CHECK_NAMES.len());
+        assert_eq!(statuses(&results), vec![CheckStatus::Pass; CHECK_NAMES.len()]);
+        assert_eq!(run_doctor(&dir, false, &mut Vec::new()).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_missing_projects_skips_later_checks() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().to_string_lossy().to_string();
+        let results = run_checks(&dir);
+
+        assert_eq!(results.len(), CHECK_NAMES.len());
+        assert_eq!(results[0].status, CheckStatus::Pass);
+        assert_eq!(results[1].status, CheckStatus::Fail);
+        assert!(results[2..7].iter().all(|r| r.status == CheckStatus::Skipped));
+        assert_eq!(results[7].status, CheckStatus::Pass);
+
+        let mut output = Vec::new();
+        assert_eq!(run_doctor(&dir, false, &mut output).unwrap(), 1);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("✗ Projects directory exists"));
+        assert!(output.contains("Fix: Run Claude Code"));
+    }
+
+    #[test]
+    fn test_corrupt_tracker_state_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().to_string_lossy().to_string();
+        let state_dir = state_dir(&dir);
+        std::fs::create_dir_all(&state_dir).unwrap();
+        std::fs::write(state_dir.join(TRACKER_STATE_FILE), "{not json").unwrap();
+
+        let results = run_checks(&dir);
+        assert_eq!(results[7].status, CheckStatus::Fail);
+    }
+}
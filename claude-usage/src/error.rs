@@ -0,0 +1,20 @@
+use std::path::PathBuf;
+
+/// Failures that scripts need to tell apart from a generic error, each with its own exit code
+#[derive(Debug, thiserror::Error)]
+pub enum UsageError {
+    #[error(
+        "Claude projects directory not found at: {}\nIs Claude Code installed? Try --claude-dir to point at your Claude directory.",
+        .0.display()
+    )]
+    ClaudeDirNotFound(PathBuf),
+}
+
+impl UsageError {
+    /// Process exit code; 1 is left for every other error and 2 is clap's usage error
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            UsageError::ClaudeDirNotFound(_) => 3,
+        }
+    }
+}
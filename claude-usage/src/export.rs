@@ -0,0 +1,202 @@
+use crate::analyze_usage_to_writer;
+use crate::cli::{Args, OutputFormat};
+use anyhow::{bail, Context, Result};
+use chrono::{NaiveDate, Utc};
+use clap::{Parser, ValueEnum};
+use serde::Serialize;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+/// When, by which version and over what dates an export was taken
+#[derive(Debug, Serialize)]
+pub struct ExportMetadata {
+    pub exported_at: String,
+    pub version: &'static str,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+}
+
+impl ExportMetadata {
+    fn new(start_date: Option<NaiveDate>, end_date: Option<NaiveDate>) -> Self {
+        Self {
+            exported_at: Utc::now().to_rfc3339(),
+            version: env!("CARGO_PKG_VERSION"),
+            start_date,
+            end_date,
+        }
+    }
+
+    /// Header lines for CSV and markdown, without comment markers
+    fn lines(&self) -> Vec<String> {
+        let date = |date: Option<NaiveDate>, open: &str| {
+            date.map_or(open.to_string(), |d| d.to_string())
+        };
+        vec![
+            format!("claude-usage {} export", self.version),
+            format!("exported_at: {}", self.exported_at),
+            format!(
+                "date range: {} to {}",
+                date(self.start_date, "first log"),
+                date(self.end_date, "last log")
+            ),
+        ]
+    }
+}
+
+/// The `show` arguments an export runs with: one row per request and every token type
+fn export_args(
+    format: &OutputFormat,
+    claude_dir: &str,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+) -> Args {
+    let format = format.to_possible_value().expect("no skipped formats");
+    let mut argv = vec![
+        "claude-usage".to_string(),
+        "--claude-dir".to_string(),
+        claude_dir.to_string(),
+        "--format".to_string(),
+        format.get_name().to_string(),
+        "--group-by".to_string(),
+        "request".to_string(),
+        "--detailed".to_string(),
+        "--quiet".to_string(),
+    ];
+    if let Some(date) = start_date {
+        argv.extend(["--start-date".to_string(), date.to_string()]);
+    }
+    if let Some(date) = end_date {
+        argv.extend(["--end-date".to_string(), date.to_string()]);
+    }
+    Args::parse_from(argv)
+}
+
+/// Add the metadata to a `show` report, returning the file contents and the record count
+fn with_metadata(
+    report: &str,
+    format: &OutputFormat,
+    metadata: &ExportMetadata,
+) -> Result<(String, usize)> {
+    // `show` prints a note instead of a report when nothing matches
+    let empty = report.starts_with("No usage data");
+    match format {
+        OutputFormat::Json => {
+            let stats: Vec<Value> = if empty { Vec::new() } else { serde_json::from_str(report)? };
+            let count = stats.len();
+            let json = serde_json::json!({ "metadata": metadata, "stats": stats });
+            Ok((format!("{}\n", serde_json::to_string_pretty(&json)?), count))
+        }
+        OutputFormat::Ndjson => {
+            let rows: Vec<&str> = if empty { Vec::new() } else { report.lines().collect() };
+            let header = serde_json::to_string(&serde_json::json!({ "metadata": metadata }))?;
+            let mut content = format!("{}\n", header);
+            for row in &rows {
+                content.push_str(row);
+                content.push('\n');
+            }
+            Ok((content, rows.len()))
+        }
+        OutputFormat::Csv => {
+            let mut content: String =
+                metadata.lines().iter().map(|line| format!("# {}\n", line)).collect();
+            let mut count = 0;
+            if !empty {
+                content.push_str(report);
+                // Every non-blank line after the header is a record
+                count = report.lines().filter(|line| !line.is_empty()).count().saturating_sub(1);
+            }
+            Ok((content, count))
+        }
+        OutputFormat::Markdown => {
+            let mut content = format!("<!--\n{}\n-->\n", metadata.lines().join("\n"));
+            let mut count = 0;
+            if !empty {
+                content.push_str(report);
+                // Table lines less the header and its separator
+                let table_lines = report.lines().filter(|line| line.starts_with('|')).count();
+                count = table_lines.saturating_sub(2);
+            }
+            Ok((content, count))
+        }
+        OutputFormat::Table | OutputFormat::AsciiChart => unreachable!("rejected by run_export"),
+    }
+}
+
+/// Run `export`: write every request between `start_date` and `end_date` to `output` with
+/// a metadata header, and report how many were written on stderr
+pub async fn run_export(
+    format: OutputFormat,
+    output: &Path,
+    claude_dir: &str,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+) -> Result<usize> {
+    if matches!(format, OutputFormat::Table | OutputFormat::AsciiChart) {
+        bail!("export writes data files: use --format json, ndjson, csv or markdown");
+    }
+    let metadata = ExportMetadata::new(start_date, end_date);
+    let mut report = Vec::new();
+    analyze_usage_to_writer(export_args(&format, claude_dir, start_date, end_date), &mut report)
+        .await?;
+    let report = String::from_utf8(report)?;
+    let (content, count) = with_metadata(&report, &format, &metadata)?;
+
+    fs::write(output, content).with_context(|| format!("writing {}", output.display()))?;
+    eprintln!("Exported {} records to {}", count, output.display());
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_fixture(claude_dir: &Path) {
+        let project = claude_dir.join("projects").join("p");
+        fs::create_dir_all(&project).unwrap();
+        let line = |uuid: &str, timestamp: &str| {
+            format!(
+                r#"{{"type":"assistant","uuid":"{}","timestamp":"{}","sessionId":"s","requestId":"req-{}","message":{{"id":"msg-{}","role":"assistant","model":"claude-sonnet-4-20250514","usage":{{"input_tokens":1000,"output_tokens":100,"cache_read_input_tokens":50}}}}}}"#,
+                uuid, timestamp, uuid, uuid
+            )
+        };
+        let lines = [
+            line("a1", "2025-03-01T10:00:00Z"),
+            line("a2", "2025-03-01T11:00:00Z"),
+            line("a3", "2025-03-02T09:00:00Z"),
+        ];
+        fs::write(project.join("session.jsonl"), lines.join("\n")).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_export_writes_readable_files() {
+        let claude_dir = TempDir::new().unwrap();
+        write_fixture(claude_dir.path());
+        let dir = claude_dir.path().to_string_lossy().to_string();
+        let out = TempDir::new().unwrap();
+
+        let csv_path = out.path().join("usage.csv");
+        let count = run_export(OutputFormat::Csv, &csv_path, &dir, None, None).await.unwrap();
+        assert_eq!(count, 3);
+        let csv = fs::read_to_string(&csv_path).unwrap();
+        let mut lines = csv.lines();
+        assert!(lines.next().unwrap().starts_with("# claude-usage "));
+        assert!(csv.contains("# date range: first log to last log"));
+        assert!(csv.contains("Cache Read Tokens"));
+        assert_eq!(csv.lines().filter(|line| line.starts_with("2025-03-")).count(), 3);
+
+        let json_path = out.path().join("usage.json");
+        let start = NaiveDate::from_ymd_opt(2025, 3, 2);
+        let count = run_export(OutputFormat::Json, &json_path, &dir, start, None).await.unwrap();
+        assert_eq!(count, 1);
+        let json: Value = serde_json::from_str(&fs::read_to_string(&json_path).unwrap()).unwrap();
+        assert_eq!(json["metadata"]["start_date"], "2025-03-02");
+        assert_eq!(json["metadata"]["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(json["stats"].as_array().unwrap().len(), 1);
+
+        let table_path = out.path().join("usage.txt");
+        assert!(run_export(OutputFormat::Table, &table_path, &dir, None, None).await.is_err());
+        assert!(!table_path.exists());
+    }
+}
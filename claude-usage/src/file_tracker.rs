@@ -1,8 +1,9 @@
+use crate::parser::{FileRead, SeenIds};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::fs::{self, File};
-use std::io::{BufReader, BufWriter};
+use std::io::{BufReader, BufWriter, Read};
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
@@ -14,8 +15,30 @@ pub struct FileState {
     pub last_line_number: usize,
     pub file_size: u64,
     pub inode: Option<u64>, // For detecting file rotation on Unix
+    /// CRC32 of the whole file, only recorded when checksums are enabled; catches rewrites
+    /// past the prefix that leave mtime and size unchanged
+    #[serde(default)]
+    pub checksum: Option<u32>,
+    /// CRC32 of the first `PREFIX_CHECKSUM_BYTES` bytes, used as a fast rewrite check
+    #[serde(default)]
+    pub prefix_checksum: Option<u32>,
 }
 
+/// `state.json` -> `state.json.bak`
+fn backup_path(state_file: &Path) -> PathBuf {
+    let mut name = state_file.as_os_str().to_owned();
+    name.push(".bak");
+    PathBuf::from(name)
+}
+
+/// `state.json` -> `state.seen.json`, where the ids of already returned entries are kept
+pub fn seen_ids_path(state_file: &Path) -> PathBuf {
+    state_file.with_extension("seen.json")
+}
+
+/// Number of leading bytes hashed by the fast prefix check in `check_file`
+const PREFIX_CHECKSUM_BYTES: u64 = 512;
+
 #[derive(Debug)]
 pub enum FileCheckResult {
     /// File is new and hasn't been tracked before
@@ -32,8 +55,12 @@ pub enum FileCheckResult {
 }
 
 pub struct FileTracker {
-    states: HashMap<PathBuf, FileState>,
+    // Ordered so the persisted state file is stable across runs
+    states: BTreeMap<PathBuf, FileState>,
     state_file: Option<PathBuf>,
+    with_checksums: bool,
+    /// Entries already returned by incremental parsing, so re-read ones aren't returned twice
+    seen_ids: SeenIds,
 }
 
 impl Default for FileTracker {
@@ -45,20 +72,56 @@ impl Default for FileTracker {
 impl FileTracker {
     pub fn new() -> Self {
         Self {
-            states: HashMap::new(),
+            states: BTreeMap::new(),
             state_file: None,
+            with_checksums: false,
+            seen_ids: SeenIds::default(),
         }
     }
 
+    /// Load persisted state, starting fresh if it can't be loaded. A corrupt state file
+    /// (e.g. a partial write from a crash) is moved aside to `<name>.bak` so the next save
+    /// doesn't depend on it.
     pub fn with_persistence(state_file: PathBuf) -> Self {
+        Self::load(state_file.clone()).unwrap_or_else(|e| {
+            if e.downcast_ref::<serde_json::Error>().is_some() {
+                let backup = backup_path(&state_file);
+                match fs::rename(&state_file, &backup) {
+                    Ok(()) => eprintln!(
+                        "Warning: Corrupt file tracker state ({}), moved to {}",
+                        e,
+                        backup.display()
+                    ),
+                    Err(rename_err) => eprintln!(
+                        "Warning: Corrupt file tracker state ({}), couldn't move it: {}",
+                        e, rename_err
+                    ),
+                }
+            } else {
+                eprintln!("Warning: Failed to load file tracker state: {}", e);
+            }
+            Self {
+                state_file: Some(state_file),
+                ..Self::new()
+            }
+        })
+    }
+
+    /// Load persisted state, failing if the state file exists but can't be read or parsed
+    pub fn load(state_file: PathBuf) -> Result<Self> {
         let mut tracker = Self {
-            states: HashMap::new(),
-            state_file: Some(state_file.clone()),
+            state_file: Some(state_file),
+            ..Self::new()
         };
-        if let Err(e) = tracker.load_state() {
-            eprintln!("Warning: Failed to load file tracker state: {}", e);
-        }
-        tracker
+        tracker.load_state()?;
+        Ok(tracker)
+    }
+
+    /// Also compare file contents via CRC32, for filesystems where mtime is unreliable
+    /// (e.g. cloud-synced directories that reset modification times)
+    pub fn with_checksums(mut self, enabled: bool) -> Self {
+        self.with_checksums = enabled;
+        self
     }
 
     pub fn check_file(&self, path: &Path) -> Result<FileCheckResult> {
@@ -89,15 +152,35 @@ impl FileTracker {
                     }
                 }
 
+                // What was already read must be unchanged, otherwise the file was rewritten
+                // rather than appended to and has to be read again from the start
+                if self.with_checksums {
+                    if let Some(old_prefix) = state.prefix_checksum {
+                        let prefix_len = state.file_size.min(PREFIX_CHECKSUM_BYTES);
+                        if compute_checksum(path, prefix_len)? != old_prefix {
+                            return Ok(FileCheckResult::Rotated);
+                        }
+                    }
+                }
+
                 // Check if file was modified
                 if state.last_modified < current_modified || state.file_size < current_size {
-                    Ok(FileCheckResult::Modified {
+                    return Ok(FileCheckResult::Modified {
                         last_position: state.last_read_position,
                         last_line: state.last_line_number,
-                    })
-                } else {
-                    Ok(FileCheckResult::Unchanged)
+                    });
                 }
+
+                // mtime and size look unchanged, but the content past the prefix may differ
+                if self.with_checksums {
+                    if let Some(old_checksum) = state.checksum {
+                        if compute_checksum(path, state.file_size)? != old_checksum {
+                            return Ok(FileCheckResult::Rotated);
+                        }
+                    }
+                }
+
+                Ok(FileCheckResult::Unchanged)
             }
             None => Ok(FileCheckResult::New),
         }
@@ -108,6 +191,53 @@ impl FileTracker {
         path: PathBuf,
         position: u64,
         line_number: usize,
+    ) -> Result<()> {
+        self.record_state(path, None, position, line_number)?;
+
+        if self.state_file.is_some() {
+            self.save_state()?;
+        }
+
+        Ok(())
+    }
+
+    /// Record a file a full parse read, to the end of what it read
+    pub fn record_read(&mut self, read: &FileRead) -> Result<()> {
+        self.record_state(read.path.clone(), Some(read.bytes), read.bytes, read.lines)?;
+
+        if self.state_file.is_some() {
+            self.save_state()?;
+        }
+
+        Ok(())
+    }
+
+    /// Record every file in `reads` as a full parse read it, exactly as incremental parsing
+    /// would after reading it itself, without reading it again. For warming up after a full
+    /// parse; the state is saved once at the end. Files that disappeared since are skipped.
+    pub fn snapshot_files(&mut self, reads: &[FileRead]) -> Result<()> {
+        for read in reads {
+            if !read.path.exists() {
+                continue;
+            }
+            self.record_state(read.path.clone(), Some(read.bytes), read.bytes, read.lines)?;
+        }
+
+        if self.state_file.is_some() {
+            self.save_state()?;
+        }
+
+        Ok(())
+    }
+
+    /// `file_size` is how much of the file has been read when that's known, and the size on
+    /// disk otherwise
+    fn record_state(
+        &mut self,
+        path: PathBuf,
+        file_size: Option<u64>,
+        position: u64,
+        line_number: usize,
     ) -> Result<()> {
         let metadata = fs::metadata(&path)?;
 
@@ -119,6 +249,16 @@ impl FileTracker {
         #[cfg(not(unix))]
         let inode = None;
 
+        let file_size = file_size.unwrap_or(metadata.len());
+        let (checksum, prefix_checksum) = if self.with_checksums {
+            (
+                Some(compute_checksum(&path, file_size)?),
+                Some(compute_checksum(&path, file_size.min(PREFIX_CHECKSUM_BYTES))?),
+            )
+        } else {
+            (None, None)
+        };
+
         self.states.insert(
             path.clone(),
             FileState {
@@ -126,15 +266,13 @@ impl FileTracker {
                 last_modified: metadata.modified()?,
                 last_read_position: position,
                 last_line_number: line_number,
-                file_size: metadata.len(),
+                file_size,
                 inode,
+                checksum,
+                prefix_checksum,
             },
         );
 
-        if self.state_file.is_some() {
-            self.save_state()?;
-        }
-
         Ok(())
     }
 
@@ -147,6 +285,8 @@ impl FileTracker {
 
     pub fn clear(&mut self) {
         self.states.clear();
+        // Everything will be read again, so nothing counts as seen any more
+        self.seen_ids.clear();
         if self.state_file.is_some() {
             let _ = self.save_state();
         }
@@ -159,6 +299,22 @@ impl FileTracker {
                 let reader = BufReader::new(file);
                 self.states = serde_json::from_reader(reader)?;
             }
+            // Losing the seen ids only risks returning an entry twice, so they never fail
+            // the load
+            let seen_file = seen_ids_path(state_file);
+            if seen_file.exists() {
+                match File::open(&seen_file)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|file| Ok(serde_json::from_reader(BufReader::new(file))?))
+                {
+                    Ok(seen_ids) => self.seen_ids = seen_ids,
+                    Err(e) => eprintln!(
+                        "Warning: Ignoring unreadable seen ids {}: {}",
+                        seen_file.display(),
+                        e
+                    ),
+                }
+            }
         }
         Ok(())
     }
@@ -177,6 +333,24 @@ impl FileTracker {
         Ok(())
     }
 
+    /// Ids of the entries incremental parsing has already returned
+    pub fn seen_ids_mut(&mut self) -> &mut SeenIds {
+        &mut self.seen_ids
+    }
+
+    /// Write the seen ids next to the state file (a no-op without persistence)
+    pub fn save_seen_ids(&self) -> Result<()> {
+        if let Some(ref state_file) = self.state_file {
+            if let Some(parent) = state_file.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            let file = File::create(seen_ids_path(state_file))?;
+            serde_json::to_writer(BufWriter::new(file), &self.seen_ids)?;
+        }
+        Ok(())
+    }
+
     /// Get the number of tracked files
     pub fn tracked_files_count(&self) -> usize {
         self.states.len()
@@ -203,6 +377,25 @@ impl FileTracker {
     }
 }
 
+/// Compute the CRC32 of the first `len` bytes of a file (all of it if it's shorter)
+fn compute_checksum(path: &Path, len: u64) -> Result<u32> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open {} for checksum", path.display()))?;
+    let mut reader = BufReader::new(file.take(len));
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buffer = [0u8; 8192];
+
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hasher.finalize())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -313,4 +506,123 @@ mod tests {
             _ => panic!("Expected Unchanged after loading state"),
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_checksum_detects_change_with_reset_mtime() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.jsonl");
+        fs::write(&file_path, "original content").unwrap();
+        let original_mtime = fs::metadata(&file_path).unwrap().modified().unwrap();
+
+        let mut tracker = FileTracker::new().with_checksums(true);
+        let file_size = fs::metadata(&file_path).unwrap().len();
+        tracker.update_state(file_path.clone(), file_size, 1).unwrap();
+
+        // Same size, different content, mtime restored to its old value
+        fs::write(&file_path, "modified content").unwrap();
+        let file = fs::OpenOptions::new().write(true).open(&file_path).unwrap();
+        file.set_modified(original_mtime).unwrap();
+        drop(file);
+
+        // Rewritten, not appended to, so it has to be read from the start
+        let result = tracker.check_file(&file_path).unwrap();
+        assert!(matches!(result, FileCheckResult::Rotated), "{:?}", result);
+    }
+
+    #[test]
+    fn test_checksum_detects_change_past_prefix() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.jsonl");
+        let original = "a".repeat(2000);
+        fs::write(&file_path, &original).unwrap();
+        let original_mtime = fs::metadata(&file_path).unwrap().modified().unwrap();
+
+        let mut tracker = FileTracker::new().with_checksums(true);
+        tracker.update_state(file_path.clone(), 2000, 1).unwrap();
+
+        // Only the last byte changes, well past the prefix
+        fs::write(&file_path, format!("{}b", &original[..1999])).unwrap();
+        let file = fs::OpenOptions::new().write(true).open(&file_path).unwrap();
+        file.set_modified(original_mtime).unwrap();
+        drop(file);
+
+        let result = tracker.check_file(&file_path).unwrap();
+        assert!(matches!(result, FileCheckResult::Rotated), "{:?}", result);
+    }
+
+    #[test]
+    fn test_checksum_allows_appends() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.jsonl");
+        fs::write(&file_path, "line one\n").unwrap();
+
+        let mut tracker = FileTracker::new().with_checksums(true);
+        tracker.update_state(file_path.clone(), 9, 1).unwrap();
+
+        let mut file = fs::OpenOptions::new().append(true).open(&file_path).unwrap();
+        file.write_all("line two\n".repeat(100).as_bytes()).unwrap();
+        drop(file);
+
+        match tracker.check_file(&file_path).unwrap() {
+            FileCheckResult::Modified { last_position, .. } => assert_eq!(last_position, 9),
+            result => panic!("Expected Modified, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_reset_mtime_undetected_without_checksums() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.jsonl");
+        fs::write(&file_path, "original content").unwrap();
+        let original_mtime = fs::metadata(&file_path).unwrap().modified().unwrap();
+
+        let mut tracker = FileTracker::new();
+        let file_size = fs::metadata(&file_path).unwrap().len();
+        tracker.update_state(file_path.clone(), file_size, 1).unwrap();
+
+        fs::write(&file_path, "modified content").unwrap();
+        let file = fs::OpenOptions::new().write(true).open(&file_path).unwrap();
+        file.set_modified(original_mtime).unwrap();
+        drop(file);
+
+        let result = tracker.check_file(&file_path).unwrap();
+        match result {
+            FileCheckResult::Unchanged => (),
+            _ => panic!("Expected Unchanged, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_update_state_stores_full_checksum() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.jsonl");
+        fs::write(&file_path, "checksum me").unwrap();
+
+        let mut tracker = FileTracker::new().with_checksums(true);
+        tracker.update_state(file_path.clone(), 11, 1).unwrap();
+
+        let state = tracker.states.get(&file_path).unwrap();
+        assert_eq!(state.checksum, Some(crc32fast::hash(b"checksum me")));
+        assert_eq!(state.prefix_checksum, Some(crc32fast::hash(b"checksum me")));
+    }
+
+    #[test]
+    fn test_corrupt_state_is_backed_up_and_reset() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("tracker.json");
+        fs::write(&state_file, "{\"truncated\": ").unwrap();
+
+        let tracker = FileTracker::with_persistence(state_file.clone());
+        assert_eq!(tracker.tracked_files_count(), 0);
+        assert!(!state_file.exists());
+        let backup = temp_dir.path().join("tracker.json.bak");
+        assert_eq!(fs::read_to_string(backup).unwrap(), "{\"truncated\": ");
+
+        // The fresh tracker persists to the original location
+        let log = temp_dir.path().join("session.jsonl");
+        fs::write(&log, "line\n").unwrap();
+        let mut tracker = tracker;
+        tracker.update_state(log, 5, 1).unwrap();
+        assert_eq!(FileTracker::load(state_file).unwrap().tracked_files_count(), 1);
+    }
+}
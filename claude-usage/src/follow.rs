@@ -0,0 +1,201 @@
+use crate::file_tracker::FileTracker;
+use crate::file_watcher::CrossPlatformWatcher;
+use crate::formatters::format_number;
+use crate::incremental_parser::IncrementalParsing;
+use crate::models::{LogEntry, PricingMap};
+use crate::cli::DedupKey;
+use crate::parser::{dedup_key, LogParser};
+use crate::pricing::{get_default_pricing, get_model_pricing};
+use anyhow::Result;
+use chrono::Local;
+use std::collections::HashSet;
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// How often to check the watcher for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Rescan anyway after this long, in case the watcher missed an event (or couldn't start)
+const RESCAN_INTERVAL: Duration = Duration::from_secs(5);
+
+/// One line for a request: local time, model, tokens and cost. `None` for entries without
+/// usage, synthetic messages and requests not matching `model_filter`.
+pub fn format_request_line(
+    entry: &LogEntry,
+    model_filter: Option<&str>,
+    pricing_map: &PricingMap,
+) -> Option<String> {
+    let message = entry.message.as_ref()?;
+    let usage = message.usage.as_ref()?;
+    if message.model.is_synthetic()
+        || model_filter.is_some_and(|filter| !crate::model_matches(&message.model, filter))
+    {
+        return None;
+    }
+
+    let cost = get_model_pricing(pricing_map, &message.model)
+        .map_or(0.0, |pricing| pricing.calculate_cost_with_tier(usage));
+    Some(format!(
+        "{}  {:<20}  {:>9} in  {:>9} out  {:>9} cache  ${:.4}",
+        entry.timestamp.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S"),
+        message.model.to_string(),
+        format_number(usage.input_tokens),
+        format_number(usage.output_tokens),
+        format_number(usage.cache_creation_input_tokens + usage.cache_read_input_tokens),
+        cost
+    ))
+}
+
+/// Requests can be logged over several lines as they stream, so they're identified by
+/// request id like the parser's deduplication
+fn seen_key(entry: &LogEntry) -> String {
+    dedup_key(DedupKey::RequestId, entry)
+}
+
+/// `format_request_line` for a request returned by incremental parsing, which hands back
+/// each later copy of a request as it streams in. Copies after the first printed one are
+/// marked as updates, so the last line printed for a request has its final tokens and cost.
+fn follow_line(
+    entry: &LogEntry,
+    printed: &mut HashSet<String>,
+    model_filter: Option<&str>,
+    pricing_map: &PricingMap,
+) -> Option<String> {
+    let line = format_request_line(entry, model_filter, pricing_map)?;
+    if printed.insert(seen_key(entry)) {
+        Some(line)
+    } else {
+        Some(format!("{}  (updated)", line))
+    }
+}
+
+/// Print every request appended to the logs from now on, one line each, until killed or
+/// the reader goes away. Existing requests are skipped, like `tail -f` with no backlog, but
+/// later copies of them are printed as updates.
+pub async fn run_follow<W: Write>(
+    claude_dirs: Vec<String>,
+    model_filter: Option<String>,
+    max_depth: usize,
+    writer: &mut W,
+) -> Result<()> {
+    let projects_dirs: Vec<PathBuf> = claude_dirs
+        .iter()
+        .map(|dir| PathBuf::from(shellexpand::tilde(dir).as_ref()).join("projects"))
+        .filter(|dir| dir.exists())
+        .collect();
+    let parser = LogParser::new_multi(claude_dirs)
+        .with_max_depth(max_depth)
+        .quiet();
+    let pricing_map = get_default_pricing();
+
+    // The tracker is only kept in memory: following always starts from the current end
+    // of every file. It also spots rotated files, which are then read from the start, and
+    // remembers which requests it has returned so only their later copies come back.
+    let mut tracker = FileTracker::new();
+    let mut printed: HashSet<String> = parser
+        .parse_logs_incremental(&mut tracker)?
+        .iter()
+        .map(seen_key)
+        .collect();
+
+    let mut watcher = match CrossPlatformWatcher::new(projects_dirs) {
+        Ok(watcher) => Some(watcher),
+        Err(e) => {
+            eprintln!("Warning: File watching unavailable, rescanning on a timer: {}", e);
+            None
+        }
+    };
+
+    let mut last_scan = Instant::now();
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let changed = watcher
+            .as_mut()
+            .is_some_and(|watcher| !watcher.poll_changes().is_empty());
+        if !changed && last_scan.elapsed() < RESCAN_INTERVAL {
+            continue;
+        }
+        last_scan = Instant::now();
+
+        // Already deduplicated within the batch and sorted by timestamp
+        for entry in parser.parse_logs_incremental(&mut tracker)? {
+            let Some(line) =
+                follow_line(&entry, &mut printed, model_filter.as_deref(), &pricing_map)
+            else {
+                continue;
+            };
+            // Stop quietly when piped into something that exits, e.g. `head`
+            match writeln!(writer, "{}", line).and_then(|_| writer.flush()) {
+                Err(e) if e.kind() == ErrorKind::BrokenPipe => return Ok(()),
+                result => result?,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model_name::ModelName;
+    use crate::models::{Message, TokenUsage};
+
+    fn entry(model: ModelName) -> LogEntry {
+        LogEntry {
+            entry_type: "assistant".to_string(),
+            uuid: "u1".to_string(),
+            parent_uuid: None,
+            timestamp: "2025-06-01T10:00:00Z".parse().unwrap(),
+            session_id: "s".to_string(),
+            request_id: Some("r1".to_string()),
+            version: None,
+            message: Some(Message {
+                id: "msg".to_string(),
+                role: "assistant".to_string(),
+                model,
+                usage: Some(TokenUsage {
+                    input_tokens: 1000,
+                    output_tokens: 500,
+                    cache_read_input_tokens: 2000,
+                    ..Default::default()
+                }),
+                tool_result_content: false,
+            }),
+            is_sidechain: None,
+        }
+    }
+
+    #[test]
+    fn test_request_line() {
+        let pricing = get_default_pricing();
+        let line = format_request_line(&entry(ModelName::Claude4Opus), None, &pricing).unwrap();
+        assert!(line.contains("1,000 in"));
+        assert!(line.contains("500 out"));
+        assert!(line.contains("2,000 cache"));
+        assert!(line.ends_with("$0.0555"));
+    }
+
+    #[test]
+    fn test_request_line_filters() {
+        let pricing = get_default_pricing();
+        let opus = entry(ModelName::Claude4Opus);
+        assert!(format_request_line(&opus, Some("sonnet"), &pricing).is_none());
+        assert!(format_request_line(&opus, Some("opus"), &pricing).is_some());
+        assert!(format_request_line(&entry(ModelName::Synthetic), None, &pricing).is_none());
+    }
+
+    #[test]
+    fn test_later_copies_print_as_updates() {
+        let pricing = get_default_pricing();
+        let mut printed = HashSet::new();
+        let partial = entry(ModelName::Claude4Opus);
+        let mut complete = partial.clone();
+        complete.uuid = "u2".to_string();
+        complete.message.as_mut().unwrap().usage.as_mut().unwrap().output_tokens = 900;
+
+        let first = follow_line(&partial, &mut printed, None, &pricing).unwrap();
+        assert!(first.contains("500 out") && !first.ends_with("(updated)"));
+        let update = follow_line(&complete, &mut printed, None, &pricing).unwrap();
+        assert!(update.contains("900 out") && update.ends_with("(updated)"));
+    }
+}
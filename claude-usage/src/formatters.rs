@@ -1,221 +1,1854 @@
-use crate::models::{TokenUsage, UsageStats};
-use colored::Colorize;
+use crate::cli::OutputField;
+use crate::currency::Currency;
+use crate::model_name::ModelName;
+use crate::models::{ModelBreakdown, PricingMap, TokenUsage, UsageStats};
+use crate::gaps::GapStats;
+use crate::parser::ParseProfile;
+use crate::pricing::{get_default_pricing, get_model_pricing};
+use crate::{Anomaly, AnomalyBaseline, AnomalyReport, AnomalyThresholds, ForecastPoint, FORECAST_HISTORY_DAYS};
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, Utc};
+use colored::{ColoredString, Colorize};
 use prettytable::{format, Cell, Row, Table};
+use serde::Serialize;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::io::{self, IsTerminal, Write};
+
+/// Decide whether output should be colored: disabled by `--no-color`, a non-empty
+/// `NO_COLOR` environment variable, or stdout not being a terminal
+pub fn should_use_color(no_color: bool) -> bool {
+    color_enabled(no_color, std::env::var_os("NO_COLOR").as_deref(), io::stdout().is_terminal())
+}
+
+/// `should_use_color` with the environment passed in
+fn color_enabled(no_color: bool, no_color_env: Option<&std::ffi::OsStr>, terminal: bool) -> bool {
+    if no_color {
+        return false;
+    }
+    if no_color_env.is_some_and(|v| !v.is_empty()) {
+        return false;
+    }
+    terminal
+}
+
+/// Globally enable or disable `colored` output for the formatters
+pub fn configure_color(no_color: bool) {
+    colored::control::set_override(should_use_color(no_color));
+}
+
+/// Apply a prettytable style spec only when colored output is enabled
+fn styled(text: &str, spec: &str, colored: bool) -> Cell {
+    let cell = Cell::new(text);
+    if colored {
+        cell.style_spec(spec)
+    } else {
+        cell
+    }
+}
+
+/// Drop the styling from a `Colorize` string when colored output is disabled
+pub(crate) fn paint(text: ColoredString, colored: bool) -> ColoredString {
+    if colored {
+        text
+    } else {
+        text.clear()
+    }
+}
+
+/// Options shared by the tabular formatters (table, CSV, markdown)
+#[derive(Debug, Clone, Default)]
+pub struct FormatOptions {
+    /// Style headers, costs and totals with terminal colors (see `should_use_color`)
+    pub colored_output: bool,
+    /// Show the per-category token breakdown
+    pub detailed: bool,
+    /// Append a totals row
+    pub show_summary: bool,
+    /// Add a cost trend sparkline column (table only)
+    pub sparkline: bool,
+    /// Add each row's share of total cost and tokens
+    pub percentages: bool,
+    /// Fold cache write/read tokens into the input column (cost is unaffected)
+    pub aggregate_cache: bool,
+    /// Add a cumulative cost column, accumulated in row order
+    pub running_total: bool,
+    /// Emit JSON without whitespace instead of pretty-printing it
+    pub compact_json: bool,
+    /// Field separator for CSV output, a comma when `None`
+    pub csv_delimiter: Option<char>,
+    /// Exactly these columns in this order (`--output-fields`) instead of the usual layout
+    pub output_fields: Option<Vec<OutputField>>,
+    /// Rows are single requests: CSV keeps each row's full timestamp and unrounded cost
+    pub per_request: bool,
+    /// Currency costs are shown in (table, CSV, markdown and the text summary; JSON stays USD)
+    pub currency: Currency,
+    /// Prices the costs were calculated with, for cache savings and `--explain`; the
+    /// built-in prices when `None`
+    pub pricing: Option<PricingMap>,
+    /// Show each row's cost formula (`--explain`, table only)
+    pub explain: bool,
+}
+
+impl FormatOptions {
+    /// `pricing`, or the built-in prices
+    fn pricing_map(&self) -> Cow<'_, PricingMap> {
+        match &self.pricing {
+            Some(pricing_map) => Cow::Borrowed(pricing_map),
+            None => Cow::Owned(get_default_pricing()),
+        }
+    }
+}
+
+/// Cumulative cost after each row, in display order
+fn running_totals(stats: &[UsageStats]) -> Vec<f64> {
+    stats
+        .iter()
+        .scan(0.0, |total, stat| {
+            *total += stat.cost_usd;
+            Some(*total)
+        })
+        .collect()
+}
+
+/// Detailed token column headers, without the cache columns when they are folded into input
+fn token_headers(aggregate_cache: bool) -> Vec<&'static str> {
+    if aggregate_cache {
+        vec!["Input", "Output"]
+    } else {
+        vec!["Input", "Output", "Cache Write", "Cache Read"]
+    }
+}
+
+/// Detailed token column values matching `token_headers`
+fn token_columns(usage: &TokenUsage, aggregate_cache: bool) -> Vec<u64> {
+    if aggregate_cache {
+        vec![
+            usage.input_tokens + usage.cache_creation_input_tokens + usage.cache_read_input_tokens,
+            usage.output_tokens,
+        ]
+    } else {
+        vec![
+            usage.input_tokens,
+            usage.output_tokens,
+            usage.cache_creation_input_tokens,
+            usage.cache_read_input_tokens,
+        ]
+    }
+}
+
+/// Cache write token and cost columns added by `--cache-detail`
+const CACHE_DETAIL_HEADERS: [&str; 4] = ["Cache 5m", "Cache 1h", "5m Cost", "1h Cost"];
+
+/// Grand totals over all rows, computed before rendering so rows can show their share
+struct Totals {
+    cost: f64,
+    /// `--reprice-as` cost, when repricing
+    repriced: Option<f64>,
+    /// 5-minute and 1-hour cache write costs, with `--cache-detail`
+    cache_write_costs: (Option<f64>, Option<f64>),
+    requests: u64,
+    usage: TokenUsage,
+}
+
+impl Totals {
+    fn of(stats: &[UsageStats]) -> Self {
+        let mut usage = TokenUsage::default();
+        for stat in stats {
+            usage.add(&stat.usage);
+        }
+        let cache_detail = stats.iter().any(|s| s.has_cache_detail());
+        Self {
+            cost: stats.iter().map(|s| s.cost_usd).sum(),
+            repriced: stats
+                .iter()
+                .any(|s| s.repriced_cost_usd.is_some())
+                .then(|| stats.iter().filter_map(|s| s.repriced_cost_usd).sum()),
+            cache_write_costs: (
+                cache_detail.then(|| stats.iter().filter_map(|s| s.cache_write_5m_cost_usd).sum()),
+                cache_detail.then(|| stats.iter().filter_map(|s| s.cache_write_1h_cost_usd).sum()),
+            ),
+            requests: stats.iter().map(|s| s.request_count).sum(),
+            usage,
+        }
+    }
+
+    fn cost_share(&self, stat: &UsageStats) -> f64 {
+        percentage(stat.cost_usd, self.cost)
+    }
+
+    fn token_share(&self, stat: &UsageStats) -> f64 {
+        percentage(stat.usage.total_tokens() as f64, self.usage.total_tokens() as f64)
+    }
+}
+
+/// `--explain` breakdown of a row's cost: each token category times its per-million rate,
+/// summed. Rows mixing models or without pricing get a note instead.
+fn explain_cost(stat: &UsageStats, pricing_map: &PricingMap, currency: &Currency) -> String {
+    if crate::is_aggregate_model(&stat.model) {
+        return "several models; only single-model rows are explained".to_string();
+    }
+    let Some(pricing) = get_model_pricing(pricing_map, &stat.model) else {
+        return "no pricing for this model".to_string();
+    };
+    let usage = &stat.usage;
+    let (cache_write_5m, cache_write_1h) = usage.cache_write_split();
+    let mut terms = vec![
+        (usage.input_tokens, "in", pricing.input_per_million),
+        (usage.output_tokens, "out", pricing.output_per_million),
+        (cache_write_5m, "cache write", pricing.cache_write_per_million),
+    ];
+    if cache_write_1h > 0 {
+        terms.push((cache_write_1h, "cache write 1h", pricing.cache_write_1h_rate()));
+    }
+    terms.push((usage.cache_read_input_tokens, "cache read", pricing.cache_read_per_million));
+
+    let formula = terms
+        .iter()
+        .map(|(tokens, label, rate)| {
+            format!("{} {} × {}/M", format_number(*tokens), label, currency.format(*rate))
+        })
+        .collect::<Vec<_>>()
+        .join(" + ");
+    let standard = pricing.calculate_cost(usage);
+    let mut explanation = format!("{} = {}", formula, currency.format(standard));
+    // Batch and background requests are billed at a discount the formula doesn't show
+    if (standard - stat.cost_usd).abs() >= 0.005 {
+        let discounted = currency.format(stat.cost_usd);
+        explanation.push_str(&format!(" ({} after batch discount)", discounted));
+    }
+    explanation
+}
+
+/// A 0.0-1.0 cache hit rate as a percentage
+fn format_hit_rate(rate: f64) -> String {
+    format!("{:.1}%", rate * 100.0)
+}
+
+/// Actual versus `--reprice-as` totals, with the change as a percentage of the actual cost
+pub fn format_reprice_comparison(
+    model: &ModelName,
+    actual: f64,
+    repriced: f64,
+    currency: &Currency,
+) -> String {
+    format!(
+        "Repriced as {}: {} vs actual {} ({:+.1}%)",
+        model,
+        currency.format(repriced),
+        currency.format(actual),
+        percentage(repriced - actual, actual)
+    )
+}
+
+/// What a single-model row's cache reads saved at the prices its cost was calculated with.
+/// Batch discounts apply per request, so the savings get the row's overall discount: its
+/// cost over what its tokens cost at list price.
+fn cache_savings(stat: &UsageStats, pricing_map: &PricingMap) -> Option<f64> {
+    let pricing = get_model_pricing(pricing_map, &stat.model)?;
+    let list_cost = pricing.calculate_cost(&stat.usage);
+    let discount = if list_cost > 0.0 { stat.cost_usd / list_cost } else { 1.0 };
+    Some(pricing.cache_savings(&stat.usage) * discount)
+}
+
+/// `part` as a percentage of `total`, or 0 when the total is zero
+fn percentage(part: f64, total: f64) -> f64 {
+    if total > 0.0 {
+        part / total * 100.0
+    } else {
+        0.0
+    }
+}
+
+/// Everything an `--output-fields` column can show, for a stats row or the totals
+struct FieldRow {
+    /// `None` for the totals row
+    stat: Option<(DateTime<Utc>, ModelName)>,
+    requests: u64,
+    usage: TokenUsage,
+    cost: f64,
+    /// Unknown for rows mixing models and for unpriced models
+    cache_savings: Option<f64>,
+    running_total: f64,
+}
+
+impl FieldRow {
+    fn rows(stats: &[UsageStats], pricing_map: &PricingMap) -> Vec<FieldRow> {
+        stats
+            .iter()
+            .zip(running_totals(stats))
+            .map(|(stat, running_total)| FieldRow {
+                stat: Some((stat.date, stat.model.clone())),
+                requests: stat.request_count,
+                usage: stat.usage.clone(),
+                cost: stat.cost_usd,
+                cache_savings: cache_savings(stat, pricing_map),
+                running_total,
+            })
+            .collect()
+    }
+
+    fn totals(rows: &[FieldRow]) -> FieldRow {
+        let mut usage = TokenUsage::default();
+        for row in rows {
+            usage.add(&row.usage);
+        }
+        let cost = rows.iter().map(|row| row.cost).sum();
+        FieldRow {
+            stat: None,
+            requests: rows.iter().map(|row| row.requests).sum(),
+            usage,
+            cost,
+            cache_savings: rows.iter().map(|row| row.cache_savings).sum(),
+            running_total: cost,
+        }
+    }
+
+    fn table_cell(&self, field: OutputField, currency: &Currency) -> String {
+        match field {
+            OutputField::Date => self.stat.as_ref().map_or("TOTAL".to_string(), |(d, _)| format_date(d)),
+            OutputField::Model => self.stat.as_ref().map(|(_, m)| m.to_string()).unwrap_or_default(),
+            OutputField::Requests => self.requests.to_string(),
+            OutputField::InputTokens => format_number(self.usage.input_tokens),
+            OutputField::OutputTokens => format_number(self.usage.output_tokens),
+            OutputField::CacheWriteTokens => format_number(self.usage.cache_creation_input_tokens),
+            OutputField::CacheReadTokens => format_number(self.usage.cache_read_input_tokens),
+            OutputField::TotalTokens => format_number(self.usage.total_tokens()),
+            OutputField::CostUsd => currency.format(self.cost),
+            OutputField::CacheSavingsUsd => currency.format_optional(self.cache_savings),
+            OutputField::CacheHitRate => format_hit_rate(self.usage.cache_hit_rate()),
+            OutputField::RunningTotal => currency.format(self.running_total),
+        }
+    }
+
+    fn csv_value(&self, field: OutputField, currency: &Currency) -> String {
+        match field {
+            OutputField::Date => self.stat.as_ref().map(|(d, _)| format_date(d)).unwrap_or_default(),
+            OutputField::Model => self.stat.as_ref().map(|(_, m)| m.to_string()).unwrap_or_default(),
+            OutputField::Requests => self.requests.to_string(),
+            OutputField::InputTokens => self.usage.input_tokens.to_string(),
+            OutputField::OutputTokens => self.usage.output_tokens.to_string(),
+            OutputField::CacheWriteTokens => self.usage.cache_creation_input_tokens.to_string(),
+            OutputField::CacheReadTokens => self.usage.cache_read_input_tokens.to_string(),
+            OutputField::TotalTokens => self.usage.total_tokens().to_string(),
+            OutputField::CostUsd => format!("{:.2}", currency.convert(self.cost)),
+            OutputField::CacheSavingsUsd => self
+                .cache_savings
+                .map(|c| format!("{:.2}", currency.convert(c)))
+                .unwrap_or_default(),
+            OutputField::CacheHitRate => format!("{:.4}", self.usage.cache_hit_rate()),
+            OutputField::RunningTotal => format!("{:.2}", currency.convert(self.running_total)),
+        }
+    }
+
+    fn json_value(&self, field: OutputField) -> serde_json::Value {
+        match field {
+            OutputField::Date => self.stat.as_ref().map(|(d, _)| d.to_rfc3339()).into(),
+            OutputField::Model => self.stat.as_ref().map(|(_, m)| m.canonical_string()).into(),
+            OutputField::Requests => self.requests.into(),
+            OutputField::InputTokens => self.usage.input_tokens.into(),
+            OutputField::OutputTokens => self.usage.output_tokens.into(),
+            OutputField::CacheWriteTokens => self.usage.cache_creation_input_tokens.into(),
+            OutputField::CacheReadTokens => self.usage.cache_read_input_tokens.into(),
+            OutputField::TotalTokens => self.usage.total_tokens().into(),
+            OutputField::CostUsd => self.cost.into(),
+            OutputField::CacheSavingsUsd => self.cache_savings.into(),
+            OutputField::CacheHitRate => self.usage.cache_hit_rate().into(),
+            OutputField::RunningTotal => self.running_total.into(),
+        }
+    }
+}
+
+/// A JSON `--output-fields` row, keyed in the order the fields were requested
+struct FieldsJsonRow(Vec<(&'static str, serde_json::Value)>);
+
+impl Serialize for FieldsJsonRow {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().map(|(key, value)| (key, value)))
+    }
+}
+
+fn field_title(field: OutputField, currency: &Currency) -> String {
+    let title = match field {
+        OutputField::Date => "Date",
+        OutputField::Model => "Model",
+        OutputField::Requests => "Requests",
+        OutputField::InputTokens => "Input",
+        OutputField::OutputTokens => "Output",
+        OutputField::CacheWriteTokens => "Cache Write",
+        OutputField::CacheReadTokens => "Cache Read",
+        OutputField::TotalTokens => "Total Tokens",
+        OutputField::CostUsd => return format!("Cost ({})", currency.code),
+        OutputField::CacheSavingsUsd => "Cache Savings",
+        OutputField::CacheHitRate => "Cache Hit%",
+        OutputField::RunningTotal => "Running Total",
+    };
+    title.to_string()
+}
+
+/// CSV header and JSON key, matching the names used by the default layouts
+fn field_key(field: OutputField) -> &'static str {
+    match field {
+        OutputField::Date => "date",
+        OutputField::Model => "model",
+        OutputField::Requests => "request_count",
+        OutputField::InputTokens => "input_tokens",
+        OutputField::OutputTokens => "output_tokens",
+        OutputField::CacheWriteTokens => "cache_creation_input_tokens",
+        OutputField::CacheReadTokens => "cache_read_input_tokens",
+        OutputField::TotalTokens => "total_tokens",
+        OutputField::CostUsd => "cost_usd",
+        OutputField::CacheSavingsUsd => "cache_savings_usd",
+        OutputField::CacheHitRate => "cache_hit_rate",
+        OutputField::RunningTotal => "running_total_usd",
+    }
+}
+
+/// A `_usd` column name renamed for the display currency, e.g. `cost_eur`
+fn currency_key(key: &str, currency: &Currency) -> String {
+    match key.strip_suffix("_usd") {
+        Some(stem) => format!("{}_{}", stem, currency.key()),
+        None => key.to_string(),
+    }
+}
+
+/// `format_table` with only `fields`
+fn format_fields_table(stats: &[UsageStats], fields: &[OutputField], options: &FormatOptions) -> String {
+    let colored = options.colored_output;
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+    let currency = &options.currency;
+    table.set_titles(
+        fields.iter().map(|&f| styled(&field_title(f, currency), "bFc", colored)).collect(),
+    );
+
+    let rows = FieldRow::rows(stats, &options.pricing_map());
+    for row in &rows {
+        table.add_row(
+            fields
+                .iter()
+                .map(|&field| {
+                    let text = row.table_cell(field, currency);
+                    if field == OutputField::CostUsd {
+                        styled(&text, "Fg", colored)
+                    } else {
+                        Cell::new(&text)
+                    }
+                })
+                .collect(),
+        );
+    }
+
+    if options.show_summary {
+        table.add_empty_row();
+        let totals = FieldRow::totals(&rows);
+        table.add_row(
+            fields
+                .iter()
+                .map(|&field| styled(&totals.table_cell(field, currency), "bFy", colored))
+                .collect(),
+        );
+    }
+
+    table.to_string()
+}
+
+pub fn format_table(stats: &[UsageStats], options: &FormatOptions) -> String {
+    if let Some(fields) = &options.output_fields {
+        return format_fields_table(stats, fields, options);
+    }
+    let detailed = options.detailed;
+    let colored = options.colored_output;
+    let currency = &options.currency;
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+
+    // Per-day model and cache detail columns only appear in detailed mode when they were
+    // computed
+    let daily = detailed && stats.iter().any(|s| s.has_daily_stats());
+    let cache_detail = detailed && stats.iter().any(|s| s.has_cache_detail());
+    let totals = Totals::of(stats);
+    let repriced = totals.repriced.is_some();
+
+    // Set headers based on detail level
+    let mut titles = if detailed {
+        let mut titles = Row::new(vec![
+            styled("Date", "bFc", colored),
+            styled("Model", "bFc", colored),
+            styled("Requests", "bFc", colored),
+        ]);
+        for header in token_headers(options.aggregate_cache) {
+            titles.add_cell(styled(header, "bFc", colored));
+        }
+        titles.add_cell(styled("Total Tokens", "bFc", colored));
+        titles.add_cell(styled(&format!("Cost ({})", currency.code), "bFc", colored));
+        if repriced {
+            titles.add_cell(styled(&format!("Repriced ({})", currency.code), "bFc", colored));
+        }
+        titles.add_cell(styled("Cache Hit%", "bFc", colored));
+        if daily {
+            titles.add_cell(styled("Peak Day", "bFc", colored));
+            titles.add_cell(styled("Avg/Day", "bFc", colored));
+            titles.add_cell(styled("Active Days", "bFc", colored));
+        }
+        if cache_detail {
+            for header in CACHE_DETAIL_HEADERS {
+                titles.add_cell(styled(header, "bFc", colored));
+            }
+        }
+        titles
+    } else {
+        let mut titles = Row::new(vec![
+            styled("Date", "bFc", colored),
+            styled("Model", "bFc", colored),
+            styled("Requests", "bFc", colored),
+            styled("Total Tokens", "bFc", colored),
+            styled(&format!("Cost ({})", currency.code), "bFc", colored),
+        ]);
+        if repriced {
+            titles.add_cell(styled(&format!("Repriced ({})", currency.code), "bFc", colored));
+        }
+        titles
+    };
+    if options.running_total {
+        titles.add_cell(styled("Running Total", "bFc", colored));
+    }
+    if options.percentages {
+        titles.add_cell(styled("% of Cost", "bFc", colored));
+        titles.add_cell(styled("% of Tokens", "bFc", colored));
+    }
+    if options.sparkline {
+        titles.add_cell(styled("Cost Trend", "bFc", colored));
+    }
+    let explain_pricing = options.explain.then(|| options.pricing_map());
+    if options.explain {
+        titles.add_cell(styled("Cost Formula", "bFc", colored));
+    }
+    table.set_titles(titles);
+
+    let costs: Vec<f64> = stats.iter().map(|s| s.cost_usd).collect();
+    let running = running_totals(stats);
+
+    for (i, stat) in stats.iter().enumerate() {
+        // --breakdown: the date on its own row, a row per model, then the day's total row
+        let date = match &stat.model_breakdown {
+            Some(models) => {
+                table.add_row(Row::new(vec![styled(&format_date(&stat.date), "b", colored)]));
+                for model in models {
+                    table.add_row(breakdown_row(model, repriced, options));
+                }
+                "  Day total".to_string()
+            }
+            None => format_date(&stat.date),
+        };
+        let mut row = if detailed {
+            let mut row = Row::new(vec![
+                Cell::new(&date),
+                Cell::new(&stat.model.to_string()),
+                Cell::new(&stat.request_count.to_string()),
+            ]);
+            for tokens in token_columns(&stat.usage, options.aggregate_cache) {
+                row.add_cell(Cell::new(&format_number(tokens)));
+            }
+            row.add_cell(Cell::new(&format_number(stat.usage.total_tokens())));
+            row.add_cell(styled(&currency.format(stat.cost_usd), "Fg", colored));
+            if repriced {
+                row.add_cell(Cell::new(&currency.format_optional(stat.repriced_cost_usd)));
+            }
+            row.add_cell(Cell::new(&format_hit_rate(stat.cache_hit_rate)));
+            if daily {
+                row.add_cell(Cell::new(&currency.format_optional(stat.peak_day_cost)));
+                row.add_cell(Cell::new(&currency.format_optional(stat.avg_daily_cost)));
+                row.add_cell(Cell::new(
+                    &stat.active_days.map(|d| d.to_string()).unwrap_or_default(),
+                ));
+            }
+            if cache_detail {
+                let (five_minute, one_hour) = stat.usage.cache_write_split();
+                row.add_cell(Cell::new(&format_number(five_minute)));
+                row.add_cell(Cell::new(&format_number(one_hour)));
+                row.add_cell(Cell::new(&currency.format_optional(stat.cache_write_5m_cost_usd)));
+                row.add_cell(Cell::new(&currency.format_optional(stat.cache_write_1h_cost_usd)));
+            }
+            row
+        } else {
+            let mut row = Row::new(vec![
+                Cell::new(&date),
+                Cell::new(&stat.model.to_string()),
+                Cell::new(&stat.request_count.to_string()),
+                Cell::new(&format_number(stat.usage.total_tokens())),
+                styled(&currency.format(stat.cost_usd), "Fg", colored),
+            ]);
+            if repriced {
+                row.add_cell(Cell::new(&currency.format_optional(stat.repriced_cost_usd)));
+            }
+            row
+        };
+        if options.running_total {
+            row.add_cell(Cell::new(&currency.format(running[i])));
+        }
+        if options.percentages {
+            row.add_cell(Cell::new(&format!("{:.1}%", totals.cost_share(stat))));
+            row.add_cell(Cell::new(&format!("{:.1}%", totals.token_share(stat))));
+        }
+        if options.sparkline {
+            // Look back from this row so the rightmost character is the current row
+            let start = (i + 1).saturating_sub(SPARKLINE_WINDOW);
+            row.add_cell(Cell::new(&make_sparkline(&costs[start..=i])));
+        }
+        if let Some(pricing_map) = &explain_pricing {
+            row.add_cell(Cell::new(&explain_cost(stat, pricing_map, currency)));
+        }
+        table.add_row(row);
+    }
+
+    // Add summary row if requested
+    if options.show_summary {
+        table.add_empty_row();
+        let mut row = if detailed {
+            let mut row = Row::new(vec![
+                styled("TOTAL", "bFy", colored),
+                styled("", "bFy", colored),
+                styled(&totals.requests.to_string(), "bFy", colored),
+            ]);
+            for tokens in token_columns(&totals.usage, options.aggregate_cache) {
+                row.add_cell(styled(&format_number(tokens), "bFy", colored));
+            }
+            row.add_cell(styled(&format_number(totals.usage.total_tokens()), "bFy", colored));
+            row.add_cell(styled(&currency.format(totals.cost), "bFgY", colored));
+            if repriced {
+                row.add_cell(styled(&currency.format_optional(totals.repriced), "bFy", colored));
+            }
+            row.add_cell(styled(
+                &format_hit_rate(totals.usage.cache_hit_rate()),
+                "bFy",
+                colored,
+            ));
+            if daily {
+                for _ in 0..3 {
+                    row.add_cell(Cell::new(""));
+                }
+            }
+            if cache_detail {
+                let (five_minute, one_hour) = totals.usage.cache_write_split();
+                let (five_minute_cost, one_hour_cost) = totals.cache_write_costs;
+                row.add_cell(styled(&format_number(five_minute), "bFy", colored));
+                row.add_cell(styled(&format_number(one_hour), "bFy", colored));
+                row.add_cell(styled(&currency.format_optional(five_minute_cost), "bFy", colored));
+                row.add_cell(styled(&currency.format_optional(one_hour_cost), "bFy", colored));
+            }
+            row
+        } else {
+            let mut row = Row::new(vec![
+                styled("TOTAL", "bFy", colored),
+                styled("", "bFy", colored),
+                styled(&totals.requests.to_string(), "bFy", colored),
+                styled(&format_number(totals.usage.total_tokens()), "bFy", colored),
+                styled(&currency.format(totals.cost), "bFgY", colored),
+            ]);
+            if repriced {
+                row.add_cell(styled(&currency.format_optional(totals.repriced), "bFy", colored));
+            }
+            row
+        };
+        if options.running_total {
+            row.add_cell(styled(&currency.format(totals.cost), "bFy", colored));
+        }
+        if options.percentages {
+            row.add_cell(styled("100.0%", "bFy", colored));
+            row.add_cell(styled("100.0%", "bFy", colored));
+        }
+        if options.sparkline {
+            row.add_cell(Cell::new(&make_sparkline(&costs)));
+        }
+        table.add_row(row);
+    }
+
+    table.to_string()
+}
+
+/// An indented `--breakdown` row for one model's part of a day, filling the token and cost
+/// columns only
+fn breakdown_row(breakdown: &ModelBreakdown, repriced: bool, options: &FormatOptions) -> Row {
+    let usage = &breakdown.usage;
+    let mut row = Row::new(vec![
+        Cell::new(""),
+        Cell::new(&format!("  {}", breakdown.model)),
+        Cell::new(""),
+    ]);
+    if options.detailed {
+        for tokens in token_columns(usage, options.aggregate_cache) {
+            row.add_cell(Cell::new(&format_number(tokens)));
+        }
+    }
+    row.add_cell(Cell::new(&format_number(usage.total_tokens())));
+    row.add_cell(Cell::new(&options.currency.format(breakdown.cost_usd)));
+    if options.detailed {
+        if repriced {
+            row.add_cell(Cell::new(""));
+        }
+        row.add_cell(Cell::new(&format_hit_rate(usage.cache_hit_rate())));
+    }
+    row
+}
+
+/// Default width of `--format ascii-chart`, in characters
+pub const ASCII_CHART_WIDTH: usize = 80;
+
+/// Rows the tallest bar of an ASCII chart spans
+const ASCII_CHART_HEIGHT: usize = 10;
+
+/// A row's label on the chart: its model when grouped by model, otherwise its date
+fn chart_label(stat: &UsageStats) -> String {
+    match &stat.model {
+        ModelName::Unknown(name) if name == "all" => format_date(&stat.date),
+        model => model.to_string(),
+    }
+}
+
+/// Vertical bar chart of each row's cost in plain ASCII, at most `width` characters wide.
+/// Bars are scaled to the most expensive row. When there are more rows than columns,
+/// adjacent rows are summed into one bar. Labels go in a row under the bars when they fit,
+/// otherwise they are listed below in bar order.
+pub fn format_ascii_chart(stats: &[UsageStats], width: usize, currency: &Currency) -> String {
+    if stats.is_empty() {
+        return "No data to chart.\n".to_string();
+    }
+
+    let per_bar = stats.len().div_ceil(width.max(1));
+    let bars: Vec<(String, f64)> = stats
+        .chunks(per_bar)
+        .map(|chunk| {
+            let first = chart_label(&chunk[0]);
+            let label = match chunk.last().filter(|_| chunk.len() > 1) {
+                Some(last) => format!("{}..{}", first, chart_label(last)),
+                None => first,
+            };
+            (label, chunk.iter().map(|s| s.cost_usd).sum())
+        })
+        .collect();
+
+    let bar_width = (width / bars.len()).max(1);
+    // Leave a space between bars when there's room for one
+    let (fill, gap) = if bar_width > 1 { (bar_width - 1, 1) } else { (1, 0) };
+    let max_cost = bars.iter().map(|(_, cost)| *cost).fold(0.0, f64::max);
+    let heights: Vec<usize> = bars
+        .iter()
+        .map(|(_, cost)| {
+            if max_cost > 0.0 {
+                (cost / max_cost * ASCII_CHART_HEIGHT as f64).round() as usize
+            } else {
+                0
+            }
+        })
+        .collect();
+
+    let mut out = format!("max {}\n", currency.format(max_cost));
+    for level in (1..=ASCII_CHART_HEIGHT).rev() {
+        let mut row = String::new();
+        for &height in &heights {
+            let cell = if height >= level { "#" } else { " " };
+            row.push_str(&cell.repeat(fill));
+            row.push_str(&" ".repeat(gap));
+        }
+        out.push_str(row.trim_end());
+        out.push('\n');
+    }
+    out.push_str(&"-".repeat(bar_width * bars.len()));
+    out.push('\n');
+
+    if bars.iter().all(|(label, _)| label.len() <= fill) {
+        let labels: Vec<String> =
+            bars.iter().map(|(label, _)| format!("{:<w$}", label, w = bar_width)).collect();
+        out.push_str(labels.concat().trim_end());
+        out.push('\n');
+    } else {
+        for (label, cost) in &bars {
+            out.push_str(&format!("  {}  {}\n", label, currency.format(*cost)));
+        }
+    }
+
+    out
+}
+
+/// Number of rows (ending at the current one) each row's cost trend covers
+const SPARKLINE_WINDOW: usize = 14;
+
+const SPARKLINE_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Render values as a one-line block sparkline, each value's height being its quantile
+/// among `values`, so one outlier doesn't flatten the rest. Equal values share their middle
+/// rank, and all-equal values render as a flat mid-height line.
+pub fn make_sparkline(values: &[f64]) -> String {
+    let top = (SPARKLINE_CHARS.len() - 1) as f64;
+
+    values
+        .iter()
+        .map(|&value| {
+            if values.len() < 2 {
+                return SPARKLINE_CHARS[3];
+            }
+            let below = values.iter().filter(|&&other| other < value).count();
+            let equal = values.iter().filter(|&&other| other == value).count();
+            let middle = below as f64 + equal.saturating_sub(1) as f64 / 2.0;
+            let quantile = middle / (values.len() - 1) as f64;
+            SPARKLINE_CHARS[(quantile * top).floor() as usize]
+        })
+        .collect()
+}
+
+pub fn format_csv(stats: &[UsageStats], options: &FormatOptions) -> String {
+    let detailed = options.detailed;
+    let delimiter = options.csv_delimiter.unwrap_or(',');
+    let currency = &options.currency;
+    let mut csv = String::new();
+    if let Some(fields) = &options.output_fields {
+        let headers: Vec<String> =
+            fields.iter().map(|&f| currency_key(field_key(f), currency)).collect();
+        push_csv_row(&mut csv, &headers, delimiter);
+        for row in FieldRow::rows(stats, &options.pricing_map()) {
+            let values: Vec<String> = fields.iter().map(|&f| row.csv_value(f, currency)).collect();
+            push_csv_row(&mut csv, &values, delimiter);
+        }
+        return csv;
+    }
+    let daily = detailed && stats.iter().any(|s| s.has_daily_stats());
+    let cache_detail = detailed && stats.iter().any(|s| s.has_cache_detail());
+    let totals = Totals::of(stats);
+    let repriced = totals.repriced.is_some();
+
+    // Headers
+    let mut headers: Vec<String> = vec!["Date".into(), "Model".into(), "Requests".into()];
+    if detailed {
+        for header in token_headers(options.aggregate_cache) {
+            headers.push(format!("{} Tokens", header));
+        }
+    }
+    headers.extend([
+        "Total Tokens".to_string(),
+        format!("Cost {}", currency.code),
+        "Cache Hit Rate".to_string(),
+    ]);
+    if daily {
+        headers.extend([
+            format!("Peak Day Cost {}", currency.code),
+            format!("Avg Daily Cost {}", currency.code),
+            "Active Days".to_string(),
+        ]);
+    }
+    if cache_detail {
+        headers.extend(
+            [
+                "Cache Write 5m Tokens".to_string(),
+                "Cache Write 1h Tokens".to_string(),
+                format!("Cache Write 5m Cost {}", currency.code),
+                format!("Cache Write 1h Cost {}", currency.code),
+            ],
+        );
+    }
+    if repriced {
+        headers.push(currency_key("repriced_cost_usd", currency));
+    }
+    if options.running_total {
+        headers.push(currency_key("running_total_usd", currency));
+    }
+    if options.percentages {
+        headers.extend(["Percent of Cost", "Percent of Tokens"].map(String::from));
+    }
+    push_csv_row(&mut csv, &headers, delimiter);
+
+    // Data rows
+    for (stat, running) in stats.iter().zip(running_totals(stats)) {
+        let mut fields = vec![
+            if options.per_request {
+                stat.date.to_rfc3339()
+            } else {
+                format_date(&stat.date)
+            },
+            stat.model.to_string(),
+            stat.request_count.to_string(),
+        ];
+        if detailed {
+            for tokens in token_columns(&stat.usage, options.aggregate_cache) {
+                fields.push(tokens.to_string());
+            }
+        }
+        fields.push(stat.usage.total_tokens().to_string());
+        if options.per_request {
+            fields.push(format!("{:.6}", currency.convert(stat.cost_usd)));
+        } else {
+            fields.push(format!("{:.2}", currency.convert(stat.cost_usd)));
+        }
+        fields.push(format!("{:.4}", stat.cache_hit_rate));
+        if daily {
+            fields.push(optional_amount(stat.peak_day_cost, currency));
+            fields.push(optional_amount(stat.avg_daily_cost, currency));
+            fields.push(stat.active_days.map(|d| d.to_string()).unwrap_or_default());
+        }
+        if cache_detail {
+            let (five_minute, one_hour) = stat.usage.cache_write_split();
+            fields.push(five_minute.to_string());
+            fields.push(one_hour.to_string());
+            for cost in [stat.cache_write_5m_cost_usd, stat.cache_write_1h_cost_usd] {
+                let cost = cost.map(|c| format!("{:.4}", currency.convert(c)));
+                fields.push(cost.unwrap_or_default());
+            }
+        }
+        if repriced {
+            fields.push(optional_amount(stat.repriced_cost_usd, currency));
+        }
+        if options.running_total {
+            fields.push(format!("{:.2}", currency.convert(running)));
+        }
+        if options.percentages {
+            fields.push(format!("{:.1}", totals.cost_share(stat)));
+            fields.push(format!("{:.1}", totals.token_share(stat)));
+        }
+        push_csv_row(&mut csv, &fields, delimiter);
+    }
+
+    if options.show_summary {
+        csv.push_str(&format_summary_csv(stats, options));
+    }
+    csv
+}
+
+/// A converted CSV amount, empty when there's none
+fn optional_amount(cost: Option<f64>, currency: &Currency) -> String {
+    cost.map(|c| format!("{:.2}", currency.convert(c))).unwrap_or_default()
+}
+
+/// A blank line then a `TOTAL` row lined up with `format_csv`'s columns
+pub fn format_summary_csv(stats: &[UsageStats], options: &FormatOptions) -> String {
+    let delimiter = options.csv_delimiter.unwrap_or(',');
+    let currency = &options.currency;
+    let mut csv = String::from("\n");
+    if let Some(fields) = &options.output_fields {
+        let totals = FieldRow::totals(&FieldRow::rows(stats, &options.pricing_map()));
+        let values: Vec<String> = fields
+            .iter()
+            .map(|&field| match field {
+                OutputField::Date => "TOTAL".to_string(),
+                field => totals.csv_value(field, currency),
+            })
+            .collect();
+        push_csv_row(&mut csv, &values, delimiter);
+        return csv;
+    }
+
+    let detailed = options.detailed;
+    let totals = Totals::of(stats);
+    let mut fields = vec!["TOTAL".to_string(), String::new(), totals.requests.to_string()];
+    if detailed {
+        for tokens in token_columns(&totals.usage, options.aggregate_cache) {
+            fields.push(tokens.to_string());
+        }
+    }
+    fields.push(totals.usage.total_tokens().to_string());
+    fields.push(format!("{:.2}", currency.convert(totals.cost)));
+    fields.push(format!("{:.4}", totals.usage.cache_hit_rate()));
+    if detailed && stats.iter().any(|s| s.has_daily_stats()) {
+        fields.extend([String::new(), String::new(), String::new()]);
+    }
+    if detailed && stats.iter().any(|s| s.has_cache_detail()) {
+        let (five_minute, one_hour) = totals.usage.cache_write_split();
+        fields.push(five_minute.to_string());
+        fields.push(one_hour.to_string());
+        for cost in [totals.cache_write_costs.0, totals.cache_write_costs.1] {
+            let cost = cost.map(|c| format!("{:.4}", currency.convert(c)));
+            fields.push(cost.unwrap_or_default());
+        }
+    }
+    if let Some(repriced) = totals.repriced {
+        fields.push(format!("{:.2}", currency.convert(repriced)));
+    }
+    if options.running_total {
+        fields.push(format!("{:.2}", currency.convert(totals.cost)));
+    }
+    if options.percentages {
+        fields.extend(["100.0".to_string(), "100.0".to_string()]);
+    }
+    push_csv_row(&mut csv, &fields, delimiter);
+    csv
+}
+
+/// Append one delimited line, quoting fields that contain the delimiter, a quote or a newline
+fn push_csv_row(csv: &mut String, fields: &[String], delimiter: char) {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            csv.push(delimiter);
+        }
+        if field.contains([delimiter, '"', '\n', '\r']) {
+            csv.push('"');
+            csv.push_str(&field.replace('"', "\"\""));
+            csv.push('"');
+        } else {
+            csv.push_str(field);
+        }
+    }
+    csv.push('\n');
+}
+
+/// A stats row with the cumulative cost up to and including it
+#[derive(Serialize)]
+struct RunningTotalRow<'a> {
+    #[serde(flatten)]
+    stats: &'a UsageStats,
+    running_total_usd: f64,
+}
+
+/// Report totals for `--summary` with machine-readable formats
+#[derive(Debug, Serialize)]
+pub struct UsageSummary {
+    pub total_cost_usd: f64,
+    pub total_requests: u64,
+    pub total_tokens: u64,
+    /// Most expensive first
+    pub by_model: Vec<ModelSummary>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModelSummary {
+    pub model: String,
+    pub requests: u64,
+    pub tokens: u64,
+    pub cost_usd: f64,
+}
+
+impl UsageSummary {
+    pub fn of(stats: &[UsageStats]) -> Self {
+        let totals = Totals::of(stats);
+        let mut by_model: HashMap<String, ModelSummary> = HashMap::new();
+        for stat in stats {
+            let model = stat.model.canonical_string();
+            let summary = by_model.entry(model.clone()).or_insert(ModelSummary {
+                model,
+                requests: 0,
+                tokens: 0,
+                cost_usd: 0.0,
+            });
+            summary.requests += stat.request_count;
+            summary.tokens += stat.usage.total_tokens();
+            summary.cost_usd += stat.cost_usd;
+        }
+        let mut by_model: Vec<ModelSummary> = by_model.into_values().collect();
+        by_model.sort_by(|a, b| b.cost_usd.total_cmp(&a.cost_usd).then(a.model.cmp(&b.model)));
+        Self {
+            total_cost_usd: totals.cost,
+            total_requests: totals.requests,
+            total_tokens: totals.usage.total_tokens(),
+            by_model,
+        }
+    }
+}
+
+/// `{"total_cost_usd": ..., "total_requests": ..., "total_tokens": ..., "by_model": [...]}`
+pub fn format_summary_json(stats: &[UsageStats]) -> Result<String, serde_json::Error> {
+    serde_json::to_string_pretty(&UsageSummary::of(stats))
+}
+
+/// Rows and their `--summary` totals in one JSON document
+#[derive(Serialize)]
+struct SummarizedReport<'a, T: Serialize + ?Sized> {
+    stats: &'a T,
+    summary: UsageSummary,
+}
+
+/// `rows` as JSON, wrapped with the summary of `stats` when `options.show_summary`
+fn to_json_report<T: Serialize + ?Sized>(
+    rows: &T,
+    stats: &[UsageStats],
+    options: &FormatOptions,
+) -> Result<String, serde_json::Error> {
+    if options.show_summary {
+        let report = SummarizedReport { stats: rows, summary: UsageSummary::of(stats) };
+        to_json(&report, options.compact_json)
+    } else {
+        to_json(rows, options.compact_json)
+    }
+}
+
+/// A JSON array of rows, or `{"stats": [...], "summary": {...}}` with `--summary`
+pub fn format_json(
+    stats: &[UsageStats],
+    options: &FormatOptions,
+) -> Result<String, serde_json::Error> {
+    if let Some(fields) = &options.output_fields {
+        let rows: Vec<FieldsJsonRow> = FieldRow::rows(stats, &options.pricing_map())
+            .iter()
+            .map(|row| {
+                FieldsJsonRow(fields.iter().map(|&field| (field_key(field), row.json_value(field))).collect())
+            })
+            .collect();
+        return to_json_report(&rows, stats, options);
+    }
+    if options.running_total {
+        let rows: Vec<_> = stats
+            .iter()
+            .zip(running_totals(stats))
+            .map(|(stats, running_total_usd)| RunningTotalRow {
+                stats,
+                running_total_usd,
+            })
+            .collect();
+        return to_json_report(&rows, stats, options);
+    }
+    to_json_report(stats, stats, options)
+}
+
+fn to_json<T: Serialize + ?Sized>(value: &T, compact: bool) -> Result<String, serde_json::Error> {
+    if compact {
+        serde_json::to_string(value)
+    } else {
+        serde_json::to_string_pretty(value)
+    }
+}
+
+/// One compact JSON object per line, without surrounding array brackets
+pub fn format_ndjson(stats: &[UsageStats]) -> Result<String, serde_json::Error> {
+    let mut ndjson = String::new();
+    for stat in stats {
+        ndjson.push_str(&serde_json::to_string(stat)?);
+        ndjson.push('\n');
+    }
+    Ok(ndjson)
+}
+
+/// Write stats as JSON Lines directly to `writer`, without building the whole output in memory
+pub fn write_ndjson_streaming<'a, W: Write>(
+    stats_iter: impl Iterator<Item = &'a UsageStats>,
+    writer: &mut W,
+) -> io::Result<()> {
+    for stat in stats_iter {
+        serde_json::to_writer(&mut *writer, stat)?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+pub fn format_markdown(stats: &[UsageStats], options: &FormatOptions) -> String {
+    let detailed = options.detailed;
+    let currency = &options.currency;
+    let mut md = String::new();
+    let daily = detailed && stats.iter().any(|s| s.has_daily_stats());
+    let cache_detail = detailed && stats.iter().any(|s| s.has_cache_detail());
+    let totals = Totals::of(stats);
+    let repriced = totals.repriced.is_some();
+
+    // Headers
+    let cost_header = format!("Cost ({})", currency.code);
+    let repriced_header = format!("Repriced ({})", currency.code);
+    let mut headers: Vec<&str> = vec!["Date", "Model", "Requests"];
+    if detailed {
+        headers.extend(token_headers(options.aggregate_cache));
+    }
+    headers.extend(["Total Tokens", &cost_header]);
+    if repriced {
+        headers.push(&repriced_header);
+    }
+    if daily {
+        headers.extend(["Peak Day", "Avg/Day", "Active Days"]);
+    }
+    if cache_detail {
+        headers.extend(CACHE_DETAIL_HEADERS);
+    }
+    if options.running_total {
+        headers.push("Running Total");
+    }
+    if options.percentages {
+        headers.extend(["% of Cost", "% of Tokens"]);
+    }
+    md.push_str(&format!("| {} |\n", headers.join(" | ")));
+    md.push_str(&format!(
+        "|{}|\n",
+        headers
+            .iter()
+            .map(|h| "-".repeat(h.len() + 2))
+            .collect::<Vec<_>>()
+            .join("|")
+    ));
+
+    // Data rows
+    for (stat, running) in stats.iter().zip(running_totals(stats)) {
+        let mut cells = vec![
+            format_date(&stat.date),
+            stat.model.to_string(),
+            stat.request_count.to_string(),
+        ];
+        if detailed {
+            cells.extend(
+                token_columns(&stat.usage, options.aggregate_cache)
+                    .into_iter()
+                    .map(format_number),
+            );
+        }
+        cells.push(format_number(stat.usage.total_tokens()));
+        cells.push(currency.format(stat.cost_usd));
+        if repriced {
+            cells.push(currency.format_optional(stat.repriced_cost_usd));
+        }
+        if daily {
+            cells.extend([
+                currency.format_optional(stat.peak_day_cost),
+                currency.format_optional(stat.avg_daily_cost),
+                stat.active_days.map(|d| d.to_string()).unwrap_or_default(),
+            ]);
+        }
+        if cache_detail {
+            let (five_minute, one_hour) = stat.usage.cache_write_split();
+            cells.extend([
+                format_number(five_minute),
+                format_number(one_hour),
+                currency.format_optional(stat.cache_write_5m_cost_usd),
+                currency.format_optional(stat.cache_write_1h_cost_usd),
+            ]);
+        }
+        if options.running_total {
+            cells.push(currency.format(running));
+        }
+        if options.percentages {
+            cells.push(format!("{:.1}%", totals.cost_share(stat)));
+            cells.push(format!("{:.1}%", totals.token_share(stat)));
+        }
+        md.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+
+    // Summary row
+    if options.show_summary {
+        let mut cells = vec![
+            "**TOTAL**".to_string(),
+            String::new(),
+            format!("**{}**", totals.requests),
+        ];
+        if detailed {
+            cells.extend(
+                token_columns(&totals.usage, options.aggregate_cache)
+                    .into_iter()
+                    .map(|tokens| format!("**{}**", format_number(tokens))),
+            );
+        }
+        cells.push(format!("**{}**", format_number(totals.usage.total_tokens())));
+        cells.push(format!("**{}**", currency.format(totals.cost)));
+        if repriced {
+            cells.push(format!("**{}**", currency.format_optional(totals.repriced)));
+        }
+        if daily {
+            cells.extend([String::new(), String::new(), String::new()]);
+        }
+        if cache_detail {
+            let (five_minute, one_hour) = totals.usage.cache_write_split();
+            let (five_minute_cost, one_hour_cost) = totals.cache_write_costs;
+            cells.extend([
+                format!("**{}**", format_number(five_minute)),
+                format!("**{}**", format_number(one_hour)),
+                format!("**{}**", currency.format_optional(five_minute_cost)),
+                format!("**{}**", currency.format_optional(one_hour_cost)),
+            ]);
+        }
+        if options.running_total {
+            cells.push(format!("**{}**", currency.format(totals.cost)));
+        }
+        if options.percentages {
+            cells.extend(["**100.0%**".to_string(), "**100.0%**".to_string()]);
+        }
+        md.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+
+    md
+}
+
+#[derive(Serialize)]
+struct ModelRow {
+    name: String,
+    canonical: String,
+    family: String,
+    input_per_million: Option<f64>,
+    output_per_million: Option<f64>,
+    cache_write_per_million: Option<f64>,
+    cache_read_per_million: Option<f64>,
+}
+
+fn model_rows(pricing_map: &PricingMap) -> Vec<ModelRow> {
+    ModelName::known_variants()
+        .into_iter()
+        .map(|model| {
+            let pricing = pricing_map.get(&model);
+            ModelRow {
+                name: model.to_string(),
+                canonical: model.canonical_string(),
+                family: model.family().to_string(),
+                input_per_million: pricing.map(|p| p.input_per_million),
+                output_per_million: pricing.map(|p| p.output_per_million),
+                cache_write_per_million: pricing.map(|p| p.cache_write_per_million),
+                cache_read_per_million: pricing.map(|p| p.cache_read_per_million),
+            }
+        })
+        .collect()
+}
+
+fn format_rate(rate: Option<f64>) -> String {
+    rate.map(|r| format!("${:.2}", r)).unwrap_or_else(|| "-".to_string())
+}
+
+/// Table of every known model with its per-million-token rates
+pub fn format_models_table(pricing_map: &PricingMap, colored: bool) -> String {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+    table.set_titles(Row::new(vec![
+        styled("Model", "bFc", colored),
+        styled("Canonical Name", "bFc", colored),
+        styled("Family", "bFc", colored),
+        styled("Input/M", "bFc", colored),
+        styled("Output/M", "bFc", colored),
+        styled("Cache Write/M", "bFc", colored),
+        styled("Cache Read/M", "bFc", colored),
+    ]));
+
+    for row in model_rows(pricing_map) {
+        table.add_row(Row::new(vec![
+            Cell::new(&row.name),
+            Cell::new(&row.canonical),
+            Cell::new(&row.family),
+            Cell::new(&format_rate(row.input_per_million)),
+            Cell::new(&format_rate(row.output_per_million)),
+            Cell::new(&format_rate(row.cache_write_per_million)),
+            Cell::new(&format_rate(row.cache_read_per_million)),
+        ]));
+    }
+
+    table.to_string()
+}
+
+pub fn format_models_csv(pricing_map: &PricingMap) -> String {
+    let mut csv = String::from(
+        "Model,Canonical Name,Family,Input Per Million,Output Per Million,Cache Write Per Million,Cache Read Per Million\n",
+    );
+    let rate = |r: Option<f64>| r.map(|r| format!("{:.2}", r)).unwrap_or_default();
+
+    for row in model_rows(pricing_map) {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{}\n",
+            row.name,
+            row.canonical,
+            row.family,
+            rate(row.input_per_million),
+            rate(row.output_per_million),
+            rate(row.cache_write_per_million),
+            rate(row.cache_read_per_million)
+        ));
+    }
+
+    csv
+}
+
+pub fn format_models_json(pricing_map: &PricingMap) -> Result<String, serde_json::Error> {
+    serde_json::to_string_pretty(&model_rows(pricing_map))
+}
+
+pub fn format_models_ndjson(pricing_map: &PricingMap) -> Result<String, serde_json::Error> {
+    let mut ndjson = String::new();
+    for row in model_rows(pricing_map) {
+        ndjson.push_str(&serde_json::to_string(&row)?);
+        ndjson.push('\n');
+    }
+    Ok(ndjson)
+}
+
+pub fn format_models_markdown(pricing_map: &PricingMap) -> String {
+    let mut md = String::new();
+    md.push_str("| Model | Canonical Name | Family | Input/M | Output/M | Cache Write/M | Cache Read/M |\n");
+    md.push_str("|-------|----------------|--------|---------|----------|---------------|--------------|\n");
+
+    for row in model_rows(pricing_map) {
+        md.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} | {} |\n",
+            row.name,
+            row.canonical,
+            row.family,
+            format_rate(row.input_per_million),
+            format_rate(row.output_per_million),
+            format_rate(row.cache_write_per_million),
+            format_rate(row.cache_read_per_million)
+        ));
+    }
+
+    md
+}
+
+/// Timing breakdown for `--profile`, as a table or as JSON
+pub fn format_parse_profile(profile: &ParseProfile, json: bool) -> String {
+    if json {
+        // ParseProfile only holds numbers and paths, so serialization can't fail
+        return serde_json::to_string_pretty(profile).unwrap_or_default() + "\n";
+    }
 
-pub fn format_table(stats: &[UsageStats], detailed: bool, show_summary: bool) -> String {
     let mut table = Table::new();
     table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+    table.set_titles(Row::new(vec![Cell::new("Phase"), Cell::new("Time (ms)")]));
+    for (phase, ms) in [
+        ("File discovery", profile.file_discovery_ms),
+        ("Parsing", profile.parse_ms),
+        ("Date filtering", profile.filter_ms),
+        ("Deduplication", profile.dedup_ms),
+        ("Total", profile.total_ms),
+    ] {
+        table.add_row(Row::new(vec![Cell::new(phase), Cell::new(&format!("{:.2}", ms))]));
+    }
 
-    // Set headers based on detail level
-    if detailed {
-        table.set_titles(Row::new(vec![
-            Cell::new("Date").style_spec("bFc"),
-            Cell::new("Model").style_spec("bFc"),
-            Cell::new("Requests").style_spec("bFc"),
-            Cell::new("Input").style_spec("bFc"),
-            Cell::new("Output").style_spec("bFc"),
-            Cell::new("Cache Write").style_spec("bFc"),
-            Cell::new("Cache Read").style_spec("bFc"),
-            Cell::new("Total Tokens").style_spec("bFc"),
-            Cell::new("Cost (USD)").style_spec("bFc"),
-        ]));
-    } else {
-        table.set_titles(Row::new(vec![
-            Cell::new("Date").style_spec("bFc"),
-            Cell::new("Model").style_spec("bFc"),
-            Cell::new("Requests").style_spec("bFc"),
-            Cell::new("Total Tokens").style_spec("bFc"),
-            Cell::new("Cost (USD)").style_spec("bFc"),
+    let mut out = table.to_string();
+    out.push_str(&format!(
+        "{} files ({} with errors), {} entries parsed, {} after filtering and deduplication\n",
+        profile.files_total, profile.files_with_errors, profile.entries_parsed, profile.entries_final
+    ));
+    if !profile.slowest_files.is_empty() {
+        out.push_str("Slowest files:\n");
+        for (path, time) in &profile.slowest_files {
+            out.push_str(&format!(
+                "  {:>8.2}ms  {}\n",
+                time.as_secs_f64() * 1000.0,
+                path.display()
+            ));
+        }
+    }
+    out
+}
+
+/// Table of 15-minute token velocity windows
+pub fn format_velocity_table(
+    rows: &[(DateTime<Utc>, f64, f64)],
+    currency: &Currency,
+    colored: bool,
+) -> String {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+    table.set_titles(Row::new(vec![
+        styled("Window Start", "bFc", colored),
+        styled("Tokens/min", "bFc", colored),
+        styled("Cost/hr (projected)", "bFc", colored),
+    ]));
+
+    for (start, tokens_per_min, cost_per_hour) in rows {
+        table.add_row(Row::new(vec![
+            Cell::new(&start.format("%Y-%m-%d %H:%M").to_string()),
+            Cell::new(&format_number(tokens_per_min.round() as u64)),
+            styled(&currency.format(*cost_per_hour), "Fg", colored),
         ]));
     }
 
-    let mut total_cost = 0.0;
-    let mut total_requests = 0;
-    let mut total_usage = TokenUsage::default();
+    table.to_string()
+}
 
-    for stat in stats {
-        total_cost += stat.cost_usd;
-        total_requests += stat.request_count;
-        total_usage.add(&stat.usage);
+/// A `--velocity` row for machine-readable output
+#[derive(Serialize)]
+struct VelocityRow {
+    window_start: DateTime<Utc>,
+    tokens_per_min: f64,
+    cost_per_hour_usd: f64,
+}
 
-        if detailed {
-            table.add_row(Row::new(vec![
-                Cell::new(&format_date(&stat.date)),
-                Cell::new(&stat.model.to_string()),
-                Cell::new(&stat.request_count.to_string()),
-                Cell::new(&format_number(stat.usage.input_tokens)),
-                Cell::new(&format_number(stat.usage.output_tokens)),
-                Cell::new(&format_number(stat.usage.cache_creation_input_tokens)),
-                Cell::new(&format_number(stat.usage.cache_read_input_tokens)),
-                Cell::new(&format_number(stat.usage.total_tokens())),
-                Cell::new(&format!("${:.2}", stat.cost_usd)).style_spec("Fg"),
-            ]));
-        } else {
-            table.add_row(Row::new(vec![
-                Cell::new(&format_date(&stat.date)),
-                Cell::new(&stat.model.to_string()),
-                Cell::new(&stat.request_count.to_string()),
-                Cell::new(&format_number(stat.usage.total_tokens())),
-                Cell::new(&format!("${:.2}", stat.cost_usd)).style_spec("Fg"),
-            ]));
-        }
+fn velocity_json_rows(rows: &[(DateTime<Utc>, f64, f64)]) -> Vec<VelocityRow> {
+    rows.iter()
+        .map(|&(window_start, tokens_per_min, cost_per_hour_usd)| VelocityRow {
+            window_start,
+            tokens_per_min,
+            cost_per_hour_usd,
+        })
+        .collect()
+}
+
+/// `--velocity` as CSV, with the projected cost in the display currency
+pub fn format_velocity_csv(
+    rows: &[(DateTime<Utc>, f64, f64)],
+    currency: &Currency,
+    delimiter: char,
+) -> String {
+    let mut csv = String::new();
+    let headers = [
+        "Window Start".to_string(),
+        "Tokens/min".to_string(),
+        format!("Cost/hr {}", currency.code),
+    ];
+    push_csv_row(&mut csv, &headers, delimiter);
+    for (start, tokens_per_min, cost_per_hour) in rows {
+        let fields = [
+            start.to_rfc3339(),
+            format!("{:.2}", tokens_per_min),
+            format!("{:.2}", currency.convert(*cost_per_hour)),
+        ];
+        push_csv_row(&mut csv, &fields, delimiter);
     }
+    csv
+}
 
-    // Add summary row if requested
-    if show_summary {
-        table.add_empty_row();
-        if detailed {
-            table.add_row(Row::new(vec![
-                Cell::new("TOTAL").style_spec("bFy"),
-                Cell::new("").style_spec("bFy"),
-                Cell::new(&total_requests.to_string()).style_spec("bFy"),
-                Cell::new(&format_number(total_usage.input_tokens)).style_spec("bFy"),
-                Cell::new(&format_number(total_usage.output_tokens)).style_spec("bFy"),
-                Cell::new(&format_number(total_usage.cache_creation_input_tokens))
-                    .style_spec("bFy"),
-                Cell::new(&format_number(total_usage.cache_read_input_tokens)).style_spec("bFy"),
-                Cell::new(&format_number(total_usage.total_tokens())).style_spec("bFy"),
-                Cell::new(&format!("${:.2}", total_cost)).style_spec("bFgY"),
-            ]));
-        } else {
-            table.add_row(Row::new(vec![
-                Cell::new("TOTAL").style_spec("bFy"),
-                Cell::new("").style_spec("bFy"),
-                Cell::new(&total_requests.to_string()).style_spec("bFy"),
-                Cell::new(&format_number(total_usage.total_tokens())).style_spec("bFy"),
-                Cell::new(&format!("${:.2}", total_cost)).style_spec("bFgY"),
-            ]));
-        }
+pub fn format_velocity_json(
+    rows: &[(DateTime<Utc>, f64, f64)],
+    compact: bool,
+) -> Result<String, serde_json::Error> {
+    to_json(&velocity_json_rows(rows), compact)
+}
+
+pub fn format_velocity_ndjson(rows: &[(DateTime<Utc>, f64, f64)]) -> Result<String, serde_json::Error> {
+    let mut ndjson = String::new();
+    for row in velocity_json_rows(rows) {
+        ndjson.push_str(&serde_json::to_string(&row)?);
+        ndjson.push('\n');
+    }
+    Ok(ndjson)
+}
+
+pub fn format_velocity_markdown(rows: &[(DateTime<Utc>, f64, f64)], currency: &Currency) -> String {
+    let mut md = String::new();
+    md.push_str("| Window Start | Tokens/min | Cost/hr (projected) |\n");
+    md.push_str("|--------------|------------|---------------------|\n");
+    for (start, tokens_per_min, cost_per_hour) in rows {
+        md.push_str(&format!(
+            "| {} | {} | {} |\n",
+            start.format("%Y-%m-%d %H:%M"),
+            format_number(tokens_per_min.round() as u64),
+            currency.format(*cost_per_hour)
+        ));
+    }
+    md
+}
+
+/// Table of idle gaps between requests, one row per session plus an overall row
+pub fn format_gaps_table(stats: &[GapStats], colored: bool) -> String {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+    table.set_titles(Row::new(vec![
+        styled("Session", "bFc", colored),
+        styled("Requests", "bFc", colored),
+        styled("Median Gap", "bFc", colored),
+        styled("P90 Gap", "bFc", colored),
+        styled("Longest Idle", "bFc", colored),
+    ]));
+
+    for gaps in stats {
+        let (session, spec) = match &gaps.session_id {
+            Some(id) => (id.as_str(), ""),
+            None => ("All sessions", "b"),
+        };
+        table.add_row(Row::new(vec![
+            styled(session, spec, colored),
+            Cell::new(&format_number(gaps.requests as u64)),
+            Cell::new(&format_gap(gaps.median)),
+            Cell::new(&format_gap(gaps.p90)),
+            styled(&format_gap(gaps.longest), "Fy", colored),
+        ]));
     }
 
     table.to_string()
 }
 
-pub fn format_csv(stats: &[UsageStats], detailed: bool) -> String {
+/// A `--gaps` row for machine-readable output, with gaps in whole seconds
+#[derive(Serialize)]
+struct GapRow<'a> {
+    /// `None` for the all-sessions summary
+    session_id: Option<&'a str>,
+    requests: usize,
+    median_gap_secs: i64,
+    p90_gap_secs: i64,
+    longest_idle_secs: i64,
+}
+
+fn gap_rows(stats: &[GapStats]) -> Vec<GapRow<'_>> {
+    stats
+        .iter()
+        .map(|gaps| GapRow {
+            session_id: gaps.session_id.as_deref(),
+            requests: gaps.requests,
+            median_gap_secs: gaps.median.num_seconds(),
+            p90_gap_secs: gaps.p90.num_seconds(),
+            longest_idle_secs: gaps.longest.num_seconds(),
+        })
+        .collect()
+}
+
+/// `--gaps` as CSV; the all-sessions row has an empty session
+pub fn format_gaps_csv(stats: &[GapStats], delimiter: char) -> String {
     let mut csv = String::new();
+    let headers = ["Session", "Requests", "Median Gap Secs", "P90 Gap Secs", "Longest Idle Secs"];
+    push_csv_row(&mut csv, &headers.map(String::from), delimiter);
+    for row in gap_rows(stats) {
+        let fields = [
+            row.session_id.unwrap_or_default().to_string(),
+            row.requests.to_string(),
+            row.median_gap_secs.to_string(),
+            row.p90_gap_secs.to_string(),
+            row.longest_idle_secs.to_string(),
+        ];
+        push_csv_row(&mut csv, &fields, delimiter);
+    }
+    csv
+}
 
-    // Headers
-    if detailed {
-        csv.push_str(
-            "Date,Model,Requests,Input Tokens,Output Tokens,Cache Write Tokens,Cache Read Tokens,Total Tokens,Cost USD\n"
-        );
-    } else {
-        csv.push_str("Date,Model,Requests,Total Tokens,Cost USD\n");
+pub fn format_gaps_json(stats: &[GapStats], compact: bool) -> Result<String, serde_json::Error> {
+    to_json(&gap_rows(stats), compact)
+}
+
+pub fn format_gaps_ndjson(stats: &[GapStats]) -> Result<String, serde_json::Error> {
+    let mut ndjson = String::new();
+    for row in gap_rows(stats) {
+        ndjson.push_str(&serde_json::to_string(&row)?);
+        ndjson.push('\n');
     }
+    Ok(ndjson)
+}
 
-    // Data rows
-    for stat in stats {
-        if detailed {
-            csv.push_str(&format!(
-                "{},{},{},{},{},{},{},{},{:.2}\n",
-                format_date(&stat.date),
-                stat.model,
-                stat.request_count,
-                stat.usage.input_tokens,
-                stat.usage.output_tokens,
-                stat.usage.cache_creation_input_tokens,
-                stat.usage.cache_read_input_tokens,
-                stat.usage.total_tokens(),
-                stat.cost_usd
-            ));
-        } else {
-            csv.push_str(&format!(
-                "{},{},{},{},{:.2}\n",
-                format_date(&stat.date),
-                stat.model,
-                stat.request_count,
-                stat.usage.total_tokens(),
-                stat.cost_usd
+pub fn format_gaps_markdown(stats: &[GapStats]) -> String {
+    let mut md = String::new();
+    md.push_str("| Session | Requests | Median Gap | P90 Gap | Longest Idle |\n");
+    md.push_str("|---------|----------|------------|---------|--------------|\n");
+    for gaps in stats {
+        md.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            gaps.session_id.as_deref().unwrap_or("**All sessions**"),
+            format_number(gaps.requests as u64),
+            format_gap(gaps.median),
+            format_gap(gaps.p90),
+            format_gap(gaps.longest)
+        ));
+    }
+    md
+}
+
+/// Compact duration using its two largest units, e.g. "2h 05m", "4m 10s", "12s"
+fn format_gap(gap: Duration) -> String {
+    let secs = gap.num_seconds().max(0);
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {:02}s", m, s),
+        (h, m, _) => format!("{}h {:02}m", h, m),
+    }
+}
+
+/// Widest bar in `format_patterns`, in characters
+const PATTERN_BAR_WIDTH: usize = 30;
+
+/// Cost by hour of day and by weekday, each row with a bar scaled to its table's busiest bucket
+pub fn format_patterns(hours: &[f64; 24], weekdays: &[f64; 7], colored: bool) -> String {
+    const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+    let section = |title: &str, rows: Vec<(String, f64)>| {
+        let max = rows.iter().map(|(_, cost)| *cost).fold(0.0, f64::max);
+        let mut out = format!("\n{}\n", paint(title.bright_cyan().bold(), colored));
+        for (label, cost) in rows {
+            let width = if max > 0.0 {
+                (cost / max * PATTERN_BAR_WIDTH as f64).round() as usize
+            } else {
+                0
+            };
+            out.push_str(&format!(
+                "  {:<5} {:>10}  {}\n",
+                label,
+                format!("${:.2}", cost),
+                paint("█".repeat(width).green(), colored)
             ));
         }
+        out
+    };
+
+    let mut out = section(
+        "=== Cost by Hour of Day ===",
+        hours.iter().enumerate().map(|(hour, &cost)| (format!("{:02}:00", hour), cost)).collect(),
+    );
+    out.push_str(&section(
+        "=== Cost by Day of Week ===",
+        WEEKDAYS.iter().zip(weekdays).map(|(day, &cost)| (day.to_string(), cost)).collect(),
+    ));
+    out
+}
+
+/// Section listing anomalous periods, with the cost above each baseline it stands out from
+/// highlighted in red
+pub fn format_anomalies(
+    reports: &[AnomalyReport],
+    thresholds: AnomalyThresholds,
+    currency: &Currency,
+    colored: bool,
+) -> String {
+    let title = format!(
+        "=== Cost Anomalies (>{:.1}σ above 7-day mean or >{:.1}σ above period mean) ===",
+        thresholds.rolling, thresholds.period
+    );
+    let mut out = format!("\n{}\n", paint(title.bright_cyan().bold(), colored));
+
+    if reports.is_empty() {
+        out.push_str("  No anomalies detected.\n");
+        return out;
     }
 
-    csv
+    for report in reports {
+        let Anomaly { date, cost_usd: cost, rolling, period } = &report.anomaly;
+        let baselines: Vec<String> = [
+            ("7-day", rolling, thresholds.rolling),
+            ("period", period, thresholds.period),
+        ]
+        .into_iter()
+        .filter_map(|(label, baseline, threshold)| {
+            let baseline = baseline.as_ref()?;
+            Some(format_anomaly_baseline(label, baseline, *cost, threshold, currency, colored))
+        })
+        .collect();
+        out.push_str(&format!(
+            "  {}  {}  cost {} ({})\n",
+            format_date(date),
+            report.stats.model,
+            paint(currency.format(*cost).green(), colored),
+            baselines.join("; ")
+        ));
+    }
+
+    out
 }
 
-pub fn format_json(stats: &[UsageStats]) -> Result<String, serde_json::Error> {
-    serde_json::to_string_pretty(stats)
+/// "7-day mean $1.00, +$9.00 / 3.2σ", in red when it crosses the threshold
+fn format_anomaly_baseline(
+    label: &str,
+    baseline: &AnomalyBaseline,
+    cost: f64,
+    threshold_sigmas: f64,
+    currency: &Currency,
+    colored: bool,
+) -> String {
+    let sigmas = if baseline.sigmas.is_finite() {
+        format!("{:.1}σ", baseline.sigmas)
+    } else {
+        "∞σ".to_string()
+    };
+    let excess = cost - baseline.mean;
+    let sign = if excess < 0.0 { "-" } else { "+" };
+    let excess = format!("{}{} / {}", sign, currency.format(excess.abs()), sigmas);
+    let excess = if baseline.sigmas >= threshold_sigmas {
+        paint(excess.red().bold(), colored).to_string()
+    } else {
+        excess
+    };
+    format!("{} mean {}, {}", label, currency.format(baseline.mean), excess)
 }
 
-pub fn format_markdown(stats: &[UsageStats], detailed: bool, show_summary: bool) -> String {
-    let mut md = String::new();
+pub fn format_forecast(points: &[ForecastPoint], currency: &Currency, colored: bool) -> String {
+    let title = format!("=== Forecast (trend over last {} days) ===", FORECAST_HISTORY_DAYS);
+    let mut out = format!("\n{}\n", paint(title.bright_cyan().bold(), colored));
 
-    // Headers
-    if detailed {
-        md.push_str("| Date | Model | Requests | Input | Output | Cache Write | Cache Read | Total Tokens | Cost (USD) |\n");
-        md.push_str("|------|-------|----------|-------|--------|-------------|------------|--------------|------------|\n");
-    } else {
-        md.push_str("| Date | Model | Requests | Total Tokens | Cost (USD) |\n");
-        md.push_str("|------|-------|----------|--------------|------------|\n");
+    if points.is_empty() {
+        out.push_str("  Not enough daily history to forecast (need at least 2 days).\n");
+        return out;
     }
 
-    let mut total_cost = 0.0;
-    let mut total_requests = 0;
-    let mut total_usage = TokenUsage::default();
+    for point in points {
+        let (low, high) = point.confidence_interval;
+        out.push_str(&format!(
+            "  {}  {}  (95% CI {} - {})\n",
+            point.date.format("%Y-%m-%d"),
+            paint(currency.format(point.projected_cost).green(), colored),
+            currency.format(low),
+            currency.format(high)
+        ));
+    }
 
-    // Data rows
-    for stat in stats {
-        total_cost += stat.cost_usd;
-        total_requests += stat.request_count;
-        total_usage.add(&stat.usage);
+    out
+}
 
-        if detailed {
-            md.push_str(&format!(
-                "| {} | {} | {} | {} | {} | {} | {} | {} | ${:.2} |\n",
-                format_date(&stat.date),
-                stat.model,
-                stat.request_count,
-                format_number(stat.usage.input_tokens),
-                format_number(stat.usage.output_tokens),
-                format_number(stat.usage.cache_creation_input_tokens),
-                format_number(stat.usage.cache_read_input_tokens),
-                format_number(stat.usage.total_tokens()),
-                stat.cost_usd
-            ));
-        } else {
-            md.push_str(&format!(
-                "| {} | {} | {} | {} | ${:.2} |\n",
-                format_date(&stat.date),
-                stat.model,
-                stat.request_count,
-                format_number(stat.usage.total_tokens()),
-                stat.cost_usd
-            ));
+/// Shades for calendar intensity levels 1-4 (level 0 is a day without spend)
+const CALENDAR_SHADES: [(u8, u8, u8); 4] =
+    [(14, 68, 41), (0, 109, 50), (38, 166, 65), (57, 211, 83)];
+
+/// Intensity level 0-4 of a day's cost relative to the most expensive day shown
+fn calendar_level(cost: f64, max_cost: f64) -> usize {
+    if cost <= 0.0 || max_cost <= 0.0 {
+        return 0;
+    }
+    ((cost / max_cost * 4.0).ceil() as usize).clamp(1, 4)
+}
+
+fn calendar_cell(level: usize, color: bool) -> String {
+    if !color {
+        return format!("{:>2}", level);
+    }
+    match level {
+        0 => "··".bright_black().to_string(),
+        _ => {
+            let (r, g, b) = CALENDAR_SHADES[level - 1];
+            "██".truecolor(r, g, b).to_string()
         }
     }
+}
+
+/// Render day-grouped stats as a month-by-month calendar whose cells shade with that
+/// day's cost. Without color, cells show the intensity level (0-4) as a digit.
+pub fn format_calendar(days: &[UsageStats], months: u32, color: bool) -> String {
+    let Some(last) = days.iter().map(|s| s.date.date_naive()).max() else {
+        return "No usage data to show.\n".to_string();
+    };
+
+    let mut costs: HashMap<NaiveDate, f64> = HashMap::new();
+    for stat in days {
+        *costs.entry(stat.date.date_naive()).or_default() += stat.cost_usd;
+    }
 
-    // Summary row
-    if show_summary {
-        if detailed {
-            md.push_str(&format!(
-                "| **TOTAL** | | **{}** | **{}** | **{}** | **{}** | **{}** | **{}** | **${:.2}** |\n",
-                total_requests,
-                format_number(total_usage.input_tokens),
-                format_number(total_usage.output_tokens),
-                format_number(total_usage.cache_creation_input_tokens),
-                format_number(total_usage.cache_read_input_tokens),
-                format_number(total_usage.total_tokens()),
-                total_cost
-            ));
-        } else {
-            md.push_str(&format!(
-                "| **TOTAL** | | **{}** | **{}** | **${:.2}** |\n",
-                total_requests,
-                format_number(total_usage.total_tokens()),
-                total_cost
-            ));
+    let last_month = last.with_day(1).unwrap();
+    let first_month = last_month - Months::new(months.saturating_sub(1));
+    let max_cost = costs
+        .iter()
+        .filter(|(date, _)| **date >= first_month)
+        .map(|(_, cost)| *cost)
+        .fold(0.0, f64::max);
+
+    let mut out = String::new();
+    let mut month = first_month;
+    while month <= last_month {
+        let next_month = month + Months::new(1);
+        out.push_str(&format!(
+            "\n{}\n",
+            paint(month.format("%B %Y").to_string().bright_cyan().bold(), color)
+        ));
+        out.push_str("Mo Tu We Th Fr Sa Su   Total\n");
+
+        // Pad the first week up to the month's starting weekday
+        let mut line = "   ".repeat(month.weekday().num_days_from_monday() as usize);
+        let mut week_total = 0.0;
+        let mut day = month;
+        while day < next_month {
+            let cost = costs.get(&day).copied().unwrap_or(0.0);
+            week_total += cost;
+            line.push_str(&calendar_cell(calendar_level(cost, max_cost), color));
+            line.push(' ');
+
+            let tomorrow = day.succ_opt().unwrap();
+            if day.weekday().num_days_from_monday() == 6 || tomorrow == next_month {
+                let padding = "   ".repeat(6 - day.weekday().num_days_from_monday() as usize);
+                out.push_str(&format!("{}{}  ${:.2}\n", line, padding, week_total));
+                line.clear();
+                week_total = 0.0;
+            }
+            day = tomorrow;
         }
+        month = next_month;
     }
 
-    md
+    // Legend: each level covers up to a quarter of the peak day's cost
+    out.push_str("\nLess ");
+    for level in 0..=4 {
+        out.push_str(&calendar_cell(level, color));
+        out.push(' ');
+    }
+    out.push_str("More\n");
+    for level in 1..=4 {
+        out.push_str(&format!(
+            "  {} up to ${:.2}\n",
+            calendar_cell(level, color),
+            max_cost * level as f64 / 4.0
+        ));
+    }
+
+    out
 }
 
 fn format_date(date: &chrono::DateTime<chrono::Utc>) -> String {
     date.format("%Y-%m-%d").to_string()
 }
 
-fn format_number(num: u64) -> String {
+pub(crate) fn format_number(num: u64) -> String {
     let num_str = num.to_string();
     let mut result = String::new();
     let mut count = 0;
@@ -232,8 +1865,15 @@ fn format_number(num: u64) -> String {
     result.chars().rev().collect()
 }
 
-pub fn print_summary(stats: &[UsageStats]) {
-    println!("\n{}", "=== Usage Summary ===".bright_cyan().bold());
+pub fn print_summary<W: Write>(
+    writer: &mut W,
+    stats: &[UsageStats],
+    options: &FormatOptions,
+) -> io::Result<()> {
+    let colored = options.colored_output;
+    let currency = &options.currency;
+    let aggregate_cache = options.aggregate_cache;
+    writeln!(writer, "\n{}", paint("=== Usage Summary ===".bright_cyan().bold(), colored))?;
 
     let total_cost: f64 = stats.iter().map(|s| s.cost_usd).sum();
     let total_requests: u64 = stats.iter().map(|s| s.request_count).sum();
@@ -253,40 +1893,606 @@ pub fn print_summary(stats: &[UsageStats]) {
         entry.2 += stat.cost_usd;
     }
 
-    println!("\n{}", "Overall Statistics:".yellow());
-    println!("  Total Requests: {}", format_number(total_requests).green());
-    println!(
+    writeln!(writer, "\n{}", paint("Overall Statistics:".yellow(), colored))?;
+    writeln!(writer, "  Total Requests: {}", paint(format_number(total_requests).green(), colored))?;
+    writeln!(
+        writer,
         "  Total Tokens: {}",
-        format_number(total_usage.total_tokens()).green()
-    );
-    println!("  Total Cost: {}", format!("${:.2}", total_cost).green().bold());
+        paint(format_number(total_usage.total_tokens()).green(), colored)
+    )?;
+    writeln!(
+        writer,
+        "  Total Cost: {}",
+        paint(currency.format(total_cost).green().bold(), colored)
+    )?;
+    let pricing_map = options.pricing_map();
+    let savings: f64 = stats.iter().filter_map(|stat| cache_savings(stat, &pricing_map)).sum();
+    writeln!(
+        writer,
+        "  Cache Hit Rate: {} (saving {})",
+        paint(format_hit_rate(total_usage.cache_hit_rate()).green(), colored),
+        paint(currency.format(savings).green(), colored)
+    )?;
 
-    println!("\n{}", "Token Breakdown:".yellow());
-    println!(
-        "  Input Tokens: {}",
-        format_number(total_usage.input_tokens).cyan()
-    );
-    println!(
-        "  Output Tokens: {}",
-        format_number(total_usage.output_tokens).cyan()
-    );
-    println!(
-        "  Cache Write Tokens: {}",
-        format_number(total_usage.cache_creation_input_tokens).cyan()
-    );
-    println!(
-        "  Cache Read Tokens: {}",
-        format_number(total_usage.cache_read_input_tokens).cyan()
-    );
+    writeln!(writer, "\n{}", paint("Token Breakdown:".yellow(), colored))?;
+    for (header, tokens) in token_headers(aggregate_cache)
+        .into_iter()
+        .zip(token_columns(&total_usage, aggregate_cache))
+    {
+        writeln!(writer, "  {} Tokens: {}", header, paint(format_number(tokens).cyan(), colored))?;
+    }
 
-    println!("\n{}", "By Model:".yellow());
+    writeln!(writer, "\n{}", paint("By Model:".yellow(), colored))?;
     let mut model_vec: Vec<_> = model_stats.into_iter().collect();
     model_vec.sort_by(|a, b| b.1 .2.partial_cmp(&a.1 .2).unwrap());
 
     for (model, (requests, usage, cost)) in model_vec {
-        println!("\n  {}:", model.bright_blue());
-        println!("    Requests: {}", format_number(requests));
-        println!("    Tokens: {}", format_number(usage.total_tokens()));
-        println!("    Cost: {}", format!("${:.2}", cost).green());
+        writeln!(writer, "\n  {}:", paint(model.bright_blue(), colored))?;
+        writeln!(writer, "    Requests: {}", format_number(requests))?;
+        writeln!(writer, "    Tokens: {}", format_number(usage.total_tokens()))?;
+        writeln!(writer, "    Cost: {}", paint(currency.format(cost).green(), colored))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn sample_stats() -> Vec<UsageStats> {
+        vec![UsageStats {
+            model: ModelName::Claude4Opus,
+            date: Utc::now(),
+            usage: TokenUsage {
+                input_tokens: 1000,
+                output_tokens: 500,
+                ..Default::default()
+            },
+            request_count: 2,
+            cost_usd: 0.0525,
+            cache_hit_rate: 0.0,
+            repriced_cost_usd: None,
+            peak_day_cost: None,
+            avg_daily_cost: None,
+            active_days: None,
+            session_id: None,
+            cache_write_5m_cost_usd: None,
+            cache_write_1h_cost_usd: None,
+            model_breakdown: None,
+        }]
+    }
+
+    #[test]
+    fn test_no_color_disables_ansi_escapes() {
+        let no_color = std::ffi::OsStr::new("1");
+        assert!(!color_enabled(false, Some(no_color), true));
+        assert!(color_enabled(false, Some(std::ffi::OsStr::new("")), true));
+        assert!(color_enabled(false, None, true));
+        assert!(!color_enabled(false, None, false));
+
+        let options = FormatOptions {
+            colored_output: false,
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        print_summary(&mut output, &sample_stats(), &options).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains("Total Requests: 2"));
+        assert!(!output.contains('\x1b'));
+    }
+
+    #[test]
+    fn test_no_color_flag() {
+        assert!(!should_use_color(true));
+    }
+
+    #[test]
+    fn test_models_json_lists_known_models() {
+        let pricing = crate::pricing::get_default_pricing();
+        let json = format_models_json(&pricing).unwrap();
+        let rows: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(rows.len(), ModelName::known_variants().len());
+        let opus = rows
+            .iter()
+            .find(|r| r["canonical"] == "claude-opus-4-20250514")
+            .unwrap();
+        assert_eq!(opus["family"], "opus");
+        assert_eq!(opus["input_per_million"], 15.0);
+
+        let synthetic = rows.iter().find(|r| r["canonical"] == "<synthetic>").unwrap();
+        assert!(synthetic["input_per_million"].is_null());
+    }
+
+    #[test]
+    fn test_ndjson_one_object_per_line() {
+        let mut stats = sample_stats();
+        stats.push(stats[0].clone());
+
+        let ndjson = format_ndjson(&stats).unwrap();
+        let lines: Vec<_> = ndjson.lines().collect();
+        assert_eq!(lines.len(), stats.len());
+        for line in &lines {
+            let value: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert_eq!(value["request_count"], 2);
+        }
+
+        let mut streamed = Vec::new();
+        write_ndjson_streaming(stats.iter(), &mut streamed).unwrap();
+        assert_eq!(String::from_utf8(streamed).unwrap(), ndjson);
+    }
+
+    #[test]
+    fn test_make_sparkline() {
+        assert_eq!(make_sparkline(&[0.0, 0.5, 1.0]), "▁▄█");
+        assert_eq!(make_sparkline(&[2.0, 2.0, 2.0]), "▄▄▄");
+        assert_eq!(make_sparkline(&[]), "");
+        assert_eq!(make_sparkline(&[5.0]), "▄");
+        // Ranked, so one expensive day doesn't flatten the others
+        assert_eq!(make_sparkline(&[1.0, 2.0, 3.0, 1000.0]), "▁▃▅█");
+        assert_eq!(make_sparkline(&[1.0, 1.0, 1.0, 1.0, 100.0]), "▃▃▃▃█");
+    }
+
+    #[test]
+    fn test_percentages() {
+        let mut stats = sample_stats();
+        let mut second = stats[0].clone();
+        second.cost_usd = 0.0525 * 3.0;
+        stats.push(second);
+
+        let options = FormatOptions {
+            percentages: true,
+            ..Default::default()
+        };
+        let csv = format_csv(&stats, &options);
+        let lines: Vec<_> = csv.lines().collect();
+        assert!(lines[0].ends_with(",Percent of Cost,Percent of Tokens"));
+        assert!(lines[1].ends_with(",25.0,50.0"));
+        assert!(lines[2].ends_with(",75.0,50.0"));
+    }
+
+    #[test]
+    fn test_percentages_with_zero_totals() {
+        let mut stats = sample_stats();
+        stats[0].cost_usd = 0.0;
+        stats[0].usage = TokenUsage::default();
+
+        let options = FormatOptions {
+            percentages: true,
+            ..Default::default()
+        };
+        let csv = format_csv(&stats, &options);
+        assert!(csv.lines().nth(1).unwrap().ends_with(",0.0,0.0"));
+        assert!(format_markdown(&stats, &options).contains("| 0.0% | 0.0% |"));
+    }
+
+    #[test]
+    fn test_breakdown_rows_under_day() {
+        let mut stats = sample_stats();
+        let usage = |input_tokens| TokenUsage {
+            input_tokens,
+            ..Default::default()
+        };
+        let breakdown = |model, input_tokens, cost_usd| ModelBreakdown {
+            model,
+            usage: usage(input_tokens),
+            cost_usd,
+        };
+        stats[0].model_breakdown = Some(vec![
+            breakdown(ModelName::Claude4Opus, 2000, 0.03),
+            breakdown(ModelName::Claude4Sonnet, 1000, 0.003),
+        ]);
+
+        let table = format_table(&stats, &FormatOptions::default());
+        let lines: Vec<&str> = table.lines().collect();
+        let opus = lines.iter().position(|line| line.contains("  Claude 4 Opus")).unwrap();
+        let sonnet = lines.iter().position(|line| line.contains("  Claude 4 Sonnet")).unwrap();
+        let total = lines.iter().position(|line| line.contains("Day total")).unwrap();
+        assert!(opus < sonnet && sonnet < total);
+        assert!(lines[opus].contains("$0.03"));
+        assert!(lines[opus - 1].contains(&format_date(&stats[0].date)));
+
+        let json = format_json(&stats, &FormatOptions::default()).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let opus = &json[0]["model_breakdown"][0];
+        assert_eq!(opus["model"], "claude-opus-4-20250514");
+        assert_eq!(opus["usage"]["input_tokens"], 2000);
+        assert_eq!(opus["cost_usd"], 0.03);
+    }
+
+    #[test]
+    fn test_calendar_levels_without_color() {
+        let mut stats = sample_stats();
+        stats[0].date = Utc.with_ymd_and_hms(2025, 6, 2, 0, 0, 0).unwrap();
+        stats[0].cost_usd = 8.0;
+        let mut cheap = stats[0].clone();
+        cheap.date = Utc.with_ymd_and_hms(2025, 6, 3, 0, 0, 0).unwrap();
+        cheap.cost_usd = 1.0;
+        stats.push(cheap);
+
+        let calendar = format_calendar(&stats, 1, false);
+        assert!(!calendar.contains('\x1b'));
+        assert!(calendar.contains("June 2025"));
+        // June 2025 starts on a Sunday, so the second week opens with the two spend days
+        let week = calendar
+            .lines()
+            .find(|line| line.starts_with(" 4  1"))
+            .expect("week with spend");
+        assert!(week.ends_with("$9.00"));
+        assert!(calendar.contains(" 4 up to $8.00"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_calendar_spans_multiple_months() {
+        let mut stats = sample_stats();
+        stats[0].date = Utc.with_ymd_and_hms(2025, 3, 15, 0, 0, 0).unwrap();
+
+        let calendar = format_calendar(&stats, 3, false);
+        let january = calendar.find("January 2025").unwrap();
+        let march = calendar.find("March 2025").unwrap();
+        assert!(january < march);
+        assert!(calendar.contains("February 2025"));
+        assert!(!calendar.contains("December 2024"));
+    }
+
+    #[test]
+    fn test_aggregate_cache_folds_into_input() {
+        let mut stats = sample_stats();
+        stats[0].usage.cache_creation_input_tokens = 300;
+        stats[0].usage.cache_read_input_tokens = 200;
+        stats[0].cache_hit_rate = stats[0].usage.cache_hit_rate();
+
+        let options = FormatOptions {
+            detailed: true,
+            aggregate_cache: true,
+            ..Default::default()
+        };
+        let csv = format_csv(&stats, &options);
+        let mut lines = csv.lines();
+        assert!(lines
+            .next()
+            .unwrap()
+            .ends_with("Requests,Input Tokens,Output Tokens,Total Tokens,Cost USD,Cache Hit Rate"));
+        // Input 1000 + cache 500, total tokens unchanged at 2000; hit rate 200 / 1200
+        assert!(lines.next().unwrap().ends_with(",2,1500,500,2000,0.05,0.1667"));
+
+        let mut output = Vec::new();
+        print_summary(&mut output, &stats, &options).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Input Tokens: 1,500"));
+        assert!(output.contains("Total Tokens: 2,000"));
+        assert!(!output.contains("Cache Read Tokens"));
+    }
+
+    #[test]
+    fn test_repriced_column() {
+        let mut stats = sample_stats();
+        let options = FormatOptions::default();
+        assert!(!format_table(&stats, &options).contains("Repriced"));
+
+        stats[0].repriced_cost_usd = Some(0.0105);
+        assert!(format_table(&stats, &options).contains("Repriced (USD)"));
+        let csv = format_csv(&stats, &options);
+        assert!(csv.lines().next().unwrap().ends_with(",repriced_cost_usd"));
+        assert!(csv.lines().nth(1).unwrap().ends_with(",0.01"));
+        assert_eq!(
+            format_reprice_comparison(&ModelName::Claude4Sonnet, 0.0525, 0.0105, &Currency::usd()),
+            "Repriced as Claude 4 Sonnet: $0.01 vs actual $0.05 (-80.0%)"
+        );
+    }
+
+    #[test]
+    fn test_currency_conversion() {
+        let mut stats = sample_stats();
+        stats[0].cost_usd = 10.0;
+        let options = FormatOptions {
+            show_summary: true,
+            running_total: true,
+            currency: Currency::new("EUR", 0.9),
+            ..Default::default()
+        };
+
+        let table = format_table(&stats, &options);
+        assert!(table.contains("Cost (EUR)"));
+        assert!(table.contains("€9.00"));
+        assert!(!table.contains('$'));
+
+        let csv = format_csv(&stats, &options);
+        let mut lines = csv.lines();
+        assert!(lines.next().unwrap().ends_with(",Cost EUR,Cache Hit Rate,running_total_eur"));
+        assert!(lines.next().unwrap().ends_with(",9.00,0.0000,9.00"));
+        assert!(csv.trim_end().ends_with(",9.00,0.0000,9.00"));
+
+        let markdown = format_markdown(&stats, &options);
+        assert!(markdown.contains("| Cost (EUR) |"));
+        assert!(markdown.contains("| **€9.00** |"));
+
+        let mut summary = Vec::new();
+        print_summary(&mut summary, &stats, &options).unwrap();
+        let summary = String::from_utf8(summary).unwrap();
+        assert!(summary.contains("Total Cost: €9.00"));
+    }
+
+    #[test]
+    fn test_cache_savings_use_active_pricing_and_discount() {
+        let mut stats = sample_stats();
+        stats[0].usage = TokenUsage {
+            cache_read_input_tokens: 1_000_000,
+            ..Default::default()
+        };
+        // $1.50 at list price, billed at half through batch requests
+        stats[0].cost_usd = 0.75;
+        let mut pricing = get_default_pricing();
+        pricing.get_mut(&ModelName::Claude4Opus).unwrap().input_per_million = 20.0;
+        let options = FormatOptions {
+            pricing: Some(pricing),
+            ..Default::default()
+        };
+
+        let mut summary = Vec::new();
+        print_summary(&mut summary, &stats, &options).unwrap();
+        // ($20.00 - $1.50) per million, halved
+        assert!(String::from_utf8(summary).unwrap().contains("(saving $9.25)"));
+    }
+
+    #[test]
+    fn test_explain_column() {
+        let mut stats = sample_stats();
+        let options = FormatOptions {
+            explain: true,
+            ..Default::default()
+        };
+        let table = format_table(&stats, &options);
+        assert!(table.contains("Cost Formula"));
+        assert!(table.contains(
+            "1,000 in × $15.00/M + 500 out × $75.00/M + 0 cache write × $18.75/M \
+             + 0 cache read × $1.50/M = $0.05"
+        ));
+
+        // Half-price batch requests
+        stats[0].cost_usd = 5.25;
+        stats[0].usage.output_tokens = 140_000;
+        let pricing_map = get_default_pricing();
+        assert!(explain_cost(&stats[0], &pricing_map, &Currency::usd())
+            .ends_with("= $10.52 ($5.25 after batch discount)"));
+
+        stats[0].model = ModelName::Unknown("all".to_string());
+        assert_eq!(
+            explain_cost(&stats[0], &pricing_map, &Currency::usd()),
+            "several models; only single-model rows are explained"
+        );
+        assert!(!format_table(&stats, &FormatOptions::default()).contains("Cost Formula"));
+    }
+
+    #[test]
+    fn test_output_fields() {
+        use clap::Parser;
+        let args = crate::cli::Args::parse_from(["claude-usage", "--output-fields", "date,cost"]);
+        let stats = sample_stats();
+        let options = FormatOptions {
+            output_fields: args.output_fields,
+            show_summary: true,
+            ..Default::default()
+        };
+
+        let table = format_table(&stats, &options);
+        let lines: Vec<&str> = table.lines().collect();
+        let columns = |line: &str| line.split('|').filter(|cell| !cell.trim().is_empty()).count();
+        assert_eq!(columns(lines[1]), 2);
+        assert!(lines[1].contains("Date") && lines[1].contains("Cost (USD)"));
+        assert!(table.contains("TOTAL"));
+        assert!(!table.contains("Requests"));
+
+        let csv = format_csv(&stats, &options);
+        assert_eq!(csv.lines().next().unwrap(), "date,cost_usd");
+        assert!(csv.lines().nth(1).unwrap().ends_with(",0.05"));
+
+        let json = format_json(&stats, &FormatOptions { compact_json: true, ..options }).unwrap();
+        assert!(json.starts_with(r#"{"stats":[{"date":"#), "{}", json);
+        assert!(json.contains(r#"00:00","cost_usd":"#), "{}", json);
+    }
+
+    #[test]
+    fn test_csv_delimiter() {
+        let mut stats = sample_stats();
+        stats[0].model = ModelName::Unknown("claude;custom \"beta\"".to_string());
+        let options = FormatOptions {
+            csv_delimiter: Some(';'),
+            ..Default::default()
+        };
+
+        let csv = format_csv(&stats, &options);
+        let mut lines = csv.lines();
+        assert!(lines.next().unwrap().starts_with("Date;Model;Requests;Total Tokens;Cost USD"));
+        let row = lines.next().unwrap();
+        assert!(row.contains(";\"claude;custom \"\"beta\"\"\";"), "{}", row);
+        assert!(!row.contains(','));
+
+        let options = FormatOptions {
+            csv_delimiter: Some('\t'),
+            ..Default::default()
+        };
+        // Quotes still force quoting with tabs
+        let csv = format_csv(&stats, &options);
+        assert!(csv.lines().nth(1).unwrap().contains("\t\"claude;custom \"\"beta\"\"\"\t"));
+    }
+
+    #[test]
+    fn test_csv_summary_row() {
+        let mut stats = sample_stats();
+        stats.push(stats[0].clone());
+        let options = FormatOptions {
+            show_summary: true,
+            ..Default::default()
+        };
+
+        let csv = format_csv(&stats, &options);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[3], "");
+        assert_eq!(lines[4], "TOTAL,,4,3000,0.10,0.0000");
+    }
+
+    #[test]
+    fn test_ascii_chart_shape() {
+        let stats: Vec<_> = [1.0, 2.0, 3.0, 2.0, 1.0]
+            .into_iter()
+            .enumerate()
+            .map(|(i, cost)| {
+                let mut stat = sample_stats().remove(0);
+                stat.model = ModelName::Unknown("all".to_string());
+                stat.date = Utc.with_ymd_and_hms(2025, 6, i as u32 + 1, 0, 0, 0).unwrap();
+                stat.cost_usd = cost;
+                stat
+            })
+            .collect();
+
+        let chart = format_ascii_chart(&stats, 60, &Currency::usd());
+        let lines: Vec<_> = chart.lines().collect();
+        assert_eq!(lines[0], "max $3.00");
+        // Each bar is 11 characters plus a space; count how tall each one is
+        let heights: Vec<usize> = (0..5)
+            .map(|bar| {
+                lines[1..=ASCII_CHART_HEIGHT]
+                    .iter()
+                    .filter(|line| line.chars().nth(bar * 12) == Some('#'))
+                    .count()
+            })
+            .collect();
+        assert_eq!(heights, [3, 7, 10, 7, 3]);
+        assert!(lines[ASCII_CHART_HEIGHT + 2].starts_with("2025-06-01  2025-06-02"));
+
+        // Narrower than the data: pairs of rows are summed into one bar
+        let narrow = format_ascii_chart(&stats, 3, &Currency::usd());
+        assert!(narrow.starts_with("max $5.00"));
+        assert!(narrow.contains("2025-06-01..2025-06-02  $3.00"));
+        assert!(narrow.contains("2025-06-05  $1.00"));
+    }
+
+    #[test]
+    fn test_compact_json_schema() {
+        let mut stats = sample_stats();
+        stats[0].date = Utc.with_ymd_and_hms(2025, 6, 1, 0, 0, 0).unwrap();
+        let options = FormatOptions {
+            compact_json: true,
+            ..Default::default()
+        };
+
+        // Every TokenUsage field is present, even when zero or unset, in declaration order
+        assert_eq!(
+            format_json(&stats, &options).unwrap(),
+            concat!(
+                r#"[{"model":"claude-opus-4-20250514","date":"2025-06-01T00:00:00Z","#,
+                r#""usage":{"input_tokens":1000,"output_tokens":500,"#,
+                r#""cache_creation_input_tokens":0,"cache_read_input_tokens":0,"#,
+                r#""service_tier":null,"cache_creation":null},"#,
+                r#""request_count":2,"cost_usd":0.0525,"cache_hit_rate":0.0}]"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_anomalies_list_each_baseline() {
+        let mut stats = sample_stats().remove(0);
+        stats.cost_usd = 10.0;
+        let report = AnomalyReport {
+            anomaly: Anomaly {
+                date: stats.date,
+                cost_usd: stats.cost_usd,
+                rolling: None,
+                period: Some(AnomalyBaseline {
+                    mean: 2.0,
+                    std_dev: 2.5,
+                    sigmas: 3.2,
+                }),
+            },
+            stats,
+        };
+        let thresholds = AnomalyThresholds { rolling: 2.5, period: 2.0 };
+
+        let output = format_anomalies(&[report], thresholds, &Currency::usd(), false);
+        assert!(output.contains(">2.5σ above 7-day mean or >2.0σ above period mean"));
+        assert!(output.contains("cost $10.00 (period mean $2.00, +$8.00 / 3.2σ)"));
+        assert!(!output.contains("(7-day mean"));
+    }
+
+    #[test]
+    fn test_no_color_output_has_no_escapes() {
+        let options = FormatOptions {
+            colored_output: false,
+            show_summary: true,
+            percentages: true,
+            ..Default::default()
+        };
+        let stats = sample_stats();
+
+        let mut output = format_table(&stats, &options);
+        let mut summary = Vec::new();
+        print_summary(&mut summary, &stats, &options).unwrap();
+        output.push_str(&String::from_utf8(summary).unwrap());
+        output.push_str(&format_models_table(&crate::pricing::get_default_pricing(), false));
+        let thresholds = AnomalyThresholds { rolling: 2.5, period: 2.0 };
+        output.push_str(&format_anomalies(&[], thresholds, &Currency::usd(), false));
+
+        assert!(output.contains("TOTAL"));
+        assert!(output.contains("=== Usage Summary ==="));
+        assert!(!output.contains('\x1b'), "unexpected ANSI escape in {:?}", output);
+    }
+
+    #[test]
+    fn test_running_total_ends_at_sum() {
+        let mut stats = sample_stats();
+        for cost in [1.25, 0.5, 3.0] {
+            let mut stat = stats[0].clone();
+            stat.cost_usd = cost;
+            stats.push(stat);
+        }
+        let sum: f64 = stats.iter().map(|s| s.cost_usd).sum();
+        let options = FormatOptions {
+            running_total: true,
+            ..Default::default()
+        };
+
+        let totals = running_totals(&stats);
+        assert!((totals.last().unwrap() - sum).abs() < 1e-9);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&format_json(&stats, &options).unwrap()).unwrap();
+        let last = json.as_array().unwrap().last().unwrap();
+        assert!((last["running_total_usd"].as_f64().unwrap() - sum).abs() < 1e-9);
+        assert_eq!(last["cost_usd"].as_f64().unwrap(), 3.0);
+
+        let csv = format_csv(&stats, &options);
+        assert!(csv.lines().last().unwrap().ends_with(&format!(",{:.2}", sum)));
+    }
+
+    #[test]
+    fn test_format_gap() {
+        assert_eq!(format_gap(Duration::seconds(12)), "12s");
+        assert_eq!(format_gap(Duration::seconds(250)), "4m 10s");
+        assert_eq!(format_gap(Duration::minutes(125)), "2h 05m");
+    }
+
+    #[test]
+    fn test_patterns_bars_scale_to_busiest_bucket() {
+        let mut hours = [0.0; 24];
+        hours[9] = 1.0;
+        hours[14] = 2.0;
+        let mut weekdays = [0.0; 7];
+        weekdays[1] = 3.0;
+
+        let output = format_patterns(&hours, &weekdays, false);
+        let bar = |prefix: &str| {
+            let line = output.lines().find(|line| line.trim_start().starts_with(prefix)).unwrap();
+            line.chars().filter(|&c| c == '█').count()
+        };
+        assert_eq!(bar("14:00"), PATTERN_BAR_WIDTH);
+        assert_eq!(bar("09:00"), PATTERN_BAR_WIDTH / 2);
+        assert_eq!(bar("03:00"), 0);
+        assert_eq!(bar("Tue"), PATTERN_BAR_WIDTH);
+        assert!(output.contains("$3.00"));
+    }
+}
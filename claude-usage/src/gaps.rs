@@ -0,0 +1,126 @@
+use crate::models::LogEntry;
+use chrono::Duration;
+use std::collections::HashMap;
+
+/// Idle-time distribution between consecutive requests
+#[derive(Debug, Clone, PartialEq)]
+pub struct GapStats {
+    /// Session the gaps were measured in, or `None` for the all-sessions summary
+    pub session_id: Option<String>,
+    pub requests: usize,
+    pub median: Duration,
+    pub p90: Duration,
+    pub longest: Duration,
+}
+
+/// Nearest-rank percentile of an ascending, non-empty slice
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn summarize(session_id: Option<String>, requests: usize, mut gaps: Vec<Duration>) -> GapStats {
+    gaps.sort();
+    GapStats {
+        session_id,
+        requests,
+        median: percentile(&gaps, 0.5),
+        p90: percentile(&gaps, 0.9),
+        longest: gaps[gaps.len() - 1],
+    }
+}
+
+/// Gap statistics per session, sorted by session id, followed by one overall entry over
+/// every session's gaps. Gaps never span two sessions, and sessions with a single
+/// request have none and are left out.
+pub fn calculate_request_gaps(entries: &[LogEntry]) -> Vec<GapStats> {
+    let mut sessions: HashMap<&str, Vec<&LogEntry>> = HashMap::new();
+    for entry in entries {
+        sessions.entry(&entry.session_id).or_default().push(entry);
+    }
+
+    let mut session_ids: Vec<&str> = sessions.keys().copied().collect();
+    session_ids.sort();
+
+    let mut stats = Vec::new();
+    let mut all_gaps = Vec::new();
+    let mut all_requests = 0;
+    for session_id in session_ids {
+        let session = sessions.get_mut(session_id).unwrap();
+        if session.len() < 2 {
+            continue;
+        }
+        session.sort_by_key(|entry| entry.timestamp);
+        let gaps: Vec<Duration> = session
+            .windows(2)
+            .map(|pair| pair[1].timestamp - pair[0].timestamp)
+            .collect();
+
+        all_gaps.extend_from_slice(&gaps);
+        all_requests += session.len();
+        stats.push(summarize(Some(session_id.to_string()), session.len(), gaps));
+    }
+
+    if !all_gaps.is_empty() {
+        stats.push(summarize(None, all_requests, all_gaps));
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(session_id: &str, timestamp: &str) -> LogEntry {
+        LogEntry {
+            entry_type: "assistant".to_string(),
+            uuid: timestamp.to_string(),
+            parent_uuid: None,
+            timestamp: timestamp.parse().unwrap(),
+            session_id: session_id.to_string(),
+            request_id: None,
+            version: None,
+            message: None,
+            is_sidechain: None,
+        }
+    }
+
+    #[test]
+    fn test_gaps_per_session() {
+        // Out of order on purpose; session "b" sits between a's requests in time
+        let entries = vec![
+            entry("a", "2024-12-01T10:00:30Z"),
+            entry("a", "2024-12-01T10:00:00Z"),
+            entry("b", "2024-12-01T10:01:00Z"),
+            entry("a", "2024-12-01T10:10:30Z"),
+            entry("b", "2024-12-01T10:01:10Z"),
+            entry("a", "2024-12-01T10:11:30Z"),
+            entry("c", "2024-12-01T12:00:00Z"),
+        ];
+
+        let stats = calculate_request_gaps(&entries);
+        assert_eq!(stats.len(), 3);
+
+        // a's gaps: 30s, 10m, 60s
+        assert_eq!(stats[0].session_id.as_deref(), Some("a"));
+        assert_eq!(stats[0].requests, 4);
+        assert_eq!(stats[0].median, Duration::seconds(60));
+        assert_eq!(stats[0].p90, Duration::minutes(10));
+        assert_eq!(stats[0].longest, Duration::minutes(10));
+
+        assert_eq!(stats[1].session_id.as_deref(), Some("b"));
+        assert_eq!(stats[1].longest, Duration::seconds(10));
+
+        // Overall: 10s, 30s, 60s, 10m
+        assert_eq!(stats[2].session_id, None);
+        assert_eq!(stats[2].requests, 6);
+        assert_eq!(stats[2].median, Duration::seconds(30));
+        assert_eq!(stats[2].longest, Duration::minutes(10));
+    }
+
+    #[test]
+    fn test_no_gaps_without_repeat_requests() {
+        let entries = vec![entry("a", "2024-12-01T10:00:00Z"), entry("b", "2024-12-01T10:05:00Z")];
+        assert!(calculate_request_gaps(&entries).is_empty());
+    }
+}
@@ -1,13 +1,24 @@
 use crate::file_tracker::{FileCheckResult, FileTracker};
 use crate::models::LogEntry;
+use crate::parser::{is_gzip_file, parse_entry_line, FileRead};
 use anyhow::{Context, Result};
+use rayon::prelude::*;
 use std::fs::File;
 use std::io::{BufRead, BufReader, Seek, SeekFrom};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Extension trait for LogParser to add incremental parsing capabilities
 pub trait IncrementalParsing {
     fn parse_logs_incremental(&self, tracker: &mut FileTracker) -> Result<Vec<LogEntry>>;
+    /// Bring `tracker` up to date after a full `parse_logs_with_reads` that returned
+    /// `entries` and `reads`, so the next incremental parse only reads what's new, without
+    /// reading the files again
+    fn warm_up_tracker(
+        &self,
+        tracker: &mut FileTracker,
+        entries: &[LogEntry],
+        reads: &[FileRead],
+    ) -> Result<()>;
     fn parse_jsonl_file_from_position(
         &self,
         path: &Path,
@@ -16,80 +27,133 @@ pub trait IncrementalParsing {
     ) -> Result<(Vec<LogEntry>, u64, usize)>;
 }
 
-impl IncrementalParsing for crate::parser::LogParser {
-    fn parse_logs_incremental(&self, tracker: &mut FileTracker) -> Result<Vec<LogEntry>> {
-        let expanded_path = shellexpand::tilde(&self.claude_dir).to_string();
-        let projects_dir = Path::new(&expanded_path).join("projects");
+/// Result of checking and parsing one file, produced in parallel and applied to the
+/// tracker afterwards
+enum FileOutcome {
+    Unchanged,
+    /// A new or rotated file, read in full
+    Read {
+        entries: Vec<LogEntry>,
+        read: FileRead,
+    },
+    Parsed {
+        path: PathBuf,
+        entries: Vec<LogEntry>,
+        position: u64,
+        line_count: usize,
+        bytes_read: u64,
+    },
+    Failed {
+        path: PathBuf,
+        error: String,
+    },
+}
 
-        if !projects_dir.exists() {
-            anyhow::bail!(
-                "Claude projects directory not found at: {}",
-                projects_dir.display()
-            );
+/// Check one file against the tracker and parse whatever is new in it, without
+/// touching the tracker so it can run on any thread
+fn check_and_parse(
+    parser: &crate::parser::LogParser,
+    tracker: &FileTracker,
+    file_path: &Path,
+) -> Result<FileOutcome> {
+    let mut check = tracker.check_file(file_path)?;
+
+    // Gzip streams aren't seekable, so a modified compressed file can't be resumed
+    // from the last position and is reparsed in full instead
+    if is_gzip_file(file_path) && matches!(check, FileCheckResult::Modified { .. }) {
+        check = FileCheckResult::Rotated;
+    }
+
+    let outcome = match check {
+        FileCheckResult::Unchanged => FileOutcome::Unchanged,
+        FileCheckResult::New | FileCheckResult::Rotated => {
+            // Parse entire file for new or rotated files
+            match parser.read_jsonl_file(file_path) {
+                Ok((entries, read)) => FileOutcome::Read { entries, read },
+                Err(e) => FileOutcome::Failed {
+                    path: file_path.to_path_buf(),
+                    error: format!("Error parsing {}: {}", file_path.display(), e),
+                },
+            }
+        }
+        FileCheckResult::Modified {
+            last_position,
+            last_line,
+        } => {
+            // Parse only new content
+            match parser.parse_jsonl_file_from_position(file_path, last_position, last_line) {
+                Ok((entries, new_position, new_line_number)) => FileOutcome::Parsed {
+                    path: file_path.to_path_buf(),
+                    entries,
+                    position: new_position,
+                    line_count: new_line_number,
+                    bytes_read: new_position - last_position,
+                },
+                Err(e) => FileOutcome::Failed {
+                    path: file_path.to_path_buf(),
+                    error: format!(
+                        "Error parsing {} from position {}: {}",
+                        file_path.display(),
+                        last_position,
+                        e
+                    ),
+                },
+            }
         }
+    };
 
-        let jsonl_files = self.find_jsonl_files(&projects_dir)?;
+    Ok(outcome)
+}
+
+impl IncrementalParsing for crate::parser::LogParser {
+    fn parse_logs_incremental(&self, tracker: &mut FileTracker) -> Result<Vec<LogEntry>> {
+        let jsonl_files = self.find_jsonl_files()?;
         let mut all_entries = Vec::new();
         let mut files_processed = 0;
         let mut bytes_read = 0u64;
 
-        for file_path in jsonl_files {
-            match tracker.check_file(&file_path)? {
-                FileCheckResult::Unchanged => {
-                    // Skip unchanged files
-                    continue;
-                }
-                FileCheckResult::New | FileCheckResult::Rotated => {
-                    // Parse entire file for new or rotated files
-                    match self.parse_jsonl_file(&file_path) {
-                        Ok(entries) => {
-                            let file_size = std::fs::metadata(&file_path)?.len();
-                            bytes_read += file_size;
-                            
-                            // Count lines for accurate tracking
-                            let line_count = entries.len();
-                            tracker.update_state(file_path.clone(), file_size, line_count)?;
-                            
-                            all_entries.extend(entries);
-                            files_processed += 1;
-                        }
-                        Err(e) => {
-                            eprintln!("Error parsing {}: {}", file_path.display(), e);
-                            // Remove from tracker if file can't be parsed
-                            tracker.remove_file(&file_path);
-                        }
-                    }
+        // Check and parse files in parallel; the tracker is only read here
+        let outcomes = {
+            let tracker = &*tracker;
+            jsonl_files
+                .par_iter()
+                .map(|file_path| check_and_parse(self, tracker, file_path))
+                .collect::<Result<Vec<_>>>()?
+        };
+
+        // Apply tracker updates sequentially, in file order, so persisted state is the
+        // same as a sequential run
+        for outcome in outcomes {
+            match outcome {
+                FileOutcome::Unchanged => {}
+                FileOutcome::Read { entries, read } => {
+                    bytes_read += read.bytes;
+                    tracker.record_read(&read)?;
+                    all_entries.extend(entries);
+                    files_processed += 1;
                 }
-                FileCheckResult::Modified {
-                    last_position,
-                    last_line,
+                FileOutcome::Parsed {
+                    path,
+                    entries,
+                    position,
+                    line_count,
+                    bytes_read: file_bytes,
                 } => {
-                    // Parse only new content
-                    match self.parse_jsonl_file_from_position(&file_path, last_position, last_line)
-                    {
-                        Ok((entries, new_position, new_line_number)) => {
-                            bytes_read += new_position - last_position;
-                            tracker.update_state(file_path.clone(), new_position, new_line_number)?;
-                            all_entries.extend(entries);
-                            files_processed += 1;
-                        }
-                        Err(e) => {
-                            eprintln!(
-                                "Error parsing {} from position {}: {}",
-                                file_path.display(),
-                                last_position,
-                                e
-                            );
-                            // On error, try full reparse next time
-                            tracker.remove_file(&file_path);
-                        }
-                    }
+                    bytes_read += file_bytes;
+                    tracker.update_state(path, position, line_count)?;
+                    all_entries.extend(entries);
+                    files_processed += 1;
+                }
+                FileOutcome::Failed { path, error } => {
+                    eprintln!("{}", error);
+                    // On error, try full reparse next time
+                    tracker.remove_file(&path);
                 }
             }
         }
 
         if !self.quiet && files_processed > 0 {
-            println!(
+            eprintln!(
                 "Incrementally processed {} files, read {} bytes",
                 files_processed,
                 format_bytes(bytes_read)
@@ -99,8 +163,29 @@ impl IncrementalParsing for crate::parser::LogParser {
         // Filter by date range if specified
         let filtered_entries = self.filter_by_date(all_entries);
 
-        // Deduplicate entries
-        Ok(self.deduplicate_entries(filtered_entries))
+        // Deduplicate only against what earlier calls returned, not the whole history
+        let entries = self.deduplicate_incremental(filtered_entries, tracker.seen_ids_mut());
+        if !entries.is_empty() {
+            tracker.save_seen_ids()?;
+        }
+        Ok(entries)
+    }
+
+    fn warm_up_tracker(
+        &self,
+        tracker: &mut FileTracker,
+        entries: &[LogEntry],
+        reads: &[FileRead],
+    ) -> Result<()> {
+        tracker.snapshot_files(reads)?;
+        let seen = tracker.seen_ids_mut();
+        for entry in entries {
+            seen.insert(entry, self.dedup_key);
+        }
+        if !entries.is_empty() {
+            tracker.save_seen_ids()?;
+        }
+        Ok(())
     }
 
     fn parse_jsonl_file_from_position(
@@ -130,39 +215,22 @@ impl IncrementalParsing for crate::parser::LogParser {
                 continue;
             }
 
-            // First check if this is a known alternative format
-            if let Ok(json_value) = serde_json::from_str::<serde_json::Value>(&line) {
-                // Skip summary entries - they don't contain usage data
-                if json_value.get("type").and_then(|t| t.as_str()) == Some("summary") {
-                    continue;
-                }
-            }
-
-            match serde_json::from_str::<LogEntry>(&line) {
-                Ok(entry) => {
-                    // Only include assistant messages with usage data
-                    if entry.entry_type == "assistant" {
-                        if let Some(message) = &entry.message {
-                            if message.usage.is_some() {
-                                entries.push(entry);
-                            }
-                        }
+            match parse_entry_line(&line) {
+                Ok(Some(entry)) => {
+                    if self.matches_entry_filters(&entry) {
+                        entries.push(entry);
                     }
                 }
+                Ok(None) => {}
                 Err(e) => {
                     // Log parse errors for debugging
                     if !self.quiet && line_num < start_line + 5 {
-                        let error_str = e.to_string();
-                        if !error_str.contains("missing field `id`")
-                            && !error_str.contains("missing field `uuid`")
-                        {
-                            eprintln!(
-                                "Skipping unexpected entry format in {} line {}: {}",
-                                path.display(),
-                                line_num,
-                                e
-                            );
-                        }
+                        eprintln!(
+                            "Skipping unexpected entry format in {} line {}: {}",
+                            path.display(),
+                            line_num,
+                            e
+                        );
                     }
                 }
             }
@@ -172,7 +240,7 @@ impl IncrementalParsing for crate::parser::LogParser {
     }
 }
 
-fn format_bytes(bytes: u64) -> String {
+pub(crate) fn format_bytes(bytes: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB"];
     let mut size = bytes as f64;
     let mut unit_index = 0;
@@ -208,8 +276,8 @@ mod tests {
         std::fs::create_dir_all(&projects_dir).unwrap();
 
         // Create initial file
-        let initial_content = r#"{"type":"assistant","uuid":"test1","timestamp":"2024-12-01T00:00:00Z","sessionId":"test-session","requestId":"req1","message":{"model":"claude-opus-4-20250514","usage":{"input_tokens":100,"output_tokens":50,"cache_creation_input_tokens":0,"cache_read_input_tokens":0}}}
-{"type":"assistant","uuid":"test2","timestamp":"2024-12-01T00:01:00Z","sessionId":"test-session","requestId":"req2","message":{"model":"claude-opus-4-20250514","usage":{"input_tokens":200,"output_tokens":100,"cache_creation_input_tokens":0,"cache_read_input_tokens":0}}}"#;
+        let initial_content = r#"{"type":"assistant","uuid":"test1","timestamp":"2024-12-01T00:00:00Z","sessionId":"test-session","requestId":"req1","message":{"id":"msg-req1","role":"assistant","model":"claude-opus-4-20250514","usage":{"input_tokens":100,"output_tokens":50,"cache_creation_input_tokens":0,"cache_read_input_tokens":0}}}
+{"type":"assistant","uuid":"test2","timestamp":"2024-12-01T00:01:00Z","sessionId":"test-session","requestId":"req2","message":{"id":"msg-req2","role":"assistant","model":"claude-opus-4-20250514","usage":{"input_tokens":200,"output_tokens":100,"cache_creation_input_tokens":0,"cache_read_input_tokens":0}}}"#;
 
         let file_path = create_test_jsonl_file(&projects_dir, "test.jsonl", initial_content);
 
@@ -227,7 +295,7 @@ mod tests {
 
         // Append new content
         let new_content = r#"
-{"type":"assistant","uuid":"test3","timestamp":"2024-12-01T00:02:00Z","sessionId":"test-session","requestId":"req3","message":{"model":"claude-opus-4-20250514","usage":{"input_tokens":150,"output_tokens":75,"cache_creation_input_tokens":0,"cache_read_input_tokens":0}}}"#;
+{"type":"assistant","uuid":"test3","timestamp":"2024-12-01T00:02:00Z","sessionId":"test-session","requestId":"req3","message":{"id":"msg-req3","role":"assistant","model":"claude-opus-4-20250514","usage":{"input_tokens":150,"output_tokens":75,"cache_creation_input_tokens":0,"cache_read_input_tokens":0}}}"#;
 
         let mut file = std::fs::OpenOptions::new()
             .append(true)
@@ -252,14 +320,16 @@ mod tests {
             .quiet();
 
         // Create and parse initial file
-        let initial_content = r#"{"type":"assistant","uuid":"test1","timestamp":"2024-12-01T00:00:00Z","sessionId":"test-session","requestId":"req1","message":{"model":"claude-opus-4-20250514","usage":{"input_tokens":100,"output_tokens":50,"cache_creation_input_tokens":0,"cache_read_input_tokens":0}}}"#;
+        let initial_content = r#"{"type":"assistant","uuid":"test1","timestamp":"2024-12-01T00:00:00Z","sessionId":"test-session","requestId":"req1","message":{"id":"msg-req1","role":"assistant","model":"claude-opus-4-20250514","usage":{"input_tokens":100,"output_tokens":50,"cache_creation_input_tokens":0,"cache_read_input_tokens":0}}}"#;
         let file_path = create_test_jsonl_file(&projects_dir, "test.jsonl", initial_content);
 
         let entries1 = parser.parse_logs_incremental(&mut tracker).unwrap();
         assert_eq!(entries1.len(), 1);
 
-        // Simulate rotation - write shorter content
-        let rotated_content = r#"{"type":"assistant","uuid":"test2","timestamp":"2024-12-01T01:00:00Z","sessionId":"test-session","requestId":"req2-new","message":{"model":"claude-opus-4-20250514","usage":{"input_tokens":50,"output_tokens":25,"cache_creation_input_tokens":0,"cache_read_input_tokens":0}}}"#;
+        // Simulate rotation - write shorter content (no cache fields), in place so the
+        // inode stays the same and only the size gives it away
+        let rotated_content = r#"{"type":"assistant","uuid":"test2","timestamp":"2024-12-01T01:00:00Z","sessionId":"test-session","requestId":"req2","message":{"id":"msg-req2","role":"assistant","model":"claude-opus-4-20250514","usage":{"input_tokens":50,"output_tokens":25}}}"#;
+        assert!(rotated_content.len() < initial_content.len());
         std::fs::write(&file_path, rotated_content).unwrap();
 
         // Should detect rotation and reparse entire file
@@ -267,4 +337,166 @@ mod tests {
         assert_eq!(entries2.len(), 1);
         assert_eq!(entries2[0].uuid, "test2");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_same_size_rewrite_with_checksums() {
+        let temp_dir = TempDir::new().unwrap();
+        let projects_dir = temp_dir.path().join("projects");
+        std::fs::create_dir_all(&projects_dir).unwrap();
+        let line = |id: &str| {
+            format!(
+                r#"{{"type":"assistant","uuid":"{id}","timestamp":"2024-12-01T00:00:00Z","sessionId":"test-session","requestId":"{id}","message":{{"id":"msg-{id}","role":"assistant","model":"claude-opus-4-20250514","usage":{{"input_tokens":100,"output_tokens":50}}}}}}"#
+            )
+        };
+        let file_path = create_test_jsonl_file(&projects_dir, "test.jsonl", &line("req1"));
+        let original_mtime = std::fs::metadata(&file_path).unwrap().modified().unwrap();
+
+        let mut tracker = FileTracker::new().with_checksums(true);
+        let parser = crate::parser::LogParser::new(temp_dir.path().to_string_lossy().to_string())
+            .quiet();
+        assert_eq!(parser.parse_logs_incremental(&mut tracker).unwrap().len(), 1);
+
+        // Rewritten in place with the same size and mtime: only the checksums notice, and
+        // the new content is read from the start
+        std::fs::write(&file_path, line("req2")).unwrap();
+        let file = std::fs::OpenOptions::new().write(true).open(&file_path).unwrap();
+        file.set_modified(original_mtime).unwrap();
+        drop(file);
+
+        let entries = parser.parse_logs_incremental(&mut tracker).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].uuid, "req2");
+    }
+
+    #[test]
+    fn test_seen_ids_persist_with_tracker() {
+        let temp_dir = TempDir::new().unwrap();
+        let projects_dir = temp_dir.path().join("projects");
+        std::fs::create_dir_all(&projects_dir).unwrap();
+        let content = r#"{"type":"assistant","uuid":"test1","timestamp":"2024-12-01T00:00:00Z","sessionId":"test-session","requestId":"req1","message":{"id":"msg-req1","role":"assistant","model":"claude-opus-4-20250514","usage":{"input_tokens":100,"output_tokens":50,"cache_creation_input_tokens":0,"cache_read_input_tokens":0}}}"#;
+        let file_path = create_test_jsonl_file(&projects_dir, "test.jsonl", content);
+        let parser = crate::parser::LogParser::new(temp_dir.path().to_string_lossy().to_string())
+            .quiet();
+        let state_file = temp_dir.path().join("state.json");
+
+        let mut tracker = FileTracker::with_persistence(state_file.clone());
+        assert_eq!(parser.parse_logs_incremental(&mut tracker).unwrap().len(), 1);
+        assert!(crate::file_tracker::seen_ids_path(&state_file).exists());
+
+        // A reloaded tracker that has to re-read the file still knows the entry was returned
+        let mut tracker = FileTracker::with_persistence(state_file);
+        assert_eq!(tracker.seen_ids_mut().len(), 1);
+        tracker.remove_file(&file_path);
+        assert!(parser.parse_logs_incremental(&mut tracker).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parallel_tracker_state_matches_sequential() {
+        let temp_dir = TempDir::new().unwrap();
+        let projects_dir = temp_dir.path().join("projects");
+        for project in 0..4 {
+            let dir = projects_dir.join(format!("project-{}", project));
+            std::fs::create_dir_all(&dir).unwrap();
+            for session in 0..5 {
+                let lines: Vec<String> = (0..3)
+                    .map(|n| {
+                        let id = format!("p{}-s{}-r{}", project, session, n);
+                        format!(
+                            r#"{{"type":"assistant","uuid":"{id}","timestamp":"2024-12-01T00:0{n}:00Z","sessionId":"s{session}","requestId":"{id}","message":{{"id":"msg-{id}","role":"assistant","model":"claude-opus-4-20250514","usage":{{"input_tokens":100,"output_tokens":50,"cache_creation_input_tokens":0,"cache_read_input_tokens":0}}}}}}"#
+                        )
+                    })
+                    .collect();
+                create_test_jsonl_file(&dir, &format!("s{}.jsonl", session), &lines.join("\n"));
+            }
+        }
+
+        let parser = crate::parser::LogParser::new(temp_dir.path().to_string_lossy().to_string())
+            .quiet();
+        let run_with_threads = |threads: usize| {
+            let state_file = temp_dir.path().join(format!("state-{}.json", threads));
+            let mut tracker = FileTracker::with_persistence(state_file.clone());
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap();
+            let entries = pool.install(|| parser.parse_logs_incremental(&mut tracker).unwrap());
+            (entries.len(), std::fs::read(state_file).unwrap())
+        };
+
+        let (sequential_count, sequential_state) = run_with_threads(1);
+        let (parallel_count, parallel_state) = run_with_threads(4);
+        assert_eq!(sequential_count, 60);
+        assert_eq!(parallel_count, sequential_count);
+        assert_eq!(parallel_state, sequential_state);
+    }
+
+    #[test]
+    fn test_warm_up_matches_incremental_first_parse() {
+        let temp_dir = TempDir::new().unwrap();
+        let projects_dir = temp_dir.path().join("projects");
+        std::fs::create_dir_all(&projects_dir).unwrap();
+        let line = |id: &str| {
+            format!(
+                r#"{{"type":"assistant","uuid":"{id}","timestamp":"2024-12-01T00:00:00Z","sessionId":"s","requestId":"{id}","message":{{"id":"msg-{id}","role":"assistant","model":"claude-opus-4-20250514","usage":{{"input_tokens":100,"output_tokens":50,"cache_creation_input_tokens":0,"cache_read_input_tokens":0}}}}}}"#
+            )
+        };
+        let first = create_test_jsonl_file(&projects_dir, "a.jsonl", &format!("{}\n", line("r1")));
+        let second = format!("{}\n{}\n", line("r2"), line("r3"));
+        create_test_jsonl_file(&projects_dir, "b.jsonl", &second);
+        let parser = crate::parser::LogParser::new(temp_dir.path().to_string_lossy().to_string())
+            .quiet();
+
+        // Warm up with checksums on, so their recorded lengths are compared too
+        let incremental_state = temp_dir.path().join("incremental.json");
+        let warmed_state = temp_dir.path().join("warmed.json");
+        let mut incremental = FileTracker::with_persistence(incremental_state.clone())
+            .with_checksums(true);
+        assert_eq!(parser.parse_logs_incremental(&mut incremental).unwrap().len(), 3);
+        let mut warmed = FileTracker::with_persistence(warmed_state.clone()).with_checksums(true);
+        let (entries, reads) = parser.parse_logs_with_reads().unwrap();
+        parser.warm_up_tracker(&mut warmed, &entries, &reads).unwrap();
+
+        // Every recorded field matches, not just the byte totals
+        assert_eq!(
+            std::fs::read(&warmed_state).unwrap(),
+            std::fs::read(&incremental_state).unwrap()
+        );
+        assert_eq!(warmed.seen_ids_mut(), incremental.seen_ids_mut());
+        assert!(matches!(warmed.check_file(&first).unwrap(), FileCheckResult::Unchanged));
+
+        // The next refresh reads only the appended line either way
+        let appended = format!("{}\n{}\n", line("r1"), line("r4"));
+        std::fs::write(&first, appended).unwrap();
+        for tracker in [&mut incremental, &mut warmed] {
+            let entries = parser.parse_logs_incremental(tracker).unwrap();
+            assert_eq!(entries.len(), 1);
+            assert_eq!(entries[0].uuid, "r4");
+        }
+        assert_eq!(warmed.total_bytes_read(), incremental.total_bytes_read());
+    }
+
+    #[test]
+    fn test_warm_up_keeps_lines_appended_after_the_full_parse() {
+        let temp_dir = TempDir::new().unwrap();
+        let projects_dir = temp_dir.path().join("projects");
+        std::fs::create_dir_all(&projects_dir).unwrap();
+        let line = |id: &str| {
+            format!(
+                r#"{{"type":"assistant","uuid":"{id}","timestamp":"2024-12-01T00:00:00Z","sessionId":"s","requestId":"{id}","message":{{"id":"msg-{id}","role":"assistant","model":"claude-opus-4-20250514","usage":{{"input_tokens":100,"output_tokens":50,"cache_creation_input_tokens":0,"cache_read_input_tokens":0}}}}}}"#
+            )
+        };
+        let file = create_test_jsonl_file(&projects_dir, "a.jsonl", &format!("{}\n", line("r1")));
+        let parser = crate::parser::LogParser::new(temp_dir.path().to_string_lossy().to_string())
+            .quiet();
+
+        let (entries, reads) = parser.parse_logs_with_reads().unwrap();
+        // A line lands between the full parse and the warm-up
+        std::fs::write(&file, format!("{}\n{}\n", line("r1"), line("r2"))).unwrap();
+        let mut tracker = FileTracker::new();
+        parser.warm_up_tracker(&mut tracker, &entries, &reads).unwrap();
+
+        let entries = parser.parse_logs_incremental(&mut tracker).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].uuid, "r2");
+    }
+}
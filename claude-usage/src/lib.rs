@@ -1,29 +1,76 @@
+pub mod cleanup;
 pub mod cli;
+pub mod currency;
 pub mod dashboard;
+pub mod diagnostics;
+pub mod error;
+pub mod export;
 pub mod file_tracker;
+pub mod file_watcher;
+pub mod follow;
 pub mod formatters;
+pub mod gaps;
 pub mod incremental_parser;
 pub mod model_name;
 pub mod models;
 pub mod parser;
+pub mod patterns;
 pub mod pricing;
+pub mod range;
+pub mod statusline;
+pub mod validate;
+pub mod version_check;
 
 use anyhow::Result;
-use chrono::{Datelike, TimeZone, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Utc};
+use colored::Colorize;
 use cli::{GroupBy, OutputFormat};
+use currency::Currency;
+use formatters::FormatOptions;
 use model_name::ModelName;
-use models::{LogEntry, TokenUsage, UsageStats};
+use models::{LogEntry, ModelBreakdown, ModelPricing, PricingMap, TokenUsage, UsageStats};
 use parser::LogParser;
 use pricing::{get_default_pricing, get_model_pricing};
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{self, Write};
+use std::path::PathBuf;
 
 pub async fn analyze_usage(args: cli::Args) -> Result<()> {
+    analyze_usage_to_writer(args, &mut io::stdout()).await
+}
+
+/// Run the analysis and write the report to `writer`.
+///
+/// Progress and warning messages still go to stderr, so only the report itself lands in `writer`.
+pub async fn analyze_usage_to_writer<W: Write>(args: cli::Args, writer: &mut W) -> Result<()> {
+    formatters::configure_color(args.no_color);
+    if args.version_check {
+        if let Some(update) = version_check::check_for_update().await {
+            eprintln!("{}", update);
+        }
+    }
+
     // Get pricing information
-    let pricing_map = if args.refresh_pricing {
-        pricing::fetch_latest_pricing().await?
+    let mut pricing_map = if args.refresh_pricing {
+        pricing::fetch_latest_pricing(std::time::Duration::from_secs_f64(args.pricing_timeout))
+            .await?
     } else {
         get_default_pricing()
     };
+    pricing::set_batch_discount(&mut pricing_map, args.batch_discount);
+    let reprice_as = args
+        .reprice_as
+        .as_deref()
+        .map(|model| reprice_target(&pricing_map, model))
+        .transpose()?;
+    let currency = currency::resolve_currency(
+        &args.currency,
+        args.exchange_rate,
+        &args.exchange_rate_url,
+        std::time::Duration::from_secs_f64(args.pricing_timeout),
+    )
+    .await;
 
     // Parse date range
     let start_date = args
@@ -34,108 +81,1055 @@ pub async fn analyze_usage(args: cli::Args) -> Result<()> {
         .map(|d| Utc.from_utc_datetime(&d.and_hms_opt(23, 59, 59).unwrap()));
 
     // Parse logs
-    let parser = LogParser::new(args.claude_dir.clone()).with_date_range(start_date, end_date);
-    let entries = parser.parse_logs()?;
+    let mut parser = LogParser::new_multi(args.claude_dirs.clone())
+        .with_date_range(start_date, end_date)
+        .with_max_depth(args.max_depth)
+        .with_tool_results(args.include_tool_results)
+        .with_dedup_key(args.dedup_key);
+
+    // An exact model name can be filtered while parsing; looser substring filters
+    // (e.g. "opus") are still applied in calculate_stats
+    if let Some(filter) = &args.model {
+        let model = ModelName::from_model_string(filter);
+        if !matches!(model, ModelName::Unknown(_)) {
+            parser = parser.with_model_filter(model);
+        }
+    }
+    if let Some(sample) = args.sample {
+        parser = parser.with_sample(sample, args.seed);
+    }
+    if args.quiet {
+        parser = parser.quiet();
+    }
+    if args.discover_max_depth {
+        parser.warn_on_depth_mismatch()?;
+    }
+
+    if args.explain && !args.quiet && !matches!(args.format, OutputFormat::Table) {
+        eprintln!("Warning: --explain only adds a column to --format table");
+    }
+    if args.breakdown
+        && !args.quiet
+        && (args.group_by != [GroupBy::Day]
+            || args.streaming
+            || matches!(
+                args.format,
+                OutputFormat::Csv | OutputFormat::Markdown | OutputFormat::AsciiChart
+            ))
+    {
+        eprintln!(
+            "Warning: --breakdown only applies to --group-by day in table or JSON output, \
+             without --streaming"
+        );
+    }
+    if args.group_by.contains(&GroupBy::Request)
+        && !args.quiet
+        && !matches!(args.format, OutputFormat::Csv | OutputFormat::Ndjson)
+    {
+        eprintln!(
+            "Warning: --group-by request prints a row for every request; for more than a \
+             few days of logs, --format csv or ndjson works best"
+        );
+    }
+
+    let filters = EntryFilters::from_args(&args);
+    if args.dry_run {
+        // Everything goes to stderr: a dry run writes no report
+        eprint!("{}", dry_run(&parser, &filters, &args.claude_dirs)?);
+        return Ok(());
+    }
+
+    if args.total_only {
+        return write_total_only(parser.quiet(), &filters, &pricing_map, &currency, writer);
+    }
+
+    if args.streaming {
+        return analyze_usage_streaming(&args, &parser, &filters, &pricing_map, currency, writer);
+    }
+
+    let entries = if args.profile {
+        let (entries, profile) = parser.parse_logs_profiled()?;
+        let json = matches!(args.format, OutputFormat::Json | OutputFormat::Ndjson);
+        eprint!("{}", formatters::format_parse_profile(&profile, json));
+        entries
+    } else {
+        parser.parse_logs()?
+    };
+    let entries = match args.recent {
+        Some(count) => most_recent(entries, count),
+        None => entries,
+    };
 
     if entries.is_empty() {
-        println!("No usage data found for the specified date range.");
+        writeln!(writer, "No usage data found for the specified date range.")?;
         return Ok(());
     }
+    let unknown_models = version_check::check_for_unknown_models(&entries, ModelName::all_known());
+    if !args.quiet {
+        for model in &unknown_models {
+            eprintln!("{}", version_check::unknown_model_warning(model));
+        }
+        if let Some(warning) = future_timestamp_warning(&entries, Utc::now()) {
+            eprintln!("{}", warning);
+        }
+    }
+
+    if !args.quiet {
+        eprintln!("Processed {} unique requests", entries.len());
+    }
+
+    if args.velocity || args.gaps {
+        let entries: Vec<LogEntry> = entries
+            .into_iter()
+            .filter(|entry| entry_group(entry, &[], &filters).is_some())
+            .collect();
+        if args.velocity {
+            let rows = velocity_rows(&entries, &pricing_map);
+            return write_velocity(&args, &rows, &currency, writer);
+        }
+        return write_gaps(&args, &gaps::calculate_request_gaps(&entries), writer);
+    }
+
+    if args.calendar {
+        let days = calculate_stats(
+            entries,
+            &[GroupBy::Day],
+            &filters,
+            &pricing_map,
+            &StatsOptions::default(),
+        )?;
+        let color = formatters::should_use_color(args.no_color);
+        write!(writer, "{}", formatters::format_calendar(&days, args.months, color))?;
+        return Ok(());
+    }
+
+    if let Some(threshold) = args.warn_context {
+        // Grouped by model too, so each row is measured against its own model's window
+        let sessions = calculate_stats(
+            entries.clone(),
+            &[GroupBy::Session, GroupBy::Model],
+            &filters,
+            &pricing_map,
+            &StatsOptions::default(),
+        )?;
+        let limits = pricing::get_context_limits();
+        let colored = formatters::should_use_color(args.no_color);
+        for warning in warn_context_usage(&sessions, threshold, &limits, colored) {
+            eprintln!("{}", warning);
+        }
+    }
 
-    println!("Processed {} unique requests", entries.len());
+    let aggregated_unknown = if args.aggregate_unknown_models {
+        aggregated_unknown_models(&entries, &filters)
+    } else {
+        0
+    };
 
     // Group and calculate stats
-    let stats = calculate_stats(entries, &args.group_by, args.model, &pricing_map)?;
+    let stats = calculate_stats(
+        entries,
+        &args.group_by,
+        &filters,
+        &pricing_map,
+        &StatsOptions {
+            reprice_as,
+            with_daily_stats: args.with_daily_stats,
+            cache_detail: args.cache_detail,
+            breakdown: args.breakdown,
+        },
+    )?;
+
+    let unknown_row = stats.iter().any(|stat| is_aggregated_unknown(&stat.model));
+    write_report(&args, stats, &parser, &pricing_map, currency, writer)?;
+    if unknown_row && !is_machine_readable(&args.format) {
+        writeln!(writer, "({} unknown model variants aggregated)", aggregated_unknown)?;
+    }
+    Ok(())
+}
+
+/// `--gaps` in the requested format; ASCII charts fall back to the table
+fn write_gaps<W: Write>(args: &cli::Args, gaps: &[gaps::GapStats], writer: &mut W) -> Result<()> {
+    match args.format {
+        OutputFormat::Table | OutputFormat::AsciiChart => {
+            let color = formatters::should_use_color(args.no_color);
+            write!(writer, "{}", formatters::format_gaps_table(gaps, color))?;
+        }
+        OutputFormat::Csv => {
+            let delimiter = if args.tsv { '\t' } else { args.delimiter.unwrap_or(',') };
+            write!(writer, "{}", formatters::format_gaps_csv(gaps, delimiter))?;
+        }
+        OutputFormat::Json => {
+            writeln!(writer, "{}", formatters::format_gaps_json(gaps, args.json_compact)?)?;
+        }
+        OutputFormat::Markdown => write!(writer, "{}", formatters::format_gaps_markdown(gaps))?,
+        OutputFormat::Ndjson => write!(writer, "{}", formatters::format_gaps_ndjson(gaps)?)?,
+    }
+    Ok(())
+}
+
+/// `--velocity` in the requested output format
+fn write_velocity<W: Write>(
+    args: &cli::Args,
+    rows: &[(DateTime<Utc>, f64, f64)],
+    currency: &Currency,
+    writer: &mut W,
+) -> Result<()> {
+    match args.format {
+        OutputFormat::Table | OutputFormat::AsciiChart => {
+            let color = formatters::should_use_color(args.no_color);
+            writeln!(writer, "{}", formatters::format_velocity_table(rows, currency, color))?;
+        }
+        OutputFormat::Csv => {
+            let delimiter = if args.tsv { '\t' } else { args.delimiter.unwrap_or(',') };
+            write!(writer, "{}", formatters::format_velocity_csv(rows, currency, delimiter))?;
+        }
+        OutputFormat::Json => {
+            writeln!(writer, "{}", formatters::format_velocity_json(rows, args.json_compact)?)?;
+        }
+        OutputFormat::Markdown => write!(writer, "{}", formatters::format_velocity_markdown(rows, currency))?,
+        OutputFormat::Ndjson => write!(writer, "{}", formatters::format_velocity_ndjson(rows)?)?,
+    }
+    Ok(())
+}
 
+/// `--streaming`: aggregate while parsing rather than loading every entry first
+fn analyze_usage_streaming<W: Write>(
+    args: &cli::Args,
+    parser: &LogParser,
+    filters: &EntryFilters,
+    pricing_map: &PricingMap,
+    currency: Currency,
+    writer: &mut W,
+) -> Result<()> {
+    let stats = aggregate_logs(parser, &args.group_by, filters, pricing_map)?;
+    if !args.quiet {
+        let requests: u64 = stats.iter().map(|stat| stat.request_count).sum();
+        eprintln!("Processed {} unique requests", requests);
+    }
+    write_report(args, stats, parser, pricing_map, currency, writer)
+}
+
+/// Prices of the `--reprice-as` model, which must be an exact model id with known pricing
+fn reprice_target<'a>(pricing_map: &'a PricingMap, model: &str) -> Result<&'a ModelPricing> {
+    pricing_map
+        .get(&ModelName::from_model_string(model))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "No pricing for --reprice-as model '{}'; run `claude-usage models` for the \
+                 model ids that have pricing",
+                model
+            )
+        })
+}
+
+/// The `count` newest entries, newest first, for `--recent`
+fn most_recent(mut entries: Vec<LogEntry>, count: usize) -> Vec<LogEntry> {
+    entries.sort_by_key(|entry| Reverse(entry.timestamp));
+    entries.truncate(count);
+    entries
+}
+
+/// Timestamps up to this far ahead of the local clock are ordinary drift, not worth a warning
+const CLOCK_SKEW_TOLERANCE_SECS: i64 = 60;
+
+/// A warning when entries are timestamped in the future, which usually means the clock
+/// was wrong on the machine that wrote them. They're still counted, on their logged dates.
+fn future_timestamp_warning(entries: &[LogEntry], now: DateTime<Utc>) -> Option<String> {
+    let cutoff = now + chrono::Duration::seconds(CLOCK_SKEW_TOLERANCE_SECS);
+    let future: Vec<DateTime<Utc>> = entries
+        .iter()
+        .map(|entry| entry.timestamp)
+        .filter(|timestamp| *timestamp > cutoff)
+        .collect();
+    let latest = future.iter().max()?;
+    Some(format!(
+        "Warning: {} entries are timestamped in the future (latest {}); check the clock \
+         of the machine that wrote them",
+        future.len(),
+        latest.to_rfc3339()
+    ))
+}
+
+/// `--dry-run`: the files, dates and entry count a report would cover, and whether the
+/// dashboard's incremental parsing state is current
+/// `--dry-run`: parse as the report would, then summarize the files read and the entries
+/// `filters` keeps
+fn dry_run(parser: &LogParser, filters: &EntryFilters, claude_dirs: &[String]) -> Result<String> {
+    // The files actually read, so a --sample without --seed isn't drawn a second time
+    let (entries, reads) = parser.parse_logs_with_reads()?;
+    if !parser.quiet {
+        eprintln!("Processed {} unique requests", entries.len());
+    }
+    let entries: Vec<LogEntry> = entries
+        .into_iter()
+        .filter(|entry| entry_group(entry, &[], filters).is_some())
+        .collect();
+    let files: Vec<PathBuf> = reads.into_iter().map(|read| read.path).collect();
+    Ok(dry_run_summary(&files, &entries, claude_dirs))
+}
+
+fn dry_run_summary(files: &[PathBuf], entries: &[LogEntry], claude_dirs: &[String]) -> String {
+    let mut out = String::from("Dry run: no report produced\n");
+    out.push_str(&format!("Files that would be analyzed: {}\n", files.len()));
+    for file in files {
+        out.push_str(&format!("  {}\n", file.display()));
+    }
+    let earliest = entries.iter().map(|entry| entry.timestamp).min();
+    let latest = entries.iter().map(|entry| entry.timestamp).max();
+    if let (Some(earliest), Some(latest)) = (earliest, latest) {
+        out.push_str(&format!(
+            "Date range: {} to {}\n",
+            earliest.format("%Y-%m-%d %H:%M UTC"),
+            latest.format("%Y-%m-%d %H:%M UTC")
+        ));
+    }
+    out.push_str(&format!("Entries found: {}\n", entries.len()));
+    let primary_dir = claude_dirs.first().map_or("~/.claude", String::as_str);
+    out.push_str(&format!("Tracker state: {}\n", tracker_state_status(primary_dir, files)));
+    out
+}
+
+/// Whether the dashboard's `FileTracker` state exists and covers `files` as they are now
+fn tracker_state_status(claude_dir: &str, files: &[PathBuf]) -> String {
+    use file_tracker::{FileCheckResult, FileTracker};
+
+    let state_file = dashboard::app::state_dir(claude_dir).join(dashboard::app::TRACKER_STATE_FILE);
+    if !state_file.exists() {
+        return format!("{} doesn't exist yet", state_file.display());
+    }
+    let tracker = match FileTracker::load(state_file.clone()) {
+        Ok(tracker) => tracker,
+        Err(e) => return format!("{} can't be read: {}", state_file.display(), e),
+    };
+    let stale = files
+        .iter()
+        .filter(|file| !matches!(tracker.check_file(file), Ok(FileCheckResult::Unchanged)))
+        .count();
+    if stale == 0 {
+        format!("{} is up to date", state_file.display())
+    } else {
+        format!(
+            "{} is out of date ({} of {} files new or changed)",
+            state_file.display(),
+            stale,
+            files.len()
+        )
+    }
+}
+
+/// Write just the grand total cost as a bare number in `currency`, for
+/// `COST=$(claude-usage show --total-only)`
+fn write_total_only<W: Write>(
+    parser: LogParser,
+    filters: &EntryFilters,
+    pricing_map: &PricingMap,
+    currency: &Currency,
+    writer: &mut W,
+) -> Result<()> {
+    let stats = aggregate_logs(
+        &parser,
+        &[GroupBy::None],
+        filters,
+        pricing_map,
+    )?;
+    let mut total: f64 = stats.iter().map(|stat| stat.cost_usd).sum();
+    if let Some(scale) = parser.sample_scale()? {
+        total *= scale;
+    }
+    writeln!(writer, "{:.2}", currency.convert(total))?;
+    Ok(())
+}
+
+/// Scale, format and write grouped stats, followed by the summary and anomaly sections
+fn write_report<W: Write>(
+    args: &cli::Args,
+    mut stats: Vec<UsageStats>,
+    parser: &LogParser,
+    pricing_map: &PricingMap,
+    currency: Currency,
+    writer: &mut W,
+) -> Result<()> {
     if stats.is_empty() {
-        println!("No usage data matches the specified filters.");
+        writeln!(writer, "No usage data matches the specified filters.")?;
         return Ok(());
     }
 
+    if let Some(scale) = parser.sample_scale()? {
+        for stat in &mut stats {
+            stat.scale(scale);
+        }
+        // On stderr so machine-readable formats still parse
+        if !args.quiet {
+            eprintln!("(estimated from sample: totals scaled by {:.2}x)", scale);
+        }
+    }
+
     // Format and display output
+    let format_options = FormatOptions {
+        colored_output: formatters::should_use_color(args.no_color),
+        detailed: args.detailed,
+        show_summary: args.summary,
+        sparkline: args.sparkline,
+        percentages: args.percentages,
+        aggregate_cache: args.aggregate_cache,
+        running_total: args.running_total,
+        compact_json: args.json_compact,
+        csv_delimiter: if args.tsv { Some('\t') } else { args.delimiter },
+        output_fields: args.output_fields.clone(),
+        per_request: args.group_by.contains(&GroupBy::Request),
+        currency,
+        pricing: Some(pricing_map.clone()),
+        explain: args.explain,
+    };
     match args.format {
         OutputFormat::Table => {
-            println!("{}", formatters::format_table(&stats, args.detailed, args.summary));
+            writeln!(writer, "{}", formatters::format_table(&stats, &format_options))?;
         }
         OutputFormat::Csv => {
-            println!("{}", formatters::format_csv(&stats, args.detailed));
+            writeln!(writer, "{}", formatters::format_csv(&stats, &format_options))?;
         }
         OutputFormat::Json => {
-            println!("{}", formatters::format_json(&stats)?);
+            writeln!(writer, "{}", formatters::format_json(&stats, &format_options)?)?;
         }
         OutputFormat::Markdown => {
-            println!("{}", formatters::format_markdown(&stats, args.detailed, args.summary));
+            writeln!(writer, "{}", formatters::format_markdown(&stats, &format_options))?;
+        }
+        OutputFormat::Ndjson => {
+            formatters::write_ndjson_streaming(stats.iter(), writer)?;
+        }
+        OutputFormat::AsciiChart => {
+            let chart = formatters::format_ascii_chart(
+                &stats,
+                formatters::ASCII_CHART_WIDTH,
+                &format_options.currency,
+            );
+            write!(writer, "{}", chart)?;
         }
     }
 
-    // Print summary if requested
-    if args.summary && args.format != OutputFormat::Table {
-        formatters::print_summary(&stats);
+    // Table, CSV and JSON include the summary themselves; NDJSON gets it as a last line so
+    // the output stays parseable
+    if args.summary {
+        match args.format {
+            OutputFormat::Table | OutputFormat::Csv | OutputFormat::Json => {}
+            OutputFormat::Ndjson => {
+                let summary = serde_json::to_string(&formatters::UsageSummary::of(&stats))?;
+                writeln!(writer, "{{\"summary\":{}}}", summary)?;
+            }
+            OutputFormat::Markdown | OutputFormat::AsciiChart => {
+                formatters::print_summary(writer, &stats, &format_options)?;
+            }
+        }
     }
 
-    Ok(())
-}
+    if !args.quiet {
+        if let Some(hint) = low_cache_hit_hint(&stats, pricing_map) {
+            eprintln!("{}", hint);
+        }
+    }
 
-fn calculate_stats(
-    entries: Vec<LogEntry>,
-    group_by: &GroupBy,
-    model_filter: Option<String>,
-    pricing_map: &HashMap<ModelName, models::ModelPricing>,
-) -> Result<Vec<UsageStats>> {
-    let mut grouped_data: HashMap<String, (ModelName, Vec<LogEntry>)> = HashMap::new();
+    if let Some(model) = &args.reprice_as {
+        if matches!(args.format, OutputFormat::Table | OutputFormat::Markdown) {
+            let actual: f64 = stats.iter().map(|stat| stat.cost_usd).sum();
+            let repriced: f64 = stats.iter().filter_map(|stat| stat.repriced_cost_usd).sum();
+            let model = ModelName::from_model_string(model);
+            let comparison = formatters::format_reprice_comparison(
+                &model,
+                actual,
+                repriced,
+                &format_options.currency,
+            );
+            writeln!(writer, "{}", comparison)?;
+        }
+    }
 
-    for entry in entries {
-        // Skip if no message or usage data
-        let message = match &entry.message {
-            Some(m) => m,
-            None => continue,
+    if let Some(days) = args.forecast {
+        let machine_readable = is_machine_readable(&args.format);
+        let colored = format_options.colored_output && !machine_readable;
+        let forecast = if matches!(args.group_by.as_slice(), [GroupBy::Day]) {
+            formatters::format_forecast(&forecast_usage(&stats, days), &format_options.currency, colored)
+        } else {
+            "\nForecast unavailable: it needs daily totals, use --group-by day\n".to_string()
         };
+        if !machine_readable {
+            write!(writer, "{}", forecast)?;
+        } else if !args.quiet {
+            // On stderr so the report still parses
+            eprint!("{}", forecast);
+        }
+    }
 
-        let _usage = match &message.usage {
-            Some(u) => u,
-            None => continue,
+    if args.anomalies {
+        let thresholds = AnomalyThresholds { rolling: args.sigma, period: args.anomaly_sigma };
+        let machine_readable = is_machine_readable(&args.format);
+        let colored = format_options.colored_output && !machine_readable;
+        let anomalies = if matches!(args.group_by.as_slice(), [GroupBy::Day]) {
+            let reports = anomaly_reports(&stats, thresholds);
+            formatters::format_anomalies(&reports, thresholds, &format_options.currency, colored)
+        } else {
+            "\nAnomalies unavailable: they need daily totals, use --group-by day\n".to_string()
         };
+        if !machine_readable {
+            write!(writer, "{}", anomalies)?;
+        } else if !args.quiet {
+            // On stderr so the report still parses
+            eprint!("{}", anomalies);
+        }
+    }
 
-        // Apply model filter if specified
-        if let Some(filter) = &model_filter {
-            let model_str = message.model.canonical_string();
-            if !model_str.contains(filter) && message.model.to_string() != *filter {
-                continue;
+    Ok(())
+}
+
+/// Whether `format` is parsed by other programs, so text sections can't be appended to it
+fn is_machine_readable(format: &OutputFormat) -> bool {
+    matches!(format, OutputFormat::Json | OutputFormat::Csv | OutputFormat::Ndjson)
+}
+
+/// Below this cache hit rate, a model's prompts are probably not being cached at all
+const LOW_CACHE_HIT_RATE: f64 = 0.05;
+
+/// A hint about prompt caching when a model that supports it has a cache hit rate under
+/// 5% across `stats`
+fn low_cache_hit_hint(stats: &[UsageStats], pricing_map: &PricingMap) -> Option<String> {
+    let mut by_model: HashMap<&ModelName, TokenUsage> = HashMap::new();
+    for stat in stats {
+        by_model.entry(&stat.model).or_default().add(&stat.usage);
+    }
+    let mut models: Vec<String> = by_model
+        .into_iter()
+        .filter(|(model, usage)| {
+            let caches = get_model_pricing(pricing_map, model)
+                .is_some_and(|pricing| pricing.cache_read_per_million > 0.0);
+            caches && usage.input_tokens > 0 && usage.cache_hit_rate() < LOW_CACHE_HIT_RATE
+        })
+        .map(|(model, _)| model.to_string())
+        .collect();
+    if models.is_empty() {
+        return None;
+    }
+    models.sort();
+    Some(format!(
+        "Hint: cache hit rate is under {:.0}% for {}. Keeping stable project context in \
+         CLAUDE.md lets it be served from the prompt cache at a fraction of the input price.",
+        LOW_CACHE_HIT_RATE * 100.0,
+        models.join(", ")
+    ))
+}
+
+/// Warnings for sessions whose input tokens exceed `threshold`, each showing how much
+/// of the model's context window that represents. `stats` should be grouped by session
+/// and model; rows without a known model are measured against `DEFAULT_CONTEXT_LIMIT`.
+pub fn warn_context_usage(
+    stats: &[UsageStats],
+    threshold: u64,
+    model_context_limits: &HashMap<ModelName, u64>,
+    colored: bool,
+) -> Vec<String> {
+    stats
+        .iter()
+        .filter(|stat| stat.usage.input_tokens > threshold)
+        .map(|stat| {
+            let limit = model_context_limits
+                .get(&stat.model)
+                .copied()
+                .unwrap_or(pricing::DEFAULT_CONTEXT_LIMIT);
+            let session = stat.session_id.as_deref().unwrap_or("unknown");
+            let warning = format!(
+                "⚠ Session {} ({}): {} input tokens ({:.0}% of {}K context)",
+                session.chars().take(8).collect::<String>(),
+                stat.model,
+                formatters::format_number(stat.usage.input_tokens),
+                stat.usage.input_tokens as f64 / limit as f64 * 100.0,
+                limit / 1000
+            );
+            formatters::paint(warning.yellow(), colored).to_string()
+        })
+        .collect()
+}
+
+/// A projected day of spend from `forecast_usage`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForecastPoint {
+    pub date: NaiveDate,
+    pub projected_cost: f64,
+    /// 95% interval around the projection, never below zero
+    pub confidence_interval: (f64, f64),
+}
+
+/// How many of the most recent days the forecast trend is fitted to
+pub const FORECAST_HISTORY_DAYS: usize = 14;
+
+/// z-score of a two-sided 95% interval
+const Z_95: f64 = 1.96;
+
+/// Project daily cost `forecast_days` past the last day in `daily_stats`, from an ordinary
+/// least squares line through the last 14 days. Days without a row count as zero spend.
+/// The interval is ±1.96 residual standard deviations. Empty with fewer than two days.
+pub fn forecast_usage(daily_stats: &[UsageStats], forecast_days: usize) -> Vec<ForecastPoint> {
+    let mut by_day: BTreeMap<NaiveDate, f64> = BTreeMap::new();
+    for stat in daily_stats {
+        *by_day.entry(stat.date.date_naive()).or_insert(0.0) += stat.cost_usd;
+    }
+    let (Some(&first), Some(&last)) = (by_day.keys().next(), by_day.keys().next_back()) else {
+        return Vec::new();
+    };
+    let history_start = first.max(last - chrono::Duration::days(FORECAST_HISTORY_DAYS as i64 - 1));
+    let costs: Vec<f64> = history_start
+        .iter_days()
+        .take_while(|day| *day <= last)
+        .map(|day| by_day.get(&day).copied().unwrap_or(0.0))
+        .collect();
+    let n = costs.len() as f64;
+    if costs.len() < 2 {
+        return Vec::new();
+    }
+
+    let mean_x = (n - 1.0) / 2.0;
+    let mean_y = costs.iter().sum::<f64>() / n;
+    let (mut sxy, mut sxx) = (0.0, 0.0);
+    for (x, y) in costs.iter().enumerate() {
+        let dx = x as f64 - mean_x;
+        sxy += dx * (y - mean_y);
+        sxx += dx * dx;
+    }
+    let slope = sxy / sxx;
+    let intercept = mean_y - slope * mean_x;
+
+    let residual_sum: f64 = costs
+        .iter()
+        .enumerate()
+        .map(|(x, y)| (y - (intercept + slope * x as f64)).powi(2))
+        .sum();
+    let residual_std_dev = if costs.len() > 2 {
+        (residual_sum / (n - 2.0)).sqrt()
+    } else {
+        0.0
+    };
+    let margin = Z_95 * residual_std_dev;
+
+    (1..=forecast_days)
+        .map(|ahead| {
+            let x = costs.len() - 1 + ahead;
+            let projected_cost = (intercept + slope * x as f64).max(0.0);
+            ForecastPoint {
+                date: last + chrono::Duration::days(ahead as i64),
+                projected_cost,
+                confidence_interval: ((projected_cost - margin).max(0.0), projected_cost + margin),
             }
+        })
+        .collect()
+}
+
+/// The costs a period is judged against, and how far above them it was
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnomalyBaseline {
+    pub mean: f64,
+    pub std_dev: f64,
+    /// How many standard deviations above the mean the cost was
+    pub sigmas: f64,
+}
+
+impl AnomalyBaseline {
+    fn new(costs: &[f64], cost: f64) -> Self {
+        let mean = costs.iter().sum::<f64>() / costs.len() as f64;
+        let variance = costs.iter().map(|c| (c - mean).powi(2)).sum::<f64>() / costs.len() as f64;
+        let std_dev = variance.sqrt();
+
+        let sigmas = if std_dev > 0.0 {
+            (cost - mean) / std_dev
+        } else if cost > mean {
+            // Perfectly flat history: any increase is infinitely unusual
+            f64::INFINITY
+        } else {
+            0.0
+        };
+
+        Self { mean, std_dev, sigmas }
+    }
+}
+
+/// Standard deviations above each baseline's mean that flag a period (`--sigma` and
+/// `--anomaly-sigma`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnomalyThresholds {
+    /// Against the preceding 7 days
+    pub rolling: f64,
+    /// Against every period
+    pub period: f64,
+}
+
+/// A period whose cost stands out from the preceding week or from the whole period
+#[derive(Debug, Clone, PartialEq)]
+pub struct Anomaly {
+    pub date: DateTime<Utc>,
+    pub cost_usd: f64,
+    /// Against the preceding 7 days, when there were enough earlier periods
+    pub rolling: Option<AnomalyBaseline>,
+    /// Against every period, when there are enough of them
+    pub period: Option<AnomalyBaseline>,
+}
+
+/// An anomaly along with the stats row it was found in
+#[derive(Debug, Clone)]
+pub struct AnomalyReport {
+    pub stats: UsageStats,
+    pub anomaly: Anomaly,
+}
+
+/// Minimum number of earlier periods needed before a period can be judged
+const MIN_ANOMALY_HISTORY: usize = 2;
+
+/// Fewer periods than this give too noisy a whole-period mean to judge outliers
+const MIN_ANOMALY_PERIODS: usize = 3;
+
+/// Flag periods whose cost is more than `thresholds.rolling` standard deviations above the
+/// mean of the preceding 7 days (or all earlier data if there is less than that), or more
+/// than `thresholds.period` above the mean of all of `daily`. The whole-period baseline also
+/// catches spikes early on, before there's any history to compare against.
+///
+/// `daily` is expected to be sorted by date.
+pub fn detect_anomalies(daily: &[(DateTime<Utc>, f64)], thresholds: AnomalyThresholds) -> Vec<Anomaly> {
+    (0..daily.len())
+        .filter_map(|i| anomaly_at(daily, i, thresholds))
+        .collect()
+}
+
+/// `detect_anomalies` over the cost of each of `stats`, as returned by `calculate_stats`
+pub fn anomaly_reports(stats: &[UsageStats], thresholds: AnomalyThresholds) -> Vec<AnomalyReport> {
+    let daily: Vec<(DateTime<Utc>, f64)> = stats.iter().map(|s| (s.date, s.cost_usd)).collect();
+    (0..daily.len())
+        .filter_map(|i| {
+            let anomaly = anomaly_at(&daily, i, thresholds)?;
+            Some(AnomalyReport { stats: stats[i].clone(), anomaly })
+        })
+        .collect()
+}
+
+/// The `i`th period of `daily`, if it crosses either threshold
+fn anomaly_at(daily: &[(DateTime<Utc>, f64)], i: usize, thresholds: AnomalyThresholds) -> Option<Anomaly> {
+    let (date, cost_usd) = daily[i];
+    let window_start = date - chrono::Duration::days(7);
+    let history: Vec<f64> = daily[..i]
+        .iter()
+        .filter(|(d, _)| *d >= window_start)
+        .map(|(_, cost)| *cost)
+        .collect();
+    let all_costs: Vec<f64> = daily.iter().map(|(_, cost)| *cost).collect();
+
+    let rolling = (history.len() >= MIN_ANOMALY_HISTORY)
+        .then(|| AnomalyBaseline::new(&history, cost_usd));
+    let period = (all_costs.len() >= MIN_ANOMALY_PERIODS)
+        .then(|| AnomalyBaseline::new(&all_costs, cost_usd));
+
+    let flagged = rolling.as_ref().is_some_and(|b| b.sigmas >= thresholds.rolling)
+        || period.as_ref().is_some_and(|b| b.sigmas >= thresholds.period);
+    flagged.then_some(Anomaly { date, cost_usd, rolling, period })
+}
+
+/// Width of the bins used for token velocity
+const VELOCITY_WINDOW_MINUTES: i64 = 15;
+
+/// Group entries into 15-minute windows keyed by window start, in chronological order
+fn bin_entries_by_window(entries: &[LogEntry]) -> BTreeMap<DateTime<Utc>, Vec<&LogEntry>> {
+    let window_secs = VELOCITY_WINDOW_MINUTES * 60;
+    let mut bins: BTreeMap<DateTime<Utc>, Vec<&LogEntry>> = BTreeMap::new();
+
+    for entry in entries {
+        let ts = entry.timestamp.timestamp();
+        let window_start = ts - ts.rem_euclid(window_secs);
+        if let Some(start) = Utc.timestamp_opt(window_start, 0).single() {
+            bins.entry(start).or_default().push(entry);
         }
+    }
 
-        // Skip synthetic models
-        if message.model.is_synthetic() {
-            continue;
+    bins
+}
+
+/// Tokens in a window's entries
+fn window_tokens(bin: &[&LogEntry]) -> u64 {
+    bin.iter()
+        .filter_map(|e| e.message.as_ref().and_then(|m| m.usage.as_ref()))
+        .map(|u| u.total_tokens())
+        .sum()
+}
+
+/// Tokens per minute for each 15-minute window that has any usage, in chronological order
+pub fn calculate_token_velocity(entries: &[LogEntry]) -> Vec<(DateTime<Utc>, f64)> {
+    bin_entries_by_window(entries)
+        .into_iter()
+        .map(|(start, bin)| (start, window_tokens(&bin) as f64 / VELOCITY_WINDOW_MINUTES as f64))
+        .collect()
+}
+
+/// Velocity rows of (window start, tokens/min, projected cost per hour)
+fn velocity_rows(
+    entries: &[LogEntry],
+    pricing_map: &HashMap<ModelName, models::ModelPricing>,
+) -> Vec<(DateTime<Utc>, f64, f64)> {
+    bin_entries_by_window(entries)
+        .into_iter()
+        .map(|(start, bin)| {
+            let cost: f64 = bin
+                .iter()
+                .filter_map(|e| e.message.as_ref())
+                .filter_map(|m| {
+                    let usage = m.usage.as_ref()?;
+                    Some(get_model_pricing(pricing_map, &m.model)?.calculate_cost_with_tier(usage))
+                })
+                .sum();
+            let tokens_per_min = window_tokens(&bin) as f64 / VELOCITY_WINDOW_MINUTES as f64;
+            (start, tokens_per_min, cost * (60 / VELOCITY_WINDOW_MINUTES) as f64)
+        })
+        .collect()
+}
+
+/// Write every recognized model with its canonical name, family and pricing
+pub fn list_models<W: Write>(format: OutputFormat, writer: &mut W) -> Result<()> {
+    let pricing_map = get_default_pricing();
+
+    match format {
+        OutputFormat::Table | OutputFormat::AsciiChart => writeln!(writer, "{}", formatters::format_models_table(&pricing_map, formatters::should_use_color(false)))?,
+        OutputFormat::Csv => write!(writer, "{}", formatters::format_models_csv(&pricing_map))?,
+        OutputFormat::Json => writeln!(writer, "{}", formatters::format_models_json(&pricing_map)?)?,
+        OutputFormat::Markdown => write!(writer, "{}", formatters::format_models_markdown(&pricing_map))?,
+        OutputFormat::Ndjson => write!(writer, "{}", formatters::format_models_ndjson(&pricing_map)?)?,
+    }
+
+    Ok(())
+}
+
+/// Loose model match used by `--model` and `--exclude-model`: a substring of the
+/// canonical name (e.g. "opus") or the exact display name
+pub(crate) fn model_matches(model: &ModelName, filter: &str) -> bool {
+    model.canonical_string().contains(filter) || model.to_string() == filter
+}
+
+/// Expand the requested groupings into single dimensions: `ModelDay` is day + model and
+/// `None` contributes nothing
+fn group_dimensions(group_by: &[GroupBy]) -> Vec<GroupBy> {
+    let mut dimensions = Vec::new();
+    for dimension in group_by {
+        match dimension {
+            GroupBy::ModelDay => dimensions.extend([GroupBy::Day, GroupBy::Model]),
+            GroupBy::None => {}
+            other => dimensions.push(other.clone()),
+        }
+    }
+    dimensions
+}
+
+/// Whether rows belong to a single session and should say which
+fn keeps_session(dimensions: &[GroupBy]) -> bool {
+    dimensions
+        .iter()
+        .any(|d| matches!(d, GroupBy::Session | GroupBy::Request))
+}
+
+/// Key for a single grouping dimension
+fn group_key(dimension: &GroupBy, entry: &LogEntry, model: &ModelName) -> String {
+    match dimension {
+        GroupBy::Day => entry.timestamp.date_naive().to_string(),
+        GroupBy::Week => {
+            let week = entry.timestamp.iso_week();
+            format!("{}-W{:02}", week.year(), week.week())
         }
+        GroupBy::Month => format!("{}-{:02}", entry.timestamp.year(), entry.timestamp.month()),
+        GroupBy::Quarter => format!(
+            "{}-Q{}",
+            entry.timestamp.year(),
+            (entry.timestamp.month() - 1) / 3 + 1
+        ),
+        GroupBy::Year => entry.timestamp.year().to_string(),
+        GroupBy::Model => model.to_string(),
+        GroupBy::Session => entry.session_id.clone(),
+        GroupBy::Request => entry.uuid.to_string(),
+        GroupBy::ModelDay | GroupBy::None => unreachable!("expanded by group_dimensions"),
+    }
+}
 
-        // Generate grouping key
-        let (key, model) = match group_by {
-            GroupBy::Day => (
-                entry.timestamp.date_naive().to_string(),
-                ModelName::Unknown("all".to_string()),
-            ),
-            GroupBy::Week => {
-                let week = entry.timestamp.iso_week();
-                (
-                    format!("{}-W{:02}", week.year(), week.week()),
-                    ModelName::Unknown("all".to_string()),
-                )
-            }
-            GroupBy::Month => (
-                format!("{}-{:02}", entry.timestamp.year(), entry.timestamp.month()),
-                ModelName::Unknown("all".to_string()),
-            ),
-            GroupBy::Model => ("all-time".to_string(), message.model.clone()),
-            GroupBy::ModelDay => (
-                format!("{}-{}", entry.timestamp.date_naive(), message.model),
-                message.model.clone(),
-            ),
-            GroupBy::None => ("all-time".to_string(), ModelName::Unknown("all".to_string())),
+/// Which sidechain (tool-use sub-conversation) entries to count
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SidechainMode {
+    /// Leave sidechains out
+    #[default]
+    Exclude,
+    /// Count sidechains alongside the main conversation
+    Include,
+    /// Count nothing but sidechains
+    Only,
+}
+
+impl SidechainMode {
+    fn keeps(self, entry: &LogEntry) -> bool {
+        let is_sidechain = entry.is_sidechain.unwrap_or(false);
+        match self {
+            SidechainMode::Exclude => !is_sidechain,
+            SidechainMode::Include => true,
+            SidechainMode::Only => is_sidechain,
+        }
+    }
+}
+
+/// Filters applied to each entry before it's grouped
+#[derive(Debug, Clone, Default)]
+pub struct EntryFilters {
+    /// Only count models matching this name (substring, e.g. "opus")
+    pub model: Option<String>,
+    /// Leave out models matching any of these, even when `model` matches
+    pub exclude_models: Vec<String>,
+    /// Count synthetic messages as a zero-cost model instead of skipping them
+    pub include_synthetic: bool,
+    pub sidechains: SidechainMode,
+    /// Group every `Unknown` model under one `unknown` model; each entry is still priced
+    /// by its own model
+    pub aggregate_unknown_models: bool,
+}
+
+/// Model every `Unknown` model is grouped under with `--aggregate-unknown-models`
+const AGGREGATED_UNKNOWN_MODEL: &str = "unknown";
+
+/// Whether a row's model stands for several models, so its entries must be priced one by one
+pub(crate) fn is_aggregate_model(model: &ModelName) -> bool {
+    matches!(model, ModelName::Unknown(s) if s == "all" || s == AGGREGATED_UNKNOWN_MODEL)
+}
+
+/// Whether a row is the `--aggregate-unknown-models` row
+fn is_aggregated_unknown(model: &ModelName) -> bool {
+    matches!(model, ModelName::Unknown(s) if s == AGGREGATED_UNKNOWN_MODEL)
+}
+
+/// Number of distinct `Unknown` models among the entries `filters` keeps, i.e. how many
+/// variants `--aggregate-unknown-models` folds into its row
+fn aggregated_unknown_models(entries: &[LogEntry], filters: &EntryFilters) -> usize {
+    entries
+        .iter()
+        .filter(|entry| entry_group(entry, &[], filters).is_some())
+        .filter_map(|entry| entry.message.as_ref())
+        .filter(|message| matches!(message.model, ModelName::Unknown(_)))
+        .map(|message| &message.model)
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+impl EntryFilters {
+    pub fn from_args(args: &cli::Args) -> Self {
+        let sidechains = if args.only_sidechains {
+            SidechainMode::Only
+        } else if args.include_sidechains {
+            SidechainMode::Include
+        } else {
+            SidechainMode::Exclude
+        };
+        Self {
+            model: args.model.clone(),
+            exclude_models: args.exclude_model.clone(),
+            include_synthetic: args.include_synthetic,
+            sidechains,
+            aggregate_unknown_models: args.aggregate_unknown_models,
+        }
+    }
+}
+
+/// Group key and row model for an entry, or `None` when the entry has no usage or is
+/// filtered out by `filters`
+fn entry_group(
+    entry: &LogEntry,
+    dimensions: &[GroupBy],
+    filters: &EntryFilters,
+) -> Option<(String, ModelName)> {
+    // Skip if no message or usage data
+    let message = entry.message.as_ref()?;
+    message.usage.as_ref()?;
+
+    if !filters.sidechains.keeps(entry) {
+        return None;
+    }
+
+    // Apply model filter if specified
+    if filters
+        .model
+        .as_deref()
+        .is_some_and(|filter| !model_matches(&message.model, filter))
+    {
+        return None;
+    }
+
+    // Exclusions run after the inclusion filter, so they win when both match
+    if filters
+        .exclude_models
+        .iter()
+        .any(|filter| model_matches(&message.model, filter))
+    {
+        return None;
+    }
+
+    // Skip synthetic models unless asked to count them
+    if message.model.is_synthetic() && !filters.include_synthetic {
+        return None;
+    }
+
+    let model = match &message.model {
+        ModelName::Unknown(_) if filters.aggregate_unknown_models => {
+            ModelName::Unknown(AGGREGATED_UNKNOWN_MODEL.to_string())
+        }
+        model => model.clone(),
+    };
+
+    // Generate grouping key from each dimension; rows keep their model only when
+    // grouping by model or request
+    let key = if dimensions.is_empty() {
+        "all-time".to_string()
+    } else {
+        dimensions
+            .iter()
+            .map(|dimension| group_key(dimension, entry, &model))
+            .collect::<Vec<_>>()
+            .join("|")
+    };
+    let model = if dimensions
+        .iter()
+        .any(|d| matches!(d, GroupBy::Model | GroupBy::Request))
+    {
+        model
+    } else {
+        ModelName::Unknown("all".to_string())
+    };
+
+    Some((key, model))
+}
+
+/// Extra columns `calculate_stats` fills in, one per report flag
+#[derive(Debug, Clone, Copy, Default)]
+struct StatsOptions<'a> {
+    /// Also price every row as this model (`--reprice-as`)
+    reprice_as: Option<&'a ModelPricing>,
+    /// Peak and average daily cost under model grouping (`--with-daily-stats`)
+    with_daily_stats: bool,
+    /// Split cache writes into their 5-minute and 1-hour parts (`--cache-detail`)
+    cache_detail: bool,
+    /// Each model's part of every row under day grouping (`--breakdown`)
+    breakdown: bool,
+}
+
+fn calculate_stats(
+    entries: Vec<LogEntry>,
+    group_by: &[GroupBy],
+    filters: &EntryFilters,
+    pricing_map: &HashMap<ModelName, models::ModelPricing>,
+    options: &StatsOptions,
+) -> Result<Vec<UsageStats>> {
+    let StatsOptions { reprice_as, with_daily_stats, cache_detail, breakdown } = *options;
+    let mut grouped_data: HashMap<String, (ModelName, Vec<LogEntry>)> = HashMap::new();
+    let dimensions = group_dimensions(group_by);
+    let by_session = keeps_session(&dimensions);
+
+    for entry in entries {
+        let Some((key, model)) = entry_group(&entry, &dimensions, filters) else {
+            continue;
         };
 
         grouped_data
-            .entry(key.clone())
+            .entry(key)
             .or_insert((model, Vec::new()))
             .1
             .push(entry);
@@ -148,54 +1142,866 @@ fn calculate_stats(
         let mut total_usage = TokenUsage::default();
         let mut request_count = 0;
         let mut total_cost = 0.0;
+        let mut repriced_cost = 0.0;
+        let mut cache_write_costs = (0.0, 0.0);
+        let mut model_totals: HashMap<ModelName, ModelBreakdown> = HashMap::new();
         let date = entries[0].timestamp;
 
-        // When aggregating across all models, calculate cost per entry
-        if matches!(&model, ModelName::Unknown(s) if s == "all") {
+        // When aggregating across models, calculate cost per entry
+        if is_aggregate_model(&model) {
             for entry in &entries {
                 if let Some(message) = &entry.message {
                     if let Some(usage) = &message.usage {
                         total_usage.add(usage);
                         request_count += 1;
+                        if let Some(target) = reprice_as {
+                            repriced_cost += target.calculate_cost_with_tier(usage);
+                        }
                         
                         // Calculate cost for this specific model
+                        let mut cost = 0.0;
                         if let Some(pricing) = get_model_pricing(pricing_map, &message.model) {
-                            total_cost += pricing.calculate_cost(usage);
-                        } else {
+                            cost = pricing.calculate_cost_with_tier(usage);
+                            add_cache_write_costs(&mut cache_write_costs, pricing, usage);
+                        } else if !message.model.is_synthetic() {
                             eprintln!("Warning: No pricing found for model: {}", message.model);
                         }
+                        total_cost += cost;
+                        if breakdown {
+                            let totals = model_totals
+                                .entry(message.model.clone())
+                                .or_insert_with(|| ModelBreakdown::new(message.model.clone()));
+                            totals.usage.add(usage);
+                            totals.cost_usd += cost;
+                        }
                     }
                 }
             }
         } else {
-            // For model-specific grouping, use the single model pricing
+            // For model-specific grouping, use the single model pricing. Cost is still
+            // summed per entry since each request's service tier can differ.
+            let pricing = get_model_pricing(pricing_map, &model);
+            if pricing.is_none() && !model.is_synthetic() {
+                eprintln!("Warning: No pricing found for model: {}", model);
+            }
             for entry in &entries {
                 if let Some(message) = &entry.message {
                     if let Some(usage) = &message.usage {
                         total_usage.add(usage);
                         request_count += 1;
+                        if let Some(target) = reprice_as {
+                            repriced_cost += target.calculate_cost_with_tier(usage);
+                        }
+                        if let Some(pricing) = pricing {
+                            total_cost += pricing.calculate_cost_with_tier(usage);
+                            add_cache_write_costs(&mut cache_write_costs, pricing, usage);
+                        }
                     }
                 }
             }
-            
-            if let Some(pricing) = get_model_pricing(pricing_map, &model) {
-                total_cost = pricing.calculate_cost(&total_usage);
-            } else {
-                eprintln!("Warning: No pricing found for model: {}", model);
-            }
         }
 
+        let (peak_day_cost, avg_daily_cost, active_days) =
+            if with_daily_stats && matches!(group_by, [GroupBy::Model]) {
+                let (peak, avg, days) = daily_cost_stats(&entries, &model, pricing_map);
+                (Some(peak), Some(avg), Some(days))
+            } else {
+                (None, None, None)
+            };
+
+        // Each day's models, most expensive first, to list under the day rows
+        let model_breakdown = (breakdown && matches!(group_by, [GroupBy::Day])).then(|| {
+            let mut models: Vec<ModelBreakdown> = model_totals.into_values().collect();
+            models.sort_by(|a, b| b.cost_usd.total_cmp(&a.cost_usd));
+            models
+        });
+
         stats.push(UsageStats {
             model: model.clone(),
             date,
+            cache_hit_rate: total_usage.cache_hit_rate(),
             usage: total_usage,
             request_count,
             cost_usd: total_cost,
+            repriced_cost_usd: reprice_as.map(|_| repriced_cost),
+            peak_day_cost,
+            avg_daily_cost,
+            active_days,
+            session_id: by_session.then(|| entries[0].session_id.clone()),
+            cache_write_5m_cost_usd: cache_detail.then_some(cache_write_costs.0),
+            cache_write_1h_cost_usd: cache_detail.then_some(cache_write_costs.1),
+            model_breakdown,
         });
     }
 
     // Sort by date
-    stats.sort_by(|a, b| a.date.cmp(&b.date));
+    stats.sort_by_key(|s| s.date);
 
     Ok(stats)
-}
\ No newline at end of file
+}
+
+/// Add `usage`'s 5-minute and 1-hour cache write costs, after any batch discount, to `totals`
+fn add_cache_write_costs(totals: &mut (f64, f64), pricing: &ModelPricing, usage: &TokenUsage) {
+    let (five_minute, one_hour) = pricing.cache_write_costs(usage);
+    let multiplier = pricing.tier_multiplier(usage);
+    totals.0 += five_minute * multiplier;
+    totals.1 += one_hour * multiplier;
+}
+
+/// Peak daily cost, average daily cost and number of active days for one model's entries
+fn daily_cost_stats(
+    entries: &[LogEntry],
+    model: &ModelName,
+    pricing_map: &HashMap<ModelName, models::ModelPricing>,
+) -> (f64, f64, u32) {
+    let pricing = match get_model_pricing(pricing_map, model) {
+        Some(pricing) => pricing,
+        None => return (0.0, 0.0, 0),
+    };
+
+    let mut daily_costs: HashMap<chrono::NaiveDate, f64> = HashMap::new();
+    for entry in entries {
+        if let Some(usage) = entry.message.as_ref().and_then(|m| m.usage.as_ref()) {
+            *daily_costs.entry(entry.timestamp.date_naive()).or_insert(0.0) +=
+                pricing.calculate_cost_with_tier(usage);
+        }
+    }
+
+    if daily_costs.is_empty() {
+        return (0.0, 0.0, 0);
+    }
+
+    let active_days = daily_costs.len() as u32;
+    let total: f64 = daily_costs.values().sum();
+    let peak = daily_costs.values().copied().fold(0.0, f64::max);
+
+    (peak, total / active_days as f64, active_days)
+}
+
+/// Running totals for one group in `aggregate_logs`
+struct GroupTotals {
+    model: ModelName,
+    date: DateTime<Utc>,
+    usage: TokenUsage,
+    request_count: u64,
+    cost_usd: f64,
+    session_id: Option<String>,
+}
+
+/// What one request added to its group, so a later copy of the request can replace it
+struct Contribution {
+    timestamp: DateTime<Utc>,
+    key: String,
+    usage: TokenUsage,
+    cost_usd: f64,
+}
+
+/// Streaming counterpart of `parse_logs` followed by `calculate_stats`: entries come from
+/// `parse_logs_streaming` one file at a time and are folded into their group's totals, so
+/// memory grows with the number of groups and unique request ids instead of with every
+/// parsed entry.
+///
+/// Deduplication still keeps the latest copy of each request; when a copy is superseded its
+/// tokens and cost are taken back out of its group. A group's date is the earliest entry it
+/// has seen, even if that entry was later superseded. Per-model daily stats need every entry
+/// and aren't computed here.
+pub fn aggregate_logs(
+    parser: &LogParser,
+    group_by: &[GroupBy],
+    filters: &EntryFilters,
+    pricing_map: &HashMap<ModelName, models::ModelPricing>,
+) -> Result<Vec<UsageStats>> {
+    let dimensions = group_dimensions(group_by);
+    let by_session = keeps_session(&dimensions);
+    let mut groups: HashMap<String, GroupTotals> = HashMap::new();
+    let mut seen: HashMap<String, Contribution> = HashMap::new();
+    let mut unpriced: HashSet<ModelName> = HashSet::new();
+
+    for entry in parser.parse_logs_streaming()? {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                eprintln!("{:#}", e);
+                continue;
+            }
+        };
+        let Some((key, model)) = entry_group(&entry, &dimensions, filters) else {
+            continue;
+        };
+        let Some(message) = &entry.message else {
+            continue;
+        };
+        let Some(usage) = &message.usage else {
+            continue;
+        };
+
+        let cost_usd = match get_model_pricing(pricing_map, &message.model) {
+            Some(pricing) => pricing.calculate_cost_with_tier(usage),
+            None if message.model.is_synthetic() => 0.0,
+            None => {
+                if unpriced.insert(message.model.clone()) {
+                    eprintln!("Warning: No pricing found for model: {}", message.model);
+                }
+                0.0
+            }
+        };
+
+        // Keep only the latest copy of each request, as deduplicate_entries does
+        let request = parser::dedup_key(parser.dedup_key, &entry);
+        if seen
+            .get(&request)
+            .is_some_and(|previous| entry.timestamp <= previous.timestamp)
+        {
+            continue;
+        }
+        let contribution = Contribution {
+            timestamp: entry.timestamp,
+            key: key.clone(),
+            usage: usage.clone(),
+            cost_usd,
+        };
+        if let Some(previous) = seen.insert(request, contribution) {
+            if let Some(group) = groups.get_mut(&previous.key) {
+                group.usage.sub(&previous.usage);
+                group.request_count -= 1;
+                group.cost_usd -= previous.cost_usd;
+            }
+        }
+
+        let group = groups.entry(key).or_insert_with(|| GroupTotals {
+            model,
+            date: entry.timestamp,
+            usage: TokenUsage::default(),
+            request_count: 0,
+            cost_usd: 0.0,
+            session_id: by_session.then(|| entry.session_id.clone()),
+        });
+        group.date = group.date.min(entry.timestamp);
+        group.usage.add(usage);
+        group.request_count += 1;
+        group.cost_usd += cost_usd;
+    }
+
+    let mut stats: Vec<UsageStats> = groups
+        .into_values()
+        .filter(|group| group.request_count > 0)
+        .map(|group| UsageStats {
+            model: group.model,
+            date: group.date,
+            cache_hit_rate: group.usage.cache_hit_rate(),
+            usage: group.usage,
+            request_count: group.request_count,
+            cost_usd: group.cost_usd,
+            repriced_cost_usd: None,
+            peak_day_cost: None,
+            avg_daily_cost: None,
+            active_days: None,
+            session_id: group.session_id,
+            cache_write_5m_cost_usd: None,
+            cache_write_1h_cost_usd: None,
+            model_breakdown: None,
+        })
+        .collect();
+
+    // Sort by date
+    stats.sort_by_key(|stat| stat.date);
+
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use models::Message;
+
+    fn entry(timestamp: &str, model: ModelName, input_tokens: u64) -> LogEntry {
+        LogEntry {
+            entry_type: "assistant".to_string(),
+            uuid: timestamp.to_string(),
+            parent_uuid: None,
+            timestamp: timestamp.parse().unwrap(),
+            session_id: "test-session".to_string(),
+            request_id: Some(timestamp.to_string()),
+            version: None,
+            message: Some(Message {
+                id: "msg".to_string(),
+                role: "assistant".to_string(),
+                model,
+                usage: Some(TokenUsage {
+                    input_tokens,
+                    ..Default::default()
+                }),
+                tool_result_content: false,
+            }),
+            is_sidechain: None,
+        }
+    }
+
+    #[test]
+    fn test_most_recent() {
+        let entries: Vec<LogEntry> = (0..10)
+            .map(|day| {
+                entry(
+                    &format!("2024-01-{:02}T10:00:00Z", day + 1),
+                    ModelName::Claude4Sonnet,
+                    100,
+                )
+            })
+            .collect();
+
+        let recent = most_recent(entries, 5);
+        assert_eq!(recent.len(), 5);
+        assert_eq!(recent[0].timestamp.to_rfc3339(), "2024-01-10T10:00:00+00:00");
+        assert_eq!(recent[4].timestamp.to_rfc3339(), "2024-01-06T10:00:00+00:00");
+    }
+
+    #[test]
+    fn test_future_timestamp_warning() {
+        let now = Utc.with_ymd_and_hms(2024, 1, 10, 12, 0, 0).unwrap();
+        let entries = vec![
+            entry("2024-01-10T11:00:00Z", ModelName::Claude4Sonnet, 100),
+            // Within the tolerance
+            entry("2024-01-10T12:00:30Z", ModelName::Claude4Sonnet, 100),
+            entry("2024-01-10T14:00:00Z", ModelName::Claude4Sonnet, 100),
+            entry("2024-01-10T13:00:00Z", ModelName::Claude4Sonnet, 100),
+        ];
+
+        let warning = future_timestamp_warning(&entries, now).unwrap();
+        assert!(warning.starts_with("Warning: 2 entries are timestamped in the future"));
+        assert!(warning.contains("latest 2024-01-10T14:00:00+00:00"));
+        assert_eq!(future_timestamp_warning(&entries[..2], now), None);
+    }
+
+    #[test]
+    fn test_dry_run_summary() {
+        let claude_dir = tempfile::TempDir::new().unwrap();
+        let dir = claude_dir.path().to_string_lossy().to_string();
+        let files = vec![claude_dir.path().join("projects/p/session.jsonl")];
+        let entries = vec![
+            entry("2024-01-10T11:00:00Z", ModelName::Claude4Sonnet, 100),
+            entry("2024-01-08T09:30:00Z", ModelName::Claude4Sonnet, 100),
+        ];
+
+        let summary = dry_run_summary(&files, &entries, &[dir]);
+        assert!(summary.contains("Files that would be analyzed: 1\n"));
+        assert!(summary.contains("Date range: 2024-01-08 09:30 UTC to 2024-01-10 11:00 UTC"));
+        assert!(summary.contains("Entries found: 2\n"));
+        assert!(summary.contains("dashboard-file-tracker.json doesn't exist yet"));
+    }
+
+    #[test]
+    fn test_dry_run_counts_filtered_entries() {
+        let claude_dir = tempfile::TempDir::new().unwrap();
+        let project = claude_dir.path().join("projects/p");
+        std::fs::create_dir_all(&project).unwrap();
+        let line = |id: &str, timestamp: &str, model: &str| {
+            format!(
+                r#"{{"type":"assistant","uuid":"{id}","timestamp":"{timestamp}","sessionId":"s","requestId":"{id}","message":{{"id":"msg-{id}","role":"assistant","model":"{model}","usage":{{"input_tokens":10,"output_tokens":5}}}}}}"#
+            )
+        };
+        let lines = [
+            line("h1", "2024-12-01T10:00:00Z", "claude-3-5-haiku-20241022"),
+            line("h2", "2024-12-02T10:00:00Z", "claude-3-5-haiku-20241022"),
+            line("o1", "2024-12-03T10:00:00Z", "claude-opus-4-20250514"),
+        ];
+        std::fs::write(project.join("session.jsonl"), lines.join("\n")).unwrap();
+        let dir = claude_dir.path().to_string_lossy().to_string();
+        let parser = LogParser::new(dir.clone()).quiet();
+        let filters = EntryFilters {
+            model: Some("haiku".to_string()),
+            ..Default::default()
+        };
+
+        let summary = dry_run(&parser, &filters, &[dir]).unwrap();
+        assert!(summary.contains("Files that would be analyzed: 1\n"), "{}", summary);
+        assert!(summary.contains("Entries found: 2\n"), "{}", summary);
+        assert!(summary.contains("to 2024-12-02 10:00 UTC"), "{}", summary);
+    }
+
+    #[test]
+    fn test_model_daily_stats() {
+        let pricing = get_default_pricing();
+        // Opus input is $15/M tokens: day one costs $1.50, day two $0.15
+        let entries = vec![
+            entry("2024-12-01T10:00:00Z", ModelName::Claude4Opus, 50_000),
+            entry("2024-12-01T11:00:00Z", ModelName::Claude4Opus, 50_000),
+            entry("2024-12-02T10:00:00Z", ModelName::Claude4Opus, 10_000),
+        ];
+
+        let filters = EntryFilters::default();
+        let stats = calculate_stats(
+            entries,
+            &[GroupBy::Model],
+            &filters,
+            &pricing,
+            &StatsOptions { with_daily_stats: true, ..Default::default() },
+        )
+        .unwrap();
+        assert_eq!(stats.len(), 1);
+        let stat = &stats[0];
+        assert_eq!(stat.active_days, Some(2));
+        assert!((stat.peak_day_cost.unwrap() - 1.5).abs() < 1e-9);
+        assert!((stat.avg_daily_cost.unwrap() - 0.825).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_model_breakdown_sums_to_day_totals() {
+        let pricing = get_default_pricing();
+        let entries = vec![
+            entry("2024-12-01T10:00:00Z", ModelName::Claude4Opus, 50_000),
+            entry("2024-12-01T11:00:00Z", ModelName::Claude4Sonnet, 20_000),
+            entry("2024-12-01T12:00:00Z", ModelName::Claude4Sonnet, 30_000),
+            entry("2024-12-02T10:00:00Z", ModelName::Claude4Sonnet, 10_000),
+        ];
+        let filters = EntryFilters::default();
+        let breakdown = |group_by: &[GroupBy]| {
+            let options = StatsOptions { breakdown: true, ..Default::default() };
+            calculate_stats(entries.clone(), group_by, &filters, &pricing, &options).unwrap()
+        };
+
+        let days = breakdown(&[GroupBy::Day]);
+        assert_eq!(days.len(), 2);
+        for day in &days {
+            let models = day.model_breakdown.as_ref().unwrap();
+            let cost: f64 = models.iter().map(|row| row.cost_usd).sum();
+            let tokens: u64 = models.iter().map(|row| row.usage.total_tokens()).sum();
+            assert!((cost - day.cost_usd).abs() < 1e-9);
+            assert_eq!(tokens, day.usage.total_tokens());
+        }
+
+        // Opus at $15/M costs more than Sonnet's 50k tokens at $3/M, so it's listed first
+        let first_day = days[0].model_breakdown.as_ref().unwrap();
+        assert_eq!(first_day[0].model, ModelName::Claude4Opus);
+        assert_eq!(first_day[1].usage.input_tokens, 50_000);
+        assert_eq!(days[1].model_breakdown.as_ref().unwrap().len(), 1);
+
+        // Only day rows are broken down
+        assert!(breakdown(&[GroupBy::Week]).iter().all(|week| week.model_breakdown.is_none()));
+    }
+
+    fn daily_stat(day: u32, cost_usd: f64) -> UsageStats {
+        UsageStats {
+            model: ModelName::Unknown("all".to_string()),
+            date: Utc.with_ymd_and_hms(2024, 12, day, 0, 0, 0).unwrap(),
+            usage: TokenUsage::default(),
+            request_count: 1,
+            cost_usd,
+            cache_hit_rate: 0.0,
+            repriced_cost_usd: None,
+            peak_day_cost: None,
+            avg_daily_cost: None,
+            active_days: None,
+            session_id: None,
+            cache_write_5m_cost_usd: None,
+            cache_write_1h_cost_usd: None,
+            model_breakdown: None,
+        }
+    }
+
+    #[test]
+    fn test_forecast_extrapolates_linear_trend() {
+        // $1, $2, ... $10 on consecutive days
+        let stats: Vec<_> = (1..=10).map(|day| daily_stat(day, day as f64)).collect();
+
+        let forecast = forecast_usage(&stats, 3);
+        assert_eq!(forecast.len(), 3);
+        assert_eq!(forecast[0].date, NaiveDate::from_ymd_opt(2024, 12, 11).unwrap());
+        for (point, expected) in forecast.iter().zip([11.0, 12.0, 13.0]) {
+            assert!((point.projected_cost - expected).abs() < 1e-9);
+            let (low, high) = point.confidence_interval;
+            assert!((high - low).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_forecast_constant_has_zero_interval() {
+        let stats: Vec<_> = (1..=20).map(|day| daily_stat(day, 2.5)).collect();
+
+        for point in forecast_usage(&stats, 7) {
+            assert!((point.projected_cost - 2.5).abs() < 1e-9);
+            assert_eq!(point.confidence_interval.0, point.confidence_interval.1);
+        }
+        assert!(forecast_usage(&stats[..1], 7).is_empty());
+    }
+
+    #[test]
+    fn test_low_cache_hit_hint() {
+        let pricing = get_default_pricing();
+        let mut stat = daily_stat(1, 1.0);
+        stat.model = ModelName::Claude4Sonnet;
+        stat.usage.input_tokens = 1000;
+        stat.usage.cache_read_input_tokens = 10;
+        let hint = low_cache_hit_hint(std::slice::from_ref(&stat), &pricing).unwrap();
+        assert!(hint.contains("CLAUDE.md"));
+
+        stat.usage.cache_read_input_tokens = 1000;
+        assert!(low_cache_hit_hint(&[stat], &pricing).is_none());
+    }
+
+    fn daily_stats(costs: &[f64]) -> Vec<UsageStats> {
+        costs
+            .iter()
+            .enumerate()
+            .map(|(i, &cost)| daily_stat(i as u32 + 1, cost))
+            .collect()
+    }
+
+    fn days(costs: &[f64]) -> Vec<(DateTime<Utc>, f64)> {
+        daily_stats(costs).iter().map(|s| (s.date, s.cost_usd)).collect()
+    }
+
+    fn thresholds(rolling: f64, period: f64) -> AnomalyThresholds {
+        AnomalyThresholds { rolling, period }
+    }
+
+    #[test]
+    fn test_detect_anomalies() {
+        let daily = days(&[1.0, 1.1, 0.9, 1.0, 1.05, 0.95, 1.0, 10.0, 1.0]);
+
+        let anomalies = detect_anomalies(&daily, thresholds(3.0, 3.0));
+        assert_eq!(anomalies.len(), 1);
+        let anomaly = &anomalies[0];
+        assert_eq!(anomaly.date, daily[7].0);
+        assert_eq!(anomaly.cost_usd, 10.0);
+        let rolling = anomaly.rolling.as_ref().unwrap();
+        assert!((rolling.mean - 1.0).abs() < 0.01);
+        assert!(rolling.sigmas > 3.0);
+        // Below the threshold against the whole period, which includes the spike itself
+        assert!(anomaly.period.as_ref().unwrap().sigmas < 3.0);
+    }
+
+    #[test]
+    fn test_period_baseline_flags_spike_without_history() {
+        // The first day has no earlier days to compare against
+        let daily = days(&[12.0, 1.0, 1.2, 0.9, 1.1, 1.0, 0.8, 1.0, 1.1, 0.9]);
+
+        let anomalies = detect_anomalies(&daily, thresholds(2.5, 2.0));
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(anomalies[0].cost_usd, 12.0);
+        assert!(anomalies[0].rolling.is_none());
+        assert!(anomalies[0].period.as_ref().unwrap().sigmas > 2.0);
+        assert!(detect_anomalies(&daily, thresholds(2.5, 3.5)).is_empty());
+    }
+
+    #[test]
+    fn test_thresholds_apply_to_their_own_baseline() {
+        let daily = days(&[1.0, 1.1, 0.9, 1.0, 1.05, 0.95, 1.0, 10.0, 1.0]);
+        let period_sigmas = detect_anomalies(&daily, thresholds(3.0, 3.0))[0]
+            .period
+            .as_ref()
+            .unwrap()
+            .sigmas;
+
+        // Flagged by the whole-period rule alone once its threshold drops below the spike
+        let anomalies = detect_anomalies(&daily, thresholds(f64::INFINITY, period_sigmas - 0.1));
+        assert_eq!(anomalies.len(), 1);
+        assert!(detect_anomalies(&daily, thresholds(f64::INFINITY, period_sigmas + 0.1)).is_empty());
+    }
+
+    #[test]
+    fn test_anomaly_reports_keep_their_stats_row() {
+        let stats = daily_stats(&[1.0, 1.1, 0.9, 1.0, 1.05, 0.95, 1.0, 10.0, 1.0]);
+        let reports = anomaly_reports(&stats, thresholds(3.0, 3.0));
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].stats.date, stats[7].date);
+        assert_eq!(reports[0].anomaly.cost_usd, 10.0);
+    }
+
+    #[test]
+    fn test_no_anomalies_in_steady_usage() {
+        let daily = days(&[2.0; 10]);
+        assert!(detect_anomalies(&daily, thresholds(2.5, 2.0)).is_empty());
+        // Too few periods to judge against either baseline
+        assert!(detect_anomalies(&days(&[1.0, 50.0]), thresholds(0.5, 0.5)).is_empty());
+    }
+
+    #[test]
+    fn test_token_velocity() {
+        let mut entries: Vec<_> = (0..5)
+            .map(|i| entry(&format!("2024-12-01T10:0{}:00Z", i), ModelName::Claude4Opus, 300))
+            .collect();
+        entries.push(entry("2024-12-01T10:31:00Z", ModelName::Claude4Opus, 300));
+        entries.push(entry("2024-12-01T10:40:00Z", ModelName::Claude4Opus, 300));
+
+        let velocity = calculate_token_velocity(&entries);
+        assert_eq!(velocity.len(), 2);
+        assert_eq!(velocity[0].0, Utc.with_ymd_and_hms(2024, 12, 1, 10, 0, 0).unwrap());
+        assert_eq!(velocity[1].0, Utc.with_ymd_and_hms(2024, 12, 1, 10, 30, 0).unwrap());
+        assert!((velocity[0].1 - 100.0).abs() < 1e-9);
+        assert!((velocity[0].1 / velocity[1].1 - 2.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_daily_stats_only_for_model_grouping() {
+        let pricing = get_default_pricing();
+        let entries = vec![entry("2024-12-01T10:00:00Z", ModelName::Claude4Opus, 1_000)];
+
+        let filters = EntryFilters::default();
+        let stats = calculate_stats(
+            entries,
+            &[GroupBy::Day],
+            &filters,
+            &pricing,
+            &StatsOptions { with_daily_stats: true, ..Default::default() },
+        )
+        .unwrap();
+        assert!(!stats[0].has_daily_stats());
+    }
+
+    #[test]
+    fn test_exclude_model() {
+        let pricing = get_default_pricing();
+        let entries = vec![
+            entry("2025-06-01T10:00:00Z", ModelName::Claude4Opus, 100),
+            entry("2025-06-01T11:00:00Z", ModelName::Claude3Opus, 100),
+            entry("2025-06-01T12:00:00Z", ModelName::Claude4Sonnet, 100),
+            entry("2025-06-01T13:00:00Z", ModelName::Claude3Haiku, 100),
+        ];
+
+        let filters = EntryFilters {
+            exclude_models: vec!["opus".to_string()],
+            ..Default::default()
+        };
+        let stats = calculate_stats(
+            entries.clone(),
+            &[GroupBy::ModelDay],
+            &filters,
+            &pricing,
+            &StatsOptions::default(),
+        )
+        .unwrap();
+        let models: Vec<_> = stats.iter().map(|s| s.model.clone()).collect();
+        assert_eq!(models.len(), 2);
+        assert!(models.contains(&ModelName::Claude4Sonnet));
+        assert!(models.contains(&ModelName::Claude3Haiku));
+
+        // Exclusion wins over an overlapping inclusion filter
+        let filters = EntryFilters {
+            model: Some("opus".to_string()),
+            exclude_models: vec!["claude-3".to_string()],
+            ..Default::default()
+        };
+        let stats = calculate_stats(
+            entries,
+            &[GroupBy::ModelDay],
+            &filters,
+            &pricing,
+            &StatsOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].model, ModelName::Claude4Opus);
+    }
+
+    #[test]
+    fn test_include_synthetic() {
+        let pricing = get_default_pricing();
+        let entries = vec![
+            entry("2025-06-01T10:00:00Z", ModelName::Claude4Opus, 1_000),
+            entry("2025-06-01T11:00:00Z", ModelName::Synthetic, 500),
+        ];
+
+        let filters = EntryFilters::default();
+        let stats = calculate_stats(
+            entries.clone(),
+            &[GroupBy::Model],
+            &filters,
+            &pricing,
+            &StatsOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].model, ModelName::Claude4Opus);
+
+        let filters = EntryFilters {
+            include_synthetic: true,
+            ..Default::default()
+        };
+        let stats = calculate_stats(
+            entries,
+            &[GroupBy::Model],
+            &filters,
+            &pricing,
+            &StatsOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(stats.len(), 2);
+        let synthetic = stats.iter().find(|s| s.model.is_synthetic()).unwrap();
+        assert_eq!(synthetic.model.to_string(), "Synthetic");
+        assert_eq!(synthetic.request_count, 1);
+        assert_eq!(synthetic.usage.input_tokens, 500);
+        assert_eq!(synthetic.cost_usd, 0.0);
+    }
+
+    #[test]
+    fn test_reprice_as() {
+        let pricing = get_default_pricing();
+        // $15/M on Opus, $3/M on Sonnet
+        let entries = vec![entry("2025-06-01T10:00:00Z", ModelName::Claude4Opus, 100_000)];
+        let sonnet = reprice_target(&pricing, "claude-sonnet-4-20250514").unwrap();
+
+        let filters = EntryFilters::default();
+        let stats = calculate_stats(
+            entries,
+            &[GroupBy::Day],
+            &filters,
+            &pricing,
+            &StatsOptions { reprice_as: Some(sonnet), ..Default::default() },
+        )
+        .unwrap();
+        assert!((stats[0].cost_usd - 1.5).abs() < 1e-9);
+        assert!((stats[0].repriced_cost_usd.unwrap() - 0.3).abs() < 1e-9);
+
+        assert!(reprice_target(&pricing, "gpt-4").is_err());
+    }
+
+    #[test]
+    fn test_sidechain_modes() {
+        let pricing = get_default_pricing();
+        let mut sidechain = entry("2025-06-01T11:00:00Z", ModelName::Claude4Opus, 500);
+        sidechain.is_sidechain = Some(true);
+        let entries = vec![entry("2025-06-01T10:00:00Z", ModelName::Claude4Opus, 1_000), sidechain];
+
+        for (sidechains, expected_requests) in [
+            (SidechainMode::Exclude, 1),
+            (SidechainMode::Include, 2),
+            (SidechainMode::Only, 1),
+        ] {
+            let filters = EntryFilters {
+                sidechains,
+                ..Default::default()
+            };
+            let stats = calculate_stats(
+                entries.clone(),
+                &[GroupBy::None],
+                &filters,
+                &pricing,
+                &StatsOptions::default(),
+            )
+            .unwrap();
+            assert_eq!(stats[0].request_count, expected_requests, "{:?}", sidechains);
+            if sidechains == SidechainMode::Only {
+                assert_eq!(stats[0].usage.input_tokens, 500);
+            }
+        }
+    }
+
+    #[test]
+    fn test_warn_context_usage() {
+        let pricing = get_default_pricing();
+        let mut long = entry("2025-06-01T10:00:00Z", ModelName::Claude4Opus, 190_000);
+        long.session_id = "abc12345-long".to_string();
+        let mut short = entry("2025-06-01T11:00:00Z", ModelName::Claude4Opus, 20_000);
+        short.session_id = "def67890-short".to_string();
+        let mut long_context = entry("2025-06-01T12:00:00Z", ModelName::Claude4Sonnet, 250_000);
+        long_context.session_id = "0123abcd-sonnet".to_string();
+        // A multi-byte character straddling the eighth byte
+        let mut accented = entry("2025-06-01T13:00:00Z", ModelName::Claude4Opus, 190_000);
+        accented.session_id = "sessión-é".to_string();
+
+        let stats = calculate_stats(
+            vec![long, short, long_context, accented],
+            &[GroupBy::Session, GroupBy::Model],
+            &EntryFilters::default(),
+            &pricing,
+            &StatsOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(stats.len(), 4);
+
+        let warnings = warn_context_usage(&stats, 180_000, &pricing::get_context_limits(), false);
+        assert_eq!(
+            warnings,
+            vec![
+                "⚠ Session abc12345 (Claude 4 Opus): 190,000 input tokens (95% of 200K context)",
+                "⚠ Session 0123abcd (Claude 4 Sonnet): 250,000 input tokens (25% of 1000K context)",
+                "⚠ Session sessión- (Claude 4 Opus): 190,000 input tokens (95% of 200K context)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_quarter_and_year_grouping() {
+        let pricing = get_default_pricing();
+        let entries = vec![
+            entry("2024-12-15T10:00:00Z", ModelName::Claude4Opus, 100),
+            entry("2025-01-10T10:00:00Z", ModelName::Claude4Opus, 100),
+            entry("2025-02-20T10:00:00Z", ModelName::Claude4Sonnet, 100),
+        ];
+
+        for group_by in [GroupBy::Quarter, GroupBy::Year] {
+            let group_by = [group_by];
+            let filters = EntryFilters::default();
+            let stats = calculate_stats(
+                entries.clone(),
+                &group_by,
+                &filters,
+                &pricing,
+                &StatsOptions::default(),
+            )
+            .unwrap();
+            assert_eq!(stats.len(), 2, "{:?}", group_by);
+            // Q4-2024 / 2024
+            assert_eq!(stats[0].date.year(), 2024);
+            assert_eq!(stats[0].request_count, 1);
+            // Q1-2025 / 2025 spans January and February
+            assert_eq!(stats[1].date.year(), 2025);
+            assert_eq!(stats[1].request_count, 2);
+        }
+    }
+
+    #[test]
+    fn test_multiple_group_dimensions() {
+        let pricing = get_default_pricing();
+        let entries = vec![
+            // ISO week 23 of 2025
+            entry("2025-06-02T10:00:00Z", ModelName::Claude4Opus, 100),
+            entry("2025-06-03T10:00:00Z", ModelName::Claude4Opus, 100),
+            entry("2025-06-03T11:00:00Z", ModelName::Claude4Sonnet, 100),
+            // ISO week 24
+            entry("2025-06-10T10:00:00Z", ModelName::Claude4Opus, 100),
+        ];
+
+        let stats = calculate_stats(
+            entries.clone(),
+            &[GroupBy::Model, GroupBy::Week],
+            &EntryFilters::default(),
+            &pricing,
+            &StatsOptions::default(),
+        )
+        .unwrap();
+        let mut rows: Vec<_> = stats
+            .iter()
+            .map(|s| (s.model.clone(), s.date.iso_week().week(), s.request_count))
+            .collect();
+        rows.sort_by_key(|a| (a.1, a.0.to_string()));
+        assert_eq!(
+            rows,
+            vec![
+                (ModelName::Claude4Opus, 23, 2),
+                (ModelName::Claude4Sonnet, 23, 1),
+                (ModelName::Claude4Opus, 24, 1),
+            ]
+        );
+
+        // ModelDay is the same as model,day
+        let model_day = calculate_stats(
+            entries.clone(),
+            &[GroupBy::ModelDay],
+            &EntryFilters::default(),
+            &pricing,
+            &StatsOptions::default(),
+        )
+        .unwrap();
+        let day_model = calculate_stats(
+            entries,
+            &[GroupBy::Day, GroupBy::Model],
+            &EntryFilters::default(),
+            &pricing,
+            &StatsOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(model_day.len(), 4);
+        assert_eq!(model_day.len(), day_model.len());
+    }
+}
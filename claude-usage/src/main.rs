@@ -1,14 +1,117 @@
 use anyhow::Result;
 use clap::Parser;
-use claude_usage::{analyze_usage, cli::{Args, Cli, Commands}, dashboard};
+use claude_usage::{analyze_usage, cli::{Args, Cli, Commands}, cleanup, dashboard, diagnostics, error::UsageError, export, follow, list_models, parser::LogParser, patterns, range, statusline, validate};
+use std::process::ExitCode;
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
+    match run().await {
+        Ok(code) => code,
+        Err(err) => match err.downcast_ref::<UsageError>() {
+            Some(usage_error) => {
+                eprintln!("Error: {}", usage_error);
+                ExitCode::from(usage_error.exit_code())
+            }
+            None => {
+                eprintln!("Error: {:?}", err);
+                ExitCode::FAILURE
+            }
+        },
+    }
+}
+
+async fn run() -> Result<ExitCode> {
     let cli = Cli::parse();
     
     match cli.command {
-        Some(Commands::Dashboard { refresh, hours, model, claude_dir }) => {
-            dashboard::run_dashboard(refresh, hours, model, claude_dir).await?;
+        Some(Commands::Dashboard {
+            refresh,
+            watch_interval,
+            min_refresh_interval_ms,
+            hours,
+            model,
+            claude_dirs,
+            checksums,
+            max_depth,
+            discover_max_depth,
+            feed_size,
+            snapshot,
+            dry_run,
+        }) => {
+            // The dashboard itself shows this warning in its header
+            if discover_max_depth && (snapshot || dry_run) {
+                LogParser::new_multi(claude_dirs.clone())
+                    .with_max_depth(max_depth)
+                    .warn_on_depth_mismatch()?;
+            }
+            if snapshot {
+                dashboard::print_snapshot(model, claude_dirs, checksums, max_depth)?;
+                return Ok(ExitCode::SUCCESS);
+            }
+            if dry_run {
+                dashboard::print_dry_run(model, claude_dirs, checksums, max_depth)?;
+                return Ok(ExitCode::SUCCESS);
+            }
+            let rates = dashboard::RefreshRates {
+                display_seconds: refresh,
+                watch_interval_seconds: watch_interval,
+                min_data_interval_ms: min_refresh_interval_ms,
+            };
+            dashboard::run_dashboard(
+                rates,
+                hours,
+                model,
+                claude_dirs,
+                checksums,
+                max_depth,
+                discover_max_depth,
+                feed_size,
+            )
+            .await?;
+        }
+        Some(Commands::Validate { claude_dir, verbose }) => {
+            let code = validate::run_validate(&claude_dir, verbose, &mut std::io::stdout())?;
+            return Ok(ExitCode::from(code));
+        }
+        Some(Commands::Doctor { claude_dir }) => {
+            let colored = claude_usage::formatters::should_use_color(false);
+            let code = diagnostics::run_doctor(&claude_dir, colored, &mut std::io::stdout())?;
+            return Ok(ExitCode::from(code));
+        }
+        Some(Commands::Clean { claude_dir, state, cache, snapshots, confirm }) => {
+            let targets = cleanup::CleanTargets { state, cache, snapshots };
+            cleanup::run_clean(&claude_dir, targets, confirm, &mut std::io::stdout())?;
+        }
+        Some(Commands::Follow { model, claude_dirs, max_depth }) => {
+            follow::run_follow(claude_dirs, model, max_depth, &mut std::io::stdout()).await?;
+        }
+        Some(Commands::Patterns { start_date, end_date, utc, claude_dirs }) => {
+            let colored = claude_usage::formatters::should_use_color(false);
+            patterns::run_patterns(
+                claude_dirs,
+                start_date,
+                end_date,
+                utc,
+                colored,
+                &mut std::io::stdout(),
+            )?;
+        }
+        Some(Commands::Range { claude_dirs, max_depth }) => {
+            range::run_range(claude_dirs, max_depth, &mut std::io::stdout())?;
+        }
+        Some(Commands::Statusline { format_string, claude_dirs, max_depth }) => {
+            statusline::run_statusline(
+                claude_dirs,
+                &format_string,
+                max_depth,
+                &mut std::io::stdout(),
+            )?;
+        }
+        Some(Commands::Export { format, output, claude_dir, start_date, end_date }) => {
+            export::run_export(format, &output, &claude_dir, start_date, end_date).await?;
+        }
+        Some(Commands::Models { format }) => {
+            list_models(format, &mut std::io::stdout())?;
         }
         Some(Commands::Show(args)) => {
             analyze_usage(args).await?;
@@ -20,5 +123,5 @@ async fn main() -> Result<()> {
         }
     }
     
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
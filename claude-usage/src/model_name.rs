@@ -2,8 +2,20 @@ use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::str::FromStr;
 
+/// Concrete models, newest first; the order the dashboard cycles through them
+static KNOWN_MODELS: &[ModelName] = &[
+    ModelName::Claude4Opus,
+    ModelName::Claude4Sonnet,
+    ModelName::Claude37Sonnet,
+    ModelName::Claude35Sonnet,
+    ModelName::Claude35Haiku,
+    ModelName::Claude3Opus,
+    ModelName::Claude3Sonnet,
+    ModelName::Claude3Haiku,
+];
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
-#[serde(try_from = "String", into = "String")]
+#[serde(from = "String", into = "String")]
 pub enum ModelName {
     // Claude 3 models
     Claude3Opus,
@@ -59,16 +71,24 @@ impl ModelName {
     /// Get a canonical string representation for pricing lookups
     pub fn canonical_string(&self) -> String {
         match self {
-            ModelName::Claude3Opus => "claude-3-opus-20240229".to_string(),
-            ModelName::Claude3Sonnet => "claude-3-sonnet-20240229".to_string(),
-            ModelName::Claude3Haiku => "claude-3-haiku-20240307".to_string(),
-            ModelName::Claude35Sonnet => "claude-3-5-sonnet-20241022".to_string(),
-            ModelName::Claude35Haiku => "claude-3-5-haiku-20241022".to_string(),
-            ModelName::Claude37Sonnet => "claude-3-7-sonnet-20250219".to_string(),
-            ModelName::Claude4Opus => "claude-opus-4-20250514".to_string(),
-            ModelName::Claude4Sonnet => "claude-sonnet-4-20250514".to_string(),
-            ModelName::Synthetic => "<synthetic>".to_string(),
             ModelName::Unknown(s) => s.clone(),
+            _ => self.static_canonical_string().map(str::to_string).unwrap_or_default(),
+        }
+    }
+    
+    /// Canonical string of every variant except `Unknown`, whose string is only known at runtime
+    fn static_canonical_string(&self) -> Option<&'static str> {
+        match self {
+            ModelName::Claude3Opus => Some("claude-3-opus-20240229"),
+            ModelName::Claude3Sonnet => Some("claude-3-sonnet-20240229"),
+            ModelName::Claude3Haiku => Some("claude-3-haiku-20240307"),
+            ModelName::Claude35Sonnet => Some("claude-3-5-sonnet-20241022"),
+            ModelName::Claude35Haiku => Some("claude-3-5-haiku-20241022"),
+            ModelName::Claude37Sonnet => Some("claude-3-7-sonnet-20250219"),
+            ModelName::Claude4Opus => Some("claude-opus-4-20250514"),
+            ModelName::Claude4Sonnet => Some("claude-sonnet-4-20250514"),
+            ModelName::Synthetic => Some("<synthetic>"),
+            ModelName::Unknown(_) => None,
         }
     }
     
@@ -83,6 +103,57 @@ impl ModelName {
         }
     }
     
+    /// Compact label of at most 8 characters, e.g. `c4-opus`, for narrow dashboard columns
+    pub fn display_short(&self) -> &'static str {
+        match self {
+            ModelName::Claude3Opus => "c3-opus",
+            ModelName::Claude3Sonnet => "c3-son",
+            ModelName::Claude3Haiku => "c3-haiku",
+            ModelName::Claude35Sonnet => "c3.5-son",
+            ModelName::Claude35Haiku => "c3.5-hku",
+            ModelName::Claude37Sonnet => "c3.7-son",
+            ModelName::Claude4Opus => "c4-opus",
+            ModelName::Claude4Sonnet => "c4-son",
+            ModelName::Synthetic => "synth",
+            ModelName::Unknown(_) => "other",
+        }
+    }
+    
+    /// Family and version in at most 16 characters, e.g. `Opus 4`; unknown model names are
+    /// cut to fit
+    pub fn display_medium(&self) -> String {
+        match self {
+            ModelName::Claude3Opus => "Opus 3".to_string(),
+            ModelName::Claude3Sonnet => "Sonnet 3".to_string(),
+            ModelName::Claude3Haiku => "Haiku 3".to_string(),
+            ModelName::Claude35Sonnet => "Sonnet 3.5".to_string(),
+            ModelName::Claude35Haiku => "Haiku 3.5".to_string(),
+            ModelName::Claude37Sonnet => "Sonnet 3.7".to_string(),
+            ModelName::Claude4Opus => "Opus 4".to_string(),
+            ModelName::Claude4Sonnet => "Sonnet 4".to_string(),
+            ModelName::Synthetic => "Synthetic".to_string(),
+            ModelName::Unknown(s) => s.chars().take(16).collect(),
+        }
+    }
+    
+    /// Every statically known variant (everything except `Unknown`): `all_known()` followed
+    /// by `Synthetic`
+    pub fn known_variants() -> Vec<ModelName> {
+        let mut variants = KNOWN_MODELS.to_vec();
+        variants.push(ModelName::Synthetic);
+        variants
+    }
+    
+    /// Every concrete model, i.e. everything except `Synthetic` and `Unknown`
+    pub fn all_known() -> &'static [ModelName] {
+        KNOWN_MODELS
+    }
+    
+    /// Canonical strings of `all_known()`, for shell completions
+    pub fn all_canonical_strings() -> Vec<&'static str> {
+        KNOWN_MODELS.iter().filter_map(ModelName::static_canonical_string).collect()
+    }
+    
     /// Check if this is a synthetic model
     pub fn is_synthetic(&self) -> bool {
         matches!(self, ModelName::Synthetic)
@@ -114,11 +185,9 @@ impl FromStr for ModelName {
     }
 }
 
-impl TryFrom<String> for ModelName {
-    type Error = std::convert::Infallible;
-    
-    fn try_from(s: String) -> Result<Self, Self::Error> {
-        Ok(ModelName::from_model_string(&s))
+impl From<String> for ModelName {
+    fn from(s: String) -> Self {
+        ModelName::from_model_string(&s)
     }
 }
 
@@ -146,6 +215,27 @@ mod tests {
         assert_eq!(ModelName::Claude3Haiku.family(), "haiku");
     }
     
+    #[test]
+    fn test_all_known() {
+        let known = ModelName::all_known();
+        assert_eq!(known.len(), 8);
+        assert!(known.iter().all(|model| !model.canonical_string().is_empty()));
+        assert!(!known.contains(&ModelName::Synthetic));
+        assert_eq!(ModelName::all_canonical_strings().len(), known.len());
+    }
+    
+    #[test]
+    fn test_display_lengths() {
+        let mut models = ModelName::known_variants();
+        models.push(ModelName::Unknown("claude-experimental-model-20990101".to_string()));
+        for model in models {
+            assert!(model.display_short().chars().count() <= 8, "{:?}", model);
+            assert!(model.display_medium().chars().count() <= 16, "{:?}", model);
+        }
+        assert_eq!(ModelName::Claude4Opus.display_short(), "c4-opus");
+        assert_eq!(ModelName::Claude35Sonnet.display_medium(), "Sonnet 3.5");
+    }
+    
     #[test]
     fn test_serde_roundtrip() {
         let model = ModelName::Claude4Opus;
@@ -3,9 +3,24 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+/// One line of a Claude Code JSONL log, dispatched on its `type` field so every known
+/// shape is handled by a single deserialize
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+#[allow(clippy::large_enum_variant)] // Short-lived: unpacked right after each line is parsed
+pub enum RawEntry {
+    Assistant(LogEntry),
+    /// Conversation summaries carry no usage data
+    Summary(serde::de::IgnoredAny),
+    /// User messages and any entry types we don't know about
+    #[serde(other)]
+    Other,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogEntry {
-    #[serde(rename = "type")]
+    // Consumed as the tag when deserialized through `RawEntry`
+    #[serde(rename = "type", default)]
     pub entry_type: String,
     pub uuid: String,
     #[serde(rename = "parentUuid")]
@@ -23,11 +38,62 @@ pub struct LogEntry {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
+    // Older log formats omit the message id; those entries are skipped by the parser
+    #[serde(default)]
     pub id: String,
     pub role: String,
     #[serde(with = "model_name_serde")]
     pub model: ModelName,
     pub usage: Option<TokenUsage>,
+    /// Whether the content looked like a tool result; the content itself isn't kept
+    #[serde(
+        default,
+        rename = "content",
+        deserialize_with = "content_is_tool_result",
+        skip_serializing
+    )]
+    pub tool_result_content: bool,
+}
+
+/// Prefixes of tool results that some log formats record as plain message text
+const TOOL_RESULT_PREFIXES: &[&str] = &["<function_results>", "<tool_result>"];
+
+impl Message {
+    /// Whether this is a tool result recorded as an assistant message. Tool results are
+    /// billed as input tokens of the next request, so counting them again inflates totals.
+    pub fn is_tool_result(&self) -> bool {
+        self.role == "tool" || self.tool_result_content
+    }
+}
+
+/// Reduce message content, either a string or a list of content blocks, to whether it is
+/// a tool result
+fn content_is_tool_result<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct Block {
+        #[serde(rename = "type", default)]
+        block_type: String,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Content {
+        Text(String),
+        Blocks(Vec<Block>),
+        Other(serde::de::IgnoredAny),
+    }
+
+    Ok(match Content::deserialize(deserializer)? {
+        Content::Text(text) => {
+            let text = text.trim_start();
+            TOOL_RESULT_PREFIXES.iter().any(|prefix| text.starts_with(prefix))
+        }
+        Content::Blocks(blocks) => blocks.iter().any(|block| block.block_type == "tool_result"),
+        Content::Other(_) => false,
+    })
 }
 
 // Custom serde implementation to handle model as string in JSON
@@ -51,15 +117,63 @@ mod model_name_serde {
     }
 }
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "RawTokenUsage")]
 pub struct TokenUsage {
     pub input_tokens: u64,
     pub output_tokens: u64,
-    #[serde(default)]
+    /// All cache writes, including any reported only through `cache_creation`
     pub cache_creation_input_tokens: u64,
-    #[serde(default)]
     pub cache_read_input_tokens: u64,
     pub service_tier: Option<String>,
+    /// Cache writes split by cache lifetime, when the log reports it (always serialized,
+    /// as null when absent, so JSON output has a fixed schema)
+    pub cache_creation: Option<CacheCreation>,
+}
+
+/// Cache writes broken down by ephemeral cache duration (newer Claude Code logs)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheCreation {
+    #[serde(default)]
+    pub ephemeral_5m_input_tokens: u64,
+    #[serde(default)]
+    pub ephemeral_1h_input_tokens: u64,
+}
+
+impl CacheCreation {
+    pub fn total(&self) -> u64 {
+        self.ephemeral_5m_input_tokens + self.ephemeral_1h_input_tokens
+    }
+}
+
+/// `usage` as it appears in the logs. Older entries only have the flat cache fields;
+/// newer ones may also (or only) report cache writes in a `cache_creation` breakdown.
+#[derive(Deserialize)]
+struct RawTokenUsage {
+    input_tokens: u64,
+    output_tokens: u64,
+    #[serde(default)]
+    cache_creation_input_tokens: u64,
+    #[serde(default)]
+    cache_read_input_tokens: u64,
+    service_tier: Option<String>,
+    #[serde(default)]
+    cache_creation: Option<CacheCreation>,
+}
+
+impl From<RawTokenUsage> for TokenUsage {
+    fn from(raw: RawTokenUsage) -> Self {
+        // When both are present the flat count already includes the breakdown
+        let breakdown = raw.cache_creation.as_ref().map_or(0, CacheCreation::total);
+        Self {
+            input_tokens: raw.input_tokens,
+            output_tokens: raw.output_tokens,
+            cache_creation_input_tokens: raw.cache_creation_input_tokens.max(breakdown),
+            cache_read_input_tokens: raw.cache_read_input_tokens,
+            service_tier: raw.service_tier,
+            cache_creation: raw.cache_creation,
+        }
+    }
 }
 
 impl TokenUsage {
@@ -75,6 +189,52 @@ impl TokenUsage {
         self.output_tokens += other.output_tokens;
         self.cache_creation_input_tokens += other.cache_creation_input_tokens;
         self.cache_read_input_tokens += other.cache_read_input_tokens;
+        if let Some(other_breakdown) = &other.cache_creation {
+            let breakdown = self.cache_creation.get_or_insert_with(CacheCreation::default);
+            breakdown.ephemeral_5m_input_tokens += other_breakdown.ephemeral_5m_input_tokens;
+            breakdown.ephemeral_1h_input_tokens += other_breakdown.ephemeral_1h_input_tokens;
+        }
+    }
+
+    /// Share of prompt tokens served from the cache: cache reads over cache reads plus
+    /// uncached input, or 0 when there were neither
+    pub fn cache_hit_rate(&self) -> f64 {
+        let prompt = self.cache_read_input_tokens + self.input_tokens;
+        if prompt == 0 {
+            return 0.0;
+        }
+        self.cache_read_input_tokens as f64 / prompt as f64
+    }
+
+    /// Subtract `other`, stopping at zero since token counts can't be negative
+    pub fn sub(&mut self, other: &TokenUsage) {
+        self.input_tokens = self.input_tokens.saturating_sub(other.input_tokens);
+        self.output_tokens = self.output_tokens.saturating_sub(other.output_tokens);
+        self.cache_creation_input_tokens = self
+            .cache_creation_input_tokens
+            .saturating_sub(other.cache_creation_input_tokens);
+        self.cache_read_input_tokens = self
+            .cache_read_input_tokens
+            .saturating_sub(other.cache_read_input_tokens);
+        if let (Some(breakdown), Some(other_breakdown)) =
+            (&mut self.cache_creation, &other.cache_creation)
+        {
+            breakdown.ephemeral_5m_input_tokens = breakdown
+                .ephemeral_5m_input_tokens
+                .saturating_sub(other_breakdown.ephemeral_5m_input_tokens);
+            breakdown.ephemeral_1h_input_tokens = breakdown
+                .ephemeral_1h_input_tokens
+                .saturating_sub(other_breakdown.ephemeral_1h_input_tokens);
+        }
+    }
+}
+
+impl std::ops::Sub<&TokenUsage> for TokenUsage {
+    type Output = TokenUsage;
+
+    fn sub(mut self, other: &TokenUsage) -> TokenUsage {
+        TokenUsage::sub(&mut self, other);
+        self
     }
 }
 
@@ -85,24 +245,355 @@ pub struct UsageStats {
     pub usage: TokenUsage,
     pub request_count: u64,
     pub cost_usd: f64,
+    /// Cache reads as a share of prompt tokens, 0.0-1.0 (see [`TokenUsage::cache_hit_rate`])
+    pub cache_hit_rate: f64,
+    /// Cost had every request used the `--reprice-as` model's prices
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repriced_cost_usd: Option<f64>,
+    /// Highest single-day cost (only with `--with-daily-stats` on model grouping)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peak_day_cost: Option<f64>,
+    /// Average cost over the days the model was used
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_daily_cost: Option<f64>,
+    /// Number of distinct days with at least one request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_days: Option<u32>,
+    /// Conversation session (only with session grouping)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// Cost of 5-minute cache writes (only with `--cache-detail`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_write_5m_cost_usd: Option<f64>,
+    /// Cost of 1-hour cache writes (only with `--cache-detail`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_write_1h_cost_usd: Option<f64>,
+    /// Each model's tokens and cost within this row, most expensive first (only with
+    /// `--breakdown` on day grouping)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_breakdown: Option<Vec<ModelBreakdown>>,
+}
+
+/// One model's part of a `UsageStats` row (`--breakdown`)
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelBreakdown {
+    pub model: ModelName,
+    pub usage: TokenUsage,
+    pub cost_usd: f64,
+}
+
+impl ModelBreakdown {
+    pub fn new(model: ModelName) -> Self {
+        Self {
+            model,
+            usage: TokenUsage::default(),
+            cost_usd: 0.0,
+        }
+    }
+}
+
+impl UsageStats {
+    /// Multiply counts and cost by `factor`, used to extrapolate from a sample of files
+    pub fn scale(&mut self, factor: f64) {
+        let scale = |value: u64| (value as f64 * factor).round() as u64;
+        let scale_usage = |usage: &mut TokenUsage| {
+            usage.input_tokens = scale(usage.input_tokens);
+            usage.output_tokens = scale(usage.output_tokens);
+            usage.cache_creation_input_tokens = scale(usage.cache_creation_input_tokens);
+            usage.cache_read_input_tokens = scale(usage.cache_read_input_tokens);
+            if let Some(breakdown) = &mut usage.cache_creation {
+                breakdown.ephemeral_5m_input_tokens = scale(breakdown.ephemeral_5m_input_tokens);
+                breakdown.ephemeral_1h_input_tokens = scale(breakdown.ephemeral_1h_input_tokens);
+            }
+        };
+        scale_usage(&mut self.usage);
+        for row in self.model_breakdown.iter_mut().flatten() {
+            scale_usage(&mut row.usage);
+            row.cost_usd *= factor;
+        }
+        self.request_count = scale(self.request_count);
+        self.cost_usd *= factor;
+        self.repriced_cost_usd = self.repriced_cost_usd.map(|c| c * factor);
+        self.peak_day_cost = self.peak_day_cost.map(|c| c * factor);
+        self.avg_daily_cost = self.avg_daily_cost.map(|c| c * factor);
+        self.cache_write_5m_cost_usd = self.cache_write_5m_cost_usd.map(|c| c * factor);
+        self.cache_write_1h_cost_usd = self.cache_write_1h_cost_usd.map(|c| c * factor);
+    }
+
+    /// Whether the optional per-day model statistics were computed
+    pub fn has_daily_stats(&self) -> bool {
+        self.active_days.is_some()
+    }
+
+    /// Whether the `--cache-detail` cache write costs were computed
+    pub fn has_cache_detail(&self) -> bool {
+        self.cache_write_5m_cost_usd.is_some()
+    }
+
+    /// Combine two groups for the same model, keeping the earlier date. Per-day statistics
+    /// can't be recombined from the totals, so they are dropped; model breakdowns are
+    /// combined model by model.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the two groups are for different models.
+    pub fn merge(mut self, other: &UsageStats) -> UsageStats {
+        assert_eq!(
+            self.model, other.model,
+            "cannot merge usage stats for different models"
+        );
+        self.date = self.date.min(other.date);
+        self.usage.add(&other.usage);
+        self.request_count += other.request_count;
+        self.cost_usd += other.cost_usd;
+        self.cache_hit_rate = self.usage.cache_hit_rate();
+        self.repriced_cost_usd = match (self.repriced_cost_usd, other.repriced_cost_usd) {
+            (Some(a), Some(b)) => Some(a + b),
+            _ => None,
+        };
+        let sum = |a: Option<f64>, b: Option<f64>| a.zip(b).map(|(a, b)| a + b);
+        self.cache_write_5m_cost_usd =
+            sum(self.cache_write_5m_cost_usd, other.cache_write_5m_cost_usd);
+        self.cache_write_1h_cost_usd =
+            sum(self.cache_write_1h_cost_usd, other.cache_write_1h_cost_usd);
+        self.model_breakdown = match (self.model_breakdown.take(), &other.model_breakdown) {
+            (Some(mut rows), Some(other_rows)) => {
+                for other_row in other_rows {
+                    match rows.iter_mut().find(|row| row.model == other_row.model) {
+                        Some(row) => {
+                            row.usage.add(&other_row.usage);
+                            row.cost_usd += other_row.cost_usd;
+                        }
+                        None => rows.push(other_row.clone()),
+                    }
+                }
+                rows.sort_by(|a, b| b.cost_usd.total_cmp(&a.cost_usd));
+                Some(rows)
+            }
+            _ => None,
+        };
+        self.peak_day_cost = None;
+        self.avg_daily_cost = None;
+        self.active_days = None;
+        if self.session_id != other.session_id {
+            self.session_id = None;
+        }
+        self
+    }
 }
 
-#[derive(Debug, Clone)]
+impl std::ops::Add<&UsageStats> for UsageStats {
+    type Output = UsageStats;
+
+    fn add(self, other: &UsageStats) -> UsageStats {
+        self.merge(other)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct ModelPricing {
     pub input_per_million: f64,
     pub output_per_million: f64,
     pub cache_write_per_million: f64,
     pub cache_read_per_million: f64,
+    /// Multiplier for batch and background requests; 1.0 (no discount) unless the price
+    /// list says otherwise
+    #[serde(default = "no_discount")]
+    pub batch_discount: f64,
+    /// Rate for 1-hour cache writes; `cache_write_per_million` covers the 5-minute ones.
+    /// Twice the input rate when the price list doesn't give one.
+    #[serde(default)]
+    pub cache_write_1h_per_million: Option<f64>,
+}
+
+fn no_discount() -> f64 {
+    1.0
+}
+
+impl TokenUsage {
+    /// Whether the request ran on a discounted tier (Batch API or background requests)
+    pub fn is_batch_tier(&self) -> bool {
+        matches!(self.service_tier.as_deref(), Some("batch") | Some("background"))
+    }
+
+    /// Cache writes as (5-minute, 1-hour) tokens. Writes without a duration breakdown
+    /// count as 5-minute, the default cache lifetime.
+    pub fn cache_write_split(&self) -> (u64, u64) {
+        let one_hour = self
+            .cache_creation
+            .as_ref()
+            .map_or(0, |breakdown| breakdown.ephemeral_1h_input_tokens)
+            .min(self.cache_creation_input_tokens);
+        (self.cache_creation_input_tokens - one_hour, one_hour)
+    }
 }
 
 impl ModelPricing {
+    /// Cost at standard rates, ignoring the service tier; see `calculate_cost_with_tier`
     pub fn calculate_cost(&self, usage: &TokenUsage) -> f64 {
+        let (cache_write_5m, cache_write_1h) = self.cache_write_costs(usage);
         (usage.input_tokens as f64 * self.input_per_million
             + usage.output_tokens as f64 * self.output_per_million
-            + usage.cache_creation_input_tokens as f64 * self.cache_write_per_million
             + usage.cache_read_input_tokens as f64 * self.cache_read_per_million)
             / 1_000_000.0
+            + cache_write_5m
+            + cache_write_1h
+    }
+
+    /// Cost with `batch_discount` applied to batch and background requests
+    pub fn calculate_cost_with_tier(&self, usage: &TokenUsage) -> f64 {
+        self.calculate_cost(usage) * self.tier_multiplier(usage)
+    }
+
+    /// `batch_discount` for batch and background requests, otherwise 1.0
+    pub fn tier_multiplier(&self, usage: &TokenUsage) -> f64 {
+        if usage.is_batch_tier() {
+            self.batch_discount
+        } else {
+            1.0
+        }
+    }
+
+    /// Per-million rate for 1-hour cache writes
+    pub fn cache_write_1h_rate(&self) -> f64 {
+        self.cache_write_1h_per_million.unwrap_or(self.input_per_million * 2.0)
+    }
+
+    /// Cost of the 5-minute and 1-hour cache writes in `usage` at standard rates
+    pub fn cache_write_costs(&self, usage: &TokenUsage) -> (f64, f64) {
+        let (five_minute, one_hour) = usage.cache_write_split();
+        (
+            five_minute as f64 * self.cache_write_per_million / 1_000_000.0,
+            one_hour as f64 * self.cache_write_1h_rate() / 1_000_000.0,
+        )
+    }
+
+    /// What the cache reads in `usage` would have cost more at the uncached input rate
+    pub fn cache_savings(&self, usage: &TokenUsage) -> f64 {
+        let discount = self.input_per_million - self.cache_read_per_million;
+        usage.cache_read_input_tokens as f64 * discount / 1_000_000.0
     }
 }
 
-pub type PricingMap = HashMap<ModelName, ModelPricing>;
\ No newline at end of file
+pub type PricingMap = HashMap<ModelName, ModelPricing>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn stats(input_tokens: u64, output_tokens: u64, cost_usd: f64) -> UsageStats {
+        let usage = TokenUsage {
+            input_tokens,
+            output_tokens,
+            cache_creation_input_tokens: 10,
+            cache_read_input_tokens: 20,
+            ..Default::default()
+        };
+        UsageStats {
+            model: ModelName::Claude4Sonnet,
+            date: Utc.with_ymd_and_hms(2025, 6, 1, 0, 0, 0).unwrap(),
+            cache_hit_rate: usage.cache_hit_rate(),
+            usage,
+            request_count: 3,
+            cost_usd,
+            repriced_cost_usd: None,
+            peak_day_cost: None,
+            avg_daily_cost: None,
+            active_days: None,
+            session_id: None,
+            cache_write_5m_cost_usd: None,
+            cache_write_1h_cost_usd: None,
+            model_breakdown: None,
+        }
+    }
+
+    #[test]
+    fn test_add_then_subtract_restores_original() {
+        let original = stats(1000, 500, 0.1);
+        let other = stats(250, 125, 0.07);
+
+        let merged = original.clone() + &other;
+        assert_eq!(merged.request_count, 6);
+        assert!((merged.cost_usd - 0.17).abs() < 1e-9);
+
+        let usage = merged.usage - &other.usage;
+        assert_eq!(usage, original.usage);
+        assert!((merged.cost_usd - other.cost_usd - original.cost_usd).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_token_usage_sub_saturates() {
+        let mut usage = stats(100, 50, 0.0).usage;
+        usage.sub(&stats(300, 20, 0.0).usage);
+        assert_eq!(usage.input_tokens, 0);
+        assert_eq!(usage.output_tokens, 30);
+        assert_eq!(usage.cache_creation_input_tokens, 0);
+        assert_eq!(usage.cache_read_input_tokens, 0);
+    }
+
+    #[test]
+    fn test_cache_hit_rate() {
+        let half = stats(20, 0, 0.0);
+        assert!((half.cache_hit_rate - 0.5).abs() < 1e-9);
+        assert_eq!(TokenUsage::default().cache_hit_rate(), 0.0);
+
+        // Recomputed from the combined tokens, not averaged
+        let merged = half + &stats(60, 0, 0.0);
+        assert!((merged.cache_hit_rate - 40.0 / 120.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_batch_tier_costs_half() {
+        let pricing = crate::pricing::get_default_pricing();
+        let sonnet = &pricing[&ModelName::Claude4Sonnet];
+        let standard = stats(1000, 500, 0.0).usage;
+        let batch = TokenUsage {
+            service_tier: Some("batch".to_string()),
+            ..standard.clone()
+        };
+
+        let full = sonnet.calculate_cost_with_tier(&standard);
+        assert!(full > 0.0);
+        assert!((sonnet.calculate_cost_with_tier(&batch) - full / 2.0).abs() < 1e-12);
+        // The plain cost ignores the tier
+        assert_eq!(sonnet.calculate_cost(&batch), full);
+    }
+
+    #[test]
+    fn test_one_hour_cache_writes_cost_more() {
+        let pricing = crate::pricing::get_default_pricing();
+        let opus = &pricing[&ModelName::Claude4Opus];
+        let usage = TokenUsage {
+            cache_creation_input_tokens: 3_000_000,
+            cache_creation: Some(CacheCreation {
+                ephemeral_5m_input_tokens: 1_000_000,
+                ephemeral_1h_input_tokens: 2_000_000,
+            }),
+            ..Default::default()
+        };
+
+        // 1M at 1.25x the $15 input rate, 2M at 2x
+        assert_eq!(usage.cache_write_split(), (1_000_000, 2_000_000));
+        let (five_minute, one_hour) = opus.cache_write_costs(&usage);
+        assert!((five_minute - 18.75).abs() < 1e-9);
+        assert!((one_hour - 60.0).abs() < 1e-9);
+        assert!((opus.calculate_cost(&usage) - 78.75).abs() < 1e-9);
+
+        // Writes without a breakdown are all 5-minute
+        let flat = TokenUsage {
+            cache_creation_input_tokens: 1_000_000,
+            ..Default::default()
+        };
+        assert_eq!(flat.cache_write_split(), (1_000_000, 0));
+        assert!((opus.calculate_cost(&flat) - 18.75).abs() < 1e-9);
+    }
+
+    #[test]
+    #[should_panic(expected = "different models")]
+    fn test_merge_rejects_different_models() {
+        let mut other = stats(1, 1, 0.0);
+        other.model = ModelName::Claude4Opus;
+        let _ = stats(1, 1, 0.0).merge(&other);
+    }
+}
@@ -1,30 +1,150 @@
-use crate::models::LogEntry;
+use crate::cli::DedupKey;
+use crate::error::UsageError;
+use crate::model_name::ModelName;
+use crate::models::{LogEntry, RawEntry};
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
 use indicatif::{ProgressBar, ProgressStyle};
-use std::collections::HashMap;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize, Serializer};
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::{BufRead, BufReader};
-use std::path::Path;
-use std::time::Instant;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use walkdir::WalkDir;
 
 pub struct LogParser {
     // CLAUDETODO: Consider using &str or Path instead of String to avoid unnecessary allocations
-    // when the claude_dir is only read and not modified. This would require lifetime parameters.
-    pub(crate) claude_dir: String,
+    // when the claude_dirs are only read and not modified. This would require lifetime parameters.
+    pub(crate) claude_dirs: Vec<String>,
     start_date: Option<DateTime<Utc>>,
     end_date: Option<DateTime<Utc>>,
     pub(crate) quiet: bool,
+    /// Print a timing breakdown of each parse phase to stderr
+    pub profile: bool,
+    /// How deep below each `projects` directory to look for log files
+    pub max_depth: usize,
+    pub(crate) model_filter: Option<ModelName>,
+    /// Only keep entries from these sessions
+    pub(crate) session_filter: Option<HashSet<String>>,
+    /// Keep tool results recorded as assistant messages, which are skipped by default
+    pub(crate) include_tool_results: bool,
+    /// Which field identifies copies of the same request
+    pub(crate) dedup_key: DedupKey,
+    /// Parse only this many randomly chosen files, for quick estimates
+    sample_size: Option<usize>,
+    sample_seed: Option<u64>,
+    /// Receives progress updates in place of the console progress output
+    progress: Option<Box<ProgressCallback>>,
 }
 
+type ProgressCallback = dyn Fn(ParseProgress) + Send + Sync;
+
+/// Which stage of `parse_logs` a `ParseProgress` update is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParsePhase {
+    Discovery,
+    Parsing,
+    Filtering,
+    Deduplication,
+    Done,
+}
+
+/// A progress update from `parse_logs`, for callers drawing their own progress UI
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseProgress {
+    pub phase: ParsePhase,
+    pub files_done: usize,
+    pub files_total: usize,
+}
+
+/// How many of the slowest files a `ParseProfile` keeps
+const SLOWEST_FILES_SHOWN: usize = 5;
+
+/// Where `parse_logs` spent its time, for `--profile`
+#[derive(Debug, Clone, Serialize)]
+pub struct ParseProfile {
+    pub file_discovery_ms: f64,
+    pub parse_ms: f64,
+    pub filter_ms: f64,
+    pub dedup_ms: f64,
+    pub total_ms: f64,
+    pub files_total: usize,
+    pub files_with_errors: usize,
+    /// Entries read before date filtering and deduplication
+    pub entries_parsed: usize,
+    pub entries_final: usize,
+    /// The slowest files to parse, slowest first
+    #[serde(serialize_with = "serialize_file_times")]
+    pub slowest_files: Vec<(PathBuf, Duration)>,
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Serialize file timings as `{"path": ..., "ms": ...}` objects
+fn serialize_file_times<S: Serializer>(
+    files: &[(PathBuf, Duration)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    #[derive(Serialize)]
+    struct FileTime<'a> {
+        path: &'a Path,
+        ms: f64,
+    }
+
+    serializer.collect_seq(files.iter().map(|(path, time)| FileTime {
+        path,
+        ms: millis(*time),
+    }))
+}
+
+/// Default depth walked below `projects`. Standard logs (`projects/<project>/<session>.jsonl`)
+/// sit at depth 2; the extra level leaves room for sessions nested one directory deeper.
+pub const DEFAULT_MAX_DEPTH: usize = 3;
+
+/// Warning for log files found at depth `discovered`, when it's beyond `max_depth`
+fn depth_warning(discovered: usize, max_depth: usize) -> Option<String> {
+    (discovered > max_depth).then(|| {
+        format!(
+            "deepest JSONL files are at depth {}, beyond --max-depth {}; they are skipped",
+            discovered, max_depth
+        )
+    })
+}
+
+/// Deepest level scanned when discovering where log files actually live
+const DISCOVERY_MAX_DEPTH: usize = 10;
+
 impl LogParser {
     pub fn new(claude_dir: String) -> Self {
+        Self::new_multi(vec![claude_dir])
+    }
+
+    /// Create a parser that aggregates logs from several Claude directories
+    /// (e.g. separate personal and work accounts)
+    pub fn new_multi(claude_dirs: Vec<String>) -> Self {
         Self {
-            claude_dir,
+            claude_dirs,
             start_date: None,
             end_date: None,
             quiet: false,
+            profile: false,
+            max_depth: DEFAULT_MAX_DEPTH,
+            model_filter: None,
+            session_filter: None,
+            include_tool_results: false,
+            dedup_key: DedupKey::default(),
+            sample_size: None,
+            sample_seed: None,
+            progress: None,
         }
     }
 
@@ -43,30 +163,136 @@ impl LogParser {
         self
     }
 
+    pub fn with_profile(mut self, profile: bool) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Only keep entries for this exact model; others are dropped while parsing
+    pub fn with_model_filter(mut self, model: ModelName) -> Self {
+        self.model_filter = Some(model);
+        self
+    }
+
+    /// Only keep entries from these sessions; others are dropped while parsing
+    pub fn with_session_filter(mut self, session_ids: Vec<String>) -> Self {
+        self.session_filter = Some(session_ids.into_iter().collect());
+        self
+    }
+
+    /// Keep tool results recorded as assistant messages instead of skipping them
+    pub fn with_tool_results(mut self, include: bool) -> Self {
+        self.include_tool_results = include;
+        self
+    }
+
+    /// Deduplicate on `key` instead of the request id
+    pub fn with_dedup_key(mut self, key: DedupKey) -> Self {
+        self.dedup_key = key;
+        self
+    }
+
+    /// Parse a random sample of `files` log files instead of all of them.
+    /// Passing a seed makes the sample reproducible.
+    pub fn with_sample(mut self, files: usize, seed: Option<u64>) -> Self {
+        self.sample_size = Some(files);
+        self.sample_seed = seed;
+        self
+    }
+
+    /// Report progress to `callback` instead of printing a file count and progress bar
+    pub fn with_progress(
+        mut self,
+        callback: impl Fn(ParseProgress) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress = Some(Box::new(callback));
+        self
+    }
+
+    fn report(&self, phase: ParsePhase, files_done: usize, files_total: usize) {
+        if let Some(callback) = &self.progress {
+            callback(ParseProgress {
+                phase,
+                files_done,
+                files_total,
+            });
+        }
+    }
+
+    /// Factor to scale totals by when sampling (`total_files / sampled_files`),
+    /// or `None` when every file is parsed
+    pub fn sample_scale(&self) -> Result<Option<f64>> {
+        let sample_size = match self.sample_size {
+            Some(n) => n,
+            None => return Ok(None),
+        };
+
+        let total_files = self.find_all_jsonl_files()?.len();
+        if sample_size == 0 || sample_size >= total_files {
+            return Ok(None);
+        }
+
+        Ok(Some(total_files as f64 / sample_size as f64))
+    }
+
+    /// Check a parsed message's model against the model filter, if any
+    pub(crate) fn matches_model_filter(&self, model: &ModelName) -> bool {
+        self.model_filter.as_ref().is_none_or(|filter| filter == model)
+    }
+
+    /// Apply the parse-time filters to an entry returned by `parse_entry_line`
+    pub(crate) fn matches_entry_filters(&self, entry: &LogEntry) -> bool {
+        entry.message.as_ref().is_some_and(|message| {
+            self.matches_model_filter(&message.model)
+                && (self.include_tool_results || !message.is_tool_result())
+        }) && self
+                .session_filter
+                .as_ref()
+                .is_none_or(|sessions| sessions.contains(&entry.session_id))
+    }
+
     pub fn parse_logs(&self) -> Result<Vec<LogEntry>> {
-        let total_start = Instant::now();
-        
-        let expanded_path = shellexpand::tilde(&self.claude_dir).into_owned();
-        let projects_dir = Path::new(&expanded_path).join("projects");
-
-        if !projects_dir.exists() {
-            anyhow::bail!(
-                "Claude projects directory not found at: {}",
-                projects_dir.display()
-            );
+        self.parse_logs_with_reads().map(|(entries, _)| entries)
+    }
+
+    /// Parse, filter and deduplicate like `parse_logs`, also returning how long each
+    /// phase took
+    pub fn parse_logs_profiled(&self) -> Result<(Vec<LogEntry>, ParseProfile)> {
+        self.parse_logs_read().map(|(entries, profile, _)| (entries, profile))
+    }
+
+    /// Parse like `parse_logs`, also returning how much of each file was read, for
+    /// recording the files in a `FileTracker` (see `IncrementalParsing::warm_up_tracker`)
+    pub fn parse_logs_with_reads(&self) -> Result<(Vec<LogEntry>, Vec<FileRead>)> {
+        let (entries, profile, reads) = self.parse_logs_read()?;
+        if self.profile {
+            eprint!("{}", crate::formatters::format_parse_profile(&profile, false));
         }
+        Ok((entries, reads))
+    }
+
+    fn parse_logs_read(&self) -> Result<(Vec<LogEntry>, ParseProfile, Vec<FileRead>)> {
+        let total_start = Instant::now();
 
         // Phase 1: File discovery
         let file_discovery_start = Instant::now();
-        let jsonl_files = self.find_jsonl_files(&projects_dir)?;
+        self.report(ParsePhase::Discovery, 0, 0);
+        let jsonl_files = self.find_jsonl_files()?;
         let file_discovery_time = file_discovery_start.elapsed();
-        
-        if !self.quiet {
-            println!("Found {} JSONL files to process", jsonl_files.len());
-            println!("File discovery took: {:.2}ms", file_discovery_time.as_millis());
+        let files_total = jsonl_files.len();
+
+        // A progress callback replaces the console output
+        let console = !self.quiet && self.progress.is_none();
+        if console {
+            eprintln!("Found {} JSONL files to process", files_total);
         }
 
-        let pb = if self.quiet {
+        let pb = if !console {
             ProgressBar::hidden()
         } else {
             ProgressBar::new(jsonl_files.len() as u64)
@@ -83,21 +309,20 @@ impl LogParser {
         // CLAUDETODO: Consider pre-allocating Vec capacity based on estimated entries per file
         // to reduce reallocations during extend operations. Could sample first few files to estimate.
         let mut all_entries = Vec::new();
-        let mut total_lines_parsed = 0usize;
         let mut files_with_errors = 0usize;
-        let mut slow_files = Vec::new();
+        let mut file_times = Vec::with_capacity(jsonl_files.len());
+        let mut reads = Vec::with_capacity(jsonl_files.len());
 
-        for file_path in &jsonl_files {
+        self.report(ParsePhase::Parsing, 0, files_total);
+        for (files_done, file_path) in jsonl_files.iter().enumerate() {
             pb.inc(1);
+            self.report(ParsePhase::Parsing, files_done + 1, files_total);
             let file_start = Instant::now();
-            match self.parse_jsonl_file(file_path) {
-                Ok(entries) => {
-                    let file_time = file_start.elapsed();
-                    if file_time.as_millis() > 100 {  // Log files that take > 100ms
-                        slow_files.push((file_path.clone(), file_time, entries.len()));
-                    }
-                    total_lines_parsed += entries.len();
+            match self.read_jsonl_file(file_path) {
+                Ok((entries, read)) => {
+                    file_times.push((file_path.clone(), file_start.elapsed()));
                     all_entries.extend(entries);
+                    reads.push(read);
                 },
                 Err(e) => {
                     files_with_errors += 1;
@@ -108,194 +333,1118 @@ impl LogParser {
 
         pb.finish_with_message("Parsing complete");
         let parsing_time = parsing_start.elapsed();
-        
-        if !self.quiet {
-            println!("Parsing took: {:.2}s for {} entries from {} files", parsing_time.as_secs_f32(), total_lines_parsed, jsonl_files.len());
-            if files_with_errors > 0 {
-                println!("  {} files had errors", files_with_errors);
-            }
-            if !slow_files.is_empty() {
-                println!("  Slowest files (>100ms):");
-                slow_files.sort_by(|a, b| b.1.cmp(&a.1));  // Sort by time descending
-                for (path, time, entries) in slow_files.iter().take(5) {
-                    println!("    {:>6.0}ms - {} ({} entries)", 
-                        time.as_millis(), 
-                        path.file_name().unwrap_or_default().to_string_lossy(),
-                        entries);
-                }
-            }
-        }
+        let entries_parsed = all_entries.len();
 
         // Phase 3: Filtering by date
+        self.report(ParsePhase::Filtering, files_total, files_total);
         let filter_start = Instant::now();
         let filtered_entries = self.filter_by_date(all_entries);
         let filter_time = filter_start.elapsed();
         
         // Phase 4: Deduplication
+        self.report(ParsePhase::Deduplication, files_total, files_total);
         let dedup_start = Instant::now();
-        let result = self.deduplicate_entries(filtered_entries);
+        let result = self.deduplicate_entries(filtered_entries).into_inner();
         let dedup_time = dedup_start.elapsed();
-        
-        let total_time = total_start.elapsed();
-        
-        if !self.quiet {
-            println!("Date filtering took: {:.2}ms", filter_time.as_millis());
-            println!("Deduplication took: {:.2}ms", dedup_time.as_millis());
-            println!("----------------------------------------");
-            println!("Total parse_logs time: {:.2}s", total_time.as_secs_f32());
-            println!("Final entry count: {}", result.len());
-            println!("----------------------------------------");
+        self.report(ParsePhase::Done, files_total, files_total);
+
+        file_times.sort_by_key(|t| Reverse(t.1)); // Slowest first
+        file_times.truncate(SLOWEST_FILES_SHOWN);
+
+        let profile = ParseProfile {
+            file_discovery_ms: millis(file_discovery_time),
+            parse_ms: millis(parsing_time),
+            filter_ms: millis(filter_time),
+            dedup_ms: millis(dedup_time),
+            total_ms: millis(total_start.elapsed()),
+            files_total,
+            files_with_errors,
+            entries_parsed,
+            entries_final: result.len(),
+            slowest_files: file_times,
+        };
+
+        Ok((result, profile, reads))
+    }
+
+    /// Iterate over every entry in the date range, reading one file at a time so only that
+    /// file's entries are in memory.
+    ///
+    /// Entries are NOT deduplicated, since that needs every entry at once: a request logged
+    /// over several lines, or in logs synced from several machines, arrives once per copy.
+    /// Use `parse_logs` for exact counts, or `aggregate_logs` which deduplicates as it goes.
+    /// Fails if the log files can't be found; a file that can't be read yields an `Err` and
+    /// iteration moves on to the next file.
+    pub fn parse_logs_streaming(&self) -> Result<impl Iterator<Item = Result<LogEntry>> + '_> {
+        let mut files = self.find_jsonl_files()?.into_iter();
+        let mut current = Vec::new().into_iter();
+
+        Ok(std::iter::from_fn(move || loop {
+            if let Some(entry) = current.next() {
+                return Some(Ok(entry));
+            }
+            let path = files.next()?;
+            match self.parse_jsonl_file(&path) {
+                Ok(entries) => current = self.filter_by_date(entries).into_iter(),
+                Err(e) => {
+                    return Some(Err(e.context(format!("Error parsing {}", path.display()))))
+                }
+            }
+        }))
+    }
+
+    /// Parse only the entries belonging to one session.
+    ///
+    /// Claude Code names session files `<session_id>.jsonl`, so a file with a matching name is
+    /// checked first. Otherwise files are scanned in turn, and the walk stops at the first file
+    /// that contains the session (that file is still read to completion).
+    pub fn parse_single_session(&self, session_id: &str) -> Result<Vec<LogEntry>> {
+        let mut jsonl_files = self.find_jsonl_files()?;
+        jsonl_files.sort_by_key(|path| session_file_stem(path) != Some(session_id));
+
+        for file_path in &jsonl_files {
+            let entries = match self.parse_jsonl_file(file_path) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    eprintln!("Error parsing {}: {}", file_path.display(), e);
+                    continue;
+                }
+            };
+
+            let session_entries: Vec<LogEntry> = entries
+                .into_iter()
+                .filter(|entry| entry.session_id == session_id)
+                .collect();
+
+            if !session_entries.is_empty() {
+                let filtered_entries = self.filter_by_date(session_entries);
+                return Ok(self.deduplicate_entries(filtered_entries).into_inner());
+            }
         }
-        
-        Ok(result)
+
+        Ok(Vec::new())
     }
 
-    pub(crate) fn find_jsonl_files(&self, dir: &Path) -> Result<Vec<std::path::PathBuf>> {
-        let mut files = Vec::new();
+    /// Parse only the given sessions, reading `<project>/<session_id>.jsonl` (or `.jsonl.gz`)
+    /// directly in each project directory instead of walking every log file.
+    ///
+    /// This relies on Claude Code's file naming: a session logged under any other file name
+    /// is not found. Use `with_session_filter` with `parse_logs` to scan everything instead.
+    pub fn parse_sessions_by_id(&self, ids: &[String]) -> Result<Vec<LogEntry>> {
+        let wanted: HashSet<&str> = ids.iter().map(String::as_str).collect();
+        let mut entries = Vec::new();
 
-        for entry in WalkDir::new(dir).max_depth(3) {
-            let entry = entry?;
-            if entry.file_type().is_file() {
-                if let Some(ext) = entry.path().extension() {
-                    if ext == "jsonl" {
-                        // CLAUDETODO: entry.path() returns a &Path, but to_path_buf() clones it.
-                        // Since we're collecting paths anyway, this is necessary, but consider
-                        // using entry.into_path() to avoid the clone if WalkDir allows it.
-                        files.push(entry.path().to_path_buf());
+        for projects_dir in self.projects_dirs()? {
+            for project in std::fs::read_dir(&projects_dir)? {
+                let project = project?.path();
+                if !project.is_dir() {
+                    continue;
+                }
+                for id in &wanted {
+                    for file_name in [format!("{}.jsonl", id), format!("{}.jsonl.gz", id)] {
+                        let file_path = project.join(file_name);
+                        if !file_path.is_file() {
+                            continue;
+                        }
+                        match self.parse_jsonl_file(&file_path) {
+                            Ok(file_entries) => entries.extend(file_entries),
+                            Err(e) => eprintln!("Error parsing {}: {}", file_path.display(), e),
+                        }
                     }
                 }
             }
         }
 
+        entries.retain(|entry| wanted.contains(entry.session_id.as_str()));
+        let filtered_entries = self.filter_by_date(entries);
+        Ok(self.deduplicate_entries(filtered_entries).into_inner())
+    }
+
+    /// Resolve the `projects` directory inside each configured Claude directory
+    pub(crate) fn projects_dirs(&self) -> Result<Vec<PathBuf>> {
+        let mut dirs = Vec::with_capacity(self.claude_dirs.len());
+
+        for claude_dir in &self.claude_dirs {
+            let expanded_path = shellexpand::tilde(claude_dir).into_owned();
+            let projects_dir = Path::new(&expanded_path).join("projects");
+
+            if !projects_dir.exists() {
+                return Err(UsageError::ClaudeDirNotFound(projects_dir).into());
+            }
+
+            dirs.push(projects_dir);
+        }
+
+        Ok(dirs)
+    }
+
+    /// Find JSONL files across the projects directories of all configured Claude directories,
+    /// narrowed to a random sample when sampling is enabled
+    pub(crate) fn find_jsonl_files(&self) -> Result<Vec<PathBuf>> {
+        let mut files = self.find_all_jsonl_files()?;
+
+        if let Some(sample_size) = self.sample_size {
+            if sample_size < files.len() {
+                // Sort first so a given seed picks the same files regardless of walk order
+                files.sort();
+                let mut rng = match self.sample_seed {
+                    Some(seed) => StdRng::seed_from_u64(seed),
+                    None => StdRng::from_entropy(),
+                };
+                files = files.choose_multiple(&mut rng, sample_size).cloned().collect();
+            }
+        }
+
         Ok(files)
     }
 
-    pub(crate) fn parse_jsonl_file(&self, path: &Path) -> Result<Vec<LogEntry>> {
-        let file = File::open(path).context("Failed to open JSONL file")?;
-        let reader = BufReader::new(file);
-        let mut entries = Vec::new();
+    fn find_all_jsonl_files(&self) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
 
-        for (line_num, line) in reader.lines().enumerate() {
-            let line = line.context("Failed to read line")?;
-            if line.trim().is_empty() {
-                continue;
+        for dir in self.projects_dirs()? {
+            for entry in WalkDir::new(&dir).max_depth(self.max_depth) {
+                let entry = entry?;
+                if entry.file_type().is_file() && is_log_file(entry.path()) {
+                    // CLAUDETODO: entry.path() returns a &Path, but to_path_buf() clones it.
+                    // Since we're collecting paths anyway, this is necessary, but consider
+                    // using entry.into_path() to avoid the clone if WalkDir allows it.
+                    files.push(entry.path().to_path_buf());
+                }
             }
+        }
 
-            // CLAUDETODO: This parses the JSON twice - once as serde_json::Value and once as LogEntry.
-            // This is inefficient. Consider either:
-            // 1. Parse once as Value and check fields before deserializing to LogEntry
-            // 2. Add a #[serde(tag = "type")] enum to handle different entry types
-            // 3. Use serde's untagged enum feature to try different formats
-            // First check if this is a known alternative format
-            if let Ok(json_value) = serde_json::from_str::<serde_json::Value>(&line) {
-                // Skip summary entries - they don't contain usage data
-                if json_value.get("type").and_then(|t| t.as_str()) == Some("summary") {
-                    continue;
+        Ok(files)
+    }
+
+    /// Scan up to depth 10 and return the deepest level at which log files were found
+    /// (0 if there are none), so a too-small `max_depth` can be detected
+    pub fn discover_max_depth(&self) -> Result<usize> {
+        let mut deepest = 0;
+
+        for dir in self.projects_dirs()? {
+            for entry in WalkDir::new(&dir).max_depth(DISCOVERY_MAX_DEPTH) {
+                let entry = entry?;
+                if entry.file_type().is_file() && is_log_file(entry.path()) {
+                    deepest = deepest.max(entry.depth());
                 }
             }
+        }
+
+        Ok(deepest)
+    }
+
+    /// Print a warning when log files exist deeper than the configured `max_depth`, so
+    /// they're being skipped. Files shallower than it are all found, so they're fine.
+    pub fn warn_on_depth_mismatch(&self) -> Result<()> {
+        if let Some(warning) = self.depth_mismatch()? {
+            eprintln!("Warning: {}", warning);
+        }
+        Ok(())
+    }
+
+    /// The warning `warn_on_depth_mismatch` prints, for callers that show it themselves
+    pub fn depth_mismatch(&self) -> Result<Option<String>> {
+        Ok(depth_warning(self.discover_max_depth()?, self.max_depth))
+    }
+
+    pub(crate) fn parse_jsonl_file(&self, path: &Path) -> Result<Vec<LogEntry>> {
+        self.read_jsonl_file(path).map(|(entries, _)| entries)
+    }
+
+    /// Parse one whole log file (plain or gzipped), also returning how much of it was read
+    pub(crate) fn read_jsonl_file(&self, path: &Path) -> Result<(Vec<LogEntry>, FileRead)> {
+        let mut entries = Vec::new();
+        let mut sink = |entry| entries.push(entry);
+        let read = if is_gzip_file(path) {
+            // Compressed files are only ever read whole, so their size on disk stands in
+            // for the bytes read
+            let size = std::fs::metadata(path)?.len();
+            let file = File::open(path).context("Failed to open compressed JSONL file")?;
+            let reader = BufReader::new(GzDecoder::new(file));
+            let (_, lines) = self.for_each_entry_in_reader(reader, path, &mut sink)?;
+            FileRead::new(path, size, lines)
+        } else {
+            let file = File::open(path).context("Failed to open JSONL file")?;
+            let reader = BufReader::new(file);
+            let (bytes, lines) = self.for_each_entry_in_reader(reader, path, &mut sink)?;
+            FileRead::new(path, bytes, lines)
+        };
+        Ok((entries, read))
+    }
+
+    /// Returns the number of bytes and lines read
+    fn for_each_entry_in_reader<R: BufRead, F: FnMut(LogEntry)>(
+        &self,
+        mut reader: R,
+        path: &Path,
+        sink: &mut F,
+    ) -> Result<(u64, usize)> {
+        // One buffer is reused for every line rather than allocating a String per line, and
+        // entries are deserialized straight from the bytes, so a line that isn't valid UTF-8
+        // is skipped like any other malformed line instead of ending the file
+        let mut line = Vec::new();
+        let mut bytes = 0u64;
+        let mut lines = 0usize;
+        for line_num in 0.. {
+            line.clear();
+            let read = reader.read_until(b'\n', &mut line).context("Failed to read line")?;
+            if read == 0 {
+                break;
+            }
+            bytes += read as u64;
+            lines += 1;
+            if line.trim_ascii().is_empty() {
+                continue;
+            }
 
-            match serde_json::from_str::<LogEntry>(&line) {
-                Ok(entry) => {
-                    // Only include assistant messages with usage data
-                    if entry.entry_type == "assistant" {
-                        if let Some(message) = &entry.message {
-                            if message.usage.is_some() {
-                                entries.push(entry);
-                            }
-                        }
+            match parse_entry_bytes(&line) {
+                Ok(Some(entry)) => {
+                    if self.matches_entry_filters(&entry) {
+                        sink(entry);
                     }
                 }
+                Ok(None) => {}
                 Err(e) => {
-                    // Only warn about parsing errors for non-summary entries
-                    // and only for the first few lines to avoid spam
+                    // Only warn for the first few lines to avoid spam
                     if !self.quiet && line_num < 5 {
-                        // Check if it's a known issue (missing fields in older formats)
-                        // CLAUDETODO: Calling to_string() on error is expensive. Consider using
-                        // pattern matching on the error type or checking error kind directly.
-                        let error_str = e.to_string();
-                        if !error_str.contains("missing field `id`") && !error_str.contains("missing field `uuid`") {
-                            eprintln!(
-                                "Skipping unexpected entry format in {} line {}: {}",
-                                path.display(),
-                                line_num + 1,
-                                e
-                            );
-                        }
+                        eprintln!(
+                            "Skipping unexpected entry format in {} line {}: {}",
+                            path.display(),
+                            line_num + 1,
+                            e
+                        );
                     }
                 }
             }
         }
 
-        Ok(entries)
+        Ok((bytes, lines))
     }
 
     pub(crate) fn filter_by_date(&self, entries: Vec<LogEntry>) -> Vec<LogEntry> {
         // Parse June 4, 2024 date once outside the loop
-        let june_4_2024 = DateTime::parse_from_rfc3339("2024-06-04T00:00:00Z")
-            .unwrap()
-            .with_timezone(&Utc);
-            
+        let june_4_2024 = format_change_date();
+
         entries
             .into_iter()
-            .filter(|entry| {
-                let in_range = match (self.start_date, self.end_date) {
-                    (Some(start), Some(end)) => entry.timestamp >= start && entry.timestamp <= end,
-                    (Some(start), None) => entry.timestamp >= start,
-                    (None, Some(end)) => entry.timestamp <= end,
-                    (None, None) => true,
-                };
+            .filter(|entry| self.in_date_range(entry, june_4_2024))
+            .collect()
+    }
+
+    fn in_date_range(&self, entry: &LogEntry, june_4_2024: DateTime<Utc>) -> bool {
+        let in_range = match (self.start_date, self.end_date) {
+            (Some(start), Some(end)) => entry.timestamp >= start && entry.timestamp <= end,
+            (Some(start), None) => entry.timestamp >= start,
+            (None, Some(end)) => entry.timestamp <= end,
+            (None, None) => true,
+        };
+
+        // Only include entries after June 4, 2024
+        in_range && entry.timestamp > june_4_2024
+    }
 
-                // Only include entries after June 4, 2024
-                in_range && entry.timestamp > june_4_2024
-            })
+    /// Only the entries of `new_entries` not already in `seen` (or later copies of ones that
+    /// are), deduplicated among themselves and sorted by timestamp; their ids are added to
+    /// `seen`, and ids from before the start date are dropped from it since no entry that
+    /// old is returned again. Unlike `deduplicate_entries`,
+    /// work is proportional to the new entries, not everything seen so far.
+    pub fn deduplicate_incremental(&self, new_entries: Vec<LogEntry>, seen: &mut SeenIds) -> Vec<LogEntry> {
+        if let Some(start) = self.start_date {
+            seen.prune_before(start);
+        }
+        self.deduplicate_entries(new_entries)
+            .into_inner()
+            .into_iter()
+            .filter(|entry| seen.insert(entry, self.dedup_key))
             .collect()
     }
 
-    pub(crate) fn deduplicate_entries(&self, entries: Vec<LogEntry>) -> Vec<LogEntry> {
+    pub(crate) fn deduplicate_entries(&self, entries: Vec<LogEntry>) -> DeduplicatedEntries {
         // CLAUDETODO: This function also takes ownership unnecessarily. Consider using &[LogEntry].
-        // Group by request_id and keep only the latest entry for each
+        // Group by dedup key and keep only the latest entry for each
         // CLAUDETODO: Consider pre-allocating HashMap capacity based on entries.len() to reduce rehashing.
-        let mut request_map: HashMap<String, LogEntry> = HashMap::new();
-        let mut no_request_id_entries = Vec::new();
+        let mut latest: HashMap<String, LogEntry> = HashMap::new();
 
         for entry in entries {
-            if let Some(request_id) = &entry.request_id {
-                // Using entry API to avoid unnecessary cloning
-                request_map.entry(request_id.clone())
-                    .and_modify(|existing| {
-                        if entry.timestamp > existing.timestamp {
-                            *existing = entry.clone();
-                        }
-                    })
-                    .or_insert(entry);
-            } else {
-                // Keep entries without request_id (synthetic messages)
-                no_request_id_entries.push(entry);
+            match latest.entry(dedup_key(self.dedup_key, &entry)) {
+                Entry::Occupied(mut existing) => {
+                    if entry.timestamp > existing.get().timestamp {
+                        existing.insert(entry);
+                    }
+                }
+                Entry::Vacant(slot) => {
+                    slot.insert(entry);
+                }
             }
         }
 
-        // Combine deduplicated entries with no-request-id entries
-        // CLAUDETODO: Pre-allocate capacity for result Vec to avoid reallocations during extend
-        let mut result: Vec<LogEntry> = request_map.into_values().collect();
-        result.extend(no_request_id_entries);
+        let mut result: Vec<LogEntry> = latest.into_values().collect();
 
         // Sort by timestamp - using unstable sort for better performance
-        result.sort_unstable_by(|a, b| a.timestamp.cmp(&b.timestamp));
+        result.sort_unstable_by_key(|e| e.timestamp);
+
+        DeduplicatedEntries(result)
+    }
+}
+
+/// Entries with at most one copy of each request, sorted by timestamp
+#[derive(Debug, Clone, Default)]
+pub struct DeduplicatedEntries(Vec<LogEntry>);
 
-        result
+impl DeduplicatedEntries {
+    pub fn into_inner(self) -> Vec<LogEntry> {
+        self.0
     }
 }
 
+impl std::ops::Deref for DeduplicatedEntries {
+    type Target = [LogEntry];
+
+    fn deref(&self) -> &[LogEntry] {
+        &self.0
+    }
+}
+
+/// How much of one log file a full parse read, so a `FileTracker` can record it as read
+/// without reading it again
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileRead {
+    pub path: PathBuf,
+    /// Bytes read, up to the end of the last line read
+    pub bytes: u64,
+    pub lines: usize,
+}
+
+impl FileRead {
+    fn new(path: &Path, bytes: u64, lines: usize) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            bytes,
+            lines,
+        }
+    }
+}
+
+/// Ids of entries already returned by incremental parsing (see `dedup_key`), with the
+/// timestamp of the latest copy returned. Persisted with the `FileTracker` state.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SeenIds(HashMap<String, DateTime<Utc>>);
+
+impl SeenIds {
+    /// Record `entry`, returning whether it hadn't been seen before or is a later copy of
+    /// one that was, which replaces it as `deduplicate_entries` would
+    pub fn insert(&mut self, entry: &LogEntry, key: DedupKey) -> bool {
+        match self.0.entry(dedup_key(key, entry)) {
+            Entry::Occupied(mut seen) if entry.timestamp > *seen.get() => {
+                seen.insert(entry.timestamp);
+                true
+            }
+            Entry::Occupied(_) => false,
+            Entry::Vacant(slot) => {
+                slot.insert(entry.timestamp);
+                true
+            }
+        }
+    }
+
+    /// Forget ids whose latest copy is from before `start`
+    pub fn prune_before(&mut self, start: DateTime<Utc>) {
+        self.0.retain(|_, timestamp| *timestamp >= start);
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+/// What identifies `entry` as one request: its request id or uuid, as `key` says. Entries
+/// without one (synthetic messages) fall back to `content_key`, so only identical copies,
+/// e.g. the same log synced from two machines, are merged.
+pub fn dedup_key(key: DedupKey, entry: &LogEntry) -> String {
+    let id = match key {
+        DedupKey::RequestId => entry.request_id.as_deref(),
+        DedupKey::Uuid => Some(entry.uuid.as_str()).filter(|uuid| !uuid.is_empty()),
+    };
+    id.map_or_else(|| content_key(entry), str::to_string)
+}
+
+/// Identifies an entry by its content, for entries without a request id: two with the same
+/// session, timestamp, model and token counts are copies of one another
+pub(crate) fn content_key(entry: &LogEntry) -> String {
+    let (model, usage) = match &entry.message {
+        Some(message) => (message.model.canonical_string(), message.usage.clone()),
+        None => (String::new(), None),
+    };
+    let usage = usage.unwrap_or_default();
+    format!(
+        "{}|{}|{}|{}|{}|{}|{}",
+        entry.session_id,
+        entry.timestamp.to_rfc3339(),
+        model,
+        usage.input_tokens,
+        usage.output_tokens,
+        usage.cache_creation_input_tokens,
+        usage.cache_read_input_tokens
+    )
+}
+
+/// Anthropic changed the log format on June 4, 2024; older entries are ignored
+pub(crate) fn format_change_date() -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339("2024-06-04T00:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+/// Check whether a path is a plain or gzip-compressed JSONL log
+pub(crate) fn is_log_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "jsonl") || is_gzip_file(path)
+}
+
+/// Why a log line could not be turned into an entry
+#[derive(Debug, thiserror::Error)]
+pub enum EntryError {
+    #[error("invalid JSON: {0}")]
+    InvalidJson(#[source] serde_json::Error),
+    #[error("unexpected entry shape: {0}")]
+    UnexpectedShape(#[source] serde_json::Error),
+}
+
+impl From<serde_json::Error> for EntryError {
+    fn from(e: serde_json::Error) -> Self {
+        match e.classify() {
+            serde_json::error::Category::Data => EntryError::UnexpectedShape(e),
+            _ => EntryError::InvalidJson(e),
+        }
+    }
+}
+
+/// Parse one JSONL line in a single pass.
+///
+/// Returns `Ok(None)` for lines that are valid but carry no billable usage: summaries,
+/// user messages, unknown entry types, older-format messages without an id, and
+/// assistant messages without usage data.
+pub fn parse_entry_line(line: &str) -> Result<Option<LogEntry>, EntryError> {
+    parse_entry_bytes(line.as_bytes())
+}
+
+/// `parse_entry_line` for a line that hasn't been checked to be UTF-8
+pub fn parse_entry_bytes(line: &[u8]) -> Result<Option<LogEntry>, EntryError> {
+    match serde_json::from_slice::<RawEntry>(line)? {
+        RawEntry::Assistant(mut entry) => {
+            let has_usage = entry
+                .message
+                .as_ref()
+                .is_some_and(|m| m.usage.is_some() && !m.id.is_empty());
+            if !has_usage {
+                return Ok(None);
+            }
+            entry.entry_type = "assistant".to_string();
+            Ok(Some(entry))
+        }
+        RawEntry::Summary(_) | RawEntry::Other => Ok(None),
+    }
+}
+
+/// File name without the `.jsonl` / `.jsonl.gz` suffix, which is the session ID for Claude Code logs
+fn session_file_stem(path: &Path) -> Option<&str> {
+    let name = path.file_name()?.to_str()?;
+    name.strip_suffix(".jsonl.gz")
+        .or_else(|| name.strip_suffix(".jsonl"))
+}
+
+/// Check whether a path is a gzip-compressed JSONL log (`.jsonl.gz`)
+pub(crate) fn is_gzip_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with(".jsonl.gz"))
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn assistant_line(uuid: &str, request_id: &str, timestamp: &str) -> String {
+        format!(
+            r#"{{"type":"assistant","uuid":"{}","timestamp":"{}","sessionId":"test-session","requestId":"{}","message":{{"id":"msg-{}","role":"assistant","model":"claude-opus-4-20250514","usage":{{"input_tokens":100,"output_tokens":50}}}}}}"#,
+            uuid, timestamp, request_id, uuid
+        )
+    }
+
+    fn session_line(uuid: &str, session_id: &str) -> String {
+        format!(
+            r#"{{"type":"assistant","uuid":"{}","timestamp":"2024-12-01T00:00:00Z","sessionId":"{}","requestId":"req-{}","message":{{"id":"msg-{}","role":"assistant","model":"claude-opus-4-20250514","usage":{{"input_tokens":100,"output_tokens":50}}}}}}"#,
+            uuid, session_id, uuid, uuid
+        )
+    }
+
+    fn create_claude_dir(lines: &[String]) -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let project_dir = temp_dir.path().join("projects").join("test-project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(project_dir.join("session.jsonl"), lines.join("\n")).unwrap();
+        temp_dir
+    }
+
+    fn dir_string(dir: &TempDir) -> String {
+        dir.path().to_string_lossy().to_string()
+    }
+
+    /// An assistant entry with the given request id (if any), timestamp and output tokens
+    fn log_entry(uuid: &str, request_id: Option<&str>, timestamp: &str, output_tokens: u64) -> LogEntry {
+        LogEntry {
+            entry_type: "assistant".to_string(),
+            uuid: uuid.to_string(),
+            parent_uuid: None,
+            timestamp: timestamp.parse().unwrap(),
+            session_id: "s".to_string(),
+            request_id: request_id.map(str::to_string),
+            version: None,
+            message: Some(crate::models::Message {
+                id: format!("msg-{}", uuid),
+                role: "assistant".to_string(),
+                model: ModelName::Claude4Sonnet,
+                usage: Some(crate::models::TokenUsage {
+                    input_tokens: 10,
+                    output_tokens,
+                    ..Default::default()
+                }),
+                tool_result_content: false,
+            }),
+            is_sidechain: None,
+        }
+    }
+
+    fn uuids(entries: &[LogEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.uuid.as_str()).collect()
+    }
 
     #[test]
     fn test_date_filtering() {
-        // Add tests here
+        let entries = || {
+            vec![
+                log_entry("may", Some("r1"), "2024-05-20T12:00:00Z", 1),
+                log_entry("cutoff", Some("r2"), "2024-06-04T00:00:00Z", 1),
+                log_entry("june", Some("r3"), "2024-06-05T00:00:00Z", 1),
+                log_entry("dec1", Some("r4"), "2024-12-01T00:00:00Z", 1),
+                log_entry("dec2", Some("r5"), "2024-12-02T23:59:59Z", 1),
+                log_entry("dec3", Some("r6"), "2024-12-03T00:00:01Z", 1),
+            ]
+        };
+
+        // Without a range only the log format change cutoff applies, and it's exclusive
+        let parser = LogParser::new("unused".to_string());
+        assert_eq!(uuids(&parser.filter_by_date(entries())), ["june", "dec1", "dec2", "dec3"]);
+
+        // Both ends of a range are inclusive
+        let parser = LogParser::new("unused".to_string()).with_date_range(
+            Some("2024-12-01T00:00:00Z".parse().unwrap()),
+            Some("2024-12-02T23:59:59Z".parse().unwrap()),
+        );
+        assert_eq!(uuids(&parser.filter_by_date(entries())), ["dec1", "dec2"]);
+
+        // An open start still can't reach before the cutoff
+        let parser = LogParser::new("unused".to_string())
+            .with_date_range(None, Some("2024-12-01T00:00:00Z".parse().unwrap()));
+        assert_eq!(uuids(&parser.filter_by_date(entries())), ["june", "dec1"]);
+
+        let parser = LogParser::new("unused".to_string())
+            .with_date_range(Some("2024-12-02T00:00:00Z".parse().unwrap()), None);
+        assert_eq!(uuids(&parser.filter_by_date(entries())), ["dec2", "dec3"]);
     }
 
     #[test]
     fn test_deduplication() {
-        // Add tests here
+        let parser = LogParser::new("unused".to_string());
+        let entries = vec![
+            log_entry("late", Some("r1"), "2024-12-01T10:00:05Z", 600),
+            log_entry("early", Some("r1"), "2024-12-01T10:00:00Z", 100),
+            log_entry("other", Some("r2"), "2024-12-01T09:00:00Z", 50),
+            // Without a request id, distinct entries are all kept...
+            log_entry("synthetic-a", None, "2024-12-01T11:00:00Z", 0),
+            log_entry("synthetic-b", None, "2024-12-01T11:00:01Z", 7),
+            // ...and only exact copies are dropped
+            log_entry("synthetic-a-copy", None, "2024-12-01T11:00:00Z", 0),
+        ];
+
+        let deduped = parser.deduplicate_entries(entries);
+        // Sorted by timestamp, with the newer copy of r1 winning
+        assert_eq!(uuids(&deduped), ["other", "late", "synthetic-a", "synthetic-b"]);
+        let r1 = deduped.iter().find(|entry| entry.request_id.as_deref() == Some("r1")).unwrap();
+        assert_eq!(r1.message.as_ref().unwrap().usage.as_ref().unwrap().output_tokens, 600);
+    }
+
+    #[test]
+    fn test_dedups_identical_copies_without_request_id() {
+        let synthetic = |uuid: &str, timestamp: &str, output_tokens: u64| {
+            format!(
+                r#"{{"type":"assistant","uuid":"{}","timestamp":"{}","sessionId":"s","message":{{"id":"msg-{}","role":"assistant","model":"<synthetic>","usage":{{"input_tokens":0,"output_tokens":{}}}}}}}"#,
+                uuid, timestamp, uuid, output_tokens
+            )
+        };
+        // The same synthetic entry synced to two machines, plus two genuinely different ones
+        let laptop = create_claude_dir(&[
+            synthetic("x1", "2024-12-01T00:00:00Z", 0),
+            synthetic("x2", "2024-12-01T00:01:00Z", 0),
+        ]);
+        let desktop = create_claude_dir(&[
+            synthetic("x1", "2024-12-01T00:00:00Z", 0),
+            synthetic("x3", "2024-12-01T00:00:00Z", 5),
+        ]);
+
+        let parser = LogParser::new_multi(vec![dir_string(&laptop), dir_string(&desktop)]).quiet();
+        let mut uuids: Vec<_> = parser.parse_logs().unwrap().into_iter().map(|e| e.uuid).collect();
+        uuids.sort();
+        assert_eq!(uuids, ["x1", "x2", "x3"]);
+    }
+
+    #[test]
+    fn test_deduplicate_incremental() {
+        let entry = |uuid: &str| parse_entry_line(&session_line(uuid, "s")).unwrap().unwrap();
+        let parser = LogParser::new("unused".to_string()).quiet();
+        let mut seen = SeenIds::default();
+
+        let first = parser.deduplicate_incremental(vec![entry("a"), entry("b"), entry("a")], &mut seen);
+        assert_eq!(first.len(), 2);
+        assert_eq!(seen.len(), 2);
+
+        let second = parser.deduplicate_incremental(vec![entry("b"), entry("c")], &mut seen);
+        let uuids: Vec<_> = second.iter().map(|e| e.uuid.as_str()).collect();
+        assert_eq!(uuids, ["c"]);
+        assert_eq!(seen.len(), 3);
+
+        // Ids older than the date window are dropped once the window has moved past them
+        let start = "2024-12-02T00:00:00Z".parse().ok();
+        let parser = parser.with_date_range(start, None);
+        assert!(parser.deduplicate_incremental(Vec::new(), &mut seen).is_empty());
+        assert!(seen.is_empty());
+    }
+
+    #[test]
+    fn test_multiple_claude_dirs() {
+        let personal = create_claude_dir(&[
+            assistant_line("a1", "req-a1", "2024-12-01T00:00:00Z"),
+            assistant_line("a2", "req-a2", "2024-12-01T00:01:00Z"),
+            assistant_line("shared", "req-shared", "2024-12-01T00:02:00Z"),
+        ]);
+        let work = create_claude_dir(&[
+            assistant_line("b1", "req-b1", "2024-12-02T00:00:00Z"),
+            assistant_line("shared", "req-shared", "2024-12-01T00:02:00Z"),
+        ]);
+
+        let personal_count = LogParser::new(dir_string(&personal)).quiet().parse_logs().unwrap().len();
+        let work_count = LogParser::new(dir_string(&work)).quiet().parse_logs().unwrap().len();
+        assert_eq!(personal_count, 3);
+        assert_eq!(work_count, 2);
+
+        let parser = LogParser::new_multi(vec![dir_string(&personal), dir_string(&work)]).quiet();
+        assert_eq!(parser.find_jsonl_files().unwrap().len(), 2);
+
+        // The shared request_id must only be counted once
+        let combined = parser.parse_logs().unwrap();
+        assert_eq!(combined.len(), personal_count + work_count - 1);
+    }
+
+    #[test]
+    fn test_streaming_yields_same_entries_before_dedup() {
+        let first = create_claude_dir(&[
+            assistant_line("a1", "req-a1", "2024-12-01T00:00:00Z"),
+            assistant_line("a1b", "req-a1", "2024-12-01T00:00:05Z"),
+            assistant_line("old", "req-old", "2024-05-01T00:00:00Z"),
+        ]);
+        let second = create_claude_dir(&[assistant_line("b1", "req-b1", "2024-12-02T00:00:00Z")]);
+        let parser = LogParser::new_multi(vec![dir_string(&first), dir_string(&second)]).quiet();
+
+        let mut streamed: Vec<LogEntry> =
+            parser.parse_logs_streaming().unwrap().collect::<Result<_>>().unwrap();
+        streamed.sort_by_key(|entry| entry.timestamp);
+        // Date filtered but not deduplicated
+        assert_eq!(uuids(&streamed), ["a1", "a1b", "b1"]);
+
+        let deduped = parser.deduplicate_entries(streamed).into_inner();
+        assert_eq!(uuids(&deduped), uuids(&parser.parse_logs().unwrap()));
+
+        let missing = TempDir::new().unwrap();
+        let missing_parser = LogParser::new(dir_string(&missing)).quiet();
+        assert!(missing_parser.parse_logs_streaming().is_err());
+    }
+
+    #[test]
+    fn test_invalid_utf8_line_is_skipped() {
+        let temp_dir = create_claude_dir(&[]);
+        let mut content = assistant_line("a1", "req-a1", "2024-12-01T00:00:00Z").into_bytes();
+        content.extend_from_slice(b"\n{\"type\":\"user\",\"text\":\"\xff\xfe\"}\n");
+        content.extend(assistant_line("a2", "req-a2", "2024-12-01T00:01:00Z").into_bytes());
+        let log = temp_dir.path().join("projects").join("test-project").join("session.jsonl");
+        std::fs::write(log, content).unwrap();
+
+        let entries = LogParser::new(dir_string(&temp_dir)).quiet().parse_logs().unwrap();
+        assert_eq!(uuids(&entries), ["a1", "a2"]);
+    }
+
+    #[test]
+    fn test_missing_claude_dir_in_multi() {
+        let existing = create_claude_dir(&[assistant_line("a1", "req-a1", "2024-12-01T00:00:00Z")]);
+        let missing = TempDir::new().unwrap();
+
+        let parser = LogParser::new_multi(vec![dir_string(&existing), dir_string(&missing)]).quiet();
+        let err = parser.parse_logs().unwrap_err();
+        let usage_error = err.downcast_ref::<UsageError>().unwrap();
+        assert!(matches!(
+            usage_error,
+            UsageError::ClaudeDirNotFound(path) if path.starts_with(missing.path())
+        ));
+        assert_eq!(usage_error.exit_code(), 3);
+    }
+
+    #[test]
+    fn test_gzip_jsonl_matches_uncompressed() {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        use std::io::Write;
+
+        let lines = [
+            assistant_line("a1", "req-a1", "2024-12-01T00:00:00Z"),
+            assistant_line("a2", "req-a2", "2024-12-01T00:01:00Z"),
+        ];
+        let plain = create_claude_dir(&lines);
+
+        let compressed = TempDir::new().unwrap();
+        let project_dir = compressed.path().join("projects").join("test-project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        let file = File::create(project_dir.join("session.jsonl.gz")).unwrap();
+        let mut encoder = GzEncoder::new(file, Compression::default());
+        encoder.write_all(lines.join("\n").as_bytes()).unwrap();
+        encoder.finish().unwrap();
+
+        let plain_entries = LogParser::new(dir_string(&plain)).quiet().parse_logs().unwrap();
+        let gz_entries = LogParser::new(dir_string(&compressed)).quiet().parse_logs().unwrap();
+
+        assert_eq!(gz_entries.len(), 2);
+        let plain_uuids: Vec<_> = plain_entries.iter().map(|e| e.uuid.as_str()).collect();
+        let gz_uuids: Vec<_> = gz_entries.iter().map(|e| e.uuid.as_str()).collect();
+        assert_eq!(plain_uuids, gz_uuids);
+    }
+
+    #[test]
+    fn test_is_log_file() {
+        assert!(is_log_file(Path::new("session.jsonl")));
+        assert!(is_log_file(Path::new("session.jsonl.gz")));
+        assert!(!is_log_file(Path::new("archive.tar.gz")));
+        assert!(!is_log_file(Path::new("notes.json")));
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let temp_dir = TempDir::new().unwrap();
+        // projects/org/team/repo/session.jsonl sits at depth 4
+        let nested_dir = temp_dir.path().join("projects").join("org").join("team").join("repo");
+        std::fs::create_dir_all(&nested_dir).unwrap();
+        std::fs::write(
+            nested_dir.join("session.jsonl"),
+            assistant_line("a1", "req-a1", "2024-12-01T00:00:00Z"),
+        )
+        .unwrap();
+
+        let parser = LogParser::new(dir_string(&temp_dir)).quiet();
+        assert_eq!(parser.max_depth, DEFAULT_MAX_DEPTH);
+        assert!(parser.find_jsonl_files().unwrap().is_empty());
+        assert_eq!(parser.discover_max_depth().unwrap(), 4);
+
+        assert!(depth_warning(4, DEFAULT_MAX_DEPTH).unwrap().contains("depth 4"));
+
+        let parser = parser.with_max_depth(4);
+        assert_eq!(parser.find_jsonl_files().unwrap().len(), 1);
+        assert_eq!(parser.parse_logs().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_no_depth_warning_for_standard_layout() {
+        // projects/<project>/<session>.jsonl is at depth 2, within the default
+        assert!(depth_warning(2, DEFAULT_MAX_DEPTH).is_none());
+        assert!(depth_warning(3, 3).is_none());
+        assert!(depth_warning(0, DEFAULT_MAX_DEPTH).is_none());
+    }
+
+    #[test]
+    fn test_parse_single_session() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_a = temp_dir.path().join("projects").join("project-a");
+        let project_b = temp_dir.path().join("projects").join("project-b");
+        std::fs::create_dir_all(&project_a).unwrap();
+        std::fs::create_dir_all(&project_b).unwrap();
+
+        std::fs::write(
+            project_a.join("session-1.jsonl"),
+            [session_line("a1", "session-1"), session_line("a2", "session-1")].join("\n"),
+        )
+        .unwrap();
+        std::fs::write(
+            project_b.join("session-2.jsonl"),
+            [session_line("b1", "session-2"), session_line("b2", "session-2")].join("\n"),
+        )
+        .unwrap();
+        // Session file not named after its ID
+        std::fs::write(
+            project_b.join("renamed.jsonl"),
+            [session_line("c1", "session-3"), session_line("b3", "session-2")].join("\n"),
+        )
+        .unwrap();
+
+        let parser = LogParser::new(dir_string(&temp_dir)).quiet();
+
+        let session_1 = parser.parse_single_session("session-1").unwrap();
+        let uuids: Vec<_> = session_1.iter().map(|e| e.uuid.as_str()).collect();
+        assert_eq!(uuids.len(), 2);
+        assert!(uuids.contains(&"a1") && uuids.contains(&"a2"));
+
+        // The named file is checked first, so the stray entry in renamed.jsonl is not reached
+        let session_2 = parser.parse_single_session("session-2").unwrap();
+        assert_eq!(session_2.len(), 2);
+        assert!(session_2.iter().all(|e| e.session_id == "session-2"));
+
+        let session_3 = parser.parse_single_session("session-3").unwrap();
+        assert_eq!(session_3.len(), 1);
+        assert_eq!(session_3[0].uuid, "c1");
+
+        assert!(parser.parse_single_session("missing").unwrap().is_empty());
+    }
+
+    /// Two sessions, each in its own `<session_id>.jsonl` in separate projects
+    fn two_session_dir() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        for (project, session, uuids) in [
+            ("project-a", "session-1", ["a1", "a2"]),
+            ("project-b", "session-2", ["b1", "b2"]),
+        ] {
+            let dir = temp_dir.path().join("projects").join(project);
+            std::fs::create_dir_all(&dir).unwrap();
+            let lines: Vec<_> = uuids.iter().map(|uuid| session_line(uuid, session)).collect();
+            std::fs::write(dir.join(format!("{}.jsonl", session)), lines.join("\n")).unwrap();
+        }
+        temp_dir
+    }
+
+    #[test]
+    fn test_session_filter() {
+        let temp_dir = two_session_dir();
+
+        let entries = LogParser::new(dir_string(&temp_dir))
+            .quiet()
+            .with_session_filter(vec!["session-2".to_string()])
+            .parse_logs()
+            .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|e| e.session_id == "session-2"));
+    }
+
+    #[test]
+    fn test_parse_sessions_by_id() {
+        let temp_dir = two_session_dir();
+        let parser = LogParser::new(dir_string(&temp_dir)).quiet();
+
+        let entries = parser.parse_sessions_by_id(&["session-1".to_string()]).unwrap();
+        let mut uuids: Vec<_> = entries.iter().map(|e| e.uuid.as_str()).collect();
+        uuids.sort();
+        assert_eq!(uuids, ["a1", "a2"]);
+
+        let both = ["session-1".to_string(), "session-2".to_string()];
+        assert_eq!(parser.parse_sessions_by_id(&both).unwrap().len(), 4);
+        assert!(parser.parse_sessions_by_id(&["missing".to_string()]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_progress_callback() {
+        let temp_dir = two_session_dir();
+        let updates = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = std::sync::Arc::clone(&updates);
+
+        let entries = LogParser::new(dir_string(&temp_dir))
+            .with_progress(move |progress| sink.lock().unwrap().push(progress))
+            .parse_logs()
+            .unwrap();
+        assert_eq!(entries.len(), 4);
+
+        let updates = updates.lock().unwrap();
+        let parsing: Vec<_> = updates
+            .iter()
+            .filter(|p| p.phase == ParsePhase::Parsing)
+            .map(|p| (p.files_done, p.files_total))
+            .collect();
+        assert_eq!(parsing, [(0, 2), (1, 2), (2, 2)]);
+        assert_eq!(updates.first().unwrap().phase, ParsePhase::Discovery);
+        assert_eq!(updates.last().unwrap().phase, ParsePhase::Done);
+    }
+
+    #[test]
+    fn test_model_filter() {
+        let sonnet_line = assistant_line("s1", "req-s1", "2024-12-01T00:05:00Z")
+            .replace("claude-opus-4-20250514", "claude-sonnet-4-20250514");
+        let temp_dir = create_claude_dir(&[
+            assistant_line("o1", "req-o1", "2024-12-01T00:00:00Z"),
+            sonnet_line,
+            assistant_line("o2", "req-o2", "2024-12-01T00:10:00Z"),
+        ]);
+
+        let all = LogParser::new(dir_string(&temp_dir)).quiet().parse_logs().unwrap();
+        assert_eq!(all.len(), 3);
+
+        let sonnet = LogParser::new(dir_string(&temp_dir))
+            .quiet()
+            .with_model_filter(ModelName::Claude4Sonnet)
+            .parse_logs()
+            .unwrap();
+        assert_eq!(sonnet.len(), 1);
+        assert_eq!(sonnet[0].uuid, "s1");
+
+        let opus = LogParser::new(dir_string(&temp_dir))
+            .quiet()
+            .with_model_filter(ModelName::Claude4Opus)
+            .parse_logs()
+            .unwrap();
+        assert!(opus.iter().all(|e| e.message.as_ref().unwrap().model == ModelName::Claude4Opus));
+        assert_eq!(opus.len(), 2);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_sampling_is_seedable() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_dir = temp_dir.path().join("projects").join("test-project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        for i in 0..10 {
+            std::fs::write(
+                project_dir.join(format!("session-{}.jsonl", i)),
+                session_line(&format!("u{}", i), &format!("session-{}", i)),
+            )
+            .unwrap();
+        }
+
+        let parser = LogParser::new(dir_string(&temp_dir)).quiet().with_sample(4, Some(42));
+        let first = parser.find_jsonl_files().unwrap();
+        let second = parser.find_jsonl_files().unwrap();
+        assert_eq!(first.len(), 4);
+        assert_eq!(first, second);
+        assert_eq!(parser.sample_scale().unwrap(), Some(2.5));
+        assert_eq!(parser.parse_logs().unwrap().len(), 4);
+
+        // Sampling more files than exist falls back to an exact parse
+        let parser = LogParser::new(dir_string(&temp_dir)).quiet().with_sample(50, None);
+        assert_eq!(parser.find_jsonl_files().unwrap().len(), 10);
+        assert_eq!(parser.sample_scale().unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_entry_line_shapes() {
+        let assistant = assistant_line("a1", "req-a1", "2024-12-01T00:00:00Z");
+        let entry = parse_entry_line(&assistant).unwrap().unwrap();
+        assert_eq!(entry.entry_type, "assistant");
+        assert_eq!(entry.uuid, "a1");
+
+        let summary = r#"{"type":"summary","summary":"A conversation","leafUuid":"abc"}"#;
+        assert!(parse_entry_line(summary).unwrap().is_none());
+
+        let user = r#"{"type":"user","uuid":"u1","timestamp":"2024-12-01T00:00:00Z","sessionId":"s","message":{"role":"user","content":"hi"}}"#;
+        assert!(parse_entry_line(user).unwrap().is_none());
+
+        // Older format: message without an id
+        let older = assistant.replace(r#""id":"msg-a1","#, "");
+        assert!(parse_entry_line(&older).unwrap().is_none());
+
+        let unknown_type = r#"{"type":"tool-progress","anything":1}"#;
+        assert!(parse_entry_line(unknown_type).unwrap().is_none());
+
+        assert!(matches!(
+            parse_entry_line(r#"{"type":"assistant","uuid":42}"#),
+            Err(EntryError::UnexpectedShape(_))
+        ));
+        assert!(matches!(
+            parse_entry_line("{not json"),
+            Err(EntryError::InvalidJson(_))
+        ));
+    }
+
+    #[test]
+    fn test_tool_results_skipped_unless_included() {
+        let tool_role = assistant_line("t1", "req-t1", "2024-12-01T00:01:00Z")
+            .replace(r#""role":"assistant""#, r#""role":"tool""#);
+        let tool_block = assistant_line("t2", "req-t2", "2024-12-01T00:02:00Z").replace(
+            r#""role":"assistant","#,
+            r#""role":"assistant","content":[{"type":"tool_result","tool_use_id":"x","content":"ok"}],"#,
+        );
+        let tool_text = assistant_line("t3", "req-t3", "2024-12-01T00:03:00Z").replace(
+            r#""role":"assistant","#,
+            r#""role":"assistant","content":"<function_results>ok</function_results>","#,
+        );
+        let tool_use = assistant_line("a2", "req-a2", "2024-12-01T00:04:00Z").replace(
+            r#""role":"assistant","#,
+            r#""role":"assistant","content":[{"type":"tool_use","id":"x","name":"Read","input":{}}],"#,
+        );
+        let temp_dir = create_claude_dir(&[
+            assistant_line("a1", "req-a1", "2024-12-01T00:00:00Z"),
+            tool_role,
+            tool_block,
+            tool_text,
+            tool_use,
+        ]);
+
+        let entries = LogParser::new(dir_string(&temp_dir)).quiet().parse_logs().unwrap();
+        assert_eq!(uuids(&entries), ["a1", "a2"]);
+
+        let entries = LogParser::new(dir_string(&temp_dir))
+            .quiet()
+            .with_tool_results(true)
+            .parse_logs()
+            .unwrap();
+        assert_eq!(entries.len(), 5);
+    }
+
+    #[test]
+    fn test_parse_logs_profiled() {
+        let temp_dir = create_claude_dir(&[
+            assistant_line("a1", "req-a1", "2024-12-01T00:00:00Z"),
+            assistant_line("a2", "req-a1", "2024-12-01T00:01:00Z"),
+        ]);
+        let (entries, profile) = LogParser::new(dir_string(&temp_dir))
+            .quiet()
+            .parse_logs_profiled()
+            .unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert!(profile.total_ms > 0.0);
+        assert_eq!(profile.files_total, 1);
+        assert_eq!(profile.entries_parsed, 2);
+        assert_eq!(profile.entries_final, 1);
+        assert_eq!(profile.slowest_files.len(), 1);
+
+        let json: serde_json::Value = serde_json::to_value(&profile).unwrap();
+        assert!(json["slowest_files"][0]["ms"].is_f64());
+    }
+
+    #[test]
+    fn test_parse_cache_creation_breakdown() {
+        // Newer logs report cache writes per cache lifetime, sometimes without the flat count
+        let breakdown_only = r#"{"type":"assistant","uuid":"n1","timestamp":"2025-08-01T00:00:00Z","sessionId":"s","requestId":"req-n1","message":{"id":"msg-n1","role":"assistant","model":"claude-sonnet-4-20250514","usage":{"input_tokens":4,"cache_read_input_tokens":12000,"cache_creation":{"ephemeral_5m_input_tokens":1500,"ephemeral_1h_input_tokens":500},"output_tokens":300,"service_tier":"standard"}}}"#;
+        let entry = parse_entry_line(breakdown_only).unwrap().unwrap();
+        let usage = entry.message.unwrap().usage.unwrap();
+        assert_eq!(usage.cache_creation_input_tokens, 2000);
+        assert_eq!(usage.cache_read_input_tokens, 12000);
+        assert_eq!(usage.total_tokens(), 14304);
+        assert_eq!(usage.cache_creation.unwrap().ephemeral_1h_input_tokens, 500);
+
+        // With both present the breakdown isn't counted twice
+        let both = breakdown_only.replace(
+            r#""input_tokens":4,"#,
+            r#""input_tokens":4,"cache_creation_input_tokens":2000,"#,
+        );
+        let entry = parse_entry_line(&both).unwrap().unwrap();
+        assert_eq!(entry.message.unwrap().usage.unwrap().cache_creation_input_tokens, 2000);
+    }
+}
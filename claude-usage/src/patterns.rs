@@ -0,0 +1,149 @@
+use crate::formatters::format_patterns;
+use crate::models::{LogEntry, PricingMap};
+use crate::parser::LogParser;
+use crate::pricing::{get_default_pricing, get_model_pricing};
+use anyhow::Result;
+use chrono::{Datelike, Local, NaiveDate, TimeZone, Timelike, Utc};
+use std::io::Write;
+
+/// Cost of every priced entry, bucketed by the index `bucket` picks from its timestamp
+fn cost_by<Tz: TimeZone, const N: usize>(
+    entries: &[LogEntry],
+    pricing_map: &PricingMap,
+    tz: &Tz,
+    bucket: impl Fn(&chrono::DateTime<Tz>) -> usize,
+) -> [f64; N] {
+    let mut costs = [0.0; N];
+    for entry in entries {
+        let Some(message) = &entry.message else {
+            continue;
+        };
+        let Some(usage) = &message.usage else {
+            continue;
+        };
+        if let Some(pricing) = get_model_pricing(pricing_map, &message.model) {
+            let cost = pricing.calculate_cost_with_tier(usage);
+            costs[bucket(&entry.timestamp.with_timezone(tz))] += cost;
+        }
+    }
+    costs
+}
+
+/// Total cost per hour of the day (0-23) on the clock of `tz`
+pub fn usage_by_hour<Tz: TimeZone>(
+    entries: &[LogEntry],
+    pricing_map: &PricingMap,
+    tz: &Tz,
+) -> [f64; 24] {
+    cost_by(entries, pricing_map, tz, |time| time.hour() as usize)
+}
+
+/// Total cost per day of the week, Monday first, on the calendar of `tz`
+pub fn usage_by_weekday<Tz: TimeZone>(
+    entries: &[LogEntry],
+    pricing_map: &PricingMap,
+    tz: &Tz,
+) -> [f64; 7] {
+    cost_by(entries, pricing_map, tz, |time| {
+        time.weekday().num_days_from_monday() as usize
+    })
+}
+
+/// Run `patterns`: cost by hour of day and by weekday over the parsed logs, on the local
+/// clock unless `utc` is set
+pub fn run_patterns<W: Write>(
+    claude_dirs: Vec<String>,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+    utc: bool,
+    colored: bool,
+    writer: &mut W,
+) -> Result<()> {
+    let start_date = start_date.map(|d| Utc.from_utc_datetime(&d.and_hms_opt(0, 0, 0).unwrap()));
+    let end_date = end_date.map(|d| Utc.from_utc_datetime(&d.and_hms_opt(23, 59, 59).unwrap()));
+    let entries = LogParser::new_multi(claude_dirs)
+        .with_date_range(start_date, end_date)
+        .quiet()
+        .parse_logs()?;
+    if entries.is_empty() {
+        writeln!(writer, "No usage data found for the specified date range.")?;
+        return Ok(());
+    }
+
+    let pricing_map = get_default_pricing();
+    let (hours, weekdays) = if utc {
+        (
+            usage_by_hour(&entries, &pricing_map, &Utc),
+            usage_by_weekday(&entries, &pricing_map, &Utc),
+        )
+    } else {
+        (
+            usage_by_hour(&entries, &pricing_map, &Local),
+            usage_by_weekday(&entries, &pricing_map, &Local),
+        )
+    };
+    write!(writer, "{}", format_patterns(&hours, &weekdays, colored))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model_name::ModelName;
+    use crate::models::{Message, TokenUsage};
+    use chrono::FixedOffset;
+
+    fn entry(timestamp: &str) -> LogEntry {
+        LogEntry {
+            entry_type: "assistant".to_string(),
+            uuid: timestamp.to_string(),
+            parent_uuid: None,
+            timestamp: timestamp.parse().unwrap(),
+            session_id: "s".to_string(),
+            request_id: Some(timestamp.to_string()),
+            version: None,
+            message: Some(Message {
+                id: "msg".to_string(),
+                role: "assistant".to_string(),
+                model: ModelName::Claude4Opus,
+                // $15/M input: $1.50
+                usage: Some(TokenUsage {
+                    input_tokens: 100_000,
+                    ..Default::default()
+                }),
+                tool_result_content: false,
+            }),
+            is_sidechain: None,
+        }
+    }
+
+    #[test]
+    fn test_usage_by_hour_and_weekday() {
+        let pricing = get_default_pricing();
+        // 2025-06-03 is a Tuesday
+        let entries = vec![
+            entry("2025-06-03T14:10:00Z"),
+            entry("2025-06-03T14:50:00Z"),
+            entry("2025-06-03T23:30:00Z"),
+        ];
+
+        let hours = usage_by_hour(&entries, &pricing, &Utc);
+        assert!((hours[14] - 3.0).abs() < 1e-9);
+        assert!((hours[23] - 1.5).abs() < 1e-9);
+        assert!((hours.iter().sum::<f64>() - 4.5).abs() < 1e-9);
+
+        let weekdays = usage_by_weekday(&entries, &pricing, &Utc);
+        assert!((weekdays[1] - 4.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_buckets_follow_timezone() {
+        let pricing = get_default_pricing();
+        let entries = vec![entry("2025-06-03T23:30:00Z")];
+        let tokyo = FixedOffset::east_opt(9 * 3600).unwrap();
+
+        // 08:30 on Wednesday in UTC+9
+        assert!(usage_by_hour(&entries, &pricing, &tokyo)[8] > 0.0);
+        assert!(usage_by_weekday(&entries, &pricing, &tokyo)[2] > 0.0);
+    }
+}
@@ -1,7 +1,12 @@
 use crate::model_name::ModelName;
 use crate::models::{ModelPricing, PricingMap};
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use std::collections::HashMap;
+use std::sync::Once;
+use std::time::Duration;
+
+/// Batch API (and background) requests are billed at half the standard rates
+pub const DEFAULT_BATCH_DISCOUNT: f64 = 0.5;
 
 // Hardcoded pricing as of June 2024
 // Source: https://docs.anthropic.com/en/docs/about-claude/models
@@ -16,6 +21,8 @@ pub fn get_default_pricing() -> PricingMap {
             output_per_million: 15.0,
             cache_write_per_million: 3.75,
             cache_read_per_million: 0.30,
+            batch_discount: DEFAULT_BATCH_DISCOUNT,
+            cache_write_1h_per_million: Some(6.0),
         },
     );
 
@@ -27,6 +34,8 @@ pub fn get_default_pricing() -> PricingMap {
             output_per_million: 4.0,
             cache_write_per_million: 1.0,
             cache_read_per_million: 0.08,
+            batch_discount: DEFAULT_BATCH_DISCOUNT,
+            cache_write_1h_per_million: Some(1.6),
         },
     );
 
@@ -38,6 +47,8 @@ pub fn get_default_pricing() -> PricingMap {
             output_per_million: 75.0,
             cache_write_per_million: 18.75,
             cache_read_per_million: 1.50,
+            batch_discount: DEFAULT_BATCH_DISCOUNT,
+            cache_write_1h_per_million: Some(30.0),
         },
     );
 
@@ -49,6 +60,8 @@ pub fn get_default_pricing() -> PricingMap {
             output_per_million: 75.0,
             cache_write_per_million: 18.75,
             cache_read_per_million: 1.50,
+            batch_discount: DEFAULT_BATCH_DISCOUNT,
+            cache_write_1h_per_million: Some(30.0),
         },
     );
 
@@ -60,6 +73,8 @@ pub fn get_default_pricing() -> PricingMap {
             output_per_million: 15.0,
             cache_write_per_million: 3.75,
             cache_read_per_million: 0.30,
+            batch_discount: DEFAULT_BATCH_DISCOUNT,
+            cache_write_1h_per_million: Some(6.0),
         },
     );
 
@@ -71,6 +86,21 @@ pub fn get_default_pricing() -> PricingMap {
             output_per_million: 15.0,
             cache_write_per_million: 3.75,
             cache_read_per_million: 0.30,
+            batch_discount: DEFAULT_BATCH_DISCOUNT,
+            cache_write_1h_per_million: Some(6.0),
+        },
+    );
+
+    // Claude 3 Sonnet
+    pricing.insert(
+        ModelName::Claude3Sonnet,
+        ModelPricing {
+            input_per_million: 3.0,
+            output_per_million: 15.0,
+            cache_write_per_million: 3.75,
+            cache_read_per_million: 0.30,
+            batch_discount: DEFAULT_BATCH_DISCOUNT,
+            cache_write_1h_per_million: Some(6.0),
         },
     );
 
@@ -82,19 +112,167 @@ pub fn get_default_pricing() -> PricingMap {
             output_per_million: 1.25,
             cache_write_per_million: 0.30,
             cache_read_per_million: 0.03,
+            batch_discount: DEFAULT_BATCH_DISCOUNT,
+            cache_write_1h_per_million: Some(0.5),
         },
     );
 
+    static WARN_MISSING: Once = Once::new();
+    WARN_MISSING.call_once(|| {
+        for model in missing_pricing(&pricing) {
+            eprintln!("Warning: No built-in pricing for {}", model);
+        }
+    });
+
     pricing
 }
 
-pub async fn fetch_latest_pricing() -> Result<PricingMap> {
-    // In a real implementation, this would fetch from Anthropic's API
-    // For now, we'll just return the hardcoded pricing
-    // This is a placeholder for future API integration
-    
-    println!("Note: Using hardcoded pricing. API integration coming soon.");
-    Ok(get_default_pricing())
+/// Known models with no entry in `pricing_map`
+pub fn missing_pricing(pricing_map: &PricingMap) -> Vec<&'static ModelName> {
+    ModelName::all_known()
+        .iter()
+        .filter(|model| !pricing_map.contains_key(model))
+        .collect()
+}
+
+/// Set every model's batch discount, for `--batch-discount`
+pub fn set_batch_discount(pricing_map: &mut PricingMap, batch_discount: f64) {
+    for pricing in pricing_map.values_mut() {
+        pricing.batch_discount = batch_discount;
+    }
+}
+
+/// Where `--refresh-pricing` fetches from. There's no public pricing endpoint yet, so the
+/// hardcoded pricing is used until one is set here.
+const PRICING_URL: Option<&str> = None;
+
+/// Default for `--pricing-timeout`, in seconds
+pub const DEFAULT_PRICING_TIMEOUT_SECS: f64 = 10.0;
+
+/// How hard to try fetching pricing before giving up
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Limit on the whole fetch, retries and backoff included
+    pub timeout: Duration,
+    /// Further attempts after the first one fails with a transient error
+    pub retries: u32,
+    /// Wait before the first retry, doubled for each one after
+    pub initial_backoff: Duration,
+}
+
+impl RetryPolicy {
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            retries: 2,
+            initial_backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::with_timeout(Duration::from_secs_f64(DEFAULT_PRICING_TIMEOUT_SECS))
+    }
+}
+
+/// Server errors, rate limiting and network failures may succeed on a retry; anything
+/// else (a 404, a malformed URL) won't
+fn is_transient(error: &reqwest::Error) -> bool {
+    match error.status() {
+        Some(status) => {
+            status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+        }
+        None => error.is_timeout() || error.is_connect() || error.is_request(),
+    }
+}
+
+/// GET `url` and return the body, retrying transient failures with exponential backoff
+/// until `policy.retries` is used up or `policy.timeout` has passed
+pub async fn fetch_with_retry(url: &str, policy: &RetryPolicy) -> Result<String> {
+    let client = reqwest::Client::new();
+    let attempts = async {
+        let mut backoff = policy.initial_backoff;
+        let mut attempt = 0;
+        loop {
+            let result = async {
+                client
+                    .get(url)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .text()
+                    .await
+            }
+            .await;
+            match result {
+                Err(e) if attempt < policy.retries && is_transient(&e) => {
+                    attempt += 1;
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                result => return result.map_err(anyhow::Error::from),
+            }
+        }
+    };
+    tokio::time::timeout(policy.timeout, attempts)
+        .await
+        .map_err(|_| anyhow!("Timed out after {:.1}s", policy.timeout.as_secs_f64()))?
+}
+
+/// Parse a JSON object of model id to per-million rates
+fn parse_pricing(body: &str) -> Result<PricingMap> {
+    let rates: HashMap<String, ModelPricing> = serde_json::from_str(body)?;
+    Ok(rates
+        .into_iter()
+        .map(|(model, pricing)| (ModelName::from_model_string(&model), pricing))
+        .collect())
+}
+
+/// Fetch pricing from `url`, falling back to the hardcoded pricing if that fails
+pub async fn fetch_pricing(url: &str, policy: &RetryPolicy) -> PricingMap {
+    match fetch_with_retry(url, policy).await.and_then(|body| parse_pricing(&body)) {
+        Ok(pricing) => pricing,
+        Err(e) => {
+            eprintln!("Warning: Failed to fetch pricing, using built-in prices: {}", e);
+            get_default_pricing()
+        }
+    }
+}
+
+/// Fetch the latest pricing, giving up after `timeout`
+pub async fn fetch_latest_pricing(timeout: Duration) -> Result<PricingMap> {
+    let Some(url) = PRICING_URL else {
+        eprintln!("Note: Using hardcoded pricing. API integration coming soon.");
+        return Ok(get_default_pricing());
+    };
+    Ok(fetch_pricing(url, &RetryPolicy::with_timeout(timeout)).await)
+}
+
+/// Context window used for models without a known size
+pub const DEFAULT_CONTEXT_LIMIT: u64 = 200_000;
+
+// Context window sizes in tokens
+pub fn get_context_limits() -> HashMap<ModelName, u64> {
+    ModelName::all_known()
+        .iter()
+        .map(|model| {
+            let limit = match model {
+                // Sonnet 4 accepts up to 1M tokens with the long-context beta
+                ModelName::Claude4Sonnet => 1_000_000,
+                ModelName::Claude4Opus
+                | ModelName::Claude37Sonnet
+                | ModelName::Claude35Sonnet
+                | ModelName::Claude35Haiku
+                | ModelName::Claude3Opus
+                | ModelName::Claude3Sonnet
+                | ModelName::Claude3Haiku
+                | ModelName::Synthetic
+                | ModelName::Unknown(_) => DEFAULT_CONTEXT_LIMIT,
+            };
+            (model.clone(), limit)
+        })
+        .collect()
 }
 
 pub fn get_model_pricing<'a>(pricing_map: &'a PricingMap, model: &'a ModelName) -> Option<&'a ModelPricing> {
@@ -110,23 +288,15 @@ pub fn get_model_pricing<'a>(pricing_map: &'a PricingMap, model: &'a ModelName)
             return Some(pricing);
         }
         
-        // If still unknown, try family matching
-        let family = if model_str.contains("sonnet") {
-            "sonnet"
-        } else if model_str.contains("opus") {
-            "opus"
-        } else if model_str.contains("haiku") {
-            "haiku"
-        } else {
-            return None;
-        };
-        
-        // Get the first pricing for this family
-        for (key, pricing) in pricing_map.iter() {
-            if key.family() == family {
-                return Some(pricing);
-            }
-        }
+        // If still unknown, fall back to the newest priced model of the same family
+        let family = ["sonnet", "opus", "haiku"]
+            .into_iter()
+            .find(|family| model_str.contains(family))?;
+
+        return ModelName::all_known()
+            .iter()
+            .filter(|known| known.family() == family)
+            .find_map(|known| pricing_map.get(known));
     }
 
     None
@@ -135,12 +305,81 @@ pub fn get_model_pricing<'a>(pricing_map: &'a PricingMap, model: &'a ModelName)
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Serve each of `responses` (status, body) to one connection in turn, counting requests
+    fn mock_server(responses: Vec<(u16, &'static str)>) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/pricing", listener.local_addr().unwrap());
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&hits);
+        std::thread::spawn(move || {
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = [0; 1024];
+                let _ = stream.read(&mut request);
+                counter.fetch_add(1, Ordering::SeqCst);
+                let response = format!(
+                    "HTTP/1.1 {} X\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (url, hits)
+    }
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy {
+            initial_backoff: Duration::from_millis(10),
+            ..RetryPolicy::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_retries_server_errors() {
+        let body = concat!(
+            r#"{"claude-opus-4-20250514":{"input_per_million":1.0,"output_per_million":2.0,"#,
+            r#""cache_write_per_million":3.0,"cache_read_per_million":0.5}}"#
+        );
+        let (url, hits) = mock_server(vec![(503, ""), (200, body)]);
+
+        let pricing = fetch_pricing(&url, &fast_policy()).await;
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+        assert_eq!(pricing[&ModelName::Claude4Opus].input_per_million, 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_gives_up_on_client_errors() {
+        let (url, hits) = mock_server(vec![(404, ""), (200, "{}")]);
+
+        assert!(fetch_with_retry(&url, &fast_policy()).await.is_err());
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_times_out() {
+        // Accepts the connection but never answers
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/pricing", listener.local_addr().unwrap());
+        let policy = RetryPolicy::with_timeout(Duration::from_millis(200));
+
+        let error = fetch_with_retry(&url, &policy).await.unwrap_err();
+        assert!(error.to_string().contains("Timed out"));
+        drop(listener);
+    }
 
     #[test]
     fn test_default_pricing() {
         let pricing = get_default_pricing();
         assert!(pricing.contains_key(&ModelName::Claude35Sonnet));
         assert!(pricing.contains_key(&ModelName::Claude3Opus));
+        assert!(missing_pricing(&pricing).is_empty());
     }
 
     #[test]
@@ -154,4 +393,27 @@ mod tests {
         let unknown = ModelName::Unknown("claude-3-5-sonnet-unknown".to_string());
         assert!(get_model_pricing(&pricing, &unknown).is_some());
     }
+
+    #[test]
+    fn test_family_fallback() {
+        let pricing = get_default_pricing();
+
+        let future_opus = ModelName::from_model_string("claude-opus-5-20260101");
+        let matched = get_model_pricing(&pricing, &future_opus).unwrap();
+        assert_eq!(matched.input_per_million, 15.0);
+
+        // 3.5 Haiku, the newest Haiku, rather than whichever the map yields first
+        let future_haiku = ModelName::from_model_string("claude-haiku-5-20260101");
+        let matched = get_model_pricing(&pricing, &future_haiku).unwrap();
+        assert_eq!(matched.input_per_million, 0.8);
+
+        // Falls back further when the newest isn't in the map
+        let mut older = pricing.clone();
+        older.remove(&ModelName::Claude35Haiku);
+        let matched = get_model_pricing(&older, &future_haiku).unwrap();
+        assert_eq!(matched.input_per_million, 0.25);
+
+        let unrelated = ModelName::from_model_string("gpt-4");
+        assert!(get_model_pricing(&pricing, &unrelated).is_none());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,152 @@
+use crate::incremental_parser::format_bytes;
+use crate::parser::LogParser;
+use crate::validate::open_log;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+/// Span and size of the logs on disk, for picking a date range before a detailed analysis
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DataRange {
+    pub earliest: Option<DateTime<Utc>>,
+    pub latest: Option<DateTime<Utc>>,
+    pub files: usize,
+    pub bytes: u64,
+    pub projects: usize,
+    pub sessions: usize,
+}
+
+/// The only fields `range` reads from a log line; everything else is skipped unparsed
+#[derive(Deserialize)]
+struct LineStamp {
+    timestamp: Option<DateTime<Utc>>,
+    #[serde(rename = "sessionId")]
+    session_id: Option<String>,
+}
+
+/// Scan every log file for timestamps and session ids only, much faster than `show`'s full
+/// parse. Lines that don't parse, and files that can't be read, are skipped.
+pub fn scan_range(claude_dirs: Vec<String>, max_depth: usize) -> Result<DataRange> {
+    let parser = LogParser::new_multi(claude_dirs).with_max_depth(max_depth);
+    let projects_dirs = parser.projects_dirs()?;
+    let mut range = DataRange::default();
+    let mut projects: HashSet<PathBuf> = HashSet::new();
+    let mut sessions: HashSet<String> = HashSet::new();
+
+    for path in parser.find_jsonl_files()? {
+        range.files += 1;
+        range.bytes += path.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+        // A project is the first directory below projects/
+        if let Some(project) = projects_dirs.iter().find_map(|dir| {
+            let relative = path.strip_prefix(dir).ok()?;
+            let first = relative.components().next()?;
+            (relative.components().count() > 1).then(|| dir.join(first))
+        }) {
+            projects.insert(project);
+        }
+
+        let Ok(file) = File::open(&path) else {
+            continue;
+        };
+        let mut reader = open_log(file, &path);
+        let mut line = Vec::new();
+        while matches!(reader.read_until(b'\n', &mut line), Ok(n) if n > 0) {
+            if let Ok(stamp) = serde_json::from_slice::<LineStamp>(&line) {
+                if let Some(timestamp) = stamp.timestamp {
+                    range.earliest = Some(range.earliest.map_or(timestamp, |t| t.min(timestamp)));
+                    range.latest = Some(range.latest.map_or(timestamp, |t| t.max(timestamp)));
+                }
+                if let Some(session_id) = stamp.session_id {
+                    sessions.insert(session_id);
+                }
+            }
+            line.clear();
+        }
+    }
+
+    range.projects = projects.len();
+    range.sessions = sessions.len();
+    Ok(range)
+}
+
+pub fn format_range(range: &DataRange) -> String {
+    let time = |timestamp: Option<DateTime<Utc>>| {
+        timestamp.map_or("-".to_string(), |t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+    };
+    let mut out = String::new();
+    out.push_str(&format!("Earliest entry: {}\n", time(range.earliest)));
+    out.push_str(&format!("Latest entry:   {}\n", time(range.latest)));
+    if let (Some(earliest), Some(latest)) = (range.earliest, range.latest) {
+        out.push_str(&format!(
+            "Span:           {} days\n",
+            (latest.date_naive() - earliest.date_naive()).num_days() + 1
+        ));
+    }
+    out.push_str(&format!(
+        "Log files:      {} ({})\n",
+        range.files,
+        format_bytes(range.bytes)
+    ));
+    out.push_str(&format!("Projects:       {}\n", range.projects));
+    out.push_str(&format!("Sessions:       {}\n", range.sessions));
+    out
+}
+
+/// Run `range`: print the span of available data and how much of it there is
+pub fn run_range<W: Write>(
+    claude_dirs: Vec<String>,
+    max_depth: usize,
+    writer: &mut W,
+) -> Result<()> {
+    let range = scan_range(claude_dirs, max_depth)?;
+    write!(writer, "{}", format_range(&range))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_scan_range() {
+        let claude_dir = TempDir::new().unwrap();
+        let projects = claude_dir.path().join("projects");
+        let first = projects.join("app");
+        let second = projects.join("lib");
+        fs::create_dir_all(&first).unwrap();
+        fs::create_dir_all(&second).unwrap();
+        fs::write(
+            first.join("s1.jsonl"),
+            [
+                r#"{"type":"summary","summary":"No timestamp here"}"#,
+                r#"{"type":"user","timestamp":"2024-12-03T10:00:00Z","sessionId":"s1"}"#,
+                "not json",
+                r#"{"type":"assistant","timestamp":"2024-11-28T08:30:00Z","sessionId":"s1"}"#,
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+        fs::write(
+            second.join("s2.jsonl"),
+            r#"{"type":"assistant","timestamp":"2025-01-05T23:00:00Z","sessionId":"s2"}"#,
+        )
+        .unwrap();
+
+        let dir = claude_dir.path().to_string_lossy().to_string();
+        let range = scan_range(vec![dir], 3).unwrap();
+        assert_eq!(range.earliest.unwrap().to_rfc3339(), "2024-11-28T08:30:00+00:00");
+        assert_eq!(range.latest.unwrap().to_rfc3339(), "2025-01-05T23:00:00+00:00");
+        assert_eq!((range.files, range.projects, range.sessions), (2, 2, 2));
+        assert!(range.bytes > 0);
+
+        let text = format_range(&range);
+        assert!(text.contains("Earliest entry: 2024-11-28 08:30 UTC"));
+        assert!(text.contains("Span:           39 days"));
+    }
+}
@@ -0,0 +1,246 @@
+use crate::dashboard::app::state_dir;
+use crate::file_tracker::{seen_ids_path, FileTracker};
+use crate::incremental_parser::IncrementalParsing;
+use crate::parser::{dedup_key, LogParser};
+use crate::pricing::{get_default_pricing, get_model_pricing};
+use anyhow::Result;
+use chrono::{Datelike, Duration, Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Incremental parsing state kept by `statusline` inside `state_dir`
+pub const STATUSLINE_TRACKER_FILE: &str = "statusline-file-tracker.json";
+
+/// Per-day costs accumulated by `statusline` inside `state_dir`
+pub const STATUSLINE_COSTS_FILE: &str = "statusline-costs.json";
+
+/// Default `--format-string`
+pub const DEFAULT_STATUSLINE_FORMAT: &str = "{today} today / {week} week";
+
+/// Cost per local calendar day, carried between runs so each one only has to read what
+/// was logged since the last
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DailyCosts {
+    days: BTreeMap<NaiveDate, f64>,
+    /// Day and cost counted for each request, by dedup key, so a later copy of a request
+    /// replaces what an earlier one added
+    #[serde(default)]
+    requests: HashMap<String, (NaiveDate, f64)>,
+}
+
+impl DailyCosts {
+    /// Load saved costs, or `None` if there are none or they can't be read
+    pub fn load(path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn add(&mut self, day: NaiveDate, cost: f64) {
+        *self.days.entry(day).or_insert(0.0) += cost;
+    }
+
+    /// Count `request`'s cost on `day`, taking back whatever an earlier copy of it added
+    pub fn add_request(&mut self, request: String, day: NaiveDate, cost: f64) {
+        if let Some((previous_day, previous_cost)) = self.requests.insert(request, (day, cost)) {
+            if let Some(total) = self.days.get_mut(&previous_day) {
+                *total -= previous_cost;
+            }
+        }
+        self.add(day, cost);
+    }
+
+    /// Total cost from `start` through the latest day
+    pub fn total_since(&self, start: NaiveDate) -> f64 {
+        self.days.range(start..).map(|(_, cost)| cost).sum()
+    }
+
+    /// Forget days, and the requests counted on them, before `start`
+    pub fn prune_before(&mut self, start: NaiveDate) {
+        self.days = self.days.split_off(&start);
+        self.requests.retain(|_, (day, _)| *day >= start);
+    }
+}
+
+/// Monday of the week containing `today`
+fn week_start(today: NaiveDate) -> NaiveDate {
+    today - Duration::days(today.weekday().num_days_from_monday() as i64)
+}
+
+/// First day of the month containing `today`
+fn month_start(today: NaiveDate) -> NaiveDate {
+    today.with_day(1).unwrap_or(today)
+}
+
+/// Fill `{today}`, `{week}` and `{month}` in `format` with dollar amounts
+pub fn render_statusline(format: &str, costs: &DailyCosts, today: NaiveDate) -> String {
+    let dollars = |start| format!("${:.2}", costs.total_since(start));
+    format
+        .replace("{today}", &dollars(today))
+        .replace("{week}", &dollars(week_start(today)))
+        .replace("{month}", &dollars(month_start(today)))
+}
+
+/// Run `statusline`: print today's, this week's and this month's cost (local calendar) on
+/// one line. Only log lines written since the previous run are read; the first run, or
+/// one after the saved costs went missing, reads everything from the start of the month.
+pub fn run_statusline<W: Write>(
+    claude_dirs: Vec<String>,
+    format: &str,
+    max_depth: usize,
+    writer: &mut W,
+) -> Result<()> {
+    let primary_dir = claude_dirs.first().cloned().unwrap_or_else(|| "~/.claude".to_string());
+    let state_dir = state_dir(&primary_dir);
+    let costs_file = state_dir.join(STATUSLINE_COSTS_FILE);
+    let today = Local::now().date_naive();
+    let start = week_start(today).min(month_start(today));
+
+    let mut tracker = FileTracker::with_persistence(state_dir.join(STATUSLINE_TRACKER_FILE));
+    let mut costs = match DailyCosts::load(&costs_file) {
+        Some(costs) => costs,
+        None => {
+            // Without the totals, files the tracker has already read would never be counted
+            tracker.clear();
+            DailyCosts::default()
+        }
+    };
+
+    let start_utc = Local
+        .from_local_datetime(&start.and_hms_opt(0, 0, 0).unwrap())
+        .earliest()
+        .map(|time| time.to_utc());
+    let parser = LogParser::new_multi(claude_dirs)
+        .with_date_range(start_utc, None)
+        .with_max_depth(max_depth)
+        .quiet();
+    let pricing_map = get_default_pricing();
+    for entry in parser.parse_logs_incremental(&mut tracker)? {
+        let Some(message) = &entry.message else {
+            continue;
+        };
+        let Some(usage) = &message.usage else {
+            continue;
+        };
+        if let Some(pricing) = get_model_pricing(&pricing_map, &message.model) {
+            let day = entry.timestamp.with_timezone(&Local).date_naive();
+            let request = dedup_key(parser.dedup_key, &entry);
+            costs.add_request(request, day, pricing.calculate_cost_with_tier(usage));
+        }
+    }
+    costs.prune_before(start);
+    costs.save(&costs_file)?;
+
+    writeln!(writer, "{}", render_statusline(format, &costs, today))?;
+    Ok(())
+}
+
+/// Files `statusline` keeps in `state_dir`, for `clean`
+pub fn state_files(state_dir: &Path) -> Vec<PathBuf> {
+    let tracker = state_dir.join(STATUSLINE_TRACKER_FILE);
+    vec![
+        seen_ids_path(&tracker),
+        tracker,
+        state_dir.join(STATUSLINE_COSTS_FILE),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use tempfile::TempDir;
+
+    fn assistant_line(uuid: &str, timestamp: &str, output_tokens: u64) -> String {
+        // Opus 4 output is $75/M
+        format!(
+            r#"{{"type":"assistant","uuid":"{}","timestamp":"{}","sessionId":"s","requestId":"req-{}","message":{{"id":"msg-{}","role":"assistant","model":"claude-opus-4-20250514","usage":{{"input_tokens":0,"output_tokens":{}}}}}}}"#,
+            uuid, timestamp, uuid, uuid, output_tokens
+        )
+    }
+
+    fn run(claude_dir: &TempDir, format: &str) -> String {
+        let mut output = Vec::new();
+        run_statusline(
+            vec![claude_dir.path().to_string_lossy().to_string()],
+            format,
+            3,
+            &mut output,
+        )
+        .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_render_statusline() {
+        // A Wednesday
+        let today = NaiveDate::from_ymd_opt(2025, 6, 4).unwrap();
+        let mut costs = DailyCosts::default();
+        costs.add(NaiveDate::from_ymd_opt(2025, 5, 31).unwrap(), 8.0);
+        costs.add(NaiveDate::from_ymd_opt(2025, 6, 1).unwrap(), 4.0);
+        costs.add(NaiveDate::from_ymd_opt(2025, 6, 2).unwrap(), 2.0);
+        costs.add(today, 1.25);
+        costs.add(today, 0.5);
+
+        assert_eq!(
+            render_statusline(DEFAULT_STATUSLINE_FORMAT, &costs, today),
+            "$1.75 today / $3.75 week"
+        );
+        assert_eq!(render_statusline("{month}", &costs, today), "$7.75");
+
+        costs.prune_before(NaiveDate::from_ymd_opt(2025, 6, 1).unwrap());
+        assert_eq!(costs.days.len(), 3);
+    }
+
+    #[test]
+    fn test_statusline_reads_only_new_lines() {
+        let claude_dir = TempDir::new().unwrap();
+        let project = claude_dir.path().join("projects").join("p");
+        fs::create_dir_all(&project).unwrap();
+        let log = project.join("session.jsonl");
+        let now = Utc::now().to_rfc3339();
+        fs::write(&log, format!("{}\n", assistant_line("a1", &now, 20_000))).unwrap();
+
+        assert_eq!(run(&claude_dir, "{today}"), "$1.50\n");
+
+        // Appended lines are added to the saved total; the earlier one isn't counted again
+        let mut file = fs::OpenOptions::new().append(true).open(&log).unwrap();
+        writeln!(file, "{}", assistant_line("a2", &now, 40_000)).unwrap();
+        assert_eq!(run(&claude_dir, "{today}"), "$4.50\n");
+        assert_eq!(run(&claude_dir, "{today}"), "$4.50\n");
+
+        // Losing the saved costs rebuilds them from scratch
+        fs::remove_file(state_dir(&claude_dir.path().to_string_lossy()).join(STATUSLINE_COSTS_FILE))
+            .unwrap();
+        assert_eq!(run(&claude_dir, "{today}"), "$4.50\n");
+    }
+
+    #[test]
+    fn test_statusline_replaces_earlier_copies_of_a_request() {
+        let claude_dir = TempDir::new().unwrap();
+        let project = claude_dir.path().join("projects").join("p");
+        fs::create_dir_all(&project).unwrap();
+        let log = project.join("session.jsonl");
+        let now = Utc::now();
+        let first = now - Duration::seconds(5);
+        fs::write(&log, format!("{}\n", assistant_line("a1", &first.to_rfc3339(), 20_000)))
+            .unwrap();
+        assert_eq!(run(&claude_dir, "{today}"), "$1.50\n");
+
+        // A later, complete copy of the same request replaces the partial one
+        let complete = assistant_line("a1", &now.to_rfc3339(), 40_000).replace("\"a1\"", "\"a1b\"");
+        let mut file = fs::OpenOptions::new().append(true).open(&log).unwrap();
+        writeln!(file, "{}", complete).unwrap();
+        assert_eq!(run(&claude_dir, "{today}"), "$3.00\n");
+    }
+}
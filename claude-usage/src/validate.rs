@@ -0,0 +1,257 @@
+use crate::error::UsageError;
+use crate::parser::{is_gzip_file, parse_entry_line, LogParser};
+use anyhow::Result;
+use flate2::read::GzDecoder;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// Lines read from the start of each file; enough to spot the common problems cheaply
+const SAMPLE_LINES: usize = 5;
+
+/// What the sampled lines of one log file contained
+#[derive(Debug, Clone, Default)]
+pub struct FileReport {
+    pub path: PathBuf,
+    pub lines_sampled: usize,
+    pub invalid_json_lines: usize,
+    pub assistant_entries: usize,
+    pub entries_with_usage: usize,
+    /// Entries `show` would actually count
+    pub valid_entries: usize,
+    /// Set when the file couldn't be read at all
+    pub read_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+    pub files: Vec<FileReport>,
+    /// How often each raw `message.model` value appeared in the sampled lines
+    pub models: BTreeMap<String, usize>,
+}
+
+impl ValidationReport {
+    pub fn valid_entries(&self) -> usize {
+        self.files.iter().map(|file| file.valid_entries).sum()
+    }
+
+    pub fn files_with_invalid_json(&self) -> usize {
+        self.count_files(|file| file.invalid_json_lines > 0 || file.read_error.is_some())
+    }
+
+    pub fn files_without_assistant_entries(&self) -> usize {
+        self.count_files(|file| file.assistant_entries == 0)
+    }
+
+    pub fn files_without_usage(&self) -> usize {
+        self.count_files(|file| file.entries_with_usage == 0)
+    }
+
+    fn count_files(&self, predicate: impl Fn(&FileReport) -> bool) -> usize {
+        self.files.iter().filter(|file| predicate(file)).count()
+    }
+
+    /// 0 when at least one usable entry was found, 1 otherwise
+    pub fn exit_code(&self) -> u8 {
+        if self.valid_entries() > 0 {
+            0
+        } else {
+            1
+        }
+    }
+}
+
+/// Sample the first lines of every log file under `claude_dir/projects`
+pub fn validate_dir(claude_dir: &str) -> Result<ValidationReport> {
+    let mut report = ValidationReport::default();
+
+    for path in LogParser::new(claude_dir.to_string()).find_jsonl_files()? {
+        let mut file_report = FileReport {
+            path: path.clone(),
+            ..Default::default()
+        };
+
+        match File::open(&path) {
+            Ok(file) => {
+                for line in open_log(file, &path).lines().take(SAMPLE_LINES) {
+                    match line {
+                        Ok(line) => sample_line(&line, &mut file_report, &mut report.models),
+                        Err(e) => {
+                            file_report.read_error = Some(e.to_string());
+                            break;
+                        }
+                    }
+                }
+            }
+            Err(e) => file_report.read_error = Some(e.to_string()),
+        }
+
+        report.files.push(file_report);
+    }
+
+    Ok(report)
+}
+
+pub(crate) fn open_log(file: File, path: &Path) -> Box<dyn BufRead> {
+    if is_gzip_file(path) {
+        Box::new(BufReader::new(GzDecoder::new(file)))
+    } else {
+        Box::new(BufReader::new(file))
+    }
+}
+
+fn sample_line(line: &str, file: &mut FileReport, models: &mut BTreeMap<String, usize>) {
+    if line.trim().is_empty() {
+        return;
+    }
+    file.lines_sampled += 1;
+
+    let value: serde_json::Value = match serde_json::from_str(line) {
+        Ok(value) => value,
+        Err(_) => {
+            file.invalid_json_lines += 1;
+            return;
+        }
+    };
+
+    if value["type"] == "assistant" {
+        file.assistant_entries += 1;
+    }
+    if !value["message"]["usage"].is_null() {
+        file.entries_with_usage += 1;
+    }
+    if let Some(model) = value["message"]["model"].as_str() {
+        *models.entry(model.to_string()).or_default() += 1;
+    }
+    if matches!(parse_entry_line(line), Ok(Some(_))) {
+        file.valid_entries += 1;
+    }
+}
+
+/// Human-readable summary, with one line per file when `verbose`
+pub fn format_validation_report(report: &ValidationReport, verbose: bool) -> String {
+    let mut out = String::new();
+    out.push_str(&format!("JSONL files found: {}\n", report.files.len()));
+    out.push_str(&format!(
+        "Files with invalid JSON: {}\n",
+        report.files_with_invalid_json()
+    ));
+    out.push_str(&format!(
+        "Files with no assistant entries: {}\n",
+        report.files_without_assistant_entries()
+    ));
+    out.push_str(&format!("Files with no usage data: {}\n", report.files_without_usage()));
+    out.push_str(&format!(
+        "Valid entries (first {} lines of each file): {}\n",
+        SAMPLE_LINES,
+        report.valid_entries()
+    ));
+
+    if !report.models.is_empty() {
+        out.push_str("\nModels:\n");
+        let mut models: Vec<_> = report.models.iter().collect();
+        models.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+        for (model, count) in models {
+            out.push_str(&format!("  {:>5}  {}\n", count, model));
+        }
+    }
+
+    if verbose {
+        out.push_str("\nFiles:\n");
+        for file in &report.files {
+            let detail = match &file.read_error {
+                Some(error) => format!("unreadable: {}", error),
+                None => format!(
+                    "{} lines, {} invalid JSON, {} assistant, {} with usage, {} valid",
+                    file.lines_sampled,
+                    file.invalid_json_lines,
+                    file.assistant_entries,
+                    file.entries_with_usage,
+                    file.valid_entries
+                ),
+            };
+            out.push_str(&format!("  {}: {}\n", file.path.display(), detail));
+        }
+    }
+
+    out
+}
+
+/// Run `validate`, writing the report and returning the process exit code:
+/// 0 if usable entries were found, 1 if none were, 2 if the directory doesn't exist
+pub fn run_validate<W: Write>(claude_dir: &str, verbose: bool, writer: &mut W) -> Result<u8> {
+    let report = match validate_dir(claude_dir) {
+        Ok(report) => report,
+        Err(err) => match err.downcast_ref::<UsageError>() {
+            Some(UsageError::ClaudeDirNotFound(_)) => {
+                eprintln!("Error: {}", err);
+                return Ok(2);
+            }
+            None => return Err(err),
+        },
+    };
+
+    write!(writer, "{}", format_validation_report(&report, verbose))?;
+    if report.valid_entries() == 0 {
+        writeln!(
+            writer,
+            "\nNo usable entries found; `claude-usage show` will report no usage data."
+        )?;
+    }
+
+    Ok(report.exit_code())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn claude_dir_with(lines: &[&str]) -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let project_dir = temp_dir.path().join("projects").join("test-project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(project_dir.join("session.jsonl"), lines.join("\n")).unwrap();
+        temp_dir
+    }
+
+    fn exit_code(dir: &TempDir) -> u8 {
+        run_validate(&dir.path().to_string_lossy(), true, &mut Vec::new()).unwrap()
+    }
+
+    #[test]
+    fn test_summary_only_directory_fails() {
+        let dir = claude_dir_with(&[
+            r#"{"type":"summary","summary":"One","leafUuid":"a"}"#,
+            r#"{"type":"summary","summary":"Two","leafUuid":"b"}"#,
+        ]);
+        assert_eq!(exit_code(&dir), 1);
+
+        let report = validate_dir(&dir.path().to_string_lossy()).unwrap();
+        assert_eq!(report.files.len(), 1);
+        assert_eq!(report.files_without_assistant_entries(), 1);
+        assert_eq!(report.files_without_usage(), 1);
+    }
+
+    #[test]
+    fn test_assistant_entries_pass() {
+        let dir = claude_dir_with(&[
+            r#"{"type":"summary","summary":"One","leafUuid":"a"}"#,
+            r#"{"type":"assistant","uuid":"a1","timestamp":"2024-12-01T00:00:00Z","sessionId":"s","requestId":"req-a1","message":{"id":"msg-a1","role":"assistant","model":"claude-opus-4-20250514","usage":{"input_tokens":100,"output_tokens":50}}}"#,
+            "{not json",
+        ]);
+        assert_eq!(exit_code(&dir), 0);
+
+        let report = validate_dir(&dir.path().to_string_lossy()).unwrap();
+        assert_eq!(report.valid_entries(), 1);
+        assert_eq!(report.files_with_invalid_json(), 1);
+        assert_eq!(report.models.get("claude-opus-4-20250514"), Some(&1));
+    }
+
+    #[test]
+    fn test_missing_directory() {
+        let dir = TempDir::new().unwrap();
+        assert_eq!(exit_code(&dir), 2);
+    }
+}
@@ -0,0 +1,131 @@
+use crate::model_name::ModelName;
+use crate::models::LogEntry;
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+/// Redirects to the newest release's tag page, e.g. `.../releases/tag/v0.2.0`
+pub const LATEST_RELEASE_URL: &str = "https://github.com/wfus/cli-tools/releases/latest";
+
+/// `--version-check` gives up after this long
+pub const VERSION_CHECK_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Canonical strings of the `Unknown` models in `entries` that aren't among
+/// `known_models`, sorted and without duplicates
+pub fn check_for_unknown_models(entries: &[LogEntry], known_models: &[ModelName]) -> Vec<String> {
+    entries
+        .iter()
+        .filter_map(|entry| entry.message.as_ref())
+        .map(|message| &message.model)
+        .filter(|model| matches!(model, ModelName::Unknown(_)) && !known_models.contains(model))
+        .map(ModelName::canonical_string)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// The warning printed for a model `check_for_unknown_models` found
+pub fn unknown_model_warning(model: &str) -> String {
+    format!(
+        "⚠ Unknown model '{}' detected. Consider updating claude-usage for accurate pricing.",
+        model
+    )
+}
+
+/// Numeric `major.minor.patch` parts of a version or release tag such as
+/// `claude-usage-v1.2.3`, ignoring anything before the first digit
+fn version_parts(version: &str) -> Option<Vec<u64>> {
+    let start = version.find(|c: char| c.is_ascii_digit())?;
+    version[start..]
+        .split(['.', '-', '+'])
+        .take(3)
+        .map(|part| part.parse().ok())
+        .collect()
+}
+
+/// Whether release tag `latest` is a newer version than `current`
+pub fn is_newer(latest: &str, current: &str) -> bool {
+    match (version_parts(latest), version_parts(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    }
+}
+
+/// Tag of the newest release, read from where `LATEST_RELEASE_URL` redirects with a HEAD
+/// request. `None` on any network error or unexpected response.
+async fn latest_release_tag(timeout: Duration) -> Option<String> {
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(timeout)
+        .user_agent(concat!("claude-usage/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .ok()?;
+    let response = client.head(LATEST_RELEASE_URL).send().await.ok()?;
+    let location = response.headers().get(reqwest::header::LOCATION)?.to_str().ok()?;
+    let (_, tag) = location.rsplit_once("/tag/")?;
+    Some(tag.trim_end_matches('/').to_string())
+}
+
+/// "Update available: v1.x.y" when a newer release than this build exists; `None` when
+/// up to date or the check failed
+pub async fn check_for_update() -> Option<String> {
+    let tag = latest_release_tag(VERSION_CHECK_TIMEOUT).await?;
+    let latest = version_parts(&tag)?
+        .iter()
+        .map(|part| part.to_string())
+        .collect::<Vec<_>>()
+        .join(".");
+    is_newer(&tag, env!("CARGO_PKG_VERSION")).then(|| format!("Update available: v{}", latest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::LogParser;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_unknown_models_in_fixture() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path().join("projects").join("p");
+        fs::create_dir_all(&project).unwrap();
+        let line = |uuid: &str, model: &str| {
+            format!(
+                r#"{{"type":"assistant","uuid":"{}","timestamp":"2026-01-02T10:00:00Z","sessionId":"s","requestId":"req-{}","message":{{"id":"msg-{}","role":"assistant","model":"{}","usage":{{"input_tokens":10,"output_tokens":5}}}}}}"#,
+                uuid, uuid, uuid, model
+            )
+        };
+        let lines = [
+            line("a1", "claude-sonnet-4-20250514"),
+            line("a2", "claude-new-model-20260101"),
+            line("a3", "claude-new-model-20260101"),
+        ];
+        fs::write(project.join("session.jsonl"), lines.join("\n")).unwrap();
+
+        let entries = LogParser::new(temp_dir.path().to_string_lossy().to_string())
+            .quiet()
+            .parse_logs()
+            .unwrap();
+        assert_eq!(entries.len(), 3);
+        let unknown = check_for_unknown_models(&entries, ModelName::all_known());
+        assert_eq!(unknown, vec!["claude-new-model-20260101".to_string()]);
+        assert_eq!(
+            unknown_model_warning(&unknown[0]),
+            "⚠ Unknown model 'claude-new-model-20260101' detected. Consider updating \
+             claude-usage for accurate pricing."
+        );
+
+        // A model the caller already knows about isn't reported
+        let known = [ModelName::Unknown("claude-new-model-20260101".to_string())];
+        assert!(check_for_unknown_models(&entries, &known).is_empty());
+    }
+
+    #[test]
+    fn test_is_newer() {
+        assert!(is_newer("v0.2.0", "0.1.0"));
+        assert!(is_newer("claude-usage-v1.0.0", "0.9.9"));
+        assert!(!is_newer("v0.1.0", "0.1.0"));
+        assert!(!is_newer("v0.0.9", "0.1.0"));
+        assert!(!is_newer("nightly", "0.1.0"));
+    }
+}
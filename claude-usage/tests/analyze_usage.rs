@@ -0,0 +1,322 @@
+use clap::Parser;
+use claude_usage::{analyze_usage_to_writer, cli::Args};
+use std::path::Path;
+use tempfile::TempDir;
+
+fn assistant_line(uuid: &str, request_id: &str, timestamp: &str, model: &str) -> String {
+    format!(
+        r#"{{"type":"assistant","uuid":"{}","timestamp":"{}","sessionId":"test-session","requestId":"{}","message":{{"id":"msg-{}","role":"assistant","model":"{}","usage":{{"input_tokens":1000,"output_tokens":500}}}}}}"#,
+        uuid, timestamp, request_id, uuid, model
+    )
+}
+
+fn create_claude_dir(lines: &[String]) -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path().join("projects").join("test-project");
+    std::fs::create_dir_all(&project_dir).unwrap();
+    std::fs::write(project_dir.join("session.jsonl"), lines.join("\n")).unwrap();
+    temp_dir
+}
+
+fn args_for(dir: &Path, extra: &[&str]) -> Args {
+    let dir = dir.to_string_lossy().to_string();
+    let mut argv = vec!["claude-usage", "--claude-dir", dir.as_str()];
+    argv.extend_from_slice(extra);
+    Args::parse_from(argv)
+}
+
+fn sample_dir() -> TempDir {
+    create_claude_dir(&[
+        assistant_line("a1", "req-a1", "2024-12-01T10:00:00Z", "claude-opus-4-20250514"),
+        assistant_line("a2", "req-a2", "2024-12-01T11:00:00Z", "claude-sonnet-4-20250514"),
+        assistant_line("a3", "req-a3", "2024-12-02T09:00:00Z", "claude-opus-4-20250514"),
+    ])
+}
+
+#[tokio::test]
+async fn test_table_output_is_captured() {
+    let dir = sample_dir();
+    let mut output = Vec::new();
+    analyze_usage_to_writer(args_for(dir.path(), &[]), &mut output)
+        .await
+        .unwrap();
+
+    let output = String::from_utf8(output).unwrap();
+    // Progress goes to stderr, not the report
+    assert!(!output.contains("Processed"));
+    assert!(output.contains("2024-12-01"));
+    assert!(output.contains("2024-12-02"));
+}
+
+#[tokio::test]
+async fn test_csv_output_is_captured() {
+    let dir = sample_dir();
+    let mut output = Vec::new();
+    analyze_usage_to_writer(args_for(dir.path(), &["--format", "csv"]), &mut output)
+        .await
+        .unwrap();
+
+    let output = String::from_utf8(output).unwrap();
+    let csv_lines: Vec<_> = output
+        .lines()
+        .skip_while(|line| !line.starts_with("Date,"))
+        .filter(|line| !line.is_empty())
+        .collect();
+    assert_eq!(csv_lines[0], "Date,Model,Requests,Total Tokens,Cost USD,Cache Hit Rate");
+    assert_eq!(csv_lines.len(), 3); // Header plus one row per day
+}
+
+#[tokio::test]
+async fn test_empty_date_range_message() {
+    let dir = sample_dir();
+    let mut output = Vec::new();
+    analyze_usage_to_writer(
+        args_for(dir.path(), &["--start-date", "2025-01-01"]),
+        &mut output,
+    )
+    .await
+    .unwrap();
+
+    let output = String::from_utf8(output).unwrap();
+    assert_eq!(output.trim(), "No usage data found for the specified date range.");
+}
+
+#[tokio::test]
+async fn test_quiet_json_output_is_pristine() {
+    let dir = sample_dir();
+    let mut output = Vec::new();
+    analyze_usage_to_writer(
+        args_for(dir.path(), &["--quiet", "--format", "json"]),
+        &mut output,
+    )
+    .await
+    .unwrap();
+
+    let output = String::from_utf8(output).unwrap();
+    let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(parsed.as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_streaming_matches_batch_output() {
+    let dir = create_claude_dir(&[
+        assistant_line("a0", "req-a0", "2024-12-01T08:00:00Z", "claude-sonnet-4-20250514"),
+        assistant_line("a1", "req-a1", "2024-12-01T10:00:00Z", "claude-opus-4-20250514"),
+        assistant_line("a2", "req-a2", "2024-12-01T11:00:00Z", "claude-sonnet-4-20250514"),
+        // A later copy of req-a2 replaces the first one
+        assistant_line("a2b", "req-a2", "2024-12-01T11:30:00Z", "claude-sonnet-4-20250514"),
+        assistant_line("a3", "req-a3", "2024-12-02T09:00:00Z", "claude-opus-4-20250514"),
+    ]);
+
+    for group_by in ["day", "model", "model,day", "none"] {
+        let mut batch = Vec::new();
+        analyze_usage_to_writer(args_for(dir.path(), &["-g", group_by]), &mut batch)
+            .await
+            .unwrap();
+        let mut streaming = Vec::new();
+        analyze_usage_to_writer(
+            args_for(dir.path(), &["-g", group_by, "--streaming"]),
+            &mut streaming,
+        )
+        .await
+        .unwrap();
+
+        let batch = String::from_utf8(batch).unwrap();
+        assert_eq!(batch, String::from_utf8(streaming).unwrap(), "group by {}", group_by);
+    }
+}
+
+#[tokio::test]
+async fn test_total_only_prints_bare_number() {
+    let dir = sample_dir();
+    let mut output = Vec::new();
+    analyze_usage_to_writer(
+        args_for(dir.path(), &["--total-only", "--end-date", "2024-12-01"]),
+        &mut output,
+    )
+    .await
+    .unwrap();
+
+    // Opus $0.0525 + Sonnet $0.0105 on the first day only
+    assert_eq!(String::from_utf8(output).unwrap(), "0.06\n");
+}
+
+#[tokio::test]
+async fn test_sampled_json_output_still_parses() {
+    let dir = sample_dir();
+    let other = dir.path().join("projects").join("test-project").join("other.jsonl");
+    let line = assistant_line("b1", "req-b1", "2024-12-03T09:00:00Z", "claude-opus-4-20250514");
+    std::fs::write(other, line).unwrap();
+
+    let mut output = Vec::new();
+    analyze_usage_to_writer(
+        args_for(dir.path(), &["--sample", "1", "--seed", "7", "--format", "json"]),
+        &mut output,
+    )
+    .await
+    .unwrap();
+
+    // The scaling note goes to stderr, leaving only the JSON report
+    let output = String::from_utf8(output).unwrap();
+    serde_json::from_str::<serde_json::Value>(&output).unwrap();
+}
+
+#[tokio::test]
+async fn test_anomalies_keep_json_output_parseable() {
+    let dir = sample_dir();
+    let mut output = Vec::new();
+    analyze_usage_to_writer(
+        args_for(dir.path(), &["--format", "json", "--anomalies"]),
+        &mut output,
+    )
+    .await
+    .unwrap();
+
+    let output = String::from_utf8(output).unwrap();
+    serde_json::from_str::<serde_json::Value>(&output).unwrap();
+}
+
+#[tokio::test]
+async fn test_anomalies_need_daily_grouping() {
+    let dir = sample_dir();
+    let mut output = Vec::new();
+    // A row per model and day would be mistaken for a day of its own
+    analyze_usage_to_writer(
+        args_for(dir.path(), &["--anomalies", "--no-color", "-g", "day,model"]),
+        &mut output,
+    )
+    .await
+    .unwrap();
+
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("Anomalies unavailable"), "{}", output);
+    assert!(!output.contains("Cost Anomalies"), "{}", output);
+}
+
+#[tokio::test]
+async fn test_forecast_keeps_csv_and_json_output_clean() {
+    let dir = sample_dir();
+    for format in ["csv", "json"] {
+        let mut output = Vec::new();
+        analyze_usage_to_writer(
+            args_for(dir.path(), &["--format", format, "--forecast", "3"]),
+            &mut output,
+        )
+        .await
+        .unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(!output.contains("Forecast"), "{}", format);
+    }
+}
+
+#[tokio::test]
+async fn test_total_only_converts_currency() {
+    let dir = sample_dir();
+    let mut output = Vec::new();
+    analyze_usage_to_writer(
+        args_for(
+            dir.path(),
+            &["--total-only", "--end-date", "2024-12-01", "--currency", "EUR", "--exchange-rate", "2"],
+        ),
+        &mut output,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(String::from_utf8(output).unwrap(), "0.13\n");
+}
+
+#[tokio::test]
+async fn test_extra_sections_use_display_currency() {
+    // One request a day, then five on the last day
+    let mut lines: Vec<String> = (1..=3)
+        .map(|day| {
+            let id = format!("d{}", day);
+            let timestamp = format!("2024-12-0{}T10:00:00Z", day);
+            assistant_line(&id, &id, &timestamp, "claude-opus-4-20250514")
+        })
+        .collect();
+    lines.extend((0..5).map(|i| {
+        let id = format!("spike{}", i);
+        let timestamp = format!("2024-12-04T10:0{}:00Z", i);
+        assistant_line(&id, &id, &timestamp, "claude-opus-4-20250514")
+    }));
+    let dir = create_claude_dir(&lines);
+
+    let currency = ["--currency", "EUR", "--exchange-rate", "0.5", "--no-color", "-g", "day"];
+    let sections: [(&[&str], &str); 4] = [
+        (&["--reprice-as", "claude-sonnet-4-20250514"], "Repriced as"),
+        (&["--forecast", "2"], "95% CI"),
+        (&["--anomalies"], " cost "),
+        (&["--format", "ascii-chart"], "max"),
+    ];
+    for (extra, marker) in sections {
+        let mut output = Vec::new();
+        let argv: Vec<&str> = currency.iter().chain(extra).copied().collect();
+        analyze_usage_to_writer(args_for(dir.path(), &argv), &mut output).await.unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let line = output.lines().find(|line| line.contains(marker)).unwrap_or_else(|| {
+            panic!("no {:?} line in\n{}", marker, output);
+        });
+        assert!(line.contains('€') && !line.contains('$'), "{}", line);
+    }
+}
+
+#[tokio::test]
+async fn test_gaps_honor_model_filter_and_format() {
+    let dir = sample_dir();
+    let mut output = Vec::new();
+    analyze_usage_to_writer(
+        args_for(dir.path(), &["--gaps", "--model", "opus", "--format", "json"]),
+        &mut output,
+    )
+    .await
+    .unwrap();
+
+    // The Sonnet request in between is filtered out, leaving one 23 hour gap
+    let rows: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(rows[0]["session_id"], "test-session");
+    assert_eq!(rows[0]["requests"], 2);
+    assert_eq!(rows[0]["longest_idle_secs"], 23 * 3600);
+    assert!(rows[1]["session_id"].is_null());
+
+    let mut output = Vec::new();
+    analyze_usage_to_writer(args_for(dir.path(), &["--gaps", "--format", "csv"]), &mut output)
+        .await
+        .unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines[0], "Session,Requests,Median Gap Secs,P90 Gap Secs,Longest Idle Secs");
+    assert_eq!(lines[1], "test-session,3,3600,79200,79200");
+    assert_eq!(lines[2], ",3,3600,79200,79200");
+}
+
+#[tokio::test]
+async fn test_velocity_honors_model_filter_and_format() {
+    let dir = sample_dir();
+    let mut output = Vec::new();
+    analyze_usage_to_writer(
+        args_for(dir.path(), &["--velocity", "--exclude-model", "opus", "--format", "json"]),
+        &mut output,
+    )
+    .await
+    .unwrap();
+
+    // Only the Sonnet request's window is left
+    let rows: Vec<serde_json::Value> = serde_json::from_slice(&output).unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0]["window_start"], "2024-12-01T11:00:00Z");
+    assert_eq!(rows[0]["tokens_per_min"], 100.0);
+
+    let mut output = Vec::new();
+    analyze_usage_to_writer(args_for(dir.path(), &["--velocity", "--format", "csv"]), &mut output)
+        .await
+        .unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines[0], "Window Start,Tokens/min,Cost/hr USD");
+    assert_eq!(lines.len(), 4);
+    assert!(lines[1].starts_with("2024-12-01T10:00:00+00:00,100.00,"), "{}", lines[1]);
+}
@@ -0,0 +1,423 @@
+//! Runs the full analysis against a fixture projects tree and checks the numbers against
+//! hand-calculated expectations.
+
+use clap::Parser;
+use claude_usage::{analyze_usage_to_writer, cli::Args};
+use serde_json::Value;
+use std::path::Path;
+use tempfile::TempDir;
+
+const OPUS: &str = "claude-opus-4-20250514";
+const SONNET: &str = "claude-sonnet-4-20250514";
+const HAIKU: &str = "claude-3-5-haiku-20241022";
+
+struct Request<'a> {
+    uuid: &'a str,
+    request_id: &'a str,
+    session: &'a str,
+    timestamp: &'a str,
+    model: &'a str,
+    /// input, output, cache write, cache read
+    tokens: [u64; 4],
+}
+
+fn assistant_line(request: &Request) -> String {
+    let [input, output, cache_write, cache_read] = request.tokens;
+    format!(
+        r#"{{"type":"assistant","uuid":"{}","timestamp":"{}","sessionId":"{}","requestId":"{}","message":{{"id":"msg-{}","role":"assistant","model":"{}","usage":{{"input_tokens":{},"output_tokens":{},"cache_creation_input_tokens":{},"cache_read_input_tokens":{}}}}}}}"#,
+        request.uuid,
+        request.timestamp,
+        request.session,
+        request.request_id,
+        request.uuid,
+        request.model,
+        input,
+        output,
+        cache_write,
+        cache_read
+    )
+}
+
+/// Two projects with one session each. Only four requests count:
+///
+/// - r1, 2024-12-01, Opus 4, s1: 0.015 + 0.045 + 0.00375 + 0.0015 = $0.06525
+/// - r2, 2024-12-01, Sonnet 4, s1: 0.006 + 0.015 = $0.021
+/// - r3, 2024-12-02, Sonnet 4, s2: 0.0015 + 0.0015 = $0.003
+/// - r4, 2024-12-02, 3.5 Haiku, s2: 0.008 + 0.008 = $0.016
+///
+/// Everything else is a summary, a user message, a synthetic entry, a sidechain, an
+/// earlier copy of r1 or from before the June 2024 log format change.
+fn fixture_dir() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let projects = temp_dir.path().join("projects");
+
+    let first = [
+        r#"{"type":"summary","summary":"Refactoring the parser","leafUuid":"u1"}"#.to_string(),
+        r#"{"type":"user","uuid":"u1","timestamp":"2024-12-01T09:59:00Z","sessionId":"s1","message":{"role":"user","content":"hi"}}"#.to_string(),
+        // First copy of r1, superseded by the later one below
+        assistant_line(&Request {
+            uuid: "a1",
+            request_id: "r1",
+            session: "s1",
+            timestamp: "2024-12-01T10:00:00Z",
+            model: OPUS,
+            tokens: [1000, 100, 200, 1000],
+        }),
+        assistant_line(&Request {
+            uuid: "a1b",
+            request_id: "r1",
+            session: "s1",
+            timestamp: "2024-12-01T10:00:05Z",
+            model: OPUS,
+            tokens: [1000, 600, 200, 1000],
+        }),
+        assistant_line(&Request {
+            uuid: "a2",
+            request_id: "r2",
+            session: "s1",
+            timestamp: "2024-12-01T12:00:00Z",
+            model: SONNET,
+            tokens: [2000, 1000, 0, 0],
+        }),
+        r#"{"type":"assistant","uuid":"x1","timestamp":"2024-12-01T12:01:00Z","sessionId":"s1","message":{"id":"msg-x1","role":"assistant","model":"<synthetic>","usage":{"input_tokens":0,"output_tokens":0}}}"#.to_string(),
+        r#"{"type":"assistant","uuid":"sc1","timestamp":"2024-12-01T12:02:00Z","sessionId":"s1","isSidechain":true,"requestId":"rsc","message":{"id":"msg-sc1","role":"assistant","model":"claude-opus-4-20250514","usage":{"input_tokens":5000,"output_tokens":5000}}}"#.to_string(),
+    ];
+    let second = [
+        assistant_line(&Request {
+            uuid: "old",
+            request_id: "r0",
+            session: "s2",
+            timestamp: "2024-05-01T09:00:00Z",
+            model: OPUS,
+            tokens: [100_000, 100_000, 0, 0],
+        }),
+        assistant_line(&Request {
+            uuid: "a3",
+            request_id: "r3",
+            session: "s2",
+            timestamp: "2024-12-02T09:00:00Z",
+            model: SONNET,
+            tokens: [500, 100, 0, 0],
+        }),
+        assistant_line(&Request {
+            uuid: "a4",
+            request_id: "r4",
+            session: "s2",
+            timestamp: "2024-12-02T10:00:00Z",
+            model: HAIKU,
+            tokens: [10_000, 2000, 0, 0],
+        }),
+    ];
+
+    for (project, lines) in [("project-a", &first[..]), ("project-b", &second[..])] {
+        let dir = projects.join(project);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("session.jsonl"), lines.join("\n")).unwrap();
+    }
+    temp_dir
+}
+
+fn args_for(dir: &Path, extra: &[&str]) -> Args {
+    let dir = dir.to_string_lossy().to_string();
+    let mut argv = vec!["claude-usage", "--claude-dir", dir.as_str(), "--quiet", "--format", "json"];
+    argv.extend_from_slice(extra);
+    Args::parse_from(argv)
+}
+
+/// Rows of the JSON report for `group_by`
+async fn report(dir: &Path, group_by: &str) -> Vec<Value> {
+    let mut output = Vec::new();
+    analyze_usage_to_writer(args_for(dir, &["-g", group_by]), &mut output)
+        .await
+        .unwrap();
+    match serde_json::from_slice(&output).unwrap() {
+        Value::Array(rows) => rows,
+        other => panic!("expected an array, got {}", other),
+    }
+}
+
+fn assert_cost(row: &Value, expected: f64) {
+    let cost = row["cost_usd"].as_f64().unwrap();
+    assert!((cost - expected).abs() < 1e-9, "expected {} in {}", expected, row);
+}
+
+fn total(rows: &[Value], field: &str) -> u64 {
+    rows.iter().map(|row| row["usage"][field].as_u64().unwrap()).sum()
+}
+
+const TOTAL_COST: f64 = 0.06525 + 0.021 + 0.003 + 0.016;
+
+#[tokio::test]
+async fn test_group_by_day() {
+    let dir = fixture_dir();
+    let rows = report(dir.path(), "day").await;
+
+    assert_eq!(rows.len(), 2);
+    assert!(rows[0]["date"].as_str().unwrap().starts_with("2024-12-01"));
+    assert_eq!(rows[0]["request_count"], 2);
+    assert_cost(&rows[0], 0.06525 + 0.021);
+    assert!(rows[1]["date"].as_str().unwrap().starts_with("2024-12-02"));
+    assert_eq!(rows[1]["request_count"], 2);
+    assert_cost(&rows[1], 0.003 + 0.016);
+
+    // The later copy of r1 replaced the earlier one
+    assert_eq!(total(&rows, "output_tokens"), 600 + 1000 + 100 + 2000);
+    assert_eq!(total(&rows, "input_tokens"), 1000 + 2000 + 500 + 10_000);
+    assert_eq!(total(&rows, "cache_creation_input_tokens"), 200);
+    assert_eq!(total(&rows, "cache_read_input_tokens"), 1000);
+}
+
+#[tokio::test]
+async fn test_group_by_model() {
+    let dir = fixture_dir();
+    let rows = report(dir.path(), "model").await;
+
+    let by_model = |model: &str| {
+        rows.iter()
+            .find(|row| row["model"] == model)
+            .unwrap_or_else(|| panic!("no row for {}", model))
+    };
+    assert_eq!(rows.len(), 3);
+    assert_eq!(by_model(OPUS)["request_count"], 1);
+    assert_cost(by_model(OPUS), 0.06525);
+    assert_eq!(by_model(SONNET)["request_count"], 2);
+    assert_cost(by_model(SONNET), 0.021 + 0.003);
+    assert_eq!(by_model(HAIKU)["request_count"], 1);
+    assert_cost(by_model(HAIKU), 0.016);
+
+    // Cache reads over cache reads plus uncached input: 1000 / (1000 + 1000)
+    let hit_rate = by_model(OPUS)["cache_hit_rate"].as_f64().unwrap();
+    assert!((hit_rate - 0.5).abs() < 1e-9);
+}
+
+#[tokio::test]
+async fn test_group_by_model_and_day() {
+    let dir = fixture_dir();
+    let rows = report(dir.path(), "model,day").await;
+
+    assert_eq!(rows.len(), 4);
+    assert!(rows.iter().all(|row| row["request_count"] == 1));
+    let sonnet_days: Vec<f64> = rows
+        .iter()
+        .filter(|row| row["model"] == SONNET)
+        .map(|row| row["cost_usd"].as_f64().unwrap())
+        .collect();
+    assert_eq!(sonnet_days.len(), 2);
+    assert!((sonnet_days.iter().sum::<f64>() - 0.024).abs() < 1e-9);
+}
+
+#[tokio::test]
+async fn test_group_by_session() {
+    let dir = fixture_dir();
+    let rows = report(dir.path(), "session").await;
+
+    assert_eq!(rows.len(), 2);
+    let by_session = |session: &str| rows.iter().find(|row| row["session_id"] == session).unwrap();
+    assert_eq!(by_session("s1")["request_count"], 2);
+    assert_cost(by_session("s1"), 0.06525 + 0.021);
+    assert_eq!(by_session("s2")["request_count"], 2);
+    assert_cost(by_session("s2"), 0.019);
+}
+
+#[tokio::test]
+async fn test_group_by_none_and_month_agree() {
+    let dir = fixture_dir();
+    for group_by in ["none", "month"] {
+        let rows = report(dir.path(), group_by).await;
+        assert_eq!(rows.len(), 1, "group by {}", group_by);
+        assert_eq!(rows[0]["request_count"], 4);
+        assert_cost(&rows[0], TOTAL_COST);
+    }
+}
+
+#[tokio::test]
+async fn test_sidechains_and_synthetic_only_when_asked() {
+    let dir = fixture_dir();
+    let mut output = Vec::new();
+    analyze_usage_to_writer(
+        args_for(dir.path(), &["-g", "none", "--include-sidechains", "--include-synthetic"]),
+        &mut output,
+    )
+    .await
+    .unwrap();
+
+    let rows: Vec<Value> = serde_json::from_slice(&output).unwrap();
+    // The sidechain request costs 5000 * 15 / 1M + 5000 * 75 / 1M = 0.45; synthetic is free
+    assert_eq!(rows.iter().map(|row| row["request_count"].as_u64().unwrap()).sum::<u64>(), 6);
+    let cost: f64 = rows.iter().map(|row| row["cost_usd"].as_f64().unwrap()).sum();
+    assert!((cost - (TOTAL_COST + 0.45)).abs() < 1e-9);
+}
+
+#[tokio::test]
+async fn test_cache_detail_splits_write_costs() {
+    // Sonnet 4: 100k 5-minute writes at $3.75/M plus 50k 1-hour writes at $6/M
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path().join("projects").join("project-c");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("session.jsonl"),
+        format!(
+            r#"{{"type":"assistant","uuid":"c1","timestamp":"2024-12-03T09:00:00Z","sessionId":"s3","requestId":"rc1","message":{{"id":"msg-c1","role":"assistant","model":"{}","usage":{{"input_tokens":0,"output_tokens":0,"cache_creation_input_tokens":150000,"cache_read_input_tokens":0,"cache_creation":{{"ephemeral_5m_input_tokens":100000,"ephemeral_1h_input_tokens":50000}}}}}}}}"#,
+            SONNET
+        ),
+    )
+    .unwrap();
+
+    let mut output = Vec::new();
+    analyze_usage_to_writer(
+        args_for(temp_dir.path(), &["-g", "none", "--cache-detail"]),
+        &mut output,
+    )
+    .await
+    .unwrap();
+    let rows: Vec<Value> = serde_json::from_slice(&output).unwrap();
+
+    assert_eq!(rows.len(), 1);
+    let five_minute = rows[0]["cache_write_5m_cost_usd"].as_f64().unwrap();
+    let one_hour = rows[0]["cache_write_1h_cost_usd"].as_f64().unwrap();
+    assert!((five_minute - 0.375).abs() < 1e-9);
+    assert!((one_hour - 0.3).abs() < 1e-9);
+    assert_cost(&rows[0], 0.675);
+
+    // Without the flag the split costs are left out, but the total is the same
+    let rows = report(temp_dir.path(), "none").await;
+    assert!(rows[0].get("cache_write_1h_cost_usd").is_none());
+    assert_cost(&rows[0], 0.675);
+}
+
+#[tokio::test]
+async fn test_recent_keeps_newest_requests() {
+    let dir = fixture_dir();
+    let mut output = Vec::new();
+    analyze_usage_to_writer(args_for(dir.path(), &["-g", "none", "--recent", "2"]), &mut output)
+        .await
+        .unwrap();
+    let rows: Vec<Value> = serde_json::from_slice(&output).unwrap();
+
+    // r3 and r4 on 2024-12-02
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0]["request_count"], 2);
+    assert_cost(&rows[0], 0.003 + 0.016);
+}
+
+#[tokio::test]
+async fn test_group_by_request_gives_one_row_each() {
+    let dir = fixture_dir();
+    let rows = report(dir.path(), "request").await;
+
+    assert_eq!(rows.len(), 4);
+    assert!(rows.iter().all(|row| row["request_count"] == 1));
+    assert_eq!(rows[0]["model"], OPUS);
+    assert_eq!(rows[0]["session_id"], "s1");
+    assert_eq!(rows[0]["date"], "2024-12-01T10:00:05Z");
+    assert_cost(&rows[0], 0.06525);
+    assert_eq!(rows[3]["model"], HAIKU);
+    assert_cost(&rows[3], 0.016);
+
+    // CSV keeps the time and sub-cent costs
+    let claude_dir = dir.path().to_string_lossy().to_string();
+    let args = Args::parse_from([
+        "claude-usage",
+        "--claude-dir",
+        claude_dir.as_str(),
+        "--quiet",
+        "--format",
+        "csv",
+        "-g",
+        "request",
+    ]);
+    let mut output = Vec::new();
+    analyze_usage_to_writer(args, &mut output).await.unwrap();
+    let csv = String::from_utf8(output).unwrap();
+    assert!(csv.contains("2024-12-02T09:00:00+00:00,Claude 4 Sonnet,1,600,0.003000"), "{}", csv);
+}
+
+#[tokio::test]
+async fn test_json_summary_stays_valid_json() {
+    let dir = fixture_dir();
+    let mut output = Vec::new();
+    analyze_usage_to_writer(args_for(dir.path(), &["-g", "model", "--summary"]), &mut output)
+        .await
+        .unwrap();
+    let report: Value = serde_json::from_slice(&output).unwrap();
+
+    assert_eq!(report["stats"].as_array().unwrap().len(), 3);
+    let summary = &report["summary"];
+    assert_eq!(summary["total_requests"], 4);
+    assert!((summary["total_cost_usd"].as_f64().unwrap() - TOTAL_COST).abs() < 1e-9);
+    let by_model = summary["by_model"].as_array().unwrap();
+    assert_eq!(by_model.len(), 3);
+    assert_eq!(by_model[0]["model"], OPUS);
+}
+
+#[tokio::test]
+async fn test_aggregate_unknown_models_into_one_row() {
+    let dir = TempDir::new().unwrap();
+    let project = dir.path().join("projects").join("beta");
+    std::fs::create_dir_all(&project).unwrap();
+    // Priced by family ($15 and $3 per million input), and one without any pricing
+    let lines: Vec<String> = [
+        ("b1", "claude-opus-next-preview"),
+        ("b2", "claude-sonnet-next-beta"),
+        ("b3", "claude-internal-build-7"),
+        ("b4", SONNET),
+    ]
+    .iter()
+    .map(|(uuid, model)| {
+        assistant_line(&Request {
+            uuid,
+            request_id: uuid,
+            session: "s1",
+            timestamp: "2024-12-01T10:00:00Z",
+            model,
+            tokens: [1000, 0, 0, 0],
+        })
+    })
+    .collect();
+    std::fs::write(project.join("session.jsonl"), lines.join("\n")).unwrap();
+
+    let mut output = Vec::new();
+    let args = args_for(dir.path(), &["-g", "model", "--aggregate-unknown-models"]);
+    analyze_usage_to_writer(args, &mut output).await.unwrap();
+    let rows: Vec<Value> = serde_json::from_slice(&output).unwrap();
+
+    assert_eq!(rows.len(), 2);
+    let unknown: Vec<&Value> = rows.iter().filter(|row| row["model"] == "unknown").collect();
+    assert_eq!(unknown.len(), 1);
+    assert_eq!(unknown[0]["request_count"], 3);
+    assert_cost(unknown[0], 0.015 + 0.003);
+
+    let dir = dir.path().to_string_lossy().to_string();
+    let argv = ["claude-usage", "--claude-dir", &dir, "--quiet", "-g", "model"];
+    let mut output = Vec::new();
+    let args = Args::parse_from(argv.iter().copied().chain(["--aggregate-unknown-models"]));
+    analyze_usage_to_writer(args, &mut output).await.unwrap();
+    let table = String::from_utf8(output).unwrap();
+    assert!(table.contains("(3 unknown model variants aggregated)"), "{}", table);
+
+    // Counted after filtering, and only noted when there's an unknown row to explain
+    let note = |extra: &[&str]| {
+        let argv = argv[..4].iter().chain(extra).copied();
+        let args = Args::parse_from(argv.chain(["--aggregate-unknown-models"]));
+        async move {
+            let mut output = Vec::new();
+            analyze_usage_to_writer(args, &mut output).await.unwrap();
+            String::from_utf8(output).unwrap()
+        }
+    };
+    let table = note(&["-g", "model", "--exclude-model", "opus-next"]).await;
+    assert!(table.contains("(2 unknown model variants aggregated)"), "{}", table);
+    assert!(!note(&["-g", "model", "--model", SONNET]).await.contains("aggregated"));
+    assert!(!note(&["-g", "day"]).await.contains("aggregated"));
+}
+
+#[tokio::test]
+async fn test_dry_run_writes_no_report() {
+    let dir = fixture_dir();
+    let claude_dir = dir.path().to_string_lossy().to_string();
+    // Not quiet, so the entry count is printed; it and the summary go to stderr
+    let argv = ["claude-usage", "--claude-dir", &claude_dir, "-g", "model", "--dry-run"];
+    let mut output = Vec::new();
+    analyze_usage_to_writer(Args::parse_from(argv), &mut output).await.unwrap();
+    assert!(output.is_empty(), "{}", String::from_utf8_lossy(&output));
+}